@@ -0,0 +1,27 @@
+/* C interface of rcalc_lib. Build the library with feature `ffi`. */
+#ifndef RCALC_H
+#define RCALC_H
+
+#ifdef __cplusplus
+extern "C" {
+#endif
+
+typedef struct RclcSession RclcSession;
+
+/* All functions returning int return 0 on success and -1 on failure.
+ * Strings are UTF-8 and NUL-terminated. Strings written to `out` and `err`
+ * must be released with rclc_string_free. `out` and `err` can be NULL. */
+
+int rclc_eval(const char *expr, char **out, char **err);
+
+RclcSession *rclc_session_new(void);
+void rclc_session_free(RclcSession *session);
+int rclc_session_eval(RclcSession *session, const char *expr, char **out, char **err);
+
+void rclc_string_free(char *s);
+
+#ifdef __cplusplus
+}
+#endif
+
+#endif /* RCALC_H */
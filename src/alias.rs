@@ -0,0 +1,105 @@
+//! User-defined names of built-in functions.
+//!
+//! A frontend for non-English speakers can let users type functions in their
+//! language, e.g. `sen` for `sin` or `wurzel` for `sqrt`. The names are
+//! usually loaded from a table:
+//!
+//! ```
+//! use rcalc_lib::alias::Aliases;
+//! use rcalc_lib::{CalcBuilder, CalcState, Value};
+//!
+//! let table = "# Spanish\nsen = sin\nraiz = sqrt\n";
+//! let calc = CalcBuilder::new().aliases(Aliases::from_table(table).unwrap()).build();
+//! assert_eq!(calc.eval("raiz(16)", &mut CalcState::new()), Ok(Value::from(4)));
+//! ```
+//!
+//! Aliases resolve to the function names when an expression is parsed, so
+//! sandboxes, function descriptions, and compiled expressions see only the
+//! built-in names.
+
+use std::collections::HashMap;
+
+use crate::calc::LogBase;
+use crate::errors::*;
+use crate::stack::{find_func, resolve_alias};
+
+/// Table of user-defined function names
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Aliases {
+    names: HashMap<String, String>,
+}
+
+impl Aliases {
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    /// Adds a new name for a function. The function can be a built-in
+    /// function or its standard alias, e.g. `log`. Names are caseinsensitive.
+    /// A name of a built-in function cannot be redefined
+    pub fn add(&mut self, alias: &str, func: &str) -> Result<(), CalcError> {
+        let alias = alias.trim().to_lowercase();
+        let func = func.trim().to_lowercase();
+        let is_ident = alias.starts_with(|c: char| c.is_ascii_lowercase())
+            && alias.chars().all(|c| c == '_' || c.is_ascii_lowercase() || c.is_ascii_digit());
+        let is_builtin = |name: &str| find_func(name).is_some() || resolve_alias(name, LogBase::default()).is_some();
+        if !is_ident || is_builtin(&alias) || !is_builtin(&func) {
+            return Err(CalcError::InvalidAlias(alias, func));
+        }
+        self.names.insert(alias, func);
+        Ok(())
+    }
+
+    /// Loads aliases from a text table. Every line is `alias = function`.
+    /// Empty lines and lines starting with `#` are skipped
+    pub fn from_table(table: &str) -> Result<Self, CalcError> {
+        let mut aliases = Aliases::new();
+        for (idx, line) in table.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            match line.split_once('=') {
+                Some((alias, func)) => aliases.add(alias, func)?,
+                None => return Err(CalcError::ParseFailed(format!("line {} of alias table", idx + 1))),
+            }
+        }
+        Ok(aliases)
+    }
+
+    /// Returns the function name that the alias stands for
+    pub fn get(&self, alias: &str) -> Option<&str> {
+        self.names.get(alias).map(|s| s.as_str())
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.names.is_empty()
+    }
+
+    // all aliases in a stable order, for cache keys
+    pub(crate) fn sorted(&self) -> Vec<(&str, &str)> {
+        let mut pairs: Vec<(&str, &str)> = self.names.iter().map(|(a, f)| (a.as_str(), f.as_str())).collect();
+        pairs.sort_unstable();
+        pairs
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_aliases() {
+        let aliases = Aliases::from_table("# German\n\nWurzel = sqrt\n ln = log\nlogarithmus = log\n");
+        assert_eq!(aliases, Err(CalcError::InvalidAlias("ln".to_string(), "log".to_string())));
+        let aliases = Aliases::from_table("wurzel = sqrt\nlogarithmus = LOG\n").unwrap();
+        assert_eq!(aliases.get("wurzel"), Some("sqrt"));
+        assert_eq!(aliases.get("logarithmus"), Some("log"));
+        assert_eq!(aliases.get("sen"), None);
+        let mut aliases = Aliases::new();
+        assert!(aliases.add("sen", "nosuchfunc").is_err());
+        assert!(aliases.add("2sen", "sin").is_err());
+        assert!(aliases.is_empty());
+        assert_eq!(Aliases::from_table("sen sin"), Err(CalcError::ParseFailed("line 1 of alias table".to_string())));
+    }
+}
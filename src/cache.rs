@@ -0,0 +1,85 @@
+use std::collections::HashMap;
+
+use crate::value::Value;
+
+/// Result of a successful evaluation saved in the cache
+#[derive(Clone)]
+pub(crate) struct CachedResult {
+    pub(crate) value: Value,
+    pub(crate) alt_result: Option<String>,
+    pub(crate) warnings: Vec<String>,
+}
+
+struct CacheItem {
+    res: CachedResult,
+    used: u64,
+}
+
+// Simple LRU cache: when the cache is full, the least recently used item
+// is removed before adding a new one
+pub(crate) struct ResultCache {
+    capacity: usize,
+    tick: u64,
+    items: HashMap<String, CacheItem>,
+}
+
+impl ResultCache {
+    pub(crate) fn new(capacity: usize) -> Self {
+        ResultCache { capacity, tick: 0, items: HashMap::new() }
+    }
+
+    #[cfg(test)]
+    pub(crate) fn len(&self) -> usize {
+        self.items.len()
+    }
+
+    pub(crate) fn get(&mut self, key: &str) -> Option<CachedResult> {
+        self.tick += 1;
+        let tick = self.tick;
+        self.items.get_mut(key).map(|item| {
+            item.used = tick;
+            item.res.clone()
+        })
+    }
+
+    pub(crate) fn put(&mut self, key: String, res: CachedResult) {
+        if self.capacity == 0 {
+            return;
+        }
+        if self.items.len() >= self.capacity && !self.items.contains_key(&key) {
+            let oldest = self.items.iter().min_by_key(|(_, item)| item.used).map(|(k, _)| k.clone());
+            if let Some(k) = oldest {
+                self.items.remove(&k);
+            }
+        }
+        self.tick += 1;
+        self.items.insert(key, CacheItem { res, used: self.tick });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use num_bigint::BigInt;
+
+    fn res(i: i32) -> CachedResult {
+        CachedResult { value: Value::Int(BigInt::from(i)), alt_result: None, warnings: Vec::new() }
+    }
+
+    #[test]
+    fn test_lru() {
+        let mut cache = ResultCache::new(2);
+        cache.put("a".to_string(), res(1));
+        cache.put("b".to_string(), res(2));
+        assert!(cache.get("a").is_some());
+        cache.put("c".to_string(), res(3));
+        assert_eq!(cache.len(), 2);
+        assert!(cache.get("b").is_none());
+        assert_eq!(cache.get("a").unwrap().value, Value::Int(BigInt::from(1)));
+        assert_eq!(cache.get("c").unwrap().value, Value::Int(BigInt::from(3)));
+
+        let mut cache = ResultCache::new(0);
+        cache.put("a".to_string(), res(1));
+        assert_eq!(cache.len(), 0);
+    }
+}
@@ -0,0 +1,1396 @@
+use crate::alias::Aliases;
+use crate::cache::CachedResult;
+use crate::errors::*;
+use crate::expr::Expr;
+use crate::parse::{
+    calls_volatile, compile_infix, compile_postfix, normalize_syntax, referenced_names, CalcState, LAST_RESULT,
+};
+use crate::resolver::Resolver;
+use crate::sandbox::{Limits, Sandbox};
+use crate::stack::{find_func, resolve_alias, FUNCS, OPERATORS};
+use crate::statement::{assign, parse_statement, Statement};
+use crate::value::*;
+use num_bigint::BigInt;
+use std::fmt;
+use std::sync::Arc;
+
+#[cfg(feature = "parallel")]
+use rayon::prelude::*;
+
+/// What to do when a function receives more arguments than it uses
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum ExtraArgs {
+    /// Drop the trailing arguments silently: `sqrt(4;5)` == `sqrt(4)`
+    #[default]
+    Ignore,
+    /// Drop the trailing arguments and add a warning to `CalcState::warnings`
+    Warn,
+    /// Fail with an error
+    Error,
+}
+
+/// Unit of angles in trigonometric functions
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum Angle {
+    /// Arguments of `sin`, `cos`, etc and results of `asin`, `acos`, etc
+    /// are in radians: `sin(pi/2)` == `1`
+    #[default]
+    Rad,
+    /// Arguments of `sin`, `cos`, etc and results of `asin`, `acos`, etc
+    /// are in degrees: `sin(90)` == `1`, `atan(1)` == `45`. Angle literals
+    /// are read as degrees: `30d30m` == `30.5`
+    Deg,
+}
+
+/// What function `log` means
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum LogBase {
+    /// `log` is the natural logarithm `ln`
+    #[default]
+    E,
+    /// `log` is the base 10 logarithm `log10`
+    Ten,
+}
+
+/// What comparison and logical operators return
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum Truth {
+    /// Integers `1` and `0`: `(2 > 1) + 1` == `2`
+    #[default]
+    Int,
+    /// Booleans `true` and `false`. In arithmetic they are `1` and `0`,
+    /// as in C: `(2 > 1) + 1` == `2`
+    Bool,
+    /// Booleans `true` and `false` that work only with logical operators,
+    /// `==`, and `!=`: `(2 > 1) + 1` is an error
+    Strict,
+}
+
+/// What operator `^` means
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum Caret {
+    /// Power, the same as `**`: `2^10` == `1024`
+    #[default]
+    Power,
+    /// Bitwise XOR, the same as `^^` and `@`: `2^10` == `8`. It is what
+    /// programmers used to C-like languages expect
+    Xor,
+}
+
+/// Separator of function arguments and list items
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum ArgSeparator {
+    /// `max(1; 2)`. Comma is a decimal separator: `1,5` == `1.5`
+    #[default]
+    Semicolon,
+    /// `max(1, 2)`. Semicolon still works, and only a point is a decimal
+    /// separator
+    Comma,
+}
+
+/// Brackets that group expressions and function arguments
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum Brackets {
+    /// Only `()` group, and `[]` make lists: `[1; 2]`
+    #[default]
+    Round,
+    /// `()` and `{}` group, and `[]` make lists
+    Curly,
+    /// `()`, `[]`, and `{}` group. Lists are made with function `list`
+    All,
+}
+
+/// Precision of float numbers
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum FloatWidth {
+    /// Double precision(`f64`)
+    #[default]
+    F64,
+    /// Single precision(`f32`): every intermediate float result is rounded
+    /// to the closest `f32` number, so the results are the same as on a
+    /// target that has only single precision float math. Calculations are
+    /// still done in `f64`: for `+`, `-`, `*`, `/`, and `sqrt` the rounded
+    /// result is exactly the `f32` one, other functions can differ from an
+    /// `f32` implementation in the last bit
+    F32,
+}
+
+/// Options that override the calculator configuration for one evaluation,
+/// e.g. a frontend can switch a single line to degrees or hexadecimal output.
+/// `None` keeps the option of the calculator
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct CalcOptions {
+    pub angle: Option<Angle>,
+    pub exact: Option<bool>,
+    pub extra_args: Option<ExtraArgs>,
+    pub int_div: Option<Rounding>,
+    pub rounding: Option<Rounding>,
+    pub float_width: Option<FloatWidth>,
+    /// Options of `Calc::format_value`
+    pub format: Option<FormatOptions>,
+}
+
+/// Result of an evaluation with its metadata, so a frontend can show
+/// `= 1\3` for an exact result and `≈ 0.333` for an approximate one
+#[derive(Clone, Debug, PartialEq)]
+pub struct CalcOutput {
+    pub value: Value,
+    /// Whether the value is exact. See `Value::is_exact`
+    pub is_exact: bool,
+    /// Type of the value. See `Value::type_name`
+    pub type_name: &'static str,
+    /// Warnings generated by the evaluation
+    pub warnings: Vec<String>,
+}
+
+impl CalcOutput {
+    pub(crate) fn new(value: Value, warnings: Vec<String>) -> Self {
+        CalcOutput { is_exact: value.is_exact(), type_name: value.type_name(), value, warnings }
+    }
+}
+
+/// Group of built-in functions, e.g. for a categorized function picker
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Category {
+    Arithmetic,
+    Rounding,
+    Trigonometry,
+    Hyperbolic,
+    Exponential,
+    Complex,
+    NumberTheory,
+    Special,
+    Statistics,
+    /// Lists, vectors, and matrices
+    Matrix,
+    /// Polynomials and equations
+    Algebra,
+    Calculus,
+    /// Number bases, bits, and bytes
+    Bits,
+    Strings,
+    /// Encodings, checksums, and hashes
+    Encoding,
+    DateTime,
+    /// Data sizes, IPv4 addresses, and durations
+    Units,
+}
+
+impl fmt::Display for Category {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let name = match self {
+            Category::Arithmetic => "arithmetic",
+            Category::Rounding => "rounding",
+            Category::Trigonometry => "trigonometry",
+            Category::Hyperbolic => "hyperbolic",
+            Category::Exponential => "exponent and logarithm",
+            Category::Complex => "complex numbers",
+            Category::NumberTheory => "number theory",
+            Category::Special => "special functions",
+            Category::Statistics => "statistics",
+            Category::Matrix => "lists and matrices",
+            Category::Algebra => "polynomials and equations",
+            Category::Calculus => "calculus",
+            Category::Bits => "bits and bytes",
+            Category::Strings => "strings",
+            Category::Encoding => "encodings and hashes",
+            Category::DateTime => "date and time",
+            Category::Units => "units",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+/// Description of a built-in function returned by `Calc::functions`
+#[derive(Clone, Debug, PartialEq)]
+pub struct FunctionInfo {
+    pub name: &'static str,
+    /// The name with argument names: `clamp(x; lo; hi)`
+    pub signature: &'static str,
+    /// The least number of arguments
+    pub min_args: usize,
+    /// The greatest number of arguments, `None` if the function accepts any number of them
+    pub max_args: Option<usize>,
+    /// The number of arguments the function takes in reverse Polish notation
+    pub rpn_args: usize,
+    pub category: Category,
+    /// One-line description
+    pub description: &'static str,
+}
+
+/// Order of evaluation of binary operators with the same precedence
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Associativity {
+    /// `a - b - c` == `(a - b) - c`
+    Left,
+    /// `a ** b ** c` == `a ** (b ** c)`
+    Right,
+}
+
+/// Description of an operator returned by `Calc::operators`
+#[derive(Clone, Debug, PartialEq)]
+pub struct OperatorInfo {
+    /// The operator as it is written in an expression. Percentage operators
+    /// are shown as `+%`, `-%`, `*%`, and `/%`
+    pub symbol: &'static str,
+    /// Operators with higher precedence are evaluated first
+    pub precedence: i32,
+    pub associativity: Associativity,
+    /// 1 for unary operators, and 2 for binary ones
+    pub operands: usize,
+    /// One-line description
+    pub description: &'static str,
+}
+
+/// Calculator configuration. It does not keep any evaluation state: variables
+/// and the last result live in `CalcState`. So, one configured calculator
+/// can be shared between threads and used with many sessions
+#[derive(Clone, Debug)]
+pub struct Calc {
+    pub(crate) sandbox: Option<Sandbox>,
+    limits: Limits,
+    pub(crate) extra_args: ExtraArgs,
+    pub(crate) int_div: Rounding,
+    pub(crate) rounding: Rounding,
+    pub(crate) exact: bool,
+    pub(crate) modulus: Option<BigInt>,
+    pub(crate) angle: Angle,
+    pub(crate) float_width: FloatWidth,
+    pub(crate) case_sensitive: bool,
+    pub(crate) log_base: LogBase,
+    pub(crate) truth: Truth,
+    pub(crate) caret: Caret,
+    pub(crate) arg_sep: ArgSeparator,
+    pub(crate) brackets: Brackets,
+    aliases: Option<Arc<Aliases>>,
+    format: FormatOptions,
+    // how many formulas are evaluated one inside another. Formula functions
+    // evaluate their expressions with a copy of the calculator that has the
+    // level increased by one
+    pub(crate) nesting: usize,
+}
+
+impl Default for Calc {
+    fn default() -> Calc {
+        Calc {
+            sandbox: None,
+            limits: Limits::default(),
+            extra_args: ExtraArgs::default(),
+            int_div: Rounding::Trunc,
+            rounding: Rounding::HalfAwayFromZero,
+            exact: false,
+            modulus: None,
+            angle: Angle::Rad,
+            float_width: FloatWidth::F64,
+            case_sensitive: false,
+            log_base: LogBase::E,
+            truth: Truth::Int,
+            caret: Caret::Power,
+            arg_sep: ArgSeparator::Semicolon,
+            brackets: Brackets::Round,
+            aliases: None,
+            format: FormatOptions::default(),
+            nesting: 0,
+        }
+    }
+}
+
+impl Calc {
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    /// Returns a calculator in strict mode: it rejects questionable input,
+    /// e.g. extra function arguments
+    pub fn strict() -> Self {
+        Calc { extra_args: ExtraArgs::Error, ..Default::default() }
+    }
+
+    /// Returns a calculator with the given extra argument policy
+    pub fn with_extra_args(mut self, policy: ExtraArgs) -> Self {
+        self.extra_args = policy;
+        self
+    }
+
+    /// Returns a calculator that rounds the result of integer division(`//`)
+    /// with the given mode. By default, the result is truncated
+    pub fn with_int_div(mut self, mode: Rounding) -> Self {
+        self.int_div = mode;
+        self
+    }
+
+    /// Returns a calculator that uses the given mode in function `round` and
+    /// in `format_decimal`. By default, ties are rounded away from zero
+    pub fn with_rounding(mut self, mode: Rounding) -> Self {
+        self.rounding = mode;
+        self
+    }
+
+    /// Returns a calculator in exact mode: decimal numbers are read as
+    /// fractions(`0.1` is `1\10`), and division and negative powers of
+    /// integers and fractions keep the result exact. Only irrational
+    /// operations, e.g. `sqrt(2)` or `sin(1)`, produce float numbers.
+    /// Use function `approx` to convert an exact result to a float number
+    pub fn exact(mut self) -> Self {
+        self.exact = true;
+        self
+    }
+
+    /// Returns a calculator in modular mode: an integer result is reduced
+    /// modulo `m`, so it is from `0` to `m - 1`. Division of integers
+    /// multiplies by the modular inverse(`1/3` is `5` modulo `7`), power is
+    /// calculated with `modpow`(`3**-1` is `5`, and `2**10**18` is fast),
+    /// and `==` compares residues. Exponents are not reduced, and float
+    /// numbers are not affected. The modulus must be a positive integer
+    pub fn with_modulus(mut self, m: BigInt) -> Self {
+        self.modulus = Some(m);
+        self
+    }
+
+    /// Returns a calculator that uses the given unit of angles in
+    /// trigonometric functions. By default, angles are in radians
+    pub fn with_angle(mut self, unit: Angle) -> Self {
+        self.angle = unit;
+        self
+    }
+
+    /// Returns a calculator that uses float numbers of the given precision.
+    /// By default, float numbers are `f64`
+    pub fn with_float_width(mut self, width: FloatWidth) -> Self {
+        self.float_width = width;
+        self
+    }
+
+    /// Returns a calculator that distinguishes the case of function names:
+    /// `sin(1)` is a function call and `SIN(1)` is an undeclared variable.
+    /// By default, function names are case-insensitive
+    pub fn with_case_sensitive(mut self, on: bool) -> Self {
+        self.case_sensitive = on;
+        self
+    }
+
+    /// Returns a calculator where `log` is the logarithm with the given base.
+    /// By default, `log` is `ln`
+    pub fn with_log_base(mut self, base: LogBase) -> Self {
+        self.log_base = base;
+        self
+    }
+
+    /// Returns a calculator where comparison and logical operators return
+    /// values of the given type. By default, they return integers
+    pub fn with_truth(mut self, truth: Truth) -> Self {
+        self.truth = truth;
+        self
+    }
+
+    /// Returns a calculator where `^` is the given operator. By default, it
+    /// is power
+    pub fn with_caret(mut self, caret: Caret) -> Self {
+        self.caret = caret;
+        self
+    }
+
+    /// Returns a calculator that separates function arguments with the given
+    /// character. By default, it is a semicolon
+    pub fn with_arg_separator(mut self, sep: ArgSeparator) -> Self {
+        self.arg_sep = sep;
+        self
+    }
+
+    /// Returns a calculator that accepts the given grouping brackets. By
+    /// default, only round brackets group expressions
+    pub fn with_brackets(mut self, brackets: Brackets) -> Self {
+        self.brackets = brackets;
+        self
+    }
+
+    /// Returns a calculator that accepts user-defined names of functions,
+    /// e.g. localized ones. See `Aliases`
+    pub fn with_aliases(mut self, aliases: Aliases) -> Self {
+        self.aliases = if aliases.is_empty() { None } else { Some(Arc::new(aliases)) };
+        self
+    }
+
+    /// Returns a calculator that prints results with the given options in
+    /// `format_value`
+    pub fn with_format(mut self, opts: FormatOptions) -> Self {
+        self.format = opts;
+        self
+    }
+
+    /// Returns a copy of the calculator with the options overridden. The
+    /// calculator itself is not changed
+    pub fn with_options(&self, opts: &CalcOptions) -> Calc {
+        let mut calc = self.clone();
+        calc.angle = opts.angle.unwrap_or(calc.angle);
+        calc.exact = opts.exact.unwrap_or(calc.exact);
+        calc.extra_args = opts.extra_args.unwrap_or(calc.extra_args);
+        calc.int_div = opts.int_div.unwrap_or(calc.int_div);
+        calc.rounding = opts.rounding.unwrap_or(calc.rounding);
+        calc.float_width = opts.float_width.unwrap_or(calc.float_width);
+        calc.format = opts.format.unwrap_or(calc.format);
+        calc
+    }
+
+    /// Returns a calculator that evaluates expressions inside a sandbox.
+    /// Use it for untrusted input
+    pub fn with_sandbox(mut self, sandbox: Sandbox) -> Self {
+        self.sandbox = Some(sandbox);
+        self
+    }
+
+    /// Returns a calculator with custom parser limits
+    pub fn with_limits(mut self, limits: Limits) -> Self {
+        self.limits = limits;
+        self
+    }
+
+    // parser limits combined with the sandbox ones
+    pub(crate) fn parse_limits(&self) -> Limits {
+        let mut limits = self.limits;
+        if let Some(sb) = &self.sandbox {
+            limits.max_length = limits.max_length.min(sb.max_length);
+            limits.max_depth = limits.max_depth.min(sb.max_depth);
+        }
+        limits
+    }
+
+    /// Formats a value with the fixed number of digits after the decimal
+    /// point using the rounding mode of the calculator
+    pub fn format_decimal(&self, v: &Value, digits: usize) -> Result<String, CalcError> {
+        v.to_decimal(digits, self.rounding)
+    }
+
+    /// Converts a value to a string using the format options of the
+    /// calculator. See `Value::to_string_with`
+    pub fn format_value(&self, v: &Value) -> String {
+        v.to_string_with(&self.format)
+    }
+
+    /// Returns the name of the built-in function that the name refers to
+    /// in this calculator: it resolves user-defined and standard aliases
+    /// (`arcsin` -> `asin`, `log` -> `ln` or `log10`) and, unless the
+    /// calculator is case-sensitive, the case of letters(`SIN` -> `sin`)
+    pub fn resolve_function(&self, name: &str) -> Option<&'static str> {
+        let lower;
+        let name = if self.case_sensitive {
+            name
+        } else {
+            lower = name.to_lowercase();
+            &lower
+        };
+        let name = self.aliases.as_ref().and_then(|a| a.get(name)).unwrap_or(name);
+        let name = resolve_alias(name, self.log_base).unwrap_or(name);
+        find_func(name).map(|f| f.name)
+    }
+
+    // name of an identifier as the parser uses it: functions and their
+    // aliases become the function names, other names are lowercase. In
+    // case-sensitive mode a function name with the wrong case keeps it, so
+    // it is neither a function nor an existing variable
+    pub(crate) fn ident_name(&self, ident: &str) -> String {
+        if let Some(name) = self.resolve_function(ident) {
+            return name.to_string();
+        }
+        let lower = ident.to_lowercase();
+        if self.case_sensitive && self.resolve_function(&lower).is_some() {
+            return ident.to_string();
+        }
+        lower
+    }
+
+    /// One-line description of a built-in function, `None` if the function
+    /// does not exist
+    pub fn function_help(&self, name: &str) -> Option<&'static str> {
+        self.resolve_function(name).and_then(find_func).map(|f| f.help)
+    }
+
+    /// Descriptions of all built-in functions the calculator can call: if
+    /// the calculator has a sandbox, only the allowed functions are listed.
+    /// Functions of the same category go together
+    pub fn functions(&self) -> Vec<FunctionInfo> {
+        FUNCS.iter().filter(|f| self.allows_function(f.name)).map(|f| f.info()).collect()
+    }
+
+    /// Description of a built-in function, `None` if the function does not
+    /// exist or the sandbox does not allow it
+    pub fn function(&self, name: &str) -> Option<FunctionInfo> {
+        self.resolve_function(name).and_then(find_func).filter(|f| self.allows_function(f.name)).map(|f| f.info())
+    }
+
+    /// Descriptions of the operators starting from the highest precedence.
+    /// If the calculator has a sandbox, only the allowed operators are listed
+    pub fn operators(&self) -> Vec<OperatorInfo> {
+        let mut ops = Vec::new();
+        for op in OPERATORS.iter() {
+            if self.sandbox.as_ref().is_some_and(|sb| sb.check_operator(op.name).is_err()) {
+                continue;
+            }
+            let xor = self.caret == Caret::Xor;
+            if xor && op.name == "^" {
+                continue;
+            }
+            if xor && op.name == "^^" {
+                ops.push(OperatorInfo { symbol: "^", ..op.info() });
+            }
+            ops.push(op.info());
+        }
+        ops
+    }
+
+    fn allows_function(&self, name: &str) -> bool {
+        self.sandbox.as_ref().is_none_or(|sb| sb.check_function(name).is_ok())
+    }
+
+    /// Parses an expression without evaluating it
+    pub fn compile(&self, expr: &str, state: &mut CalcState) -> Result<Expr, CalcError> {
+        state.has_alt = false;
+        compile_infix(expr, self)
+    }
+
+    /// Parses an expression written in reverse Polish notation without evaluating it
+    pub fn compile_rpn(&self, expr: &str, state: &mut CalcState) -> Result<Expr, CalcError> {
+        state.has_alt = false;
+        compile_postfix(expr, self)
+    }
+
+    /// Evaluates an expression inside a session and returns either result or error
+    pub fn eval(&self, expr: &str, state: &mut CalcState) -> CalcResult {
+        let key = if state.cache.is_some() { cache_key(expr, self, state)? } else { None };
+        if let (Some(k), Some(cache)) = (&key, &mut state.cache) {
+            if let Some(res) = cache.get(k) {
+                state.add_variable(LAST_RESULT, res.value.clone());
+                state.has_alt = res.alt_result.is_some();
+                state.alt_result = res.alt_result.unwrap_or_default();
+                state.warnings = res.warnings;
+                return Ok(res.value);
+            }
+        }
+
+        let e = self.compile(expr, state)?;
+        let v = e.eval_in(self, state, None)?;
+        if let (Some(k), Some(cache)) = (key, &mut state.cache) {
+            let alt_result = if state.has_alt { Some(state.alt_result.clone()) } else { None };
+            cache.put(k, CachedResult { value: v.clone(), alt_result, warnings: state.warnings.clone() });
+        }
+        Ok(v)
+    }
+
+    /// Executes a statement inside a session: an expression, or an assignment
+    /// of its result to a variable, `x = 2*pi`. A list, e.g. the result of
+    /// `divmod`, can be unpacked into several variables: `(q; r) = divmod(17; 5)`.
+    /// `x += 5`, `x -= 5`, `x *= 5`, and `x /= 5` update an existing variable.
+    /// `x++` and `x--`(or `inc(x)` and `dec(x)`) add or subtract one.
+    /// Returns the result of the expression
+    pub fn exec(&self, stmt: &str, state: &mut CalcState) -> CalcResult {
+        match parse_statement(stmt, self, state)? {
+            Statement::Expr(expr) => self.eval(expr, state),
+            Statement::Assign(names, expr) => {
+                let v = self.eval(expr, state)?;
+                assign(&names, v.clone(), state)?;
+                Ok(v)
+            }
+            Statement::Update(name, op, expr) => {
+                let v = self.eval(&format!("{} {} ({})", name, op, expr), state)?;
+                state.add_variable(&name, v.clone());
+                Ok(v)
+            }
+        }
+    }
+
+    /// Evaluates an expression with the options overridden for this call only
+    pub fn eval_with_options(&self, expr: &str, state: &mut CalcState, opts: &CalcOptions) -> CalcResult {
+        self.with_options(opts).eval(expr, state)
+    }
+
+    /// Evaluates an expression inside a session like `eval` and returns
+    /// the result together with its type, exactness, and warnings
+    pub fn eval_output(&self, expr: &str, state: &mut CalcState) -> Result<CalcOutput, CalcError> {
+        let v = self.eval(expr, state)?;
+        Ok(CalcOutput::new(v, state.warnings.clone()))
+    }
+
+    /// Evaluates an expression inside a session. Variables that are not
+    /// defined in the session are requested from `resolver`. The results
+    /// are never cached because the resolver can return new values every time
+    pub fn eval_with_resolver(&self, expr: &str, state: &mut CalcState, resolver: &dyn Resolver) -> CalcResult {
+        let e = self.compile(expr, state)?;
+        e.eval_in(self, state, Some(resolver))
+    }
+
+    /// Evaluates an expression written in reverse Polish notation
+    pub fn eval_rpn(&self, expr: &str, state: &mut CalcState) -> CalcResult {
+        let e = self.compile_rpn(expr, state)?;
+        e.eval_in(self, state, None)
+    }
+
+    /// Evaluates a list of independent expressions, every expression in its
+    /// own new session. With feature `parallel` the expressions are evaluated
+    /// in parallel. The results are in the same order as the expressions
+    pub fn evaluate_batch(&self, exprs: &[&str]) -> Vec<CalcResult> {
+        #[cfg(feature = "parallel")]
+        let iter = exprs.par_iter();
+        #[cfg(not(feature = "parallel"))]
+        let iter = exprs.iter();
+        iter.map(|expr| {
+            let mut state = CalcState::new();
+            self.eval(expr, &mut state)
+        })
+        .collect()
+    }
+}
+
+/// Builder of a calculator configuration. The resulting `Calc` is immutable,
+/// and it can be cloned or shared between threads and sessions:
+///
+/// ```
+/// use rcalc_lib::calc::{Angle, CalcBuilder};
+/// use rcalc_lib::CalcState;
+///
+/// let calc = CalcBuilder::new().angle(Angle::Deg).precision(3).strict(true).allow_functions(&["sin", "sqrt"]).build();
+/// let v = calc.eval("sin(30) + sqrt(2)", &mut CalcState::new()).unwrap();
+/// assert_eq!(calc.format_value(&v), "1.914");
+/// assert!(calc.eval("cos(0)", &mut CalcState::new()).is_err());
+/// ```
+#[derive(Clone, Debug, Default)]
+pub struct CalcBuilder {
+    calc: Calc,
+}
+
+impl CalcBuilder {
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    /// Sets the unit of angles in trigonometric functions
+    pub fn angle(mut self, unit: Angle) -> Self {
+        self.calc.angle = unit;
+        self
+    }
+
+    /// Sets the precision of float numbers
+    pub fn float_width(mut self, width: FloatWidth) -> Self {
+        self.calc.float_width = width;
+        self
+    }
+
+    /// Sets the number of digits after the decimal point that
+    /// `Calc::format_value` prints for float numbers
+    pub fn precision(mut self, digits: usize) -> Self {
+        self.calc.format.precision = Some(digits);
+        self
+    }
+
+    /// Sets all options of `Calc::format_value`
+    pub fn format(mut self, opts: FormatOptions) -> Self {
+        self.calc.format = opts;
+        self
+    }
+
+    /// Turns strict mode on or off. A strict calculator rejects extra
+    /// function arguments, a non-strict one ignores them
+    pub fn strict(mut self, on: bool) -> Self {
+        self.calc.extra_args = if on { ExtraArgs::Error } else { ExtraArgs::Ignore };
+        self
+    }
+
+    /// Turns case-sensitive function names on or off. See
+    /// `Calc::with_case_sensitive`
+    pub fn case_sensitive(mut self, on: bool) -> Self {
+        self.calc.case_sensitive = on;
+        self
+    }
+
+    /// Sets what function `log` means
+    pub fn log_base(mut self, base: LogBase) -> Self {
+        self.calc.log_base = base;
+        self
+    }
+
+    /// Sets what comparison and logical operators return
+    pub fn truth(mut self, truth: Truth) -> Self {
+        self.calc.truth = truth;
+        self
+    }
+
+    /// Sets what operator `^` means
+    pub fn caret(mut self, caret: Caret) -> Self {
+        self.calc.caret = caret;
+        self
+    }
+
+    /// Sets the separator of function arguments
+    pub fn arg_separator(mut self, sep: ArgSeparator) -> Self {
+        self.calc.arg_sep = sep;
+        self
+    }
+
+    /// Sets the brackets that group expressions
+    pub fn brackets(mut self, brackets: Brackets) -> Self {
+        self.calc.brackets = brackets;
+        self
+    }
+
+    /// Sets user-defined names of functions. See `Aliases`
+    pub fn aliases(mut self, aliases: Aliases) -> Self {
+        self.calc = self.calc.with_aliases(aliases);
+        self
+    }
+
+    /// Sets the extra argument policy
+    pub fn extra_args(mut self, policy: ExtraArgs) -> Self {
+        self.calc.extra_args = policy;
+        self
+    }
+
+    /// Sets the rounding mode of integer division(`//`)
+    pub fn int_div(mut self, mode: Rounding) -> Self {
+        self.calc.int_div = mode;
+        self
+    }
+
+    /// Sets the rounding mode of function `round` and `Calc::format_decimal`
+    pub fn rounding(mut self, mode: Rounding) -> Self {
+        self.calc.rounding = mode;
+        self
+    }
+
+    /// Turns exact mode on or off. See `Calc::exact`
+    pub fn exact(mut self, on: bool) -> Self {
+        self.calc.exact = on;
+        self
+    }
+
+    /// Evaluates integer expressions modulo `m`. See `Calc::with_modulus`
+    pub fn modulus(mut self, m: BigInt) -> Self {
+        self.calc.modulus = Some(m);
+        self
+    }
+
+    /// Sets the parser limits
+    pub fn limits(mut self, limits: Limits) -> Self {
+        self.calc.limits = limits;
+        self
+    }
+
+    /// Evaluates expressions inside the sandbox. It replaces the lists of
+    /// allowed functions and operators set before
+    pub fn sandbox(mut self, sandbox: Sandbox) -> Self {
+        self.calc.sandbox = Some(sandbox);
+        self
+    }
+
+    /// Allows only the listed functions. If the sandbox is not set, the
+    /// calculator gets a sandbox without size limits
+    pub fn allow_functions(mut self, names: &[&str]) -> Self {
+        let sb = self.calc.sandbox.take().unwrap_or_else(unlimited_sandbox);
+        self.calc.sandbox = Some(sb.allow_functions(names));
+        self
+    }
+
+    /// Allows only the listed operators. If the sandbox is not set, the
+    /// calculator gets a sandbox without size limits
+    pub fn allow_operators(mut self, ops: &[&str]) -> Self {
+        let sb = self.calc.sandbox.take().unwrap_or_else(unlimited_sandbox);
+        self.calc.sandbox = Some(sb.allow_operators(ops));
+        self
+    }
+
+    pub fn build(self) -> Calc {
+        self.calc
+    }
+}
+
+// a sandbox that only restricts functions and operators
+fn unlimited_sandbox() -> Sandbox {
+    Sandbox::new().max_length(usize::MAX).max_depth(usize::MAX).max_int_bits(u64::MAX)
+}
+
+// cache key contains the normalized expression, the calculator options and
+// limits that change or reject results, and the values of all variables the
+// expression uses. Expressions with volatile functions(e.g, `now`) have no key.
+// The key is built from the text with the standard syntax, so the options
+// and symbols that only the compiler understands(e.g, `{}` brackets or `x²`)
+// do not break the parsers below
+fn cache_key(expr: &str, calc: &Calc, state: &CalcState) -> Result<Option<String>, CalcError> {
+    let expr = &normalize_syntax(expr, calc);
+    if calls_volatile(expr, calc)? {
+        return Ok(None);
+    }
+    // string literals are case and whitespace sensitive, so expressions
+    // with them are used as is. So are all expressions if function names
+    // are case-sensitive
+    let mut key = if expr.contains('"') || calc.case_sensitive {
+        expr.to_string()
+    } else {
+        expr.split_whitespace().collect::<Vec<&str>>().join(" ").to_lowercase()
+    };
+    key += &format!(
+        "\0{:?}\0{:?}\0{:?}\0{:?}\0{:?}\0{}\0{:?}\0{:?}\0{:?}\0{:?}\0{:?}\0{:?}\0{:?}\0{:?}",
+        calc.sandbox,
+        calc.limits,
+        calc.extra_args,
+        calc.int_div,
+        calc.rounding,
+        calc.exact,
+        calc.modulus,
+        calc.angle,
+        calc.float_width,
+        calc.log_base,
+        calc.truth,
+        calc.caret,
+        calc.arg_sep,
+        calc.brackets
+    );
+    if let Some(aliases) = &calc.aliases {
+        key += &format!("\0{:?}", aliases.sorted());
+    }
+    for name in referenced_names(expr, calc, state)? {
+        match state.variable(&name) {
+            Some(v) => key += &format!("\0{}={:?}", name, v),
+            None => key += &format!("\0{}", name),
+        }
+    }
+    Ok(Some(key))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::resolver::Resolved;
+    use num_rational::BigRational;
+    use std::collections::HashMap;
+
+    struct Sheet {
+        cells: HashMap<&'static str, Resolved>,
+    }
+
+    impl Resolver for Sheet {
+        fn resolve(&self, name: &str) -> Option<Resolved> {
+            self.cells.get(name).cloned()
+        }
+    }
+
+    // `n0` = `n1 + 1`, `n1` = `n2 + 1`, and so on up to `n{len}` = 0
+    struct Chain {
+        len: usize,
+    }
+
+    impl Resolver for Chain {
+        fn resolve(&self, name: &str) -> Option<Resolved> {
+            let i: usize = name.strip_prefix('n')?.parse().ok()?;
+            if i < self.len {
+                Some(Resolved::Formula(format!("n{} + 1", i + 1)))
+            } else {
+                Some(Resolved::Value(Value::Int(BigInt::from(0))))
+            }
+        }
+    }
+
+    fn is_send_sync<T: Send + Sync>() {}
+
+    #[test]
+    fn test_shared() {
+        is_send_sync::<Calc>();
+        is_send_sync::<CalcState>();
+
+        let calc = Calc::new();
+        let res = calc.evaluate_batch(&["1+2", "ans", "sqr 4", "2+"]);
+        assert_eq!(
+            res,
+            vec![
+                Ok(Value::Int(BigInt::from(3))),
+                Err(CalcError::VarUndeclared("ans".to_string())),
+                Ok(Value::Int(BigInt::from(16))),
+                Err(CalcError::TooManyOps),
+            ]
+        );
+
+        let mut state = CalcState::new();
+        state.enable_cache(10);
+        state.add_variable("x", Value::Int(BigInt::from(2)));
+        assert_eq!(calc.eval("hex(x * 10)", &mut state), Ok(Value::Int(BigInt::from(20))));
+        assert_eq!(calc.eval("  HEX(x *  10) ", &mut state), Ok(Value::Int(BigInt::from(20))));
+        assert_eq!(state.cache.as_ref().map(|c| c.len()), Some(1));
+        assert!(state.has_alt);
+        assert_eq!(state.alt_result, "0x14");
+        assert_eq!(calc.eval("ans + 1", &mut state), Ok(Value::Int(BigInt::from(21))));
+        assert!(!state.has_alt);
+        assert_eq!(calc.eval("ans + 1", &mut state), Ok(Value::Int(BigInt::from(22))));
+        state.add_variable("x", Value::Int(BigInt::from(3)));
+        assert_eq!(calc.eval("hex(x * 10)", &mut state), Ok(Value::Int(BigInt::from(30))));
+        assert_eq!(state.result(), Some(Value::Int(BigInt::from(30))));
+        // string literals are not normalized
+        assert_eq!(calc.eval("unroman(\"XIV\")", &mut state), Ok(Value::Int(BigInt::from(14))));
+        let r = calc.eval("unroman(\"X IV\")", &mut state);
+        assert_eq!(r, Err(CalcError::InvalidAgrument("unroman".to_string(), "X IV".to_string())));
+        // the current time is never cached
+        let cached = state.cache.as_ref().map(|c| c.len());
+        #[cfg(feature = "datetime")]
+        assert!(calc.eval("now() - unix(\"2024-01-01\")", &mut state).is_ok());
+        assert_eq!(state.cache.as_ref().map(|c| c.len()), cached);
+        // variables inside an equation are not visible to the cache
+        let r = calc.eval("solve(\"x = y\"; \"y\")", &mut state);
+        assert_eq!(r, Ok(Value::List(vec![Value::Int(BigInt::from(3))])));
+        state.add_variable("x", Value::Int(BigInt::from(4)));
+        let r = calc.eval("solve(\"x = y\"; \"y\")", &mut state);
+        assert_eq!(r, Ok(Value::List(vec![Value::Int(BigInt::from(4))])));
+
+        let calc = std::sync::Arc::new(calc);
+        let handles: Vec<_> = (0..4)
+            .map(|i| {
+                let calc = calc.clone();
+                std::thread::spawn(move || {
+                    let mut state = CalcState::new();
+                    calc.eval(&format!("{}*10", i), &mut state)
+                })
+            })
+            .collect();
+        for (i, h) in handles.into_iter().enumerate() {
+            assert_eq!(h.join().unwrap(), Ok(Value::Int(BigInt::from(i * 10))));
+        }
+    }
+
+    #[test]
+    fn test_resolver() {
+        let mut cells = HashMap::new();
+        cells.insert("a1", Resolved::Value(Value::Int(BigInt::from(10))));
+        cells.insert("b1", Resolved::Formula("A1 * 2 + price.total".to_string()));
+        cells.insert("price.total", Resolved::Value(Value::Int(BigInt::from(5))));
+        cells.insert("c1", Resolved::Formula("d1 + 1".to_string()));
+        cells.insert("d1", Resolved::Formula("sqr(c1)".to_string()));
+        cells.insert("e1", Resolved::Formula("c1".to_string()));
+        let sheet = Sheet { cells };
+
+        let calc = Calc::new();
+        let mut state = CalcState::new();
+        assert_eq!(calc.eval_with_resolver("b1 + a1", &mut state, &sheet), Ok(Value::Int(BigInt::from(35))));
+        assert_eq!(calc.eval_with_resolver("ans - B1", &mut state, &sheet), Ok(Value::Int(BigInt::from(10))));
+        // session variables hide the host's ones
+        state.add_variable("a1", Value::Int(BigInt::from(1)));
+        assert_eq!(calc.eval_with_resolver("b1", &mut state, &sheet), Ok(Value::Int(BigInt::from(7))));
+        assert_eq!(
+            calc.eval_with_resolver("e1", &mut state, &sheet),
+            Err(CalcError::CircularReference("e1 -> c1 -> d1 -> c1".to_string()))
+        );
+        assert_eq!(
+            calc.eval_with_resolver("z1 + 1", &mut state, &sheet),
+            Err(CalcError::VarUndeclared("z1".to_string()))
+        );
+        assert_eq!(calc.eval("price.total", &mut state), Err(CalcError::VarUndeclared("price.total".to_string())));
+
+        let chain = Chain { len: 100 };
+        let r = calc.eval_with_resolver("n0", &mut state, &chain);
+        assert!(matches!(r, Err(CalcError::TooDeepReference(64, _))), "{:?}", r);
+        let r = calc.eval_with_resolver("n90", &mut state, &chain);
+        assert_eq!(r, Ok(Value::Int(BigInt::from(10))));
+        let short = Calc::new().with_limits(Limits { max_nesting: 3, ..Limits::default() });
+        assert_eq!(
+            short.eval_with_resolver("n0", &mut state, &chain),
+            Err(CalcError::TooDeepReference(3, "n0 -> n1 -> n2 -> n3".to_string()))
+        );
+        assert_eq!(short.eval_with_resolver("n97", &mut state, &chain), Ok(Value::Int(BigInt::from(3))));
+        let deep = Calc::new().with_limits(Limits { max_nesting: 200, ..Limits::default() });
+        assert_eq!(deep.eval_with_resolver("n0", &mut state, &chain), Ok(Value::Int(BigInt::from(100))));
+
+        let e = calc.compile("a1 * price.total", &mut state).unwrap();
+        assert_eq!(e.to_postfix(), "a1 price.total *");
+        assert_eq!(e.eval_with(&mut state, &sheet), Ok(Value::Int(BigInt::from(5))));
+    }
+
+    #[test]
+    fn test_extra_args() {
+        let mut state = CalcState::new();
+        assert_eq!(Calc::new().eval("deg(0; 2; 3)", &mut state), Ok(Value::Float(0.0)));
+        assert!(state.warnings.is_empty());
+
+        let calc = Calc::new().with_extra_args(ExtraArgs::Warn);
+        assert_eq!(calc.eval("sqrt(4; 5) + iif(1; 2; 3; 4)", &mut state), Ok(Value::Int(BigInt::from(4))));
+        assert_eq!(
+            state.warnings,
+            vec![
+                "Function 'sqrt' uses 1 argument(s), 1 extra dropped".to_string(),
+                "Function 'iif' uses 3 argument(s), 1 extra dropped".to_string(),
+            ]
+        );
+        assert_eq!(calc.eval("max(1; 5; 3)", &mut state), Ok(Value::Int(BigInt::from(5))));
+        assert!(state.warnings.is_empty());
+
+        let calc = Calc::strict();
+        assert_eq!(calc.eval("deg(1; 2; 3)", &mut state), Err(CalcError::FunctionTooManyArgs("deg".to_string(), 1)));
+        assert_eq!(
+            calc.eval("ratio(1; 2; 3)", &mut state),
+            Err(CalcError::FunctionTooManyArgs("ratio".to_string(), 2))
+        );
+        assert_eq!(calc.eval("ratio(1; 2)", &mut state), Ok(Value::Ratio(BigRational::new(1.into(), 2.into()))));
+        assert_eq!(calc.eval("gcd(12; 18; 8)", &mut state), Ok(Value::Int(BigInt::from(2))));
+        assert_eq!(calc.eval("cov([1; 2]; [2; 4])", &mut state), Ok(Value::Float(1.0)));
+        #[cfg(feature = "datetime")]
+        assert_eq!(calc.eval("daysin(2; 2024)", &mut state), Ok(Value::Int(BigInt::from(29))));
+        #[cfg(feature = "datetime")]
+        assert_eq!(calc.eval("now(1)", &mut state), Err(CalcError::FunctionTooManyArgs("now".to_string(), 0)));
+    }
+
+    #[test]
+    fn test_cache_policy() {
+        let mut state = CalcState::new();
+        state.enable_cache(10);
+        // a result cached under one policy is not served under another
+        assert_eq!(Calc::new().eval("sqrt(4; 5)", &mut state), Ok(Value::Int(BigInt::from(2))));
+        assert_eq!(
+            Calc::strict().eval("sqrt(4; 5)", &mut state),
+            Err(CalcError::FunctionTooManyArgs("sqrt".to_string(), 1))
+        );
+        let calc = Calc::new().with_extra_args(ExtraArgs::Warn);
+        assert_eq!(calc.eval("sqrt(4; 5)", &mut state), Ok(Value::Int(BigInt::from(2))));
+        assert_eq!(state.warnings, vec!["Function 'sqrt' uses 1 argument(s), 1 extra dropped".to_string()]);
+        assert_eq!(calc.eval("sqrt(4; 5)", &mut state), Ok(Value::Int(BigInt::from(2))));
+        assert_eq!(state.warnings.len(), 1);
+
+        assert_eq!(Calc::new().eval("sin(0) + 2**10000 % 3", &mut state), Ok(Value::Int(BigInt::from(1))));
+        let calc = Calc::new().with_sandbox(Sandbox::new().allow_functions(&["sqrt"]));
+        assert_eq!(calc.eval("sin(0) + 2**10000 % 3", &mut state), Err(CalcError::NotAllowed("sin".to_string())));
+        let calc = Calc::new().with_sandbox(Sandbox::new().max_int_bits(64));
+        assert!(calc.eval("sin(0) + 2**10000 % 3", &mut state).is_err());
+
+        let calc = Calc::new().with_limits(Limits { max_length: 4, ..Limits::default() });
+        assert_eq!(calc.eval("sin(0) + 2**10000 % 3", &mut state), Err(CalcError::ExprTooLong(4)));
+    }
+
+    #[test]
+    fn test_cache_syntax() {
+        // the same expressions are calculated with and without the cache
+        let int = |n: i64| Ok(Value::Int(BigInt::from(n)));
+        let mut state = CalcState::new();
+        state.enable_cache(10);
+        state.add_variable("x", Value::from(3));
+        let calc = Calc::new();
+        assert_eq!(calc.eval("2²", &mut state), int(4));
+        assert_eq!(calc.eval("x²", &mut state), int(9));
+        assert_eq!(calc.eval("2×3", &mut state), int(6));
+        assert_eq!(calc.eval("√16", &mut state), int(4));
+        state.add_variable("x", Value::from(4));
+        assert_eq!(calc.eval("x²", &mut state), int(16));
+        assert_eq!(calc.eval("½ + 1", &mut state), Ok(Value::Ratio(BigRational::new(3.into(), 2.into()))));
+        assert_eq!(calc.eval("1½ * 2", &mut state), int(3));
+        assert_eq!(calc.eval("x¾", &mut state), int(3));
+
+        let calc = CalcBuilder::new().arg_separator(ArgSeparator::Comma).brackets(Brackets::Curly).build();
+        assert_eq!(calc.eval("{1+2}*3", &mut state), int(9));
+        assert_eq!(calc.eval("max(x, {2})", &mut state), int(4));
+    }
+
+    #[test]
+    fn test_function_help() {
+        let mut state = CalcState::new();
+        let calc = Calc::new();
+        assert_eq!(calc.function_help("sin"), Some("sine"));
+        assert_eq!(calc.function_help("mod"), Some("Euclidean remainder that is never negative"));
+        assert_eq!(calc.function_help("nosuchfunc"), None);
+        let clamp = calc.function("clamp").unwrap();
+        assert_eq!((clamp.signature, clamp.min_args, clamp.max_args), ("clamp(x; lo; hi)", 3, Some(3)));
+        assert_eq!(calc.function("gcd").map(|f| f.rpn_args), Some(2));
+        assert_eq!(clamp.category, Category::Arithmetic);
+        assert_eq!(calc.function("gcd").map(|f| f.max_args), Some(None));
+        assert_eq!(Category::NumberTheory.to_string(), "number theory");
+        let funcs = calc.functions();
+        assert!(funcs.iter().any(|f| f.name == "sin" && f.category == Category::Trigonometry));
+        assert!(funcs.iter().all(|f| f.signature.starts_with(f.name) && !f.description.is_empty()));
+        let sandboxed = CalcBuilder::new().allow_functions(&["sin", "sqrt"]).build();
+        let names: Vec<&str> = sandboxed.functions().iter().map(|f| f.name).collect();
+        assert_eq!(names, vec!["sqrt", "sin"]);
+        assert_eq!(sandboxed.function("cos"), None);
+        assert_eq!(calc.eval("next_prime()", &mut state), Err(CalcError::FunctionNoArgs("next_prime".to_string())));
+        assert_eq!(calc.eval("mod(5)", &mut state), Err(CalcError::FunctionNotEnoughArgs("mod".to_string(), 2)));
+    }
+
+    #[test]
+    fn test_aliases() {
+        let mut state = CalcState::new();
+        let calc = Calc::new();
+        assert_eq!(calc.eval("arcsin(1) == asin(1)", &mut state), Ok(Value::Int(BigInt::from(1))));
+        assert_eq!(calc.eval("lg(1000)", &mut state), Ok(Value::Float(3.0)));
+        assert_eq!(calc.eval("LOG(1)", &mut state), Ok(Value::Float(0.0)));
+        assert_eq!(calc.resolve_function("ArcTan"), Some("atan"));
+        assert_eq!(calc.resolve_function("nosuchfunc"), None);
+        assert_eq!(calc.function("arccos").map(|f| f.name), Some("acos"));
+        let calc = CalcBuilder::new().log_base(LogBase::Ten).case_sensitive(true).build();
+        assert_eq!(calc.eval("log(100)", &mut state), Ok(Value::Float(2.0)));
+        assert_eq!(calc.resolve_function("SIN"), None);
+        assert_eq!(calc.eval("SIN(0)", &mut state), Err(CalcError::VarUndeclared("SIN".to_string())));
+        assert_eq!(state.variable_name_validate("arcsin"), Err("Function name cannot be used as a variable"));
+        let sandboxed = CalcBuilder::new().allow_functions(&["ln"]).build();
+        assert_eq!(sandboxed.eval("log(1)", &mut state), Ok(Value::Float(0.0)));
+        assert!(sandboxed.eval("lg(1)", &mut state).is_err());
+
+        let spanish = CalcBuilder::new().aliases(Aliases::from_table("sen = sin\nraiz = sqrt").unwrap()).build();
+        let german = CalcBuilder::new().aliases(Aliases::from_table("wurzel = sqrt\nsen = cos").unwrap()).build();
+        state.enable_cache(8);
+        assert_eq!(spanish.eval("SEN(0)", &mut state), Ok(Value::Float(0.0)));
+        assert_eq!(german.eval("sen(0)", &mut state), Ok(Value::Float(1.0)));
+        assert_eq!(german.eval("wurzel(2)", &mut state), calc.eval("sqrt(2)", &mut state));
+        assert_eq!(spanish.function("raiz").map(|f| f.name), Some("sqrt"));
+        assert!(calc.eval("raiz(4)", &mut state).is_err());
+
+        // aliases of volatile and formula functions are not cached either
+        let german = CalcBuilder::new().aliases(Aliases::from_table("karte = map").unwrap()).build();
+        let cached = state.cache.as_ref().map(|c| c.len());
+        state.add_variable("k", Value::from(2));
+        assert_eq!(german.eval("karte([1;2]; \"x*k\")", &mut state).map(|v| v.to_string()), Ok("[2; 4]".to_string()));
+        state.add_variable("k", Value::from(3));
+        assert_eq!(german.eval("karte([1;2]; \"x*k\")", &mut state).map(|v| v.to_string()), Ok("[3; 6]".to_string()));
+        #[cfg(feature = "datetime")]
+        {
+            let german = CalcBuilder::new().aliases(Aliases::from_table("jetzt = now").unwrap()).build();
+            assert!(german.eval("jetzt()", &mut state).is_ok());
+        }
+        assert_eq!(state.cache.as_ref().map(|c| c.len()), cached);
+    }
+
+    #[test]
+    fn test_syntax_options() {
+        let mut state = CalcState::new();
+        let int = |n: i64| Ok(Value::Int(BigInt::from(n)));
+        let calc = Calc::new();
+        assert_eq!(calc.eval("max(1,5; 1)", &mut state), Ok(Value::Float(1.5)));
+        assert!(calc.eval("{1 + 2} * 3", &mut state).is_err());
+        let calc = CalcBuilder::new().arg_separator(ArgSeparator::Comma).brackets(Brackets::Curly).build();
+        assert_eq!(calc.eval("max(1,5; 2)", &mut state), int(5));
+        assert_eq!(calc.eval("{1 + 2} * len([3, 4])", &mut state), int(6));
+        assert_eq!(calc.eval("len(\"a,b{}\")", &mut state), int(5));
+        let calc = Calc::new().with_brackets(Brackets::All);
+        assert_eq!(calc.eval("[1 + 2] * {3 - 1}", &mut state), int(6));
+        assert_eq!(calc.eval("len(list(1; 2))", &mut state), int(2));
+    }
+
+    #[test]
+    fn test_truth() {
+        let mut state = CalcState::new();
+        let int = |n: i64| Ok(Value::Int(BigInt::from(n)));
+        let calc = Calc::new();
+        assert_eq!(calc.eval("(2 > 1) + 1", &mut state), int(2));
+        assert_eq!(calc.eval("(1 < 2) && 3", &mut state), int(1));
+        let calc = Calc::new().with_truth(Truth::Bool);
+        assert_eq!(calc.eval("2 > 1", &mut state), Ok(Value::Bool(true)));
+        assert_eq!(calc.eval("!(2 > 1) || 0", &mut state), Ok(Value::Bool(false)));
+        assert_eq!(calc.eval("(2 > 1) + 1", &mut state), int(2));
+        assert_eq!(calc.eval("(2 > 1) == (3 > 1)", &mut state), Ok(Value::Bool(true)));
+        assert_eq!(calc.eval("[1 == 1; 2]", &mut state).map(|v| v.to_string()), Ok("[true; 2]".to_string()));
+        let calc = CalcBuilder::new().truth(Truth::Strict).build();
+        assert_eq!(calc.eval("(1 < 2) && (2 < 3)", &mut state), Ok(Value::Bool(true)));
+        assert_eq!(calc.eval("(2 > 1) + 1", &mut state), Err(CalcError::NotForBool("+".to_string())));
+        assert_eq!(calc.eval("1 < 2 < 3", &mut state), Err(CalcError::NotForBool("<".to_string())));
+        assert_eq!(calc.eval("sqrt(1 > 0)", &mut state), Err(CalcError::NotForBool("sqrt".to_string())));
+        assert_eq!(Value::Bool(true).type_name(), "bool");
+    }
+
+    #[test]
+    fn test_caret() {
+        let mut state = CalcState::new();
+        let int = |n: i64| Ok(Value::Int(BigInt::from(n)));
+        let calc = Calc::new();
+        assert_eq!(calc.eval("2^10", &mut state), int(1024));
+        assert_eq!(calc.eval("2^^10", &mut state), int(8));
+        let calc = CalcBuilder::new().caret(Caret::Xor).build();
+        assert_eq!(calc.eval("2^10", &mut state), int(8));
+        assert_eq!(calc.eval("2**10 + 1^3", &mut state), int(1026));
+        assert_eq!(calc.eval_rpn("2 10 ^", &mut state), int(8));
+    }
+
+    #[test]
+    fn test_formula_options() {
+        // formulas passed to functions use all options of the calculator
+        let mut state = CalcState::new();
+        let list = |calc: &Calc, expr: &str, state: &mut CalcState| calc.eval(expr, state).map(|v| v.to_string());
+        let calc = CalcBuilder::new().caret(Caret::Xor).build();
+        assert_eq!(list(&calc, "map([3]; \"x^1\")", &mut state), Ok("[2]".to_string()));
+        let calc = CalcBuilder::new().log_base(LogBase::Ten).build();
+        assert_eq!(list(&calc, "map([100]; \"log(x)\")", &mut state), Ok("[2.0]".to_string()));
+        let calc = CalcBuilder::new().truth(Truth::Bool).build();
+        assert_eq!(list(&calc, "map([2]; \"x > 1\")", &mut state), Ok("[true]".to_string()));
+        let calc = CalcBuilder::new().arg_separator(ArgSeparator::Comma).build();
+        assert_eq!(list(&calc, "map([1, 3], \"max(x, 2)\")", &mut state), Ok("[2; 3]".to_string()));
+        let calc = CalcBuilder::new().brackets(Brackets::Curly).build();
+        assert_eq!(list(&calc, "map([1]; \"{x + 1} * 2\")", &mut state), Ok("[4]".to_string()));
+        let calc = CalcBuilder::new().aliases(Aliases::from_table("raiz = sqrt").unwrap()).build();
+        assert_eq!(list(&calc, "map([4]; \"raiz(x)\")", &mut state), Ok("[2]".to_string()));
+    }
+
+    #[test]
+    fn test_operators() {
+        let calc = Calc::new();
+        let ops = calc.operators();
+        assert_eq!(ops[0].symbol, "!");
+        assert!(ops.windows(2).all(|w| w[0].precedence >= w[1].precedence));
+        let pow = ops.iter().find(|o| o.symbol == "**").unwrap();
+        let mul = ops.iter().find(|o| o.symbol == "*").unwrap();
+        assert_eq!((pow.associativity, pow.operands), (Associativity::Right, 2));
+        assert_eq!(mul.associativity, Associativity::Left);
+        assert!(pow.precedence > mul.precedence);
+        let neg = ops.iter().find(|o| o.symbol == "-" && o.operands == 1).unwrap();
+        assert_eq!(neg.description, "unary minus");
+        assert!(ops.iter().any(|o| o.symbol == "+%"));
+        let xor = Calc::new().with_caret(Caret::Xor).operators();
+        assert_eq!(xor.iter().find(|o| o.symbol == "^").map(|o| o.description), Some("bitwise XOR, the same as @"));
+        let sandboxed = CalcBuilder::new().allow_operators(&["+", "-"]).build();
+        let symbols: Vec<&str> = sandboxed.operators().iter().map(|o| o.symbol).collect();
+        assert_eq!(symbols, vec!["-", "+", "-"]);
+    }
+
+    #[test]
+    fn test_output() {
+        let mut state = CalcState::new();
+        let calc = Calc::new().exact().with_extra_args(ExtraArgs::Warn);
+        let out = calc.eval_output("1/3", &mut state).unwrap();
+        assert_eq!(out.value, Value::Ratio(BigRational::new(1.into(), 3.into())));
+        assert!(out.is_exact);
+        assert_eq!(out.type_name, "ratio");
+        let out = calc.eval_output("[1; sqrt(2; 3)]", &mut state).unwrap();
+        assert!(!out.is_exact);
+        assert_eq!(out.type_name, "list");
+        assert_eq!(out.warnings, vec!["Function 'sqrt' uses 1 argument(s), 1 extra dropped".to_string()]);
+        let out = Calc::new().eval_output("roman(12) + \" \" + 1.5", &mut state).unwrap();
+        assert_eq!((out.type_name, out.is_exact), ("string", true));
+        assert_eq!(calc.eval_output("1/0", &mut state), Err(CalcError::DividedByZero("1".to_string())));
+    }
+
+    #[test]
+    fn test_builder() {
+        let mut state = CalcState::new();
+        state.enable_cache(10);
+        let calc = CalcBuilder::new().angle(Angle::Deg).precision(4).build();
+        assert_eq!(calc.eval("sin(90) + cos(180)", &mut state), Ok(Value::Float(0.0)));
+        assert_eq!(calc.eval("asin(1)", &mut state), Ok(Value::Int(BigInt::from(90))));
+        assert_eq!(calc.eval("atan(1)", &mut state), Ok(Value::Int(BigInt::from(45))));
+        assert_eq!(calc.eval("30d30m", &mut state), Ok(Value::Float(30.5)));
+        assert_eq!(calc.eval_rpn("90d sin", &mut state), Ok(Value::Float(1.0)));
+        let v = calc.eval("tan(30)", &mut state).unwrap();
+        assert_eq!(calc.format_value(&v), "0.5774");
+        // the same expression is cached separately for every angle unit
+        assert_eq!(Calc::new().eval("asin(1)", &mut state), Ok(Value::Float(std::f64::consts::FRAC_PI_2)));
+        assert_eq!(Calc::new().eval("sin(90d)", &mut state), Ok(Value::Float(1.0)));
+
+        let calc = CalcBuilder::new().strict(true).allow_functions(&["sqrt"]).allow_operators(&["+"]).build();
+        assert_eq!(calc.eval("sqrt(4) + 1", &mut state), Ok(Value::Int(BigInt::from(3))));
+        assert_eq!(calc.eval("sqrt(4; 1)", &mut state), Err(CalcError::FunctionTooManyArgs("sqrt".to_string(), 1)));
+        assert_eq!(calc.eval("sin(4)", &mut state), Err(CalcError::NotAllowed("sin".to_string())));
+        assert_eq!(calc.eval("2 * 2", &mut state), Err(CalcError::NotAllowed("*".to_string())));
+        let long = vec!["1"; 1000].join(" + ");
+        assert_eq!(calc.eval(&long, &mut state), Ok(Value::Int(BigInt::from(1000))));
+
+        let calc = CalcBuilder::new().exact(true).modulus(BigInt::from(7)).build();
+        assert_eq!(calc.clone().eval("1/3", &mut state), Ok(Value::Int(BigInt::from(5))));
+    }
+
+    #[test]
+    fn test_options() {
+        let mut state = CalcState::new();
+        state.enable_cache(10);
+        let calc = CalcBuilder::new().precision(2).build();
+        let deg = CalcOptions { angle: Some(Angle::Deg), ..Default::default() };
+        assert_eq!(calc.eval_with_options("asin(1)", &mut state, &deg), Ok(Value::Int(BigInt::from(90))));
+        assert_eq!(calc.eval("asin(1)", &mut state), Ok(Value::Float(std::f64::consts::FRAC_PI_2)));
+        let v = calc.eval("asin(1)", &mut state).unwrap();
+        assert_eq!(calc.format_value(&v), "1.57");
+
+        let hex = CalcOptions { format: Some(FormatOptions { radix: 16, ..Default::default() }), ..Default::default() };
+        let v = calc.eval_with_options("255", &mut state, &hex).unwrap();
+        assert_eq!(calc.with_options(&hex).format_value(&v), "0xff");
+        assert_eq!(calc.format_value(&v), "255");
+        let exact = CalcOptions { exact: Some(true), ..Default::default() };
+        let r = calc.eval_with_options("1/4", &mut state, &exact);
+        assert_eq!(r, Ok(Value::Ratio(BigRational::new(1.into(), 4.into()))));
+        assert_eq!(calc.eval("1/4", &mut state), Ok(Value::Float(0.25)));
+    }
+
+    #[test]
+    fn test_float_width() {
+        let mut state = CalcState::new();
+        state.enable_cache(10);
+        let calc = CalcBuilder::new().float_width(FloatWidth::F32).build();
+        assert_eq!(calc.eval("0.1", &mut state), Ok(Value::Float(f64::from(0.1f32))));
+        assert_eq!(calc.eval("0.1 + 0.2", &mut state), Ok(Value::Float(f64::from(0.1f32 + 0.2f32))));
+        assert_eq!(calc.eval("sqrt(2) / 3", &mut state), Ok(Value::Float(f64::from(2f32.sqrt() / 3f32))));
+        assert_eq!(calc.eval("1e39 * 1.0", &mut state), Ok(Value::Float(f64::INFINITY)));
+        let v = calc.eval("[1.1; 2+0.5i]", &mut state).unwrap();
+        let c = Value::Complex(num_complex::Complex::new(2.0, 0.5));
+        assert_eq!(v, Value::List(vec![Value::Float(f64::from(1.1f32)), c]));
+        // integers and fractions are exact
+        assert_eq!(calc.eval("2**40 + 1", &mut state), Ok(Value::Int(BigInt::from((1u64 << 40) + 1))));
+        assert_eq!(Calc::new().eval("0.1 + 0.2", &mut state), Ok(Value::Float(0.1 + 0.2)));
+        let opts = CalcOptions { float_width: Some(FloatWidth::F32), ..Default::default() };
+        assert_eq!(Calc::new().eval_with_options("0.1", &mut state, &opts), Ok(Value::Float(f64::from(0.1f32))));
+    }
+
+    #[test]
+    fn test_int_div() {
+        let mut state = CalcState::new();
+        state.enable_cache(10);
+        assert_eq!(Calc::new().eval("-7 // 2", &mut state), Ok(Value::Int(BigInt::from(-3))));
+        let calc = Calc::new().with_int_div(Rounding::Floor);
+        assert_eq!(calc.eval("-7 // 2", &mut state), Ok(Value::Int(BigInt::from(-4))));
+        assert_eq!(calc.eval("-7.5 // 2", &mut state), Ok(Value::Int(BigInt::from(-4))));
+        let calc = Calc::new().with_int_div(Rounding::HalfEven);
+        assert_eq!(calc.eval("5 // 2 + 7 // 2", &mut state), Ok(Value::Int(BigInt::from(6))));
+        assert_eq!(calc.eval_rpn("9 2 //", &mut state), Ok(Value::Int(BigInt::from(4))));
+    }
+
+    #[test]
+    fn test_rounding() {
+        let mut state = CalcState::new();
+        state.enable_cache(10);
+        assert_eq!(Calc::new().eval("round(2.5) + round(-0.5)", &mut state), Ok(Value::Int(BigInt::from(2))));
+        let calc = Calc::new().with_rounding(Rounding::HalfEven);
+        assert_eq!(calc.eval("round(2.5) + round(-0.5)", &mut state), Ok(Value::Int(BigInt::from(2))));
+        assert_eq!(calc.eval("round(3.5)", &mut state), Ok(Value::Int(BigInt::from(4))));
+        let v = calc.eval("1.125 * 2", &mut state).unwrap();
+        assert_eq!(calc.format_decimal(&v, 1), Ok("2.2".to_string()));
+        let calc = Calc::new().with_rounding(Rounding::HalfUp);
+        assert_eq!(calc.eval("round(-2.5)", &mut state), Ok(Value::Int(BigInt::from(-2))));
+        assert_eq!(calc.format_decimal(&v, 1), Ok("2.3".to_string()));
+    }
+
+    #[test]
+    fn test_exact() {
+        let mut state = CalcState::new();
+        state.enable_cache(10);
+        let r = |n: i64, d: i64| Value::Ratio(BigRational::new(n.into(), d.into()));
+        assert_eq!(Calc::new().eval("1/4", &mut state), Ok(Value::Float(0.25)));
+        let calc = Calc::new().exact();
+        assert_eq!(calc.eval("1/4", &mut state), Ok(r(1, 4)));
+        assert_eq!(calc.eval("1/3 + 1/6", &mut state), Ok(r(1, 2)));
+        assert_eq!(calc.eval("0.1 + 0.2", &mut state), Ok(r(3, 10)));
+        assert_eq!(calc.eval("0.1 + 0.2 == 0.3", &mut state), Ok(Value::Int(BigInt::from(1))));
+        assert_eq!(calc.eval("2 ** -2", &mut state), Ok(r(1, 4)));
+        assert_eq!(calc.eval("sqrt(4) + sqrt(1/4)", &mut state), Ok(r(5, 2)));
+        assert_eq!(calc.eval("avg(1; 2)", &mut state), Ok(r(3, 2)));
+        assert_eq!(calc.eval("10 + 5%", &mut state), Ok(r(21, 2)));
+        assert_eq!(calc.eval("sqrt(2) * sqrt(2)", &mut state).map(|v| v.as_f64().unwrap().round()), Ok(2.0));
+        assert_eq!(calc.eval("approx(1/4)", &mut state), Ok(Value::Float(0.25)));
+        assert_eq!(calc.eval_rpn("1 8 /", &mut state), Ok(r(1, 8)));
+    }
+
+    #[test]
+    fn test_modulus() {
+        let mut state = CalcState::new();
+        state.enable_cache(10);
+        let int = |i: i64| Value::Int(BigInt::from(i));
+        let calc = Calc::new().with_modulus(BigInt::from(7));
+        assert_eq!(Calc::new().eval("1/4", &mut state), Ok(Value::Float(0.25)));
+        assert_eq!(calc.eval("1/3", &mut state), Ok(int(5)));
+        assert_eq!(calc.eval("3**-1", &mut state), Ok(int(5)));
+        assert_eq!(calc.eval("2**10", &mut state), Ok(int(2)));
+        assert_eq!(calc.eval("2**1000000000000", &mut state), Ok(int(2)));
+        assert_eq!(calc.eval("3^-1", &mut state), Ok(int(5)));
+        assert_eq!(calc.eval("2^1000000000000", &mut state), Ok(int(2)));
+        assert_eq!(calc.eval("-3", &mut state), Ok(int(4)));
+        assert_eq!(calc.eval("5 * 6 + 1", &mut state), Ok(int(3)));
+        assert_eq!(calc.eval("10 == 3", &mut state), Ok(int(1)));
+        assert_eq!(calc.eval("1.5 * 3", &mut state), Ok(Value::Float(4.5)));
+        let msg = "7 has no inverse modulo 7".to_string();
+        assert_eq!(calc.eval("1/7", &mut state), Err(CalcError::InvalidAgrument("modinv".to_string(), msg)));
+        let calc = Calc::new().with_modulus(BigInt::from(0));
+        let r = calc.eval("1", &mut state);
+        assert_eq!(r, Err(CalcError::ArgumentOutOfRange("modulus".to_string(), "0".to_string(), "[1..]".to_string())));
+    }
+}
@@ -0,0 +1,89 @@
+//! Date and time helpers.
+//!
+//! Dates are strings in ISO 8601 form: `2024-03-01`, `2024-03-01 12:30:00`,
+//! or `2024-03-01T12:30:00+02:00`. Dates without time zone are in UTC.
+//! Timestamps are the number of seconds since the Unix epoch. Time zones
+//! are names from IANA time zone database: `Europe/Stockholm`.
+
+use chrono::{DateTime, Datelike, FixedOffset, NaiveDate, NaiveDateTime, SecondsFormat, Utc};
+use chrono_tz::Tz;
+use std::convert::TryFrom;
+
+const DATETIME_FORMATS: [&str; 4] =
+    ["%Y-%m-%d %H:%M:%S%.f", "%Y-%m-%dT%H:%M:%S%.f", "%Y-%m-%d %H:%M", "%Y-%m-%dT%H:%M"];
+
+/// Parses a date string. Returns `None` if the string is not a valid date
+pub(crate) fn parse(s: &str) -> Option<DateTime<FixedOffset>> {
+    let s = s.trim();
+    if let Ok(dt) = DateTime::parse_from_rfc3339(s) {
+        return Some(dt);
+    }
+    let utc = FixedOffset::east_opt(0)?;
+    for fmt in DATETIME_FORMATS.iter() {
+        if let Ok(dt) = NaiveDateTime::parse_from_str(s, fmt) {
+            return Some(dt.and_utc().with_timezone(&utc));
+        }
+    }
+    let dt = NaiveDate::parse_from_str(s, "%Y-%m-%d").ok()?.and_hms_opt(0, 0, 0)?;
+    Some(dt.and_utc().with_timezone(&utc))
+}
+
+/// Formats a timestamp as UTC date: `2024-03-01T12:30:00Z`. Fractional
+/// part of seconds is added only if it is not zero. Returns `None` if the
+/// timestamp is out of range
+pub(crate) fn format_utc(secs: i64, nanos: u32) -> Option<String> {
+    let dt = DateTime::<Utc>::from_timestamp(secs, nanos)?;
+    Some(dt.to_rfc3339_opts(SecondsFormat::AutoSi, true))
+}
+
+/// Converts a timestamp to UTC date. Returns `None` if the timestamp is
+/// out of range
+pub(crate) fn from_timestamp(secs: i64, nanos: u32) -> Option<DateTime<FixedOffset>> {
+    let dt = DateTime::<Utc>::from_timestamp(secs, nanos)?;
+    Some(dt.with_timezone(&FixedOffset::east_opt(0)?))
+}
+
+/// Formats a date as the local time of the time zone:
+/// `2024-03-01T13:00:00+01:00`. Returns `None` if the time zone is unknown
+pub(crate) fn in_timezone(dt: &DateTime<FixedOffset>, zone: &str) -> Option<String> {
+    let tz: Tz = zone.trim().parse().ok()?;
+    Some(dt.with_timezone(&tz).to_rfc3339_opts(SecondsFormat::AutoSi, false))
+}
+
+/// The number of days in the month(from `1` to `12`) of the year
+pub(crate) fn days_in_month(month: u32, year: i32) -> Option<u32> {
+    let first = NaiveDate::from_ymd_opt(year, month, 1)?;
+    let next = if month == 12 { NaiveDate::from_ymd_opt(year + 1, 1, 1)? } else { first.with_month(month + 1)? };
+    u32::try_from(next.signed_duration_since(first).num_days()).ok()
+}
+
+/// The current time as a timestamp
+pub(crate) fn now() -> i64 {
+    Utc::now().timestamp()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse() {
+        let ts = |s: &str| parse(s).map(|dt| dt.timestamp());
+        assert_eq!(ts("1970-01-01"), Some(0));
+        assert_eq!(ts("2000-01-01 00:00"), Some(946_684_800));
+        assert_eq!(ts("2000-01-01T01:00:00+01:00"), Some(946_684_800));
+        assert_eq!(ts("2000-01-01T00:00:00Z"), Some(946_684_800));
+        assert_eq!(ts(" 2000-02-29 12:00:00 "), Some(951_825_600));
+        assert_eq!(parse("2000-01-01 12:00:00.25").map(|dt| dt.timestamp_subsec_millis()), Some(250));
+        assert_eq!(ts("2001-02-29"), None);
+        assert_eq!(ts("yesterday"), None);
+    }
+
+    #[test]
+    fn test_format() {
+        assert_eq!(format_utc(0, 0), Some("1970-01-01T00:00:00Z".to_string()));
+        assert_eq!(format_utc(951_825_600, 500_000_000), Some("2000-02-29T12:00:00.500Z".to_string()));
+        assert_eq!(format_utc(-86_400, 0), Some("1969-12-31T00:00:00Z".to_string()));
+        assert_eq!(format_utc(i64::MAX, 0), None);
+    }
+}
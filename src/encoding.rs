@@ -0,0 +1,93 @@
+//! Text encodings of byte sequences: hexadecimal and Base64.
+//!
+//! Base64 uses the standard alphabet with padding for encoding. Decoding
+//! accepts both the standard and URL-safe alphabets, and padding is
+//! optional. Whitespace is ignored, so a text copied from an e-mail or
+//! a PEM file can be decoded as is.
+
+const B64_CHARS: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+
+/// Lowercase hexadecimal form of bytes, two digits per byte
+pub(crate) fn hex_encode(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+/// Base64 form of bytes
+pub(crate) fn b64_encode(bytes: &[u8]) -> String {
+    let mut s = String::with_capacity(bytes.len().div_ceil(3) * 4);
+    for chunk in bytes.chunks(3) {
+        let n = chunk.iter().enumerate().fold(0u32, |n, (i, b)| n | (u32::from(*b) << (16 - 8 * i)));
+        for i in 0..4 {
+            if i <= chunk.len() {
+                s.push(char::from(B64_CHARS[((n >> (18 - 6 * i)) & 0x3F) as usize]));
+            } else {
+                s.push('=');
+            }
+        }
+    }
+    s
+}
+
+fn b64_digit(c: u8) -> Option<u32> {
+    let d = match c {
+        b'A'..=b'Z' => c - b'A',
+        b'a'..=b'z' => c - b'a' + 26,
+        b'0'..=b'9' => c - b'0' + 52,
+        b'+' | b'-' => 62,
+        b'/' | b'_' => 63,
+        _ => return None,
+    };
+    Some(u32::from(d))
+}
+
+/// Decodes Base64 text. Returns `None` if the text contains invalid
+/// characters or its length is impossible for Base64
+pub(crate) fn b64_decode(text: &str) -> Option<Vec<u8>> {
+    let chars: Vec<u8> = text.bytes().filter(|c| !c.is_ascii_whitespace()).collect();
+    let data = match chars.iter().position(|c| *c == b'=') {
+        Some(pos) => {
+            // padding is allowed only at the end and completes the last group
+            if chars[pos..].iter().any(|c| *c != b'=') || !chars.len().is_multiple_of(4) || chars.len() - pos > 2 {
+                return None;
+            }
+            &chars[..pos]
+        }
+        None => &chars[..],
+    };
+    if data.len() % 4 == 1 {
+        return None;
+    }
+    let mut bytes = Vec::with_capacity(data.len() * 3 / 4);
+    for chunk in data.chunks(4) {
+        let mut n = 0u32;
+        for (i, c) in chunk.iter().enumerate() {
+            n |= b64_digit(*c)? << (18 - 6 * i);
+        }
+        for i in 0..chunk.len() - 1 {
+            bytes.push((n >> (16 - 8 * i)) as u8);
+        }
+    }
+    Some(bytes)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_b64() {
+        let cases = [("", ""), ("f", "Zg=="), ("fo", "Zm8="), ("foo", "Zm9v"), ("foobar", "Zm9vYmFy")];
+        for (text, enc) in cases.iter() {
+            assert_eq!(b64_encode(text.as_bytes()), *enc);
+            assert_eq!(b64_decode(enc), Some(text.as_bytes().to_vec()));
+        }
+        assert_eq!(b64_decode("Zm8"), Some(b"fo".to_vec()));
+        assert_eq!(b64_decode("Zm9v\nYmFy"), Some(b"foobar".to_vec()));
+        assert_eq!(b64_decode("-_8="), b64_decode("+/8="));
+        assert_eq!(b64_encode(&[0xFB, 0xFF]), "+/8=");
+        assert_eq!(b64_decode("Zm9vY"), None);
+        assert_eq!(b64_decode("Zm=8"), None);
+        assert_eq!(b64_decode("Zm9v!"), None);
+        assert_eq!(hex_encode(&[0, 0x1F, 0xAB]), "001fab");
+    }
+}
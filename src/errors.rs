@@ -3,14 +3,15 @@ use num_rational::BigRational;
 use std::fmt;
 
 #[derive(PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum CalcError {
     None,
 
     StrToFloat(String),
     StrToInt(String),
     StrToRatio(String),
-    IntToFloat(BigInt),
-    RatioToFloat(BigRational),
+    IntToFloat(#[cfg_attr(feature = "serde", serde(with = "crate::serialize::bigint_str"))] BigInt),
+    RatioToFloat(#[cfg_attr(feature = "serde", serde(with = "crate::serialize::ratio_str"))] BigRational),
     AngleToFloat(String),
     FloatToInt(f64),
     FloatToRatio(f64),
@@ -19,6 +20,13 @@ pub enum CalcError {
 
     NotForComplex(String),
     NotForNegativeInt(String),
+    NotForList(String),
+    ListToNumber(String),
+    NotForString(String),
+    StrToNumber(String),
+    NotForPoly(String),
+    NotForBool(String),
+    PolyToNumber(String),
 
     OnlyInt(String),
 
@@ -34,11 +42,28 @@ pub enum CalcError {
     FunctionUnfinished(String),
     FunctionNoArgs(String),
     FunctionNotEnoughArgs(String, usize),
+    FunctionTooManyArgs(String, usize),
     EmptyExpression,
     InsufficientOps,
     VarUndeclared(String),
+    CircularReference(String),
+    TooDeepReference(usize, String),
+    TooDeepNesting(usize),
+    NotAllowed(String),
+    InvalidAlias(String, String),
+    InvalidVariable(String, String),
+    ValueCountMismatch(usize, usize),
+    ExprTooLong(usize),
+    ExprTooDeep(usize),
+    TooManyTokens(usize),
+    NumberTooBig(u64),
+    AssertionFailed(String),
 
     NoRoots,
+    NoLimit(String),
+    OneSidedLimits(String, String),
+    SingularMatrix(String),
+    DimensionMismatch(String, String),
     ParseFailed(String),
 
     Unreachable,
@@ -61,6 +86,13 @@ impl fmt::Display for CalcError {
 
             CalcError::NotForComplex(s) => write!(f, "Function '{}' is not supported for complex numbers", s),
             CalcError::NotForNegativeInt(s) => write!(f, "Function '{}' is not supported for negative integers", s),
+            CalcError::NotForList(s) => write!(f, "Function '{}' is not supported for lists", s),
+            CalcError::ListToNumber(s) => write!(f, "Failed to convert list {} to number", s),
+            CalcError::NotForString(s) => write!(f, "Function '{}' is not supported for strings", s),
+            CalcError::StrToNumber(s) => write!(f, "Failed to convert string \"{}\" to number", s),
+            CalcError::NotForPoly(s) => write!(f, "Function '{}' is not supported for polynomials", s),
+            CalcError::NotForBool(s) => write!(f, "'{}' is not supported for booleans", s),
+            CalcError::PolyToNumber(s) => write!(f, "Failed to convert polynomial {} to number", s),
 
             CalcError::OnlyInt(s) => write!(f, "{} supports only integers", s),
 
@@ -80,12 +112,32 @@ impl fmt::Display for CalcError {
             CalcError::FunctionUnfinished(s) => write!(f, "Closing bracket for function '{}' not found", s),
             CalcError::FunctionNoArgs(s) => write!(f, "Function '{}' requires an argument", s),
             CalcError::FunctionNotEnoughArgs(s, i) => write!(f, "Function '{}' requires at least {} arguments", s, i),
+            CalcError::FunctionTooManyArgs(s, i) => write!(f, "Function '{}' accepts at most {} arguments", s, i),
             CalcError::EmptyExpression => write!(f, "Nothing to calculate"),
             CalcError::InsufficientOps => write!(f, "Too many numbers"),
 
             CalcError::NoRoots => write!(f, "Equation has no roots"),
+            CalcError::NoLimit(s) => write!(f, "Limit does not exist: {} diverges", s),
+            CalcError::OneSidedLimits(l, r) => {
+                write!(f, "Limit does not exist: {} from the left, {} from the right", l, r)
+            }
+            CalcError::SingularMatrix(s) => write!(f, "Function '{}' failed: matrix is singular", s),
+            CalcError::DimensionMismatch(s, d) => write!(f, "Invalid dimensions for function '{}': {}", s, d),
             CalcError::ParseFailed(s) => write!(f, "Failed to parse expression: {}", s),
             CalcError::VarUndeclared(s) => write!(f, "Variable '{}' not found", s),
+            CalcError::CircularReference(s) => write!(f, "Circular reference: {}", s),
+            CalcError::TooDeepReference(n, s) => write!(f, "References are nested deeper than {} levels: {}", n, s),
+            CalcError::TooDeepNesting(n) => write!(f, "Formulas are nested deeper than {} levels", n),
+            CalcError::NotAllowed(s) => write!(f, "'{}' is not allowed", s),
+            CalcError::InvalidAlias(a, s) => write!(f, "Cannot use '{}' as a name of function '{}'", a, s),
+            CalcError::InvalidVariable(n, s) => write!(f, "Cannot assign to '{}': {}", n, s),
+            CalcError::ValueCountMismatch(n, v) => write!(f, "Cannot assign {} values to {} variables", v, n),
+            CalcError::ExprTooLong(n) => write!(f, "Expression is longer than {} characters", n),
+            CalcError::ExprTooDeep(n) => write!(f, "Expression has more than {} nested brackets", n),
+            CalcError::TooManyTokens(n) => write!(f, "Expression has more than {} elements", n),
+            CalcError::NumberTooBig(n) => write!(f, "Number is bigger than {} bits", n),
+            CalcError::AssertionFailed(s) if s.is_empty() => write!(f, "Assertion failed"),
+            CalcError::AssertionFailed(s) => write!(f, "Assertion failed: {}", s),
 
             CalcError::Unreachable => write!(f, "unreachable"),
         }
@@ -109,6 +161,13 @@ impl fmt::Debug for CalcError {
 
             CalcError::NotForComplex(s) => write!(f, "Function '{}' is not supported for complex numbers", s),
             CalcError::NotForNegativeInt(s) => write!(f, "Function '{}' is not supported for negative integers", s),
+            CalcError::NotForList(s) => write!(f, "Function '{}' is not supported for lists", s),
+            CalcError::ListToNumber(s) => write!(f, "Failed to convert list {} to number", s),
+            CalcError::NotForString(s) => write!(f, "Function '{}' is not supported for strings", s),
+            CalcError::StrToNumber(s) => write!(f, "Failed to convert string \"{}\" to number", s),
+            CalcError::NotForPoly(s) => write!(f, "Function '{}' is not supported for polynomials", s),
+            CalcError::NotForBool(s) => write!(f, "'{}' is not supported for booleans", s),
+            CalcError::PolyToNumber(s) => write!(f, "Failed to convert polynomial {} to number", s),
 
             CalcError::OnlyInt(s) => write!(f, "{} supports only integers", s),
 
@@ -128,12 +187,32 @@ impl fmt::Debug for CalcError {
             CalcError::FunctionUnfinished(s) => write!(f, "Closing bracket for function '{}' not found", s),
             CalcError::FunctionNoArgs(s) => write!(f, "Function '{}' requires an argument", s),
             CalcError::FunctionNotEnoughArgs(s, i) => write!(f, "Function '{}' requires at least {} arguments", s, i),
+            CalcError::FunctionTooManyArgs(s, i) => write!(f, "Function '{}' accepts at most {} arguments", s, i),
             CalcError::EmptyExpression => write!(f, "Nothing to calculate"),
             CalcError::InsufficientOps => write!(f, "Too many numbers"),
 
             CalcError::NoRoots => write!(f, "Equation has no roots"),
+            CalcError::NoLimit(s) => write!(f, "Limit does not exist: {} diverges", s),
+            CalcError::OneSidedLimits(l, r) => {
+                write!(f, "Limit does not exist: {} from the left, {} from the right", l, r)
+            }
+            CalcError::SingularMatrix(s) => write!(f, "Function '{}' failed: matrix is singular", s),
+            CalcError::DimensionMismatch(s, d) => write!(f, "Invalid dimensions for function '{}': {}", s, d),
             CalcError::ParseFailed(s) => write!(f, "Failed to parse expression: {}", s),
             CalcError::VarUndeclared(s) => write!(f, "Variable '{}' not found", s),
+            CalcError::CircularReference(s) => write!(f, "Circular reference: {}", s),
+            CalcError::TooDeepReference(n, s) => write!(f, "References are nested deeper than {} levels: {}", n, s),
+            CalcError::TooDeepNesting(n) => write!(f, "Formulas are nested deeper than {} levels", n),
+            CalcError::NotAllowed(s) => write!(f, "'{}' is not allowed", s),
+            CalcError::InvalidAlias(a, s) => write!(f, "Cannot use '{}' as a name of function '{}'", a, s),
+            CalcError::InvalidVariable(n, s) => write!(f, "Cannot assign to '{}': {}", n, s),
+            CalcError::ValueCountMismatch(n, v) => write!(f, "Cannot assign {} values to {} variables", v, n),
+            CalcError::ExprTooLong(n) => write!(f, "Expression is longer than {} characters", n),
+            CalcError::ExprTooDeep(n) => write!(f, "Expression has more than {} nested brackets", n),
+            CalcError::TooManyTokens(n) => write!(f, "Expression has more than {} elements", n),
+            CalcError::NumberTooBig(n) => write!(f, "Number is bigger than {} bits", n),
+            CalcError::AssertionFailed(s) if s.is_empty() => write!(f, "Assertion failed"),
+            CalcError::AssertionFailed(s) => write!(f, "Assertion failed: {}", s),
 
             CalcError::Unreachable => write!(f, "unreachable"),
         }
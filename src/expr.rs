@@ -0,0 +1,245 @@
+use crate::calc::Calc;
+use crate::errors::*;
+use crate::parse::{compile_infix, CalcState, LAST_RESULT};
+use crate::resolver::{Resolved, Resolver};
+use crate::stack::{op_args, op_display_name, rpn_func_args, Entry, Stack};
+use crate::value::*;
+
+/// An element of an expression in postfix form
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Token {
+    /// A number
+    Value(Value),
+    /// A variable: its value is taken from the session or from a resolver
+    /// at evaluation time
+    Variable(String),
+    /// An operator and the number of its operands.
+    /// Operators that do not have their own symbol use special names:
+    /// `neg` - unary minus, `!` - factorial, `+%`, `-%`, `*%`, and `/%` - percentage operators
+    Operator(String, usize),
+    /// A function and the number of its arguments
+    Function(String, usize),
+}
+
+// expression tree built from postfix form
+pub(crate) enum Node {
+    Val(Value),
+    Var(String),
+    Op(String, Vec<Node>),
+    Func(String, Vec<Node>),
+}
+
+/// Parsed expression in postfix form. It can be evaluated or dumped
+/// to a string for debugging and interoperability with other evaluators
+#[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Expr {
+    pub(crate) entries: Vec<Entry>,
+}
+
+impl Expr {
+    pub(crate) fn new(entries: Vec<Entry>) -> Self {
+        Expr { entries }
+    }
+
+    /// Evaluates the expression and returns either result or error
+    pub fn eval(&self, state: &mut CalcState) -> CalcResult {
+        self.eval_in(&Calc::new(), state, None)
+    }
+
+    /// Evaluates the expression. Variables that are not defined in the
+    /// session are requested from `resolver`
+    pub fn eval_with(&self, state: &mut CalcState, resolver: &dyn Resolver) -> CalcResult {
+        self.eval_in(&Calc::new(), state, Some(resolver))
+    }
+
+    pub(crate) fn eval_in(&self, calc: &Calc, state: &mut CalcState, resolver: Option<&dyn Resolver>) -> CalcResult {
+        state.has_alt = false;
+        let mut visiting: Vec<String> = Vec::new();
+        let mut stk = new_stack(self.entries.clone(), calc);
+        let output = stk.calculate_with(&mut |name| lookup(name, calc, state, resolver, &mut visiting));
+        state.warnings = stk.warnings;
+        if let Ok(ref v) = output {
+            state.add_variable(LAST_RESULT, v.clone());
+            if stk.has_alt {
+                state.has_alt = true;
+                state.alt_result = stk.alt_result;
+            }
+        }
+        output
+    }
+
+    /// Returns the list of expression elements in postfix order
+    pub fn tokens(&self) -> Vec<Token> {
+        self.entries
+            .iter()
+            .filter_map(|e| match e {
+                Entry::Val(v) => Some(Token::Value(v.clone())),
+                Entry::Var(name) => Some(Token::Variable(name.to_string())),
+                Entry::Op(op, ..) => Some(Token::Operator(op_display_name(op).to_string(), op_args(op))),
+                Entry::Func(name, args) => Some(Token::Function(name.to_string(), *args)),
+                Entry::OpenB => None,
+            })
+            .collect()
+    }
+
+    /// Returns names of all variables the expression uses, in the order of
+    /// their first appearance. Names are lowercase, constants are not included
+    pub fn variables(&self) -> Vec<String> {
+        let mut names: Vec<String> = Vec::new();
+        for e in &self.entries {
+            if let Entry::Var(name) = e {
+                if !names.contains(name) {
+                    names.push(name.to_string());
+                }
+            }
+        }
+        names
+    }
+
+    /// Returns names of all functions the expression calls, in the order
+    /// of their first call
+    pub fn functions(&self) -> Vec<String> {
+        let mut names: Vec<String> = Vec::new();
+        for e in &self.entries {
+            if let Entry::Func(name, ..) = e {
+                if !names.contains(name) {
+                    names.push(name.to_string());
+                }
+            }
+        }
+        names
+    }
+
+    /// Returns the expression in postfix form: `(2+3)*4` -> `2 3 + 4 *`.
+    /// If a function takes the same number of arguments as it does in RPN
+    /// mode, only the function name is printed. Otherwise, the number of arguments
+    /// is added after slash: `max(1;2;3)` -> `1 2 3 max/3`
+    pub fn to_postfix(&self) -> String {
+        let parts: Vec<String> = self
+            .tokens()
+            .iter()
+            .map(|t| match t {
+                Token::Value(v) => literal(v),
+                Token::Variable(name) => name.to_string(),
+                Token::Operator(op, ..) => op.to_string(),
+                Token::Function(name, args) => {
+                    if *args == rpn_func_args(name) {
+                        name.to_string()
+                    } else {
+                        format!("{}/{}", name, args)
+                    }
+                }
+            })
+            .collect();
+        parts.join(" ")
+    }
+
+    /// Returns the expression as S-expression: `(2+3)*4` -> `(* (+ 2 3) 4)`
+    pub fn to_sexpr(&self) -> Result<String, CalcError> {
+        let node = self.tree()?;
+        Ok(node_to_sexpr(&node))
+    }
+
+    // converts postfix form to a tree. Returns the same errors as evaluation
+    // does for the expressions with incorrect number of operands
+    pub(crate) fn tree(&self) -> Result<Node, CalcError> {
+        let mut nodes: Vec<Node> = Vec::new();
+        for e in &self.entries {
+            match e {
+                Entry::Val(v) => nodes.push(Node::Val(v.clone())),
+                Entry::Var(name) => nodes.push(Node::Var(name.to_string())),
+                Entry::Op(op, ..) => {
+                    let cnt = op_args(op);
+                    if nodes.len() < cnt {
+                        return Err(CalcError::TooManyOps);
+                    }
+                    let args = nodes.split_off(nodes.len() - cnt);
+                    nodes.push(Node::Op(op.to_string(), args));
+                }
+                Entry::Func(name, cnt) => {
+                    if nodes.len() < *cnt {
+                        return Err(CalcError::FunctionUnfinished(name.to_string()));
+                    }
+                    let args = nodes.split_off(nodes.len() - cnt);
+                    nodes.push(Node::Func(name.to_string(), args));
+                }
+                Entry::OpenB => return Err(CalcError::Unreachable),
+            }
+        }
+        match nodes.len() {
+            0 => Err(CalcError::EmptyExpression),
+            1 => Ok(nodes.pop().unwrap()),
+            _ => Err(CalcError::InsufficientOps),
+        }
+    }
+}
+
+// returns the value of a variable: session variables go first, then the
+// resolver. `visiting` is the chain of formulas being evaluated, it is
+// used to detect circular references and too long chains of formulas
+fn lookup(
+    name: &str,
+    calc: &Calc,
+    state: &CalcState,
+    resolver: Option<&dyn Resolver>,
+    visiting: &mut Vec<String>,
+) -> CalcResult {
+    if let Some(v) = state.variable(name) {
+        return Ok(v);
+    }
+    let formula = match resolver.and_then(|r| r.resolve(name)) {
+        None => return Err(CalcError::VarUndeclared(name.to_string())),
+        Some(Resolved::Value(v)) => return Ok(v),
+        Some(Resolved::Formula(f)) => f,
+    };
+    if visiting.iter().any(|n| n == name) {
+        let mut chain = visiting.clone();
+        chain.push(name.to_string());
+        return Err(CalcError::CircularReference(chain.join(" -> ")));
+    }
+    let max_nesting = calc.parse_limits().max_nesting;
+    if visiting.len() >= max_nesting {
+        let mut chain = visiting.clone();
+        chain.push(name.to_string());
+        return Err(CalcError::TooDeepReference(max_nesting, chain.join(" -> ")));
+    }
+    let expr = compile_infix(&formula, calc)?;
+    visiting.push(name.to_string());
+    let mut stk = new_stack(expr.entries, calc);
+    let res = stk.calculate_with(&mut |n| lookup(n, calc, state, resolver, visiting));
+    visiting.pop();
+    res
+}
+
+// creates a stack to evaluate an expression with the calculator options
+pub(crate) fn new_stack(entries: Vec<Entry>, calc: &Calc) -> Stack {
+    let mut stk = Stack::from_postfix(entries);
+    stk.calc = calc.clone();
+    stk
+}
+
+// a value as it is written in an expression: strings are quoted
+fn literal(v: &Value) -> String {
+    match v {
+        Value::Str(s) => format!("\"{}\"", s),
+        _ => v.to_string(),
+    }
+}
+
+fn node_to_sexpr(node: &Node) -> String {
+    let (name, args) = match node {
+        Node::Val(v) => return literal(v),
+        Node::Var(name) => return name.to_string(),
+        Node::Op(op, args) => (op_display_name(op), args),
+        Node::Func(name, args) => (name.as_str(), args),
+    };
+    let mut s = format!("({}", name);
+    for a in args {
+        s += " ";
+        s += &node_to_sexpr(a);
+    }
+    s += ")";
+    s
+}
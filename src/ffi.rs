@@ -0,0 +1,185 @@
+//! C interface (enabled with feature `ffi`). See `include/rcalc.h`.
+//!
+//! All strings are UTF-8 and NUL-terminated. Strings returned by the library
+//! are owned by the caller and must be released with `rclc_string_free`.
+
+use std::ffi::{CStr, CString};
+use std::os::raw::{c_char, c_int};
+use std::ptr;
+
+use crate::parse::{eval, CalcState};
+
+/// Calculator session: keeps variables and the last result between evaluations
+pub struct RclcSession {
+    state: CalcState,
+}
+
+const RCLC_OK: c_int = 0;
+const RCLC_ERROR: c_int = -1;
+
+// converts a Rust string to a C string. NUL characters cannot be inside
+// a C string, so they are replaced with spaces
+fn to_c_string(s: &str) -> *mut c_char {
+    let s = s.replace('\0', " ");
+    match CString::new(s) {
+        Ok(cs) => cs.into_raw(),
+        Err(..) => ptr::null_mut(),
+    }
+}
+
+unsafe fn set_out(p: *mut *mut c_char, s: &str) {
+    if !p.is_null() {
+        *p = to_c_string(s);
+    }
+}
+
+unsafe fn eval_with_state(
+    state: &mut CalcState,
+    expr: *const c_char,
+    out: *mut *mut c_char,
+    err: *mut *mut c_char,
+) -> c_int {
+    if !out.is_null() {
+        *out = ptr::null_mut();
+    }
+    if !err.is_null() {
+        *err = ptr::null_mut();
+    }
+    if expr.is_null() {
+        set_out(err, "expression is NULL");
+        return RCLC_ERROR;
+    }
+    let expr = match CStr::from_ptr(expr).to_str() {
+        Ok(s) => s,
+        Err(..) => {
+            set_out(err, "expression is not a valid UTF-8 string");
+            return RCLC_ERROR;
+        }
+    };
+    match eval(expr, state) {
+        Ok(v) => {
+            set_out(out, &v.to_string());
+            RCLC_OK
+        }
+        Err(e) => {
+            set_out(err, &e.to_string());
+            RCLC_ERROR
+        }
+    }
+}
+
+/// Evaluates an expression in a new empty session. Returns `0` on success
+/// and fills `out` with the result. On failure returns `-1` and fills `err`
+/// with the error message. `out` and `err` can be NULL if the caller is not
+/// interested in them.
+///
+/// # Safety
+///
+/// `expr` must be NULL or a valid NUL-terminated string. `out` and `err` must be
+/// NULL or valid pointers to write a string pointer to.
+#[no_mangle]
+pub unsafe extern "C" fn rclc_eval(expr: *const c_char, out: *mut *mut c_char, err: *mut *mut c_char) -> c_int {
+    let mut state = CalcState::new();
+    eval_with_state(&mut state, expr, out, err)
+}
+
+/// Creates a new session. The session must be destroyed with `rclc_session_free`
+#[no_mangle]
+pub extern "C" fn rclc_session_new() -> *mut RclcSession {
+    Box::into_raw(Box::new(RclcSession { state: CalcState::new() }))
+}
+
+/// Destroys a session created with `rclc_session_new`
+///
+/// # Safety
+///
+/// `session` must be NULL or a pointer returned by `rclc_session_new` that
+/// has not been freed yet.
+#[no_mangle]
+pub unsafe extern "C" fn rclc_session_free(session: *mut RclcSession) {
+    if !session.is_null() {
+        drop(Box::from_raw(session));
+    }
+}
+
+/// Evaluates an expression inside a session: the expression can use the result
+/// of the previous evaluation(`ans`). Return value, `out`, and `err` are the
+/// same as for `rclc_eval`
+///
+/// # Safety
+///
+/// `session` must be a valid pointer returned by `rclc_session_new`. Other
+/// arguments must satisfy the requirements of `rclc_eval`.
+#[no_mangle]
+pub unsafe extern "C" fn rclc_session_eval(
+    session: *mut RclcSession,
+    expr: *const c_char,
+    out: *mut *mut c_char,
+    err: *mut *mut c_char,
+) -> c_int {
+    if session.is_null() {
+        set_out(err, "session is NULL");
+        return RCLC_ERROR;
+    }
+    eval_with_state(&mut (*session).state, expr, out, err)
+}
+
+/// Releases a string returned by the library
+///
+/// # Safety
+///
+/// `s` must be NULL or a string returned by the library that has not been freed yet.
+#[no_mangle]
+pub unsafe extern "C" fn rclc_string_free(s: *mut c_char) {
+    if !s.is_null() {
+        drop(CString::from_raw(s));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    unsafe fn take(s: *mut c_char) -> String {
+        let res = CStr::from_ptr(s).to_str().unwrap().to_string();
+        rclc_string_free(s);
+        res
+    }
+
+    #[test]
+    fn test_eval() {
+        unsafe {
+            let expr = CString::new("2+3*4").unwrap();
+            let mut out: *mut c_char = ptr::null_mut();
+            let mut err: *mut c_char = ptr::null_mut();
+            assert_eq!(rclc_eval(expr.as_ptr(), &mut out, &mut err), RCLC_OK);
+            assert!(err.is_null());
+            assert_eq!(take(out), "14");
+
+            let expr = CString::new("2+").unwrap();
+            assert_eq!(rclc_eval(expr.as_ptr(), &mut out, &mut err), RCLC_ERROR);
+            assert!(out.is_null());
+            assert_eq!(take(err), "Too many operators");
+
+            let bad = [0xffu8, 0];
+            assert_eq!(rclc_eval(bad.as_ptr() as *const c_char, &mut out, &mut err), RCLC_ERROR);
+            assert_eq!(take(err), "expression is not a valid UTF-8 string");
+            assert_eq!(rclc_eval(ptr::null(), ptr::null_mut(), ptr::null_mut()), RCLC_ERROR);
+        }
+    }
+
+    #[test]
+    fn test_session() {
+        unsafe {
+            let session = rclc_session_new();
+            let mut out: *mut c_char = ptr::null_mut();
+            let expr = CString::new("10/4").unwrap();
+            assert_eq!(rclc_session_eval(session, expr.as_ptr(), &mut out, ptr::null_mut()), RCLC_OK);
+            assert_eq!(take(out), "2.5");
+            let expr = CString::new("ans*2").unwrap();
+            assert_eq!(rclc_session_eval(session, expr.as_ptr(), &mut out, ptr::null_mut()), RCLC_OK);
+            assert_eq!(take(out), "5");
+            rclc_session_free(session);
+        }
+    }
+}
@@ -0,0 +1,100 @@
+//! Transcendental functions of real numbers.
+//!
+//! By default the functions call the methods of `f64`, that use the math
+//! library of the platform. Their results may differ in the last bits
+//! between operating systems and CPUs. With feature `libm` the functions
+//! are calculated by the `libm` crate, a pure Rust port of MUSL math
+//! library, and the results are the same everywhere. Arithmetic and square
+//! root are correctly rounded by IEEE 754, so they are not listed here.
+//!
+//! Complex numbers use `num-complex` and are not affected by the feature.
+
+macro_rules! unary_fn {
+    ($id:ident, $libm:ident) => {
+        #[cfg(feature = "libm")]
+        #[inline]
+        pub(crate) fn $id(x: f64) -> f64 {
+            libm::$libm(x)
+        }
+        #[cfg(not(feature = "libm"))]
+        #[inline]
+        pub(crate) fn $id(x: f64) -> f64 {
+            x.$id()
+        }
+    };
+}
+
+unary_fn!(sin, sin);
+unary_fn!(cos, cos);
+unary_fn!(tan, tan);
+unary_fn!(asin, asin);
+unary_fn!(acos, acos);
+unary_fn!(atan, atan);
+unary_fn!(sinh, sinh);
+unary_fn!(cosh, cosh);
+unary_fn!(tanh, tanh);
+unary_fn!(asinh, asinh);
+unary_fn!(acosh, acosh);
+unary_fn!(atanh, atanh);
+unary_fn!(exp, exp);
+unary_fn!(exp2, exp2);
+unary_fn!(exp_m1, expm1);
+unary_fn!(ln, log);
+unary_fn!(ln_1p, log1p);
+unary_fn!(log2, log2);
+unary_fn!(log10, log10);
+unary_fn!(cbrt, cbrt);
+
+/// Four quadrant arctangent of `y/x`
+#[cfg(feature = "libm")]
+#[inline]
+pub(crate) fn atan2(y: f64, x: f64) -> f64 {
+    libm::atan2(y, x)
+}
+#[cfg(not(feature = "libm"))]
+#[inline]
+pub(crate) fn atan2(y: f64, x: f64) -> f64 {
+    y.atan2(x)
+}
+
+/// Raises `x` to a floating point power
+#[cfg(feature = "libm")]
+#[inline]
+pub(crate) fn powf(x: f64, y: f64) -> f64 {
+    libm::pow(x, y)
+}
+#[cfg(not(feature = "libm"))]
+#[inline]
+pub(crate) fn powf(x: f64, y: f64) -> f64 {
+    x.powf(y)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::f64::consts;
+
+    #[test]
+    fn test_fmath() {
+        let close = |a: f64, b: f64| (a - b).abs() <= 4.0 * f64::EPSILON * b.abs().max(1.0);
+        for x in [-2.5f64, -0.3, 0.0, 0.7, 1.0, 3.25].iter() {
+            let x = *x;
+            assert!(close(sin(x), x.sin()));
+            assert!(close(cos(x), x.cos()));
+            assert!(close(atan(x), x.atan()));
+            assert!(close(sinh(x), x.sinh()));
+            assert!(close(exp(x), x.exp()));
+            assert!(close(exp_m1(x), x.exp_m1()));
+            assert!(close(cbrt(x), x.cbrt()));
+            assert!(close(atan2(x, 1.5), x.atan2(1.5)));
+            assert!(close(powf(1.5, x), 1.5f64.powf(x)));
+        }
+        assert_eq!(exp(0.0), 1.0);
+        assert_eq!(ln(1.0), 0.0);
+        assert_eq!(log2(1024.0), 10.0);
+        assert_eq!(powf(2.0, 10.0), 1024.0);
+        assert_eq!(cbrt(27.0), 3.0);
+        assert_eq!(atan2(1.0, 1.0), consts::FRAC_PI_4);
+        assert!(ln(-1.0).is_nan());
+    }
+}
@@ -0,0 +1,412 @@
+//! Format specifications of function `format`.
+//!
+//! A specification is similar to the one of Python and Rust format strings
+//! without braces: `[[fill]align][sign][0][width][grouping][.precision][type]`
+//!
+//! * `align` - `<` left, `>` right, `^` center. Numbers are aligned to the
+//!   right by default, other values - to the left. `fill` is a padding
+//!   character, space by default
+//! * `sign` - `+` shows the plus sign of non-negative numbers, space
+//!   adds a space before them
+//! * `0` pads a number with zeroes after its sign
+//! * `grouping` - `,` or `_` separates thousands of the integer part
+//!   (groups of four digits for binary, octal, and hexadecimal numbers)
+//! * `precision` - the number of digits after the decimal point, or the
+//!   maximum number of characters of a string
+//! * `type` - `d` integer, `f` fixed point(6 digits by default), `e` and
+//!   `E` scientific notation, `x`, `X`, `o`, `b` hexadecimal, octal, and
+//!   binary integers, `%` percentage, `s` string. Without type a value is
+//!   shown as it is, or in fixed point if the precision is set
+//!
+//! E.g, `format(1234.5; ">+12,.2f")` is `"   +1,234.50"`
+//!
+//! Function `fmt` uses a template: a text with one placeholder that is
+//! either a printf-style conversion `%[flags][width][.precision]type` or
+//! a brace one `{:spec}`: `fmt(x; "%.3e")`, `fmt(x; "x = {:>10.2}")`.
+//! `%%`, `{{`, and `}}` are literal characters
+
+use num_bigint::BigInt;
+use num_traits::Signed;
+
+use crate::errors::*;
+use crate::value::*;
+
+// the maximum width and precision: bigger values are likely typos that
+// generate huge strings
+const MAX_SPEC_NUMBER: usize = 1_000;
+// the default precision of `f`, `e`, and `%` types
+const DEFAULT_PRECISION: usize = 6;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Align {
+    Left,
+    Right,
+    Center,
+}
+
+/// Parsed format specification
+#[derive(Clone, Debug, PartialEq)]
+pub(crate) struct Spec {
+    fill: char,
+    align: Option<Align>,
+    sign: Option<char>,
+    zero: bool,
+    width: usize,
+    group: Option<char>,
+    precision: Option<usize>,
+    kind: Option<char>,
+}
+
+fn invalid_spec(spec: &str) -> CalcError {
+    CalcError::InvalidAgrument("format".to_string(), format!("invalid format specification \"{}\"", spec))
+}
+
+fn align_of(c: char) -> Option<Align> {
+    match c {
+        '<' => Some(Align::Left),
+        '>' => Some(Align::Right),
+        '^' => Some(Align::Center),
+        _ => None,
+    }
+}
+
+// reads a decimal number at position `pos` and moves the position after it
+fn spec_number(chars: &[char], pos: &mut usize) -> Result<Option<usize>, CalcError> {
+    let start = *pos;
+    while *pos < chars.len() && chars[*pos].is_ascii_digit() {
+        *pos += 1;
+    }
+    if start == *pos {
+        return Ok(None);
+    }
+    let s: String = chars[start..*pos].iter().collect();
+    match s.parse::<usize>() {
+        Ok(n) if n <= MAX_SPEC_NUMBER => Ok(Some(n)),
+        _ => Err(CalcError::ArgumentOutOfRange("format".to_string(), s, format!("[0..{}]", MAX_SPEC_NUMBER))),
+    }
+}
+
+/// Parses a format specification
+pub(crate) fn parse_spec(spec: &str) -> Result<Spec, CalcError> {
+    let chars: Vec<char> = spec.chars().collect();
+    let mut res =
+        Spec { fill: ' ', align: None, sign: None, zero: false, width: 0, group: None, precision: None, kind: None };
+    let mut pos = 0;
+    if chars.len() > 1 && align_of(chars[1]).is_some() {
+        res.fill = chars[0];
+        res.align = align_of(chars[1]);
+        pos = 2;
+    } else if !chars.is_empty() && align_of(chars[0]).is_some() {
+        res.align = align_of(chars[0]);
+        pos = 1;
+    }
+    if pos < chars.len() && (chars[pos] == '+' || chars[pos] == ' ' || chars[pos] == '-') {
+        res.sign = Some(chars[pos]).filter(|c| *c != '-');
+        pos += 1;
+    }
+    if pos < chars.len() && chars[pos] == '0' {
+        res.zero = true;
+        pos += 1;
+    }
+    res.width = spec_number(&chars, &mut pos)?.unwrap_or(0);
+    if pos < chars.len() && (chars[pos] == ',' || chars[pos] == '_') {
+        res.group = Some(chars[pos]);
+        pos += 1;
+    }
+    if pos < chars.len() && chars[pos] == '.' {
+        pos += 1;
+        res.precision = Some(spec_number(&chars, &mut pos)?.ok_or_else(|| invalid_spec(spec))?);
+    }
+    if pos < chars.len() && "dfFeExXobs%".contains(chars[pos]) {
+        res.kind = Some(chars[pos]);
+        pos += 1;
+    }
+    if pos != chars.len() {
+        return Err(invalid_spec(spec));
+    }
+    Ok(res)
+}
+
+fn invalid_template(template: &str) -> CalcError {
+    CalcError::InvalidAgrument("fmt".to_string(), format!("invalid template \"{}\"", template))
+}
+
+// converts a printf conversion at the start of `chars` into a specification.
+// Returns the specification and the length of the conversion
+fn printf_spec(chars: &[char], template: &str) -> Result<(Spec, usize), CalcError> {
+    let mut spec = parse_spec("")?;
+    let mut pos = 1;
+    while pos < chars.len() {
+        match chars[pos] {
+            '-' => spec.align = Some(Align::Left),
+            '+' => spec.sign = Some('+'),
+            ' ' => spec.sign = spec.sign.or(Some(' ')),
+            '0' => spec.zero = true,
+            ',' => spec.group = Some(','),
+            _ => break,
+        }
+        pos += 1;
+    }
+    // printf aligns all values to the right by default
+    if spec.align.is_none() && !spec.zero {
+        spec.align = Some(Align::Right);
+    }
+    spec.width = spec_number(chars, &mut pos)?.unwrap_or(0);
+    if pos < chars.len() && chars[pos] == '.' {
+        pos += 1;
+        spec.precision = Some(spec_number(chars, &mut pos)?.unwrap_or(0));
+    }
+    spec.kind = match chars.get(pos) {
+        Some('d' | 'i' | 'u') => Some('d'),
+        Some('F') => Some('f'),
+        Some('g' | 'G') => None,
+        Some(c @ ('f' | 'e' | 'E' | 'x' | 'X' | 'o' | 'b' | 's')) => Some(*c),
+        _ => return Err(invalid_template(template)),
+    };
+    Ok((spec, pos + 1))
+}
+
+// converts a brace placeholder `{}` or `{:spec}` at the start of `chars`
+// into a specification. Returns the specification and the length of the
+// placeholder
+fn brace_spec(chars: &[char], template: &str) -> Result<(Spec, usize), CalcError> {
+    let end = match chars.iter().position(|c| *c == '}') {
+        Some(end) => end,
+        None => return Err(invalid_template(template)),
+    };
+    let inner: String = chars[1..end].iter().collect();
+    let spec = match inner.strip_prefix(':') {
+        Some(spec) => parse_spec(spec)?,
+        None if inner.is_empty() => parse_spec("")?,
+        None => return Err(invalid_template(template)),
+    };
+    Ok((spec, end + 1))
+}
+
+/// Formats a value using a template with one printf-style or brace
+/// placeholder
+pub(crate) fn format_template(v: &Value, template: &str, mode: Rounding) -> Result<String, CalcError> {
+    let chars: Vec<char> = template.chars().collect();
+    let mut res = String::new();
+    let mut found = false;
+    let mut i = 0;
+    while i < chars.len() {
+        let c = chars[i];
+        if (c == '%' || c == '{' || c == '}') && chars.get(i + 1) == Some(&c) {
+            res.push(c);
+            i += 2;
+            continue;
+        }
+        match c {
+            '%' | '{' if !found => {
+                let (spec, len) =
+                    if c == '%' { printf_spec(&chars[i..], template)? } else { brace_spec(&chars[i..], template)? };
+                res += &format_value(v, &spec, mode)?;
+                found = true;
+                i += len;
+            }
+            '%' | '{' | '}' => return Err(invalid_template(template)),
+            _ => {
+                res.push(c);
+                i += 1;
+            }
+        }
+    }
+    if !found {
+        return Err(invalid_template(template));
+    }
+    Ok(res)
+}
+
+// inserts the separator between every `size` digits of the integer part
+pub(crate) fn group_digits(digits: &str, sep: char, size: usize) -> String {
+    let int_len = digits.find(|c: char| !c.is_ascii_alphanumeric()).unwrap_or(digits.len());
+    let (int_part, rest) = digits.split_at(int_len);
+    let mut res = String::new();
+    for (i, c) in int_part.chars().enumerate() {
+        if i != 0 && (int_len - i) % size == 0 {
+            res.push(sep);
+        }
+        res.push(c);
+    }
+    res + rest
+}
+
+// scientific notation with at least two digits of the exponent: `1.50e+03`
+fn exp_notation(f: f64, precision: usize, upper: bool) -> String {
+    if !f.is_finite() {
+        return if f.is_nan() { "NaN".to_string() } else { "inf".to_string() };
+    }
+    let s = format!("{:.*e}", precision, f);
+    let (mantissa, exp) = s.split_at(s.find('e').unwrap_or(s.len()));
+    let exp: i32 = exp.trim_start_matches('e').parse().unwrap_or(0);
+    let e = if upper { 'E' } else { 'e' };
+    format!("{}{}{}{:02}", mantissa, e, if exp < 0 { '-' } else { '+' }, exp.abs())
+}
+
+fn int_of(v: &Value) -> Result<&BigInt, CalcError> {
+    match v {
+        Value::Int(i) => Ok(i),
+        _ => Err(CalcError::OnlyInt("format".to_string())),
+    }
+}
+
+// splits the text of a number into its sign and its digits
+fn split_sign(s: String) -> (bool, String) {
+    match s.strip_prefix('-') {
+        Some(digits) => (true, digits.to_string()),
+        None => (false, s),
+    }
+}
+
+/// Formats a value using the specification. Fixed point numbers are
+/// rounded with the given rounding mode
+pub(crate) fn format_value(v: &Value, spec: &Spec, mode: Rounding) -> Result<String, CalcError> {
+    match (v, spec.kind) {
+        (Value::Str(s), None | Some('s')) => {
+            let s: String = match spec.precision {
+                Some(p) => s.chars().take(p).collect(),
+                None => s.clone(),
+            };
+            return Ok(pad(String::new(), s, spec, Align::Left));
+        }
+        (Value::Complex(..) | Value::List(..) | Value::Poly(..), None | Some('s')) => {
+            let s = match spec.precision {
+                Some(p) => v.to_decimal(p, mode)?,
+                None => v.to_string(),
+            };
+            return Ok(pad(String::new(), s, spec, Align::Left));
+        }
+        (Value::Str(..), _) => return Err(CalcError::NotForString("format".to_string())),
+        (Value::List(..), _) => return Err(CalcError::NotForList("format".to_string())),
+        (Value::Poly(..), _) => return Err(CalcError::NotForPoly("format".to_string())),
+        (Value::Complex(..), _) => return Err(CalcError::NotForComplex("format".to_string())),
+        _ => {}
+    }
+
+    let (neg, digits, group_size) = match spec.kind {
+        Some('d') => {
+            let i = int_of(v)?;
+            (i.is_negative(), i.abs().to_string(), 3)
+        }
+        Some(k @ ('x' | 'X' | 'o' | 'b')) => {
+            let i = int_of(v)?;
+            let radix = match k {
+                'o' => 8,
+                'b' => 2,
+                _ => 16,
+            };
+            let s = i.abs().to_str_radix(radix);
+            (i.is_negative(), if k == 'X' { s.to_uppercase() } else { s }, 4)
+        }
+        Some(k @ ('e' | 'E')) => {
+            let f = v.as_f64()?;
+            let s = exp_notation(f.abs(), spec.precision.unwrap_or(DEFAULT_PRECISION), k == 'E');
+            // the mantissa has a single digit before the decimal point
+            (f.is_sign_negative() && f != 0.0, s, 0)
+        }
+        Some('%') => {
+            let p = v.clone().multiply(Value::Int(BigInt::from(100)))?;
+            let (neg, s) = split_sign(p.to_decimal(spec.precision.unwrap_or(DEFAULT_PRECISION), mode)?);
+            (neg, s + "%", 3)
+        }
+        Some(..) => {
+            let (neg, s) = split_sign(v.to_decimal(spec.precision.unwrap_or(DEFAULT_PRECISION), mode)?);
+            (neg, s, 3)
+        }
+        None => {
+            let s = match spec.precision {
+                Some(p) => v.to_decimal(p, mode)?,
+                None => v.to_string(),
+            };
+            let (neg, s) = split_sign(s);
+            (neg, s, 3)
+        }
+    };
+    let digits = match spec.group {
+        Some(sep) if group_size != 0 => group_digits(&digits, sep, group_size),
+        _ => digits,
+    };
+    let sign = if neg { "-".to_string() } else { spec.sign.map(String::from).unwrap_or_default() };
+    Ok(pad(sign, digits, spec, Align::Right))
+}
+
+// pads the text up to the width of the specification. Zero padding goes
+// between the sign and the digits
+fn pad(sign: String, body: String, spec: &Spec, default_align: Align) -> String {
+    let len = sign.chars().count() + body.chars().count();
+    if len >= spec.width {
+        return sign + &body;
+    }
+    let n = spec.width - len;
+    if spec.zero && spec.align.is_none() {
+        return sign + &"0".repeat(n) + &body;
+    }
+    let fill = |k: usize| spec.fill.to_string().repeat(k);
+    match spec.align.unwrap_or(default_align) {
+        Align::Left => sign + &body + &fill(n),
+        Align::Right => fill(n) + &sign + &body,
+        Align::Center => fill(n / 2) + &sign + &body + &fill(n - n / 2),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_template() {
+        let f = |v: Value, t: &str| format_template(&v, t, Rounding::HalfAwayFromZero);
+        let s = |s: &str| Ok(s.to_string());
+        assert_eq!(f(Value::Float(1234.5), "%.3e"), s("1.234e+03"));
+        assert_eq!(f(Value::Float(2.5), "{:>10.2}"), s("      2.50"));
+        assert_eq!(f(Value::Float(2.5), "x = {} m"), s("x = 2.5 m"));
+        assert_eq!(f(Value::Float(-2.5), "[%08.3f]"), s("[-002.500]"));
+        assert_eq!(f(Value::Int(BigInt::from(42)), "%-5d|"), s("42   |"));
+        assert_eq!(f(Value::Int(BigInt::from(255)), "%+x"), s("+ff"));
+        assert_eq!(f(Value::Float(0.5), "%g%%"), s("0.5%"));
+        assert_eq!(f(Value::Float(0.5), "{{{:.1f}}}"), s("{0.5}"));
+        assert_eq!(f(Value::Str("ab".to_string()), "<%5s>"), s("<   ab>"));
+
+        let err = |t: &str| Err(invalid_template(t));
+        assert_eq!(f(Value::Float(1.0), "no placeholder"), err("no placeholder"));
+        assert_eq!(f(Value::Float(1.0), "%f and %f"), err("%f and %f"));
+        assert_eq!(f(Value::Float(1.0), "%q"), err("%q"));
+        assert_eq!(f(Value::Float(1.0), "{:.2"), err("{:.2"));
+        assert_eq!(f(Value::Float(1.0), "{0}"), err("{0}"));
+        assert_eq!(f(Value::Float(1.0), "{} }"), err("{} }"));
+    }
+
+    #[test]
+    fn test_format() {
+        let f = |v: Value, spec: &str| format_value(&v, &parse_spec(spec)?, Rounding::HalfAwayFromZero);
+        let int = |i: i64| Value::Int(BigInt::from(i));
+        let s = |s: &str| Ok(s.to_string());
+        assert_eq!(f(Value::Float(1234.5), ">+12,.2f"), s("   +1,234.50"));
+        assert_eq!(f(Value::Float(1.23456), ".2"), s("1.23"));
+        assert_eq!(f(Value::Float(-2.5), "08.2f"), s("-0002.50"));
+        assert_eq!(f(Value::Float(0.125), ".1%"), s("12.5%"));
+        assert_eq!(f(Value::Float(1500.0), ".2e"), s("1.50e+03"));
+        assert_eq!(f(Value::Float(-0.00015), "E"), s("-1.500000E-04"));
+        assert_eq!(f(int(255), "x"), s("ff"));
+        assert_eq!(f(int(-255), "#>6X"), s("###-FF"));
+        assert_eq!(f(int(0xDEAD_BEEF), "_x"), s("dead_beef"));
+        assert_eq!(f(int(5), "b"), s("101"));
+        assert_eq!(f(int(1_234_567), ","), s("1,234,567"));
+        assert_eq!(f(int(42), "^6"), s("  42  "));
+        assert_eq!(f(int(42), " d"), s(" 42"));
+        assert_eq!(f(Value::Str("hello".to_string()), "*^9.3"), s("***hel***"));
+        assert_eq!(f(Value::Str("ab".to_string()), "4"), s("ab  "));
+        assert_eq!(f(Value::List(vec![int(1), Value::Float(0.5)]), ".1"), s("[1.0; 0.5]"));
+
+        assert_eq!(f(Value::Float(1.5), "d"), Err(CalcError::OnlyInt("format".to_string())));
+        assert_eq!(f(Value::Str("a".to_string()), "f"), Err(CalcError::NotForString("format".to_string())));
+        assert_eq!(f(int(1), "5.2q"), Err(invalid_spec("5.2q")));
+        assert_eq!(f(int(1), "."), Err(invalid_spec(".")));
+        let r = f(int(1), "5000");
+        assert_eq!(
+            r,
+            Err(CalcError::ArgumentOutOfRange("format".to_string(), "5000".to_string(), "[0..1000]".to_string()))
+        );
+    }
+}
@@ -0,0 +1,900 @@
+//! Functions that take an expression as a string: `solve("x**2 = 2"; "x")`.
+//!
+//! The expression is compiled with the calculator options and evaluated for
+//! different values of its variables. Other variables are taken from the
+//! session, so `solve("a*x = 1"; "x")` uses the current value of `a`.
+
+use num_bigint::BigInt;
+use num_traits::Zero;
+
+use crate::calc::Calc;
+use crate::errors::*;
+use crate::expr::new_stack;
+use crate::fmath;
+use crate::matrix;
+use crate::parse::compile_infix;
+use crate::poly;
+use crate::series;
+use crate::stack::Entry;
+use crate::value::*;
+
+/// Returns the value of a variable that is not the formula variable
+pub(crate) type Lookup<'a> = dyn FnMut(&str) -> CalcResult + 'a;
+
+// the numeric solver looks for sign changes of a function between the
+// points `0` and `±10**k` for `k` from SCAN_MIN_EXP to SCAN_MAX_EXP
+const SCAN_MIN_EXP: f64 = -4.0;
+const SCAN_MAX_EXP: f64 = 4.0;
+const SCAN_STEPS: usize = 800;
+const BISECTION_ITERATIONS: usize = 200;
+// a sign change is a root only if the function is close to zero at the
+// found point: it filters out poles, like `0` for `1/x`
+const ROOT_EPS: f64 = 1e-9;
+// numeric roots(and other float results) closer than this value(relative
+// to their magnitude) are the same value
+const SAME_VALUE_EPS: f64 = 1e-9;
+// the maximum order of a Taylor series
+const MAX_TAYLOR_ORDER: usize = 100;
+// `limit` evaluates an expression at the distances `LIMIT_STEP`,
+// `LIMIT_STEP/2`, `LIMIT_STEP/4`, and so on(relative to the magnitude of
+// the point) from the point on both sides
+const LIMIT_STEP: f64 = 0.125;
+const LIMIT_POINTS: usize = 16;
+// the extrapolated limit is accepted if its error estimate is less than
+// this value(relative to the limit magnitude)
+const LIMIT_EPS: f64 = 1e-8;
+// the maximum number of points `sample` returns
+const MAX_SAMPLES: usize = 100_000;
+// `nderiv` starts with the step `NDERIV_STEP`(relative to the magnitude of
+// the point if it is not zero) and divides it by `NDERIV_SHRINK` up to `NDERIV_STEPS` times
+const NDERIV_STEP: f64 = 0.1;
+const NDERIV_SHRINK: f64 = 1.4;
+const NDERIV_STEPS: usize = 10;
+// Brent's minimization: the golden section ratio, the relative accuracy of
+// the argument(about the square root of the float precision, because a
+// function is flat near its minimum), and the maximum number of iterations
+const GOLDEN_SECTION: f64 = 0.381_966_011_250_105_1;
+const MINIMIZE_TOL: f64 = 3e-8;
+const MINIMIZE_ITERATIONS: usize = 500;
+// the maximum number of steps `rk4` makes
+const MAX_RK4_STEPS: usize = 100_000;
+// the maximum number of times `iterate` applies an expression
+const MAX_ITERATIONS: usize = 1_000_000;
+// variables of expressions passed to `map`, `filter`, and `reduce`: the
+// current item and the accumulated value
+const ITEM_VAR: &str = "x";
+const ACC_VAR: &str = "acc";
+
+/// An expression of one or more variables
+pub(crate) struct Formula {
+    entries: Vec<Entry>,
+    vars: Vec<String>,
+    calc: Calc,
+}
+
+impl Formula {
+    pub(crate) fn new(text: &str, var: &str, calc: &Calc) -> Result<Formula, CalcError> {
+        Formula::with_vars(text, &[var.to_string()], calc)
+    }
+
+    pub(crate) fn with_vars(text: &str, vars: &[String], calc: &Calc) -> Result<Formula, CalcError> {
+        let expr = compile_infix(text, calc)?;
+        let vars = vars.iter().map(|v| v.to_lowercase()).collect();
+        Ok(Formula { entries: expr.entries, vars, calc: calc.clone() })
+    }
+
+    /// Value of the expression for the given value of the variable
+    pub(crate) fn eval(&self, x: &Value, lookup: &mut Lookup) -> CalcResult {
+        self.eval_at(std::slice::from_ref(x), lookup)
+    }
+
+    /// Value of the expression for the given values of all variables
+    pub(crate) fn eval_at(&self, xs: &[Value], lookup: &mut Lookup) -> CalcResult {
+        let mut stk = new_stack(self.entries.clone(), &self.calc);
+        stk.calculate_with(&mut |name| {
+            let name_low = name.to_lowercase();
+            match self.vars.iter().position(|v| *v == name_low) {
+                Some(i) => Ok(xs[i].clone()),
+                None => lookup(name),
+            }
+        })
+    }
+
+    /// Series of the expression for the series `x` of the variable. See
+    /// module `series`
+    pub(crate) fn series(&self, x: &[Value], lookup: &mut Lookup) -> Result<series::Series, CalcError> {
+        series::eval(&self.entries, &self.vars[0], x, &self.calc, lookup)
+    }
+
+    // real value of the expression, `None` if it is complex
+    fn eval_f64(&self, x: f64, lookup: &mut Lookup) -> Result<Option<f64>, CalcError> {
+        match self.eval(&Value::Float(x), lookup)? {
+            Value::Complex(c) if c.im != 0.0 => Ok(None),
+            v => Ok(Some(v.as_f64()?)),
+        }
+    }
+}
+
+/// Returns the text of a string argument
+pub(crate) fn str_arg(v: &Value, name: &str) -> Result<String, CalcError> {
+    match v {
+        Value::Str(s) => Ok(s.clone()),
+        _ => Err(CalcError::InvalidAgrument(name.to_string(), format!("{} is not a string", v))),
+    }
+}
+
+/// Returns the name of a variable: a string with an identifier
+pub(crate) fn var_arg(v: &Value, name: &str) -> Result<String, CalcError> {
+    let s = str_arg(v, name)?;
+    let s = s.trim();
+    let mut chars = s.chars();
+    let valid = match chars.next() {
+        Some(c) => (c.is_alphabetic() || c == '_') && chars.all(|c| c.is_alphanumeric() || c == '_'),
+        None => false,
+    };
+    if !valid {
+        return Err(CalcError::InvalidAgrument(name.to_string(), format!("invalid variable name \"{}\"", s)));
+    }
+    Ok(s.to_string())
+}
+
+fn list_arg<'a>(v: &'a Value, name: &str) -> Result<&'a [Value], CalcError> {
+    match v {
+        Value::List(items) => Ok(items),
+        _ => Err(CalcError::InvalidAgrument(name.to_string(), format!("{} is not a list", v))),
+    }
+}
+
+/// Values of an expression of `x` for every item of a list:
+/// `map([1; 2; 3]; "x**2")` is `[1; 4; 9]`
+pub(crate) fn map(xs: &Value, expr: &Value, calc: &Calc, lookup: &mut Lookup) -> CalcResult {
+    let items = list_arg(xs, "map")?;
+    let f = Formula::new(&str_arg(expr, "map")?, ITEM_VAR, calc)?;
+    let res = items.iter().map(|x| f.eval(x, lookup)).collect::<Result<Vec<Value>, CalcError>>()?;
+    Ok(Value::List(res))
+}
+
+/// Items of a list for which a condition of `x` is true(not zero):
+/// `filter([1; 2; 3; 4]; "x % 2 == 0")` is `[2; 4]`
+pub(crate) fn filter(xs: &Value, cond: &Value, calc: &Calc, lookup: &mut Lookup) -> CalcResult {
+    let items = list_arg(xs, "filter")?;
+    let f = Formula::new(&str_arg(cond, "filter")?, ITEM_VAR, calc)?;
+    let mut res = Vec::new();
+    for x in items {
+        if !f.eval(x, lookup)?.is_zero() {
+            res.push(x.clone());
+        }
+    }
+    Ok(Value::List(res))
+}
+
+/// Combines the items of a list into one value. The expression calculates
+/// the new value of `acc` from its current value and an item `x`, and the
+/// first value of `acc` is `init`: `reduce([1; 2; 3]; "acc * x"; 1)` is `6`
+pub(crate) fn reduce(xs: &Value, expr: &Value, init: &Value, calc: &Calc, lookup: &mut Lookup) -> CalcResult {
+    let items = list_arg(xs, "reduce")?;
+    let vars = [ACC_VAR.to_string(), ITEM_VAR.to_string()];
+    let f = Formula::with_vars(&str_arg(expr, "reduce")?, &vars, calc)?;
+    let mut acc = init.clone();
+    for x in items {
+        acc = f.eval_at(&[acc, x.clone()], lookup)?;
+    }
+    Ok(acc)
+}
+
+/// Applies an expression to its own result `n` times: `x = expr` starting
+/// from `x = init`. `iterate("r*x*(1 - x)"; "x"; 0.5; 100)` is the 100th
+/// value of the logistic map
+pub(crate) fn iterate(
+    expr: &Value,
+    var: &Value,
+    init: &Value,
+    n: &Value,
+    calc: &Calc,
+    lookup: &mut Lookup,
+) -> CalcResult {
+    let text = str_arg(expr, "iterate")?;
+    let var = var_arg(var, "iterate")?;
+    let n = bounded_int_arg(n, "iterate", MAX_ITERATIONS)?;
+    let f = Formula::new(&text, &var, calc)?;
+    let mut x = init.clone();
+    for _ in 0..n {
+        x = f.eval(&x, lookup)?;
+    }
+    Ok(x)
+}
+
+// splits an equation into its left and right sides. Comparison operators
+// `==`, `!=`, `<=`, and `>=` are not equal signs
+fn split_equation<'a>(eq: &'a str, name: &str) -> Result<(&'a str, Option<&'a str>), CalcError> {
+    let bytes = eq.as_bytes();
+    let mut pos = None;
+    for (i, b) in bytes.iter().enumerate() {
+        if *b != b'=' {
+            continue;
+        }
+        let prev = if i > 0 { bytes[i - 1] } else { b' ' };
+        let next = bytes.get(i + 1).copied().unwrap_or(b' ');
+        if b"=!<>".contains(&prev) || next == b'=' {
+            continue;
+        }
+        if pos.is_some() {
+            return Err(CalcError::InvalidAgrument(name.to_string(), "more than one '=' in equation".to_string()));
+        }
+        pos = Some(i);
+    }
+    match pos {
+        Some(i) => Ok((&eq[..i], Some(&eq[i + 1..]))),
+        None => Ok((eq, None)),
+    }
+}
+
+// converts an equation `lhs = rhs` to the expression `(lhs)-(rhs)` that
+// is zero for its solutions
+fn equation_arg(eq: &Value, name: &str) -> Result<String, CalcError> {
+    let text = str_arg(eq, name)?;
+    match split_equation(&text, name)? {
+        (lhs, Some(rhs)) => Ok(format!("({})-({})", lhs, rhs)),
+        (lhs, None) => Ok(lhs.to_string()),
+    }
+}
+
+/// Solves an equation `lhs = rhs`(or `expr = 0` if it has no `=`) for the
+/// variable `var`. Polynomial equations of degree up to 4 are solved
+/// exactly, other equations are solved numerically. Returns a list of
+/// distinct solutions
+pub(crate) fn solve(eq: &Value, var: &Value, calc: &Calc, lookup: &mut Lookup) -> CalcResult {
+    let text = equation_arg(eq, "solve")?;
+    let var = var_arg(var, "solve")?;
+    let f = Formula::new(&text, &var, calc)?;
+    // the variable is replaced with the polynomial `x`: if the expression
+    // uses only `+`, `-`, `*`, division by a number, and integer powers,
+    // the result is a polynomial
+    let coefs = match f.eval(&poly_x(), lookup) {
+        Ok(Value::Poly(c)) => Some(c),
+        Ok(v @ Value::Int(..)) | Ok(v @ Value::Ratio(..)) | Ok(v @ Value::Float(..)) => Some(vec![v]),
+        Err(e @ CalcError::TooDeepNesting(..)) => return Err(e),
+        _ => None,
+    };
+    let roots = match coefs {
+        Some(c) if c.len() == 1 && c[0].is_zero() => {
+            return Err(CalcError::InvalidAgrument(
+                "solve".to_string(),
+                format!("every value of {} is a solution", var),
+            ));
+        }
+        Some(c) if c.len() <= 5 => poly::roots(&c)?,
+        _ => numeric_roots(&f, lookup)?,
+    };
+    let mut res: Vec<Value> = Vec::with_capacity(roots.len());
+    for r in roots {
+        if !res.iter().any(|v| same_value(v, &r)) {
+            res.push(r);
+        }
+    }
+    Ok(Value::List(res))
+}
+
+// the polynomial `x`
+fn poly_x() -> Value {
+    Value::Poly(vec![Value::Int(BigInt::from(1)), Value::Int(BigInt::zero())])
+}
+
+fn same_value(a: &Value, b: &Value) -> bool {
+    if a == b {
+        return true;
+    }
+    match (a.clone().into_raw_complex(), b.clone().into_raw_complex()) {
+        (Ok(a), Ok(b)) => (a - b).norm() <= SAME_VALUE_EPS * a.norm().max(1.0),
+        _ => false,
+    }
+}
+
+// a string or a non-empty list of strings
+fn str_list<T, F>(v: &Value, name: &str, what: &str, conv: F) -> Result<Vec<T>, CalcError>
+where
+    F: Fn(&Value, &str) -> Result<T, CalcError>,
+{
+    match v {
+        Value::Str(..) => Ok(vec![conv(v, name)?]),
+        Value::List(items) if !items.is_empty() => items.iter().map(|item| conv(item, name)).collect(),
+        _ => Err(CalcError::InvalidAgrument(name.to_string(), format!("{} is not a list of {}", v, what))),
+    }
+}
+
+/// Solves a system of linear equations `solvesys(["x + y = 3"; "x - y =
+/// 1"]; ["x"; "y"])`. The result is a list of pairs `[variable; value]`
+/// in the order of the variables. Integer and fractional coefficients are
+/// processed exactly
+pub(crate) fn solvesys(eqs: &Value, vars: &Value, calc: &Calc, lookup: &mut Lookup) -> CalcResult {
+    let eqs = str_list(eqs, "solvesys", "equations", equation_arg)?;
+    let vars = str_list(vars, "solvesys", "variables", var_arg)?;
+    for (i, v) in vars.iter().enumerate() {
+        if vars[..i].iter().any(|prev| prev.to_lowercase() == v.to_lowercase()) {
+            return Err(CalcError::InvalidAgrument("solvesys".to_string(), format!("duplicate variable \"{}\"", v)));
+        }
+    }
+    let n = vars.len();
+    let mut m = Vec::with_capacity(eqs.len());
+    for (i, text) in eqs.iter().enumerate() {
+        let f = Formula::with_vars(text, &vars, calc)?;
+        m.push(linear_coefficients(&f, i + 1, lookup)?);
+    }
+    // the column of right sides is eliminated as well: it gets a pivot only
+    // if the equations contradict each other
+    let e = matrix::eliminate(&mut m, n + 1)?;
+    if e.pivots.contains(&n) {
+        return Err(CalcError::NoRoots);
+    }
+    if e.pivots.len() < n {
+        return Err(CalcError::InvalidAgrument(
+            "solvesys".to_string(),
+            "the system has infinitely many solutions".to_string(),
+        ));
+    }
+    let res = vars.into_iter().zip(m).map(|(v, row)| Value::List(vec![Value::Str(v), row[n].clone()]));
+    Ok(Value::List(res.collect()))
+}
+
+// converts a linear equation `a1*x1 + ... + an*xn + c = 0` to the matrix
+// row `[a1; ...; an; -c]`. `idx` is the number of the equation for error
+// messages
+fn linear_coefficients(f: &Formula, idx: usize, lookup: &mut Lookup) -> Result<Vec<Value>, CalcError> {
+    let not_linear = || CalcError::InvalidAgrument("solvesys".to_string(), format!("equation {} is not linear", idx));
+    let n = f.vars.len();
+    let zero = Value::Int(BigInt::zero());
+    let mut xs = vec![zero.clone(); n];
+    let c = matrix::element(&f.eval_at(&xs, lookup)?, "solvesys")?;
+    let mut row = Vec::with_capacity(n + 1);
+    // the coefficient of a variable is found by replacing the variable with
+    // the polynomial `x` and all other variables with zeroes
+    for i in 0..n {
+        xs[i] = poly_x();
+        let a = match f.eval_at(&xs, lookup) {
+            Ok(Value::Poly(p)) if p.len() == 2 => p[0].clone(),
+            Ok(Value::Poly(p)) if p.len() > 2 => return Err(not_linear()),
+            Ok(_) => zero.clone(),
+            Err(_) => return Err(not_linear()),
+        };
+        xs[i] = zero.clone();
+        row.push(matrix::element(&a, "solvesys")?);
+    }
+    // products of variables(e.g, `x*y`) are zeroes if any variable is zero,
+    // so the equation is checked at a point where all variables are not
+    let point: Vec<Value> = (0..n).map(|i| Value::Int(BigInt::from(i + 2))).collect();
+    let mut expected = c.clone();
+    for (a, x) in row.iter().zip(point.iter()) {
+        expected = expected.addition(a.clone().multiply(x.clone())?)?;
+    }
+    match f.eval_at(&point, lookup) {
+        Ok(v) if same_value(&v, &expected) => {}
+        _ => return Err(not_linear()),
+    }
+    row.push(c.negate()?);
+    Ok(row)
+}
+
+/// Taylor series of an expression at the point `around` up to the given
+/// order. The result is a polynomial of the variable:
+/// `taylor("exp(x)"; "x"; 0; 2)` is `0.5*x**2+x+1`
+pub(crate) fn taylor(
+    expr: &Value,
+    var: &Value,
+    around: &Value,
+    order: &Value,
+    calc: &Calc,
+    lookup: &mut Lookup,
+) -> CalcResult {
+    let text = str_arg(expr, "taylor")?;
+    let var = var_arg(var, "taylor")?;
+    let a = matrix::element(around, "taylor")?;
+    let order = bounded_int_arg(order, "taylor", MAX_TAYLOR_ORDER)?;
+    let f = Formula::new(&text, &var, calc)?;
+    let mut c = f.series(&series::variable(a.clone(), order + 1), lookup)?;
+    // the series is in powers of `x - a`, and the polynomial is in powers
+    // of `x` starting from the highest one
+    c.reverse();
+    let c = c.iter().map(|v| matrix::element(v, "taylor")).collect::<Result<Vec<Value>, CalcError>>()?;
+    let x = Value::Poly(poly::normalize(vec![Value::Int(BigInt::from(1)), a.negate()?]));
+    poly::eval(&c, &x)
+}
+
+/// Values of an expression at `n` evenly spaced points from `a` to `b`
+/// as a list of pairs `[x; y]` of float numbers for plotting. `y` is NaN
+/// at points where the expression fails or is complex. If the expression
+/// has a pole between two points, a pair with NaN `y` is inserted between
+/// them, so a plotter does not connect the branches of the function
+pub(crate) fn sample(
+    expr: &Value,
+    var: &Value,
+    a: &Value,
+    b: &Value,
+    n: &Value,
+    calc: &Calc,
+    lookup: &mut Lookup,
+) -> CalcResult {
+    let text = str_arg(expr, "sample")?;
+    let var = var_arg(var, "sample")?;
+    let a = matrix::element(a, "sample")?.as_f64()?;
+    let b = matrix::element(b, "sample")?.as_f64()?;
+    let n = bounded_int_arg(n, "sample", MAX_SAMPLES)?;
+    if n < 2 {
+        return Err(CalcError::ArgumentOutOfRange(
+            "sample".to_string(),
+            n.to_string(),
+            format!("[2..{}]", MAX_SAMPLES),
+        ));
+    }
+    let f = Formula::new(&text, &var, calc)?;
+    let mut eval = |x: f64| match f.eval_f64(x, lookup) {
+        Ok(Some(y)) => Ok(y),
+        Ok(None) => Ok(f64::NAN),
+        // errors at some points(e.g, division by zero) are gaps in the
+        // plot, while an unknown variable is an error everywhere
+        Err(e @ CalcError::VarUndeclared(..))
+        | Err(e @ CalcError::NotAllowed(..))
+        | Err(e @ CalcError::TooDeepNesting(..)) => Err(e),
+        Err(_) => Ok(f64::NAN),
+    };
+    let pair = |x: f64, y: f64| Value::List(vec![Value::Float(x), Value::Float(y)]);
+    let step = (b - a) / (n - 1) as f64;
+    let mut res = Vec::with_capacity(n);
+    let mut prev: Option<(f64, f64)> = None;
+    for i in 0..n {
+        let x = if i == n - 1 { b } else { a + step * i as f64 };
+        let y = eval(x)?;
+        if let Some((px, py)) = prev {
+            // a continuous function that changes its sign has smaller
+            // values between the points, and a pole has bigger ones
+            if py.is_finite() && y.is_finite() && py.signum() != y.signum() {
+                let mx = px + (x - px) / 2.0;
+                let my = eval(mx)?;
+                if !my.is_finite() || my.abs() > py.abs().max(y.abs()) {
+                    res.push(pair(mx, f64::NAN));
+                }
+            }
+        }
+        res.push(pair(x, y));
+        prev = Some((x, y));
+    }
+    Ok(Value::List(res))
+}
+
+/// Derivative of an expression at the point `x0`. Central differences
+/// with decreasing steps are extrapolated to the zero step(Ridders'
+/// method), so the step is selected automatically and the result is
+/// usually accurate to 10 or more digits
+pub(crate) fn nderiv(expr: &Value, var: &Value, x0: &Value, calc: &Calc, lookup: &mut Lookup) -> CalcResult {
+    let text = str_arg(expr, "nderiv")?;
+    let var = var_arg(var, "nderiv")?;
+    let x0 = matrix::element(x0, "nderiv")?.as_f64()?;
+    let f = Formula::new(&text, &var, calc)?;
+    let mut eval = |x: f64| match f.eval_f64(x, lookup)? {
+        Some(y) => Ok(y),
+        None => Err(CalcError::NotForComplex("nderiv".to_string())),
+    };
+    // the step is relative to the point, so the points stay in the domain
+    // of functions like `ln(x)` near zero
+    let mut h = if x0 == 0.0 { NDERIV_STEP } else { NDERIV_STEP * x0.abs() };
+    let shrink2 = NDERIV_SHRINK * NDERIV_SHRINK;
+    // `table[j]` is the difference extrapolated `j` times
+    let mut prev: Vec<f64> = Vec::new();
+    let (mut best, mut best_err) = (f64::NAN, f64::INFINITY);
+    for i in 0..NDERIV_STEPS {
+        let mut table = vec![(eval(x0 + h)? - eval(x0 - h)?) / (2.0 * h)];
+        let mut fac = shrink2;
+        for j in 1..=i {
+            let t = (table[j - 1] * fac - prev[j - 1]) / (fac - 1.0);
+            fac *= shrink2;
+            let err = (t - table[j - 1]).abs().max((t - prev[j - 1]).abs());
+            if err <= best_err {
+                best = t;
+                best_err = err;
+            }
+            table.push(t);
+        }
+        if i == 0 {
+            best = table[0];
+        } else if (table[i] - prev[i - 1]).abs() >= 2.0 * best_err {
+            // higher orders get worse when rounding errors dominate
+            break;
+        }
+        prev = table;
+        h /= NDERIV_SHRINK;
+    }
+    Ok(Value::Float(best))
+}
+
+/// Minimum(or maximum if `max` is true) of an expression on the interval
+/// `[a; b]` as a pair `[x; value]`. Brent's method(golden section search
+/// with parabolic interpolation) finds a local extremum inside the
+/// interval, and it is compared with the values at the ends
+pub(crate) fn minimize(
+    expr: &Value,
+    var: &Value,
+    a: &Value,
+    b: &Value,
+    max: bool,
+    calc: &Calc,
+    lookup: &mut Lookup,
+) -> CalcResult {
+    let name = if max { "maximize" } else { "minimize" };
+    let text = str_arg(expr, name)?;
+    let var = var_arg(var, name)?;
+    let a = matrix::element(a, name)?.as_f64()?;
+    let b = matrix::element(b, name)?.as_f64()?;
+    let f = Formula::new(&text, &var, calc)?;
+    let sign = if max { -1.0 } else { 1.0 };
+    let mut eval = |x: f64| match f.eval_f64(x, lookup)? {
+        Some(y) => Ok(sign * y),
+        None => Err(CalcError::NotForComplex(name.to_string())),
+    };
+    let (lo, hi) = if a <= b { (a, b) } else { (b, a) };
+    let mut best = brent(&mut eval, lo, hi)?;
+    for x in [lo, hi].iter() {
+        let y = eval(*x)?;
+        if y < best.1 {
+            best = (*x, y);
+        }
+    }
+    Ok(Value::List(vec![Value::Float(best.0), Value::Float(sign * best.1)]))
+}
+
+// Brent's method for a local minimum of a function on `[a; b]`
+fn brent<F>(f: &mut F, mut a: f64, mut b: f64) -> Result<(f64, f64), CalcError>
+where
+    F: FnMut(f64) -> Result<f64, CalcError>,
+{
+    let mut x = a + GOLDEN_SECTION * (b - a);
+    let (mut w, mut v) = (x, x);
+    let mut fx = f(x)?;
+    let (mut fw, mut fv) = (fx, fx);
+    // `d` is the last step, `e` is the step before it
+    let (mut d, mut e) = (0.0f64, 0.0f64);
+    for _ in 0..MINIMIZE_ITERATIONS {
+        let xm = a + (b - a) / 2.0;
+        let tol1 = MINIMIZE_TOL * x.abs() + f64::EPSILON;
+        let tol2 = 2.0 * tol1;
+        if (x - xm).abs() <= tol2 - (b - a) / 2.0 {
+            break;
+        }
+        let mut golden = true;
+        if e.abs() > tol1 {
+            // a parabola through `x`, `w`, and `v`
+            let r = (x - w) * (fx - fv);
+            let q = (x - v) * (fx - fw);
+            let mut p = (x - v) * q - (x - w) * r;
+            let mut q = 2.0 * (q - r);
+            if q > 0.0 {
+                p = -p;
+            }
+            q = q.abs();
+            // the parabola step is used only if it is inside the interval
+            // and smaller than half of the step before the last one
+            if p.abs() < (q * e / 2.0).abs() && p > q * (a - x) && p < q * (b - x) {
+                e = d;
+                d = p / q;
+                let u = x + d;
+                if u - a < tol2 || b - u < tol2 {
+                    d = tol1.copysign(xm - x);
+                }
+                golden = false;
+            }
+        }
+        if golden {
+            e = if x >= xm { a - x } else { b - x };
+            d = GOLDEN_SECTION * e;
+        }
+        let u = if d.abs() >= tol1 { x + d } else { x + tol1.copysign(d) };
+        let fu = f(u)?;
+        if fu <= fx {
+            if u >= x {
+                a = x;
+            } else {
+                b = x;
+            }
+            v = w;
+            fv = fw;
+            w = x;
+            fw = fx;
+            x = u;
+            fx = fu;
+        } else {
+            if u < x {
+                a = u;
+            } else {
+                b = u;
+            }
+            if fu <= fw || w == x {
+                v = w;
+                fv = fw;
+                w = u;
+                fw = fu;
+            } else if fu <= fv || v == x || v == w {
+                v = u;
+                fv = fu;
+            }
+        }
+    }
+    Ok((x, fx))
+}
+
+/// Integrates the ordinary differential equation `y' = f(t, y)` with the
+/// classic 4th order Runge-Kutta method. `vars` is the name of `y`(the
+/// independent variable is `t` then) or a list of the names of `t` and
+/// `y`. The result is a list of pairs `[t; y]` starting with `[t0; y0]`,
+/// one pair for every step
+pub(crate) fn rk4(args: &[Value], calc: &Calc, lookup: &mut Lookup) -> CalcResult {
+    let text = str_arg(&args[0], "rk4")?;
+    let vars = match &args[1] {
+        Value::List(names) if names.len() == 2 => vec![var_arg(&names[0], "rk4")?, var_arg(&names[1], "rk4")?],
+        v => vec!["t".to_string(), var_arg(v, "rk4")?],
+    };
+    let mut t = matrix::element(&args[2], "rk4")?.as_f64()?;
+    let mut y = matrix::element(&args[3], "rk4")?.as_f64()?;
+    let h = matrix::element(&args[4], "rk4")?.as_f64()?;
+    let steps = bounded_int_arg(&args[5], "rk4", MAX_RK4_STEPS)?;
+    let f = Formula::with_vars(&text, &vars, calc)?;
+    let mut eval = |t: f64, y: f64| match f.eval_at(&[Value::Float(t), Value::Float(y)], lookup)? {
+        Value::Complex(c) if c.im != 0.0 => Err(CalcError::NotForComplex("rk4".to_string())),
+        v => v.as_f64(),
+    };
+    let pair = |t: f64, y: f64| Value::List(vec![Value::Float(t), Value::Float(y)]);
+    let mut res = Vec::with_capacity(steps + 1);
+    res.push(pair(t, y));
+    let t0 = t;
+    for i in 1..=steps {
+        let k1 = eval(t, y)?;
+        let k2 = eval(t + h / 2.0, y + h * k1 / 2.0)?;
+        let k3 = eval(t + h / 2.0, y + h * k2 / 2.0)?;
+        let k4 = eval(t + h, y + h * k3)?;
+        y += h * (k1 + 2.0 * k2 + 2.0 * k3 + k4) / 6.0;
+        // `t` is not accumulated to avoid the growth of rounding errors
+        t = t0 + h * i as f64;
+        res.push(pair(t, y));
+    }
+    Ok(Value::List(res))
+}
+
+// the limit of an expression from one side of a point
+enum OneSided {
+    // the expression is not defined or complex near the point
+    Undefined(CalcError),
+    // a number or an infinity
+    Limit(f64),
+    // the expression oscillates or converges too slowly
+    Diverges,
+}
+
+/// Limit of an expression at the point `a`. The expression is evaluated
+/// at points approaching `a` from both sides, and the limits of the sides
+/// are found with Richardson extrapolation. If the expression is defined
+/// only on one side, the limit of that side is returned. An infinite limit
+/// is a float infinity. Limits that differ on the sides and expressions
+/// that do not converge generate distinct errors
+pub(crate) fn limit(expr: &Value, var: &Value, a: &Value, calc: &Calc, lookup: &mut Lookup) -> CalcResult {
+    let text = str_arg(expr, "limit")?;
+    let var = var_arg(var, "limit")?;
+    let a = matrix::element(a, "limit")?;
+    let f = Formula::new(&text, &var, calc)?;
+    let af = a.as_f64()?;
+    let left = one_sided(&f, af, -1.0, lookup);
+    if let OneSided::Undefined(CalcError::TooDeepNesting(n)) = left {
+        return Err(CalcError::TooDeepNesting(n));
+    }
+    let right = one_sided(&f, af, 1.0, lookup);
+    let (l, r) = match (left, right) {
+        (OneSided::Limit(l), OneSided::Limit(r)) => (l, r),
+        (OneSided::Limit(l), OneSided::Undefined(..)) => (l, l),
+        (OneSided::Undefined(..), OneSided::Limit(r)) => (r, r),
+        (OneSided::Undefined(e), OneSided::Undefined(..)) => return Err(e),
+        _ => return Err(CalcError::NoLimit(text)),
+    };
+    let mismatch = || CalcError::OneSidedLimits(Value::Float(l).to_string(), Value::Float(r).to_string());
+    if l.is_infinite() || r.is_infinite() {
+        return if l == r { Ok(Value::Float(l)) } else { Err(mismatch()) };
+    }
+    let scale = l.abs().max(r.abs()).max(1.0);
+    if (l - r).abs() > LIMIT_EPS * scale {
+        return Err(mismatch());
+    }
+    let lim = l + (r - l) / 2.0;
+    // if the expression is continuous at the point, its value there is
+    // the exact limit
+    if let Ok(v) = f.eval(&a, lookup) {
+        if matches!(v.as_f64(), Ok(fv) if (fv - lim).abs() <= LIMIT_EPS * scale) {
+            return Ok(v);
+        }
+    }
+    Ok(Value::Float(lim))
+}
+
+fn one_sided(f: &Formula, a: f64, dir: f64, lookup: &mut Lookup) -> OneSided {
+    let step = LIMIT_STEP * a.abs().max(1.0);
+    let mut vals = Vec::with_capacity(LIMIT_POINTS);
+    let mut h = step;
+    for _ in 0..LIMIT_POINTS {
+        match f.eval_f64(a + dir * h, lookup) {
+            Ok(Some(v)) if v.is_finite() => vals.push(v),
+            Ok(Some(v)) => return OneSided::Undefined(CalcError::InvalidAgrument("limit".to_string(), v.to_string())),
+            Ok(None) => return OneSided::Undefined(CalcError::NotForComplex("limit".to_string())),
+            Err(e) => return OneSided::Undefined(e),
+        }
+        h /= 2.0;
+    }
+    if let Some(v) = richardson(&vals) {
+        return OneSided::Limit(v);
+    }
+    // the values grow without extrapolation settling: e.g, `1/x` or
+    // `ln(x)` at zero
+    let tail = &vals[vals.len() / 2..];
+    if tail.windows(2).all(|w| w[0].signum() == w[1].signum() && w[1].abs() > w[0].abs()) {
+        return OneSided::Limit(f64::INFINITY.copysign(tail[0]));
+    }
+    // errors that are not integer powers of the distance(e.g, `sqrt(x)`
+    // at zero) are removed by Aitken's extrapolation
+    match aitken(tail) {
+        Some(v) => OneSided::Limit(v),
+        None => OneSided::Diverges,
+    }
+}
+
+fn settled(est: f64, err: f64) -> bool {
+    err <= LIMIT_EPS * est.abs().max(1.0)
+}
+
+// Richardson extrapolation of values at distances `h`, `h/2`, `h/4`, ...
+// that assumes that their errors are integer powers of the distance. Returns
+// `None` if the extrapolated values do not settle
+fn richardson(vals: &[f64]) -> Option<f64> {
+    let mut prev: Vec<f64> = Vec::new();
+    let (mut best, mut best_err) = (vals[0], f64::INFINITY);
+    for (i, v) in vals.iter().enumerate() {
+        let mut row = vec![*v];
+        let mut fac = 1.0;
+        for j in 1..=i {
+            fac *= 2.0;
+            let t = row[j - 1] + (row[j - 1] - prev[j - 1]) / (fac - 1.0);
+            let err = (t - row[j - 1]).abs().max((t - prev[j - 1]).abs());
+            if err <= best_err {
+                best = t;
+                best_err = err;
+            }
+            row.push(t);
+        }
+        // higher orders get worse when rounding errors dominate
+        if i > 0 && (row[i] - prev[i - 1]).abs() >= 2.0 * best_err {
+            break;
+        }
+        prev = row;
+    }
+    if settled(best, best_err) {
+        Some(best)
+    } else {
+        None
+    }
+}
+
+// Aitken's delta-squared extrapolation of a sequence. Returns `None` if
+// the last extrapolated values differ
+fn aitken(vals: &[f64]) -> Option<f64> {
+    let mut res = Vec::with_capacity(vals.len());
+    for w in vals.windows(3) {
+        let d2 = w[2] - 2.0 * w[1] + w[0];
+        if d2 == 0.0 {
+            res.push(w[2]);
+        } else {
+            res.push(w[2] - (w[2] - w[1]) * (w[2] - w[1]) / d2);
+        }
+    }
+    let last = *res.last()?;
+    let err = res[res.len().saturating_sub(3)..].iter().map(|v| (v - last).abs()).fold(0.0, f64::max);
+    if settled(last, err) {
+        Some(last)
+    } else {
+        None
+    }
+}
+
+// finds real roots by looking for sign changes and refining them with
+// bisection. Roots that do not change the sign(e.g, `x**2 = 0`) are found
+// only if they are exactly at one of the scan points
+fn numeric_roots(f: &Formula, lookup: &mut Lookup) -> Result<Vec<Value>, CalcError> {
+    let step = (SCAN_MAX_EXP - SCAN_MIN_EXP) / SCAN_STEPS as f64;
+    let positive: Vec<f64> = (0..=SCAN_STEPS).map(|i| fmath::powf(10.0, SCAN_MIN_EXP + step * i as f64)).collect();
+    let mut points: Vec<f64> = positive.iter().rev().map(|x| -x).collect();
+    points.push(0.0);
+    points.extend(positive);
+
+    let mut first_err = None;
+    let mut evaluated = false;
+    let mut values = Vec::with_capacity(points.len());
+    for x in &points {
+        match f.eval_f64(*x, lookup) {
+            Ok(v) => {
+                evaluated = true;
+                values.push(v);
+            }
+            Err(e @ CalcError::TooDeepNesting(..)) => return Err(e),
+            Err(e) => {
+                first_err.get_or_insert(e);
+                values.push(None);
+            }
+        }
+    }
+    if !evaluated {
+        return Err(first_err.unwrap_or(CalcError::NoRoots));
+    }
+
+    let mut roots = Vec::new();
+    for i in 0..points.len() {
+        let fa = match values[i] {
+            Some(v) => v,
+            None => continue,
+        };
+        if fa == 0.0 {
+            roots.push(Value::Float(points[i]));
+            continue;
+        }
+        let fb = match values.get(i + 1) {
+            Some(Some(v)) => *v,
+            _ => continue,
+        };
+        if fb == 0.0 || fa.signum() == fb.signum() {
+            continue;
+        }
+        if let Some(r) = bisect(f, lookup, (points[i], fa), (points[i + 1], fb)) {
+            roots.push(Value::Float(r));
+        }
+    }
+    if roots.is_empty() {
+        return Err(CalcError::NoRoots);
+    }
+    Ok(roots)
+}
+
+// refines a root between two points with different signs of the function
+fn bisect(f: &Formula, lookup: &mut Lookup, a: (f64, f64), b: (f64, f64)) -> Option<f64> {
+    let (mut a, mut fa) = a;
+    let (mut b, mut fb) = b;
+    let limit = ROOT_EPS * fa.abs().min(fb.abs()).max(1.0);
+    for _ in 0..BISECTION_ITERATIONS {
+        let m = a + (b - a) / 2.0;
+        if m == a || m == b {
+            break;
+        }
+        let fm = f.eval_f64(m, lookup).ok()??;
+        if fm == 0.0 {
+            return Some(m);
+        }
+        if fm.signum() == fa.signum() {
+            a = m;
+            fa = fm;
+        } else {
+            b = m;
+            fb = fm;
+        }
+    }
+    let (x, fx) = if fa.abs() <= fb.abs() { (a, fa) } else { (b, fb) };
+    if fx.abs() <= limit {
+        Some(x)
+    } else {
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_split() {
+        assert_eq!(split_equation("2*x = 4", "solve").unwrap(), ("2*x ", Some(" 4")));
+        assert_eq!(split_equation("x**2 - 1", "solve").unwrap(), ("x**2 - 1", None));
+        assert_eq!(split_equation("iif(x == 1; 2; 3) = x", "solve").unwrap(), ("iif(x == 1; 2; 3) ", Some(" x")));
+        assert_eq!(split_equation("x >= 1", "solve").unwrap(), ("x >= 1", None));
+        assert!(split_equation("x = 1 = 2", "solve").is_err());
+        assert!(var_arg(&Value::Str("2x".to_string()), "solve").is_err());
+        assert_eq!(var_arg(&Value::Str(" t ".to_string()), "solve"), Ok("t".to_string()));
+    }
+}
@@ -11,11 +11,13 @@
 //!
 //! Internally degrees are stored and act as float numbers. They only support
 //! their own input format. Output format is always a float number.
+//! A calculator in degree mode(`CalcBuilder::new().angle(Angle::Deg)`) reads bare
+//! numbers as degrees in trigonometric functions: `sin(90)` returns `1`, and `asin(1)` returns `90`.
 //!
 //! If two numbers have different types, they are converted to highest type and
 //! then the result is calculated. The list of number types starting from highest:
 //! * Complex numbers. Two input formats supported: `1+2i` and `1+i2`. `i` can be capital. `j` can
-//! be used instead of `i`
+//!   be used instead of `i`
 //! * Float numbers (degrees are float numbers, too)
 //! * Rational numbers
 //! * Big integer numbers
@@ -27,40 +29,198 @@
 //! * `1\2 + 1\2` -> `1` - half and half is an integer one
 //!
 //! The list of supported functions:
-//! * trigonometric functions (including inverted ones): sin, cos, tan, asin, acos, atan
-//! * hyperbolic functions (including inverted ones): sinh, cosh, tanh, asinh, acosh, atanh
+//! * trigonometric functions (including inverted ones): sin, cos, tan, cot, sec, csc, asin, acos, atan,
+//!   acot, asec, acsc
+//! * hyperbolic functions (including inverted ones): sinh, cosh, tanh, coth, sech, csch, asinh, acosh, atanh,
+//!   acoth, asech, acsch
 //! * square and square root: sqr and sqrt
-//! * exponent, logarithm: exp, ln
-//! * complex functions: norm, re, im, conjugate
+//! * exponent, logarithm: exp, ln, log10, exp2, and the functions accurate near zero: expm1(`exp(x)-1`), ln1p(`ln(1+x)`)
+//! * complex functions: norm, re, im, conj, arg(or phase), proj
 //! * rounding: ceil, floor, trunc, round
+//! * degrees, minutes, and seconds: dms(12.5125) returns `[12; 30; 45]` and sets alternative result to `12°30'45"`, fromdms(12; 30; 45) returns `12.5125`
+//! * coordinates: topolar(x; y)(or polar) returns `[r; theta]`, tocart(r; theta) returns `[x; y]`. Angles are in radians
+//! * Lambert W function: lambertw(x) for the principal branch, lambertw(x; -1) for the lower one
+//! * Bessel functions of integer order: besselj(n; x), bessely(n; x)
+//! * Riemann zeta function: zeta(s) for real `s`
+//! * primorial(n) and multifactorial: multifact(n; k) is `n*(n-k)*(n-2k)*...`, e.g. multifact(n; 2) is double factorial
+//! * Stirling numbers of the first(signed) and second kind: stirling1(n; k), stirling2(n; k), and Bell numbers: bell(n)
+//! * harmonic numbers: harmonic(n) returns an exact fraction for integer `n`, and a float number otherwise
 //! * float to rational: ratio
+//! * rational to float: approx
+//! * best rational approximation: as_fraction(x; max_denominator), the default maximum denominator is 10000
 //! * absolute value and sign: abs, signum
+//! * conditions: iif(cond; a; b), piecewise(cond1; a1; cond2; a2; ...; default) evaluates only the selected value
+//! * assertions: assert(cond; "message") returns the condition, or generates an error with the message if it is zero
 //! * fractional part of a float number: fract
+//! * range functions: clamp, lerp(linear interpolation), wrap(e.g, angle normalization)
+//! * signal processing and graphics: sinc(`sin(x)/x`), sincn(normalized `sin(PI*x)/(PI*x)`), sigmoid, smoothstep(a; b; x)
+//! * float utilities: copysign, fma(fused multiply-add), ldexp, frexp(returns a list `[mantissa; exponent]`)
+//! * Euclidean division: mod(remainder is never negative, unlike `%`), divmod(returns a list `[quotient; remainder]`)
+//! * extended GCD: egcd(a; b) returns a list `[g; x; y]` where `a*x + b*y = g`
+//! * modular arithmetic: modinv(a; m) returns the inverse of `a` modulo `m`, modpow(a; e; m) - `a**e` modulo `m`.
+//!   `Calc::new().with_modulus(m)` evaluates integer expressions modulo `m`: `1/3` is `5` and `2**10` is `2` modulo `7`
+//! * Collatz sequence: collatz(n) returns the number of steps to reach `1`
+//! * Roman numerals: roman(2024) returns a string `"MMXXIV"`, unroman("MMXXIV") returns `2024`
+//! * number to English words: spell(1234) returns `"one thousand two hundred thirty-four"`, spell(105; "en-gb") returns `"one hundred and five"`
+//! * exact integer logarithms: ilog2(n), ilog10(n), ilog(n; base), and the next power of two: nextpow2(n)
+//! * two's-complement: signed(x; width) reads the lowest `width` bits as a signed number(`signed(0xFF; 8)` is `-1`), unsigned(x; width) - as an unsigned one(`unsigned(-1; 8)` is `255`)
+//! * bit fields: bits(x; hi; lo) extracts bits from `hi` down to `lo`(`bits(0xABCD; 11; 4)` is `0xBC`), setbits(x; hi; lo; v) replaces them with `v`
+//! * byte order: bswap16(x), bswap32(x), bswap64(x), and bswap(x; width) reverse the order of bytes: `bswap16(0x1234)` is `0x3412`
+//! * checksums: crc32(n) or crc32("text") sets alternative result to the hexadecimal checksum, luhn(n) returns `1` if the number passes Luhn check
+//! * hashes: fnv1a(x)(64-bit FNV-1a), murmur3(x)(32-bit MurmurHash3), and sha256(x)(with feature `sha256`) of a string or an integer.
+//!   Like `crc32`, they set alternative result to the hexadecimal hash
+//! * strings: `+` concatenates a string with any value, len(s) returns the number of characters(or items of a list),
+//!   format(x; spec) formats a value: `format(1234.5; ",.2f")` returns `"1,234.50"`
+//! * templates: fmt(x; template) formats a value with a printf-style or a brace placeholder: `fmt(1500; "%.2e")` returns `"1.50e+03"`,
+//!   `fmt(2.5; "x = {:>6.2}")` returns `"x =   2.50"`
+//! * encodings: hex("text") returns hexadecimal form of string bytes, unhex("ff") converts a hexadecimal string to an integer,
+//!   b64(x) returns Base64 form of a string or an integer, unb64("Zm9v") decodes it to a string(or an integer if the bytes are not UTF-8 text)
+//! * dates and time: now() returns the current Unix timestamp, unix("2024-03-01 12:30") converts an ISO 8601 date to
+//!   a timestamp, fromunix(seconds) converts a timestamp to UTC date string. Dates without time zone are in UTC
+//! * statistics over paired lists: linreg(xs; ys) returns a list [slope; intercept; r2] of the least squares line,
+//!   corr(xs; ys) is Pearson correlation coefficient, cov(xs; ys) is sample covariance
+//! * discrete distributions: binompmf(k; n; p) and binomcdf(k; n; p) - binomial, poispmf(k; lambda) and
+//!   poiscdf(k; lambda) - Poisson. They are calculated in logarithms, so big `n` does not overflow
+//! * continuous distributions: tcdf(x; df) and tinv(p; df) - Student's t-distribution, chi2cdf(x; df) and
+//!   chi2inv(p; df) - chi-squared distribution(`df` is the number of degrees of freedom)
+//! * items of lists and strings: item(xs; i) or `xs[i]`, slice(xs; i; j) or `xs[i:j]`. Indexes start from zero,
+//!   and negative indexes count from the end
+//! * list utilities: sort(xs), sortdesc(xs), unique(xs) - items without repetitions, reverse(xs) - a list or a string
+//!   in reverse order, len(xs) - the number of items
+//! * paired data: zip(xs; ys) - a list of pairs `[x; y]`, zip(pairs) - the list `[xs; ys]`, col(rows; i) - items with
+//!   index `i` of every row
+//! * list processing: map(xs; "x**2") - an expression of `x` for every item, filter(xs; "x > 0") - items that satisfy
+//!   a condition, reduce(xs; "acc + x"; 0) - combines items into `acc` starting from the initial value
+//! * matrices are lists of rows: det([[1; 2]; [3; 4]]) returns `-2`, inv(m) is inverse matrix, rank(m), and
+//!   solve(a; b) solves the system of linear equations `a*x = b`. Integer and fractional matrices are calculated exactly
+//! * vectors are lists of numbers: dot(u; v) - dot product, cross(u; v) - cross product of 3D vectors,
+//!   vnorm(u) - Euclidean length, angle(u; v) - angle between vectors in radians
+//! * polynomials: poly(1; -3; 2) creates a polynomial `x**2-3*x+2` that supports `+`, `-`, `*`, `/`, and `**`, polyval(p; x)
+//!   evaluates it, roots(p) returns a list of all roots(including complex ones) of a polynomial of degree up to 4
+//! * equations: solve("x**2 = 2*x + 3"; "x") returns a list of solutions `[-1; 3]`. Polynomial equations of degree
+//!   up to 4 are solved exactly, others - numerically. The equation can use session variables.
+//!   solvesys(["x + y = 3"; "2*x - y = 0"]; ["x"; "y"]) solves a system of linear equations and returns a list of
+//!   pairs `[[x; 1]; [y; 2]]`
+//! * calculus: taylor("sqrt(x)"; "x"; 4; 2) returns the Taylor series of the expression at `x = 4` up to `x**2` as a
+//!   polynomial `-1\64*x**2+3\8*x+3\4`
+//!   limit("sin(x)/x"; "x"; 0) is a numeric limit. Infinite limits are float infinities, and limits that differ on
+//!   the left and right sides or do not exist generate errors
+//!   nderiv("ln(x)"; "x"; 2) is a numeric derivative at `x = 2`
+//!   minimize("(x - 2)**2 + 1"; "x"; 0; 5) and maximize(...) return a pair `[x; value]` of the extremum on the interval
+//!   rk4("-2*t*y"; "y"; 0; 1; 0.1; 10) integrates `y' = f(t, y)` with Runge-Kutta method and returns a list of `[t; y]`
+//! * iterations: iterate("cos(x)"; "x"; 1; 100) applies an expression to its own result 100 times starting from `x = 1`
+//! * plotting: sample("1/x"; "x"; -1; 1; 101) returns a list of 101 pairs `[x; y]`. `y` is NaN where the expression
+//!   is not defined, and a pair with NaN is inserted at poles, so a plotter does not connect the branches
+//! * calendar: tz(date; "Europe/Stockholm") converts a date to the local time of the time zone, weekday(date) is ISO
+//!   day of the week(1 - Monday), isoweek(date) and isoyear(date) are ISO week number and its year, daysin(month; year).
+//!   A date can be a string or Unix timestamp
+//! * duration literals are numbers of seconds: `1h30m` = 5400, `90s`, `2d4h`(days require hours because `2d` is an angle),
+//!   `1.5h` = 5400. `h`, `m`, and `s` right after a number are units, not variables: `2m` = 120.
+//!   duration(seconds) formats seconds back: duration(5400) = "1h30m"
+//! * data sizes: kib, mib, gib, tib convert binary units to bytes, kb, mb, gb, tb convert decimal units to bytes, and
+//!   hsize(bytes; base) formats a size with a human-readable unit(base is 1024 - default, or 1000): hsize(1536) = "1.5 KiB"
+//! * IPv4 addresses: ip("192.168.1.10") converts an address to an integer, ipstr(n) converts it back to a string, netmask(prefix),
+//!   network(ip; prefix), broadcast(ip; prefix). Addresses can be integers or strings, and the functions set alternative result to the address in dotted-quad form
+//! * digits of integers: digitsum(n; base), numdigits(n; base), revdigits(n; base). The base is optional, the default is 10
+//!
+//! Dates and time, special functions and distributions, and data sizes, IPv4 addresses, and durations are
+//! enabled by default with cargo features `datetime`, `special`, and `units`. They can be turned off for a smaller build.
+//! Feature `libm` makes results of real transcendental functions identical on all platforms.
 //!
 //! Operators (starting from highest priority):
 //! * `!` - factorial (when used after a number or closing bracket)
-//! * `-` - unary minus
-//! * `**` - power
-//! * `*`, `/`, `//` - multiplication, division, integer division
+//! * `-`, `~`, `!` - unary minus, bitwise NOT, and logical NOT
+//! * `**`, `^` - power
+//! * `<<`, `>>` - bitwise SHL and SHR
+//! * `*`, `/`, `//`, `%` - multiplication, division, integer division, and remainder
+//! * `+%`, `-%`, `*%`, `/%` - percentage operators(`200 + 10%` is `220`)
 //! * `+`, `-` - addition, subtraction
-//! * `&`, `^` - bitwise AND and XOR
-//! * `|`, `<<`, `>>` - bitwise OR, SHL, and SHR
+//! * `&`, `@`, `^^` - bitwise AND and XOR
+//! * `..` - range of numbers: `1..5` is `[1; 2; 3; 4; 5]`. `step` sets the step of a range: `0..1 step 0.25`
+//! * `|` - bitwise OR
+//! * `&&` - logical AND
+//! * `||` - logical OR
 //! * `==`, `!=`, `>`, `<`, `>=`, and `<=` - comparison operators
 //!
+//! `Calc::operators` returns the same list with precedence and associativity.
+//!
 //! Predefined constants:
 //! * `PI` - 3.14159...
 //! * `E` - 2.71828...
 //! * `PHI` - golden section - 1.6180...
+//! * `INF` - infinity
 //!
-//! The calculator does not have special type for boolean numbers. When using logical
+//! By default, the calculator does not have special type for boolean numbers. When using logical
 //! operators any zero value is treated as `false` and `true` otherwise. So, doing
 //! double NOT transforms any number into big integer `1` or `0` depending on if the
-//! original value was zero
+//! original value was zero. With `Truth::Bool` or `Truth::Strict` comparison and logical
+//! operators return `Value::Bool`
 
 #[macro_use]
 extern crate pest_derive;
 
+pub mod alias;
+mod cache;
+pub mod calc;
+#[cfg(feature = "datetime")]
+mod datetime;
+mod encoding;
 pub mod errors;
+pub mod expr;
+#[cfg(feature = "ffi")]
+pub mod ffi;
+mod fmath;
+mod format;
+mod formula;
+mod list;
+mod matrix;
 pub mod parse;
+mod poly;
+#[cfg(feature = "python")]
+mod python;
+pub mod resolver;
+pub mod sandbox;
+#[cfg(feature = "serde")]
+mod serialize;
+mod series;
+mod special;
 pub mod stack;
+mod statement;
 pub mod value;
+
+pub use crate::calc::{
+    Angle, ArgSeparator, Associativity, Brackets, Calc, CalcBuilder, CalcOptions, CalcOutput, Caret, Category,
+    FloatWidth, FunctionInfo, LogBase, OperatorInfo, Truth,
+};
+pub use crate::errors::CalcError;
+pub use crate::parse::CalcState;
+pub use crate::value::{CalcResult, Value};
+
+/// Evaluates an expression with the default calculator options in a new
+/// session. It is the simplest way to use the library:
+///
+/// ```
+/// use rcalc_lib::{evaluate, Value};
+///
+/// assert_eq!(evaluate("2 ** 10 + 1").unwrap().to_string(), "1025");
+/// assert_eq!(evaluate("sqrt(-4)").unwrap(), Value::Complex(num_complex::Complex::new(0.0, 2.0)));
+/// ```
+pub fn evaluate(expr: &str) -> CalcResult {
+    Calc::new().eval(expr, &mut CalcState::new())
+}
+
+/// Evaluates an expression with the given calculator options inside
+/// a session. The session keeps variables and the last result between
+/// calls, and the options can be shared between many sessions:
+///
+/// ```
+/// use rcalc_lib::{evaluate_with, Calc, CalcState};
+///
+/// let calc = Calc::new().exact();
+/// let mut session = CalcState::new();
+/// evaluate_with("1/3", &calc, &mut session).unwrap();
+/// assert_eq!(evaluate_with("ans + 1/6", &calc, &mut session).unwrap().to_string(), "1\\2");
+/// ```
+pub fn evaluate_with(expr: &str, calc: &Calc, session: &mut CalcState) -> CalcResult {
+    calc.eval(expr, session)
+}
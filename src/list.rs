@@ -0,0 +1,317 @@
+//! List functions.
+//!
+//! Lists are created with brackets, `[1; 2; 3]`, or with the range operator:
+//! `1..5` is `[1; 2; 3; 4; 5]`, and `0..1 step 0.25` is
+//! `[0.0; 0.25; 0.5; 0.75; 1.0]`.
+//!
+//! Items of lists and characters of strings are numbered from zero. A
+//! negative index counts from the end: `xs[-1]` is the last item.
+//!
+//! Sorted lists contain either only numbers or only strings. Numbers are
+//! sorted by value, and strings are sorted by their characters.
+
+use num_bigint::BigInt;
+use num_traits::{Signed, ToPrimitive};
+use std::cmp::Ordering;
+
+use crate::errors::*;
+use crate::value::*;
+
+// the longest list a range can produce
+const MAX_RANGE: usize = 1_000_000;
+// a float range includes its end even if the last step is a bit short
+// because of rounding errors: `0..1 step 0.1`
+const RANGE_EPS: f64 = 1e-9;
+
+fn range_bound(v: Value) -> CalcResult {
+    match v {
+        Value::Int(..) | Value::Ratio(..) | Value::Float(..) => Ok(v),
+        Value::Bool(..) => v.into_int(),
+        Value::Complex(..) => Err(CalcError::NotForComplex("..".to_string())),
+        Value::Str(..) => Err(CalcError::NotForString("..".to_string())),
+        Value::List(..) => Err(CalcError::NotForList("..".to_string())),
+        Value::Poly(..) => Err(CalcError::NotForPoly("..".to_string())),
+    }
+}
+
+/// Numbers from `start` to `end` inclusive. Without a step, the range goes up
+/// or down by one. A range of integers or fractions contains exact numbers.
+/// A step in the direction opposite to the range produces an empty list
+pub(crate) fn range(start: Value, end: Value, step: Option<Value>) -> CalcResult {
+    let start = range_bound(start)?;
+    let end = range_bound(end)?;
+    let step = match step {
+        Some(s) => range_bound(s)?,
+        None if end.as_f64()? < start.as_f64()? => Value::from(-1),
+        None => Value::from(1),
+    };
+    let fstep = step.as_f64()?;
+    if fstep == 0.0 {
+        return Err(CalcError::ArgumentOutOfRange("step".to_string(), step.to_string(), "non-zero".to_string()));
+    }
+    let steps = (end.as_f64()? - start.as_f64()?) / fstep;
+    if steps < 0.0 {
+        return Ok(Value::List(Vec::new()));
+    }
+    let steps = (steps + RANGE_EPS).floor();
+    if !steps.is_finite() || steps >= MAX_RANGE as f64 {
+        return Err(CalcError::ArgumentOutOfRange(
+            "..".to_string(),
+            format!("{}..{}", start, end),
+            format!("at most {} items", MAX_RANGE),
+        ));
+    }
+    let mut items = Vec::with_capacity(steps as usize + 1);
+    for i in 0..=steps as usize {
+        let v = start.clone().addition(step.clone().multiply(Value::from(i as u64))?)?;
+        items.push(v);
+    }
+    Ok(Value::List(items))
+}
+
+// items of a list or characters of a string
+fn items(v: Value, name: &str) -> Result<(Vec<Value>, bool), CalcError> {
+    match v {
+        Value::List(items) => Ok((items, false)),
+        Value::Str(s) => Ok((s.chars().map(|c| Value::Str(c.to_string())).collect(), true)),
+        _ => Err(CalcError::InvalidAgrument(name.to_string(), v.to_string())),
+    }
+}
+
+// an index counted from the end becomes an ordinary one, so the result can
+// be negative or greater than the length
+fn offset(idx: &Value, len: usize, name: &str) -> Result<BigInt, CalcError> {
+    let i = match idx {
+        Value::Int(..) | Value::Ratio(..) | Value::Float(..) => {
+            idx.as_bigint().map_err(|_| CalcError::OnlyInt(name.to_string()))?
+        }
+        _ => return Err(CalcError::OnlyInt(name.to_string())),
+    };
+    if i.is_negative() {
+        Ok(i + BigInt::from(len))
+    } else {
+        Ok(i)
+    }
+}
+
+/// Item of a list or character of a string
+pub(crate) fn item(v: Value, idx: &Value) -> CalcResult {
+    let (mut items, _) = items(v, "item")?;
+    let len = items.len();
+    match offset(idx, len, "item")?.to_usize() {
+        Some(i) if i < len => Ok(items.swap_remove(i)),
+        _ => {
+            let range = if len == 0 { "empty list".to_string() } else { format!("-{}..{}", len, len - 1) };
+            Err(CalcError::ArgumentOutOfRange("index".to_string(), idx.to_string(), range))
+        }
+    }
+}
+
+// a bound of a slice is clamped to the list. Infinite bound is the end of
+// the list from its side
+fn slice_bound(v: &Value, len: usize) -> Result<usize, CalcError> {
+    if let Value::Float(f) = v {
+        if f.is_infinite() {
+            return Ok(if *f > 0.0 { len } else { 0 });
+        }
+    }
+    let i = offset(v, len, "slice")?;
+    if i.is_negative() {
+        return Ok(0);
+    }
+    Ok(i.to_usize().map_or(len, |i| i.min(len)))
+}
+
+/// Items from `start` up to, but not including, `end`. Bounds outside the
+/// list are clamped, so a slice is never out of range
+pub(crate) fn slice(v: Value, start: &Value, end: &Value) -> CalcResult {
+    let (items, is_str) = items(v, "slice")?;
+    let len = items.len();
+    let start = slice_bound(start, len)?;
+    let end = slice_bound(end, len)?.max(start);
+    let items = items[start..end].to_vec();
+    if is_str {
+        return Ok(Value::Str(items.iter().map(|c| c.to_string()).collect()));
+    }
+    Ok(Value::List(items))
+}
+
+fn list_arg(v: Value, name: &str) -> Result<Vec<Value>, CalcError> {
+    match v {
+        Value::List(items) => Ok(items),
+        _ => Err(CalcError::InvalidAgrument(name.to_string(), v.to_string())),
+    }
+}
+
+// checks that all items can be compared with each other
+fn check_sortable(items: &[Value], name: &str) -> Result<(), CalcError> {
+    let strings = items.iter().filter(|v| matches!(v, Value::Str(..))).count();
+    if strings == items.len() {
+        return Ok(());
+    }
+    if strings != 0 {
+        return Err(CalcError::InvalidAgrument(name.to_string(), "strings and numbers are mixed".to_string()));
+    }
+    for v in items {
+        match v {
+            Value::Float(f) if f.is_nan() => return Err(CalcError::InvalidAgrument(name.to_string(), v.to_string())),
+            Value::Int(..) | Value::Ratio(..) | Value::Float(..) | Value::Bool(..) => {}
+            Value::Complex(..) => return Err(CalcError::NotForComplex(name.to_string())),
+            Value::Poly(..) => return Err(CalcError::NotForPoly(name.to_string())),
+            _ => return Err(CalcError::NotForList(name.to_string())),
+        }
+    }
+    Ok(())
+}
+
+// the order of two items that passed `check_sortable`
+fn order(a: &Value, b: &Value) -> Ordering {
+    if let (Value::Str(a), Value::Str(b)) = (a, b) {
+        return a.cmp(b);
+    }
+    let less = |a: &Value, b: &Value| a.clone().less(b.clone()).is_ok_and(|v| !v.is_zero());
+    if less(a, b) {
+        Ordering::Less
+    } else if less(b, a) {
+        Ordering::Greater
+    } else {
+        Ordering::Equal
+    }
+}
+
+/// Items of a list in ascending or descending order. Equal items keep
+/// their order
+pub(crate) fn sort(v: Value, desc: bool, name: &str) -> CalcResult {
+    let mut items = list_arg(v, name)?;
+    check_sortable(&items, name)?;
+    if desc {
+        items.sort_by(|a, b| order(b, a));
+    } else {
+        items.sort_by(order);
+    }
+    Ok(Value::List(items))
+}
+
+/// Items of a list without repetitions. The first occurrence of an item
+/// is kept
+pub(crate) fn unique(v: Value) -> CalcResult {
+    let items = list_arg(v, "unique")?;
+    let mut res: Vec<Value> = Vec::with_capacity(items.len());
+    for item in items {
+        if !res.contains(&item) {
+            res.push(item);
+        }
+    }
+    Ok(Value::List(res))
+}
+
+/// A list or a string in reverse order
+pub(crate) fn reverse(v: Value) -> CalcResult {
+    match v {
+        Value::Str(s) => Ok(Value::Str(s.chars().rev().collect())),
+        _ => {
+            let mut items = list_arg(v, "reverse")?;
+            items.reverse();
+            Ok(Value::List(items))
+        }
+    }
+}
+
+/// Lists of items with the same index from several lists of the same
+/// length: `zip([1; 2]; [3; 4])` is `[[1; 3]; [2; 4]]`. Zipping the result
+/// again restores the original lists
+pub(crate) fn zip(lists: Vec<Value>) -> CalcResult {
+    let lists = lists.into_iter().map(|v| list_arg(v, "zip")).collect::<Result<Vec<Vec<Value>>, CalcError>>()?;
+    let len = lists.first().map_or(0, |l| l.len());
+    if lists.iter().any(|l| l.len() != len) {
+        return Err(CalcError::DimensionMismatch("zip".to_string(), "lists of different lengths".to_string()));
+    }
+    let mut rows: Vec<Vec<Value>> = (0..len).map(|_| Vec::with_capacity(lists.len())).collect();
+    for list in lists {
+        for (row, item) in rows.iter_mut().zip(list) {
+            row.push(item);
+        }
+    }
+    Ok(Value::List(rows.into_iter().map(Value::List).collect()))
+}
+
+/// Items with the same index from every row of a list: `col([[1; 2]; [3;
+/// 4]]; 1)` is `[2; 4]`
+pub(crate) fn col(v: Value, idx: &Value) -> CalcResult {
+    let rows = list_arg(v, "col")?;
+    let res = rows.into_iter().map(|row| item(row, idx)).collect::<Result<Vec<Value>, CalcError>>()?;
+    Ok(Value::List(res))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_range() {
+        let ints = |v: &[i64]| Value::List(v.iter().map(|i| Value::from(*i)).collect());
+        assert_eq!(range(Value::from(1), Value::from(4), None), Ok(ints(&[1, 2, 3, 4])));
+        assert_eq!(range(Value::from(2), Value::from(-1), None), Ok(ints(&[2, 1, 0, -1])));
+        assert_eq!(range(Value::from(1), Value::from(10), Some(Value::from(4))), Ok(ints(&[1, 5, 9])));
+        assert_eq!(range(Value::from(1), Value::from(4), Some(Value::from(-1))), Ok(ints(&[])));
+        let v = range(Value::from(0), Value::from(1), Some(Value::Float(0.1))).unwrap();
+        assert_eq!(v.clone().len(), Ok(Value::from(11)));
+        if let Value::List(items) = v {
+            assert_eq!(items[10].as_f64(), Ok(1.0));
+        }
+        assert!(range(Value::from(1), Value::from(4), Some(Value::from(0))).is_err());
+        assert!(range(Value::from(0), Value::Float(1e10), None).is_err());
+        assert!(range(Value::from(0), Value::Str("a".to_string()), None).is_err());
+    }
+
+    #[test]
+    fn test_item_slice() {
+        let list = || range(Value::from(1), Value::from(5), None).unwrap();
+        let s = || Value::Str("hello".to_string());
+        assert_eq!(item(list(), &Value::from(0)), Ok(Value::from(1)));
+        assert_eq!(item(list(), &Value::from(-1)), Ok(Value::from(5)));
+        assert_eq!(item(s(), &Value::from(1)), Ok(Value::Str("e".to_string())));
+        assert_eq!(
+            item(list(), &Value::from(5)),
+            Err(CalcError::ArgumentOutOfRange("index".to_string(), "5".to_string(), "-5..4".to_string()))
+        );
+        assert!(item(list(), &Value::from(-6)).is_err());
+        assert!(item(list(), &Value::Float(0.5)).is_err());
+        assert!(item(Value::from(5), &Value::from(0)).is_err());
+        let ints = |v: &[i64]| Value::List(v.iter().map(|i| Value::from(*i)).collect());
+        assert_eq!(slice(list(), &Value::from(1), &Value::from(3)), Ok(ints(&[2, 3])));
+        assert_eq!(slice(list(), &Value::from(-2), &Value::Float(f64::INFINITY)), Ok(ints(&[4, 5])));
+        assert_eq!(slice(list(), &Value::from(3), &Value::from(1)), Ok(ints(&[])));
+        assert_eq!(slice(list(), &Value::from(-10), &Value::from(10)), Ok(list()));
+        assert_eq!(slice(s(), &Value::from(1), &Value::from(-1)), Ok(Value::Str("ell".to_string())));
+    }
+
+    #[test]
+    fn test_sort() {
+        let list = |v: Vec<Value>| Value::List(v);
+        let nums = || list(vec![Value::from(3), Value::Float(1.5), Value::from(-2), Value::from(3)]);
+        let sorted = list(vec![Value::from(-2), Value::Float(1.5), Value::from(3), Value::from(3)]);
+        assert_eq!(sort(nums(), false, "sort"), Ok(sorted.clone()));
+        assert_eq!(sort(nums(), true, "sortdesc"), reverse(sorted));
+        assert_eq!(unique(nums()), Ok(list(vec![Value::from(3), Value::Float(1.5), Value::from(-2)])));
+        let strs = |v: &[&str]| list(v.iter().map(|s| Value::Str(s.to_string())).collect());
+        assert_eq!(sort(strs(&["b", "ab", "a"]), false, "sort"), Ok(strs(&["a", "ab", "b"])));
+        assert_eq!(reverse(Value::Str("abc".to_string())), Ok(Value::Str("cba".to_string())));
+        assert!(sort(list(vec![Value::from(1), Value::Str("a".to_string())]), false, "sort").is_err());
+        assert!(sort(list(vec![Value::from(1), Value::Float(f64::NAN)]), false, "sort").is_err());
+        assert!(sort(Value::from(1), false, "sort").is_err());
+    }
+
+    #[test]
+    fn test_zip() {
+        let ints = |v: &[i64]| Value::List(v.iter().map(|i| Value::from(*i)).collect());
+        let pairs = Value::List(vec![ints(&[1, 4]), ints(&[2, 5]), ints(&[3, 6])]);
+        assert_eq!(zip(vec![ints(&[1, 2, 3]), ints(&[4, 5, 6])]), Ok(pairs.clone()));
+        let unzipped = zip(vec![ints(&[1, 4]), ints(&[2, 5]), ints(&[3, 6])]);
+        assert_eq!(unzipped, Ok(Value::List(vec![ints(&[1, 2, 3]), ints(&[4, 5, 6])])));
+        assert_eq!(zip(vec![ints(&[]), ints(&[])]), Ok(ints(&[])));
+        assert_eq!(col(pairs.clone(), &Value::from(-1)), Ok(ints(&[4, 5, 6])));
+        assert!(zip(vec![ints(&[1, 2]), ints(&[1])]).is_err());
+        assert!(col(pairs, &Value::from(2)).is_err());
+    }
+}
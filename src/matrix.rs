@@ -0,0 +1,205 @@
+//! Matrix and vector functions.
+//!
+//! A matrix is a list of rows, and every row is a list of numbers of the
+//! same length: `[[1; 2]; [3; 4]]`. A vector is a plain list of numbers:
+//! `[1; 2; 3]`. Elements are integers, fractions, or float numbers.
+//! Integer and fractional matrices are processed exactly, so
+//! `det([[1; 2]; [3; 4]])` is exactly `-2`.
+
+use num_bigint::BigInt;
+
+use crate::errors::*;
+use crate::value::*;
+
+// float elements smaller than the biggest element of a matrix multiplied
+// by this value are treated as zeroes after elimination
+const PIVOT_EPS: f64 = 1e-12;
+
+pub(crate) type Matrix = Vec<Vec<Value>>;
+
+/// Converts a value to a matrix. A plain list of numbers is a column
+/// if `column` is true, and an error otherwise
+pub(crate) fn to_matrix(v: &Value, name: &str, column: bool) -> Result<Matrix, CalcError> {
+    let rows = match v {
+        Value::List(rows) if !rows.is_empty() => rows,
+        _ => return Err(CalcError::DimensionMismatch(name.to_string(), format!("{} is not a matrix", v))),
+    };
+    let mut m = Vec::with_capacity(rows.len());
+    for row in rows {
+        match row {
+            Value::List(r) => m.push(r.iter().map(|x| element(x, name)).collect::<Result<Vec<Value>, CalcError>>()?),
+            _ if column => m.push(vec![element(row, name)?]),
+            _ => return Err(CalcError::DimensionMismatch(name.to_string(), format!("{} is not a matrix", v))),
+        }
+    }
+    let cols = m[0].len();
+    if cols == 0 || m.iter().any(|r| r.len() != cols) {
+        return Err(CalcError::DimensionMismatch(name.to_string(), "rows of different lengths".to_string()));
+    }
+    Ok(m)
+}
+
+pub(crate) fn element(v: &Value, name: &str) -> CalcResult {
+    match v {
+        Value::Int(..) | Value::Ratio(..) | Value::Float(..) => Ok(v.clone()),
+        Value::Bool(..) => v.clone().into_int(),
+        Value::Complex(..) => Err(CalcError::NotForComplex(name.to_string())),
+        Value::Str(..) => Err(CalcError::NotForString(name.to_string())),
+        Value::Poly(..) => Err(CalcError::NotForPoly(name.to_string())),
+        Value::List(..) => Err(CalcError::DimensionMismatch(name.to_string(), "matrix element is a list".to_string())),
+    }
+}
+
+/// Converts a value to a vector
+pub(crate) fn to_vector(v: &Value, name: &str) -> Result<Vec<Value>, CalcError> {
+    match v {
+        Value::List(items) if !items.is_empty() => items.iter().map(|x| element(x, name)).collect(),
+        _ => Err(CalcError::DimensionMismatch(name.to_string(), format!("{} is not a vector", v))),
+    }
+}
+
+/// Converts two values to vectors of the same length
+pub(crate) fn vector_pair(u: &Value, v: &Value, name: &str) -> Result<(Vec<Value>, Vec<Value>), CalcError> {
+    let (u, v) = (to_vector(u, name)?, to_vector(v, name)?);
+    if u.len() != v.len() {
+        return Err(CalcError::DimensionMismatch(
+            name.to_string(),
+            format!("vectors of different lengths {} and {}", u.len(), v.len()),
+        ));
+    }
+    Ok((u, v))
+}
+
+/// Sum of products of vector elements
+pub(crate) fn dot(u: &[Value], v: &[Value]) -> CalcResult {
+    let mut sum = Value::Int(BigInt::from(0));
+    for (a, b) in u.iter().zip(v.iter()) {
+        sum = sum.addition(a.clone().multiply(b.clone())?)?;
+    }
+    Ok(sum)
+}
+
+/// Converts a matrix to a list of rows
+pub(crate) fn from_matrix(m: Matrix) -> Value {
+    Value::List(m.into_iter().map(Value::List).collect())
+}
+
+/// Returns an error if the matrix is not square
+pub(crate) fn check_square(m: &Matrix, name: &str) -> Result<(), CalcError> {
+    if m.len() != m[0].len() {
+        return Err(CalcError::DimensionMismatch(
+            name.to_string(),
+            format!("matrix {}x{} is not square", m.len(), m[0].len()),
+        ));
+    }
+    Ok(())
+}
+
+fn magnitude(v: &Value) -> f64 {
+    v.as_f64().map(f64::abs).unwrap_or(0.0)
+}
+
+// exact values are zero only if they are exactly zero, float ones are
+// compared with the matrix scale
+fn is_negligible(v: &Value, scale: f64) -> bool {
+    match v {
+        Value::Float(f) => f.abs() <= scale * PIVOT_EPS,
+        _ => v.is_zero(),
+    }
+}
+
+/// The result of Gauss-Jordan elimination
+pub(crate) struct Elimination {
+    /// Columns with pivots, its length is the rank of the matrix
+    pub(crate) pivots: Vec<usize>,
+    /// The product of pivots with the sign of row permutation: the
+    /// determinant of a square matrix if its rank is full
+    pub(crate) det: Value,
+}
+
+/// Reduces the first `cols` columns of the matrix to reduced row echelon
+/// form with partial pivoting(the row with the biggest element becomes
+/// the pivot row). The rest columns(e.g, the right sides of equations)
+/// are transformed along
+pub(crate) fn eliminate(m: &mut Matrix, cols: usize) -> Result<Elimination, CalcError> {
+    let scale = m.iter().flat_map(|r| r.iter().take(cols)).map(magnitude).fold(0.0, f64::max);
+    let mut pivots = Vec::new();
+    let mut det = Value::Int(BigInt::from(1));
+    let mut r = 0;
+    for c in 0..cols {
+        if r == m.len() {
+            break;
+        }
+        let mut best = r;
+        for i in r + 1..m.len() {
+            if magnitude(&m[i][c]) > magnitude(&m[best][c]) {
+                best = i;
+            }
+        }
+        if is_negligible(&m[best][c], scale) {
+            continue;
+        }
+        if best != r {
+            m.swap(best, r);
+            det = det.negate()?;
+        }
+        let p = m[r][c].clone();
+        det = det.multiply(p.clone())?;
+        for j in c..m[r].len() {
+            m[r][j] = m[r][j].clone().divide_exact(p.clone())?;
+        }
+        for i in 0..m.len() {
+            if i == r || m[i][c].is_zero() {
+                continue;
+            }
+            let f = m[i][c].clone();
+            for j in c..m[i].len() {
+                let d = f.clone().multiply(m[r][j].clone())?;
+                m[i][j] = m[i][j].clone().subtract(d)?;
+            }
+        }
+        pivots.push(c);
+        r += 1;
+    }
+    Ok(Elimination { pivots, det })
+}
+
+/// Appends columns of `rhs` to the right of the matrix
+pub(crate) fn augment(m: &mut Matrix, rhs: Matrix) {
+    for (row, extra) in m.iter_mut().zip(rhs) {
+        row.extend(extra);
+    }
+}
+
+/// Identity matrix of size `n`
+pub(crate) fn identity(n: usize) -> Matrix {
+    (0..n).map(|i| (0..n).map(|j| Value::Int(BigInt::from(u8::from(i == j)))).collect()).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_eliminate() {
+        let int = |i: i64| Value::Int(BigInt::from(i));
+        let mut m = vec![vec![int(2), int(1), int(1)], vec![int(4), int(3), int(5)]];
+        let e = eliminate(&mut m, 2).unwrap();
+        assert_eq!(e.pivots, vec![0, 1]);
+        assert_eq!(e.det, int(2));
+        assert_eq!(m, vec![vec![int(1), int(0), int(-1)], vec![int(0), int(1), int(3)]]);
+
+        let mut m = vec![vec![int(1), int(2)], vec![int(2), int(4)], vec![int(0), int(0)]];
+        assert_eq!(eliminate(&mut m, 2).unwrap().pivots, vec![0]);
+        let mut m = vec![vec![Value::Float(1.0), Value::Float(0.1)], vec![Value::Float(3.0), Value::Float(0.3)]];
+        assert_eq!(eliminate(&mut m, 2).unwrap().pivots, vec![0]);
+
+        let r = to_matrix(&Value::List(vec![Value::List(vec![int(1)]), Value::List(vec![])]), "det", false);
+        assert_eq!(r, Err(CalcError::DimensionMismatch("det".to_string(), "rows of different lengths".to_string())));
+        let r = to_matrix(&Value::List(vec![int(1), int(2)]), "det", true);
+        assert_eq!(r, Ok(vec![vec![int(1)], vec![int(2)]]));
+        let r = vector_pair(&Value::List(vec![int(1)]), &Value::List(vec![int(1), int(2)]), "dot");
+        let msg = "vectors of different lengths 1 and 2".to_string();
+        assert_eq!(r, Err(CalcError::DimensionMismatch("dot".to_string(), msg)));
+    }
+}
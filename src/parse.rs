@@ -1,8 +1,16 @@
+use pest::iterators::{Pair, Pairs};
 use pest::Parser;
+use std::borrow::Cow;
 use std::f64::consts::{E, PI};
 
+use crate::cache::ResultCache;
+use crate::calc::{Angle, ArgSeparator, Brackets, Calc, Caret, LogBase};
 use crate::errors::*;
-use crate::stack::{Stack, FACTORIAL, PERCENT_ADD, PERCENT_DIV, PERCENT_MUL, PERCENT_SUB, STD_FUNCS, UNARY_MINUS};
+use crate::expr::Expr;
+use crate::stack::{
+    find_func, resolve_alias, Stack, FACTORIAL, FORMULA_FUNCS, ITEM_FUNC, LIST_FUNC, PERCENT_ADD, PERCENT_DIV,
+    PERCENT_MUL, PERCENT_SUB, STEP, UNARY_MINUS, VOLATILE_FUNCS,
+};
 use crate::value::*;
 
 #[derive(Parser)]
@@ -10,7 +18,20 @@ use crate::value::*;
 pub struct CalcParser;
 
 pub const PHI: f64 = 1.618_033_988_749_895;
-const LAST_RESULT: &str = "ans";
+pub(crate) const LAST_RESULT: &str = "ans";
+
+// constants are the same for all sessions, so they are resolved when an
+// expression is compiled
+pub(crate) fn constant(name: &str) -> Option<Value> {
+    let a = name.to_lowercase();
+    match a.as_str() {
+        "e" => Some(Value::Float(E)),
+        "pi" => Some(Value::Float(PI)),
+        "inf" => Some(Value::Float(f64::INFINITY)),
+        "phi" | "golden" | "gold" => Some(Value::Float(PHI)),
+        _ => None,
+    }
+}
 
 /// holds user user-defined variable
 pub struct Variable {
@@ -26,21 +47,16 @@ impl Variable {
 /// holds the current state of the engine, e.g list of all previously used variables
 pub struct CalcState {
     variables: Vec<Variable>,
-    is_last_value: bool,
-    is_last_func: bool,
+    pub(crate) cache: Option<ResultCache>,
     pub has_alt: bool,
     pub alt_result: String,
+    /// Warnings generated by the last evaluation
+    pub warnings: Vec<String>,
 }
 
 impl Default for CalcState {
     fn default() -> CalcState {
-        CalcState {
-            variables: Vec::new(),
-            is_last_value: false,
-            is_last_func: false,
-            has_alt: true,
-            alt_result: "".to_owned(),
-        }
+        CalcState { variables: Vec::new(), cache: None, has_alt: true, alt_result: "".to_owned(), warnings: Vec::new() }
     }
 }
 
@@ -51,13 +67,7 @@ impl CalcState {
 
     /// Returns a constant value by its name. Name is caseinsensitive
     pub fn constant(&self, name: &str) -> Option<Value> {
-        let a = name.to_lowercase();
-        match a.as_str() {
-            "e" => Some(Value::Float(E)),
-            "pi" => Some(Value::Float(PI)),
-            "phi" | "golden" | "gold" => Some(Value::Float(PHI)),
-            _ => None,
-        }
+        constant(name)
     }
 
     /// Returns a variable value by its name. Name is caseinsensitive
@@ -86,6 +96,19 @@ impl CalcState {
         self.variables.push(Variable::new(&name, val));
     }
 
+    /// Enables caching of evaluation results. When an expression is evaluated
+    /// again and the variables it uses have not changed, the saved result is
+    /// returned without calculation. When the cache is full, the least recently
+    /// used result is removed
+    pub fn enable_cache(&mut self, capacity: usize) {
+        self.cache = Some(ResultCache::new(capacity));
+    }
+
+    /// Disables caching and removes all saved results
+    pub fn disable_cache(&mut self) {
+        self.cache = None;
+    }
+
     /// Returns the result of the last successful evaluation
     pub fn result(&self) -> Option<Value> {
         self.variable(LAST_RESULT)
@@ -98,7 +121,7 @@ impl CalcState {
     /// - does not conflict with special variables, like `ans`
     pub fn variable_name_validate(&self, name: &str) -> Result<(), &'static str> {
         let name = name.to_lowercase();
-        if let Some(p) = name.find(|c: char| ('a'..='z').contains(&c)) {
+        if let Some(p) = name.find(|c: char| c.is_ascii_lowercase()) {
             if p != 0 {
                 return Err("Variable name must start with 'a'..'z'");
             }
@@ -106,7 +129,7 @@ impl CalcState {
             return Err("Variable name must start with 'a'..'z'");
         }
 
-        let p = name.find(|c: char| c != '_' && !('0'..='9').contains(&c) && !('a'..='z').contains(&c));
+        let p = name.find(|c: char| c != '_' && !c.is_ascii_digit() && !c.is_ascii_lowercase());
         if p.is_some() {
             return Err("Variable name must contain only Latin letters, digits, and underscore");
         }
@@ -118,47 +141,83 @@ impl CalcState {
             return Err("The name is reserved for an internal variable");
         }
 
-        for fname in STD_FUNCS.iter() {
-            if fname == &name {
-                return Err("Function name cannot be used as a variable");
-            }
+        if find_func(&name).is_some() || resolve_alias(&name, LogBase::default()).is_some() {
+            return Err("Function name cannot be used as a variable");
         }
 
         Ok(())
     }
 }
 
+// what was the previous token of the expression: used to detect omitted
+// multiplication signs and brackets
+#[derive(Default)]
+struct ParseFlags {
+    is_last_value: bool,
+    is_last_func: bool,
+    // the last function requires arguments, so it can take one without
+    // brackets: `sin 0.5`
+    takes_args: bool,
+}
+
+// a function name followed by a token that starts its argument: a value, a
+// name, or a unary operator. Brackets start a regular call
+fn starts_implicit_arg(rule: Rule, flags: &ParseFlags) -> bool {
+    flags.is_last_func
+        && flags.takes_args
+        && !matches!(
+            rule,
+            Rule::open_b | Rule::open_l | Rule::close_b | Rule::close_l | Rule::arg_sep | Rule::slice_sep
+        )
+}
+
+// a token that ends an argument passed without brackets: a binary operator,
+// a separator, a closing bracket, a function, or a value after whitespace.
+// A value or a bracket right after a value continues the argument, so
+// `sin 2x` is `sin(2*x)`, and `sin 2 x` is `sin(2)*x`
+fn ends_implicit_arg(pair: &PrepRule, flags: &ParseFlags, is_func: bool) -> bool {
+    match pair.r {
+        Rule::operator => flags.is_last_value,
+        Rule::complex => flags.is_last_value && pair.v.starts_with('-'),
+        Rule::arg_sep | Rule::slice_sep | Rule::close_b | Rule::close_l => true,
+        Rule::ident if is_func => flags.is_last_value,
+        _ => flags.is_last_value && !pair.joined,
+    }
+}
+
 macro_rules! process_value {
-    ($id: ident, $stack: ident, $state: ident, $val: ident) => {
-        if $state.is_last_func {
+    ($id: ident, $stack: ident, $flags: ident, $val: ident) => {
+        if $flags.is_last_func {
             $stack.push("(", None)?;
-        } else if $state.is_last_value {
+        } else if $flags.is_last_value {
             $stack.push("*", None)?;
         }
         let v = Value::$id(&$val)?;
         $stack.push("", Some(v))?;
-        if $state.is_last_func {
+        if $flags.is_last_func {
             $stack.push(")", None)?;
         }
-        $state.is_last_value = true;
-        $state.is_last_func = false;
+        $flags.is_last_value = true;
+        $flags.is_last_func = false;
     };
 }
 
 struct PrepRule {
     r: Rule,
     v: String,
+    // the token follows the previous one without whitespace: `2x`
+    joined: bool,
 }
 
-fn fixup_last_prc_op(pairs: &mut Vec<PrepRule>) -> bool {
+fn fixup_last_prc_op(pairs: &mut [PrepRule]) -> bool {
     let mut id: usize = pairs.len() - 1;
     let mut level = 0;
     while id > 0 {
         let r = pairs[id].r;
         let v = pairs[id].v.clone();
         match r {
-            Rule::close_b => level += 1,
-            Rule::open_b => level -= 1,
+            Rule::close_b | Rule::close_l => level += 1,
+            Rule::open_b | Rule::open_l => level -= 1,
             Rule::operator if level == 0 => match v.as_str() {
                 "+" => {
                     pairs[id].v = PERCENT_ADD.to_string();
@@ -185,41 +244,179 @@ fn fixup_last_prc_op(pairs: &mut Vec<PrepRule>) -> bool {
     false
 }
 
-fn preprocess_expr(expr: &str) -> Result<Vec<PrepRule>, CalcError> {
-    let pairs = match CalcParser::parse(Rule::expr, expr) {
-        Ok(p) => p,
+fn parse_tokens(expr: &str) -> Result<Pairs<'_, Rule>, CalcError> {
+    match CalcParser::parse(Rule::expr, expr) {
+        Ok(p) => Ok(p),
         // detailed error from pest parser
         // Err(e) => return Err(CalcError::ParseFailed(e.to_string())),
         // rcalc own error
-        Err(..) => return Err(CalcError::ParseFailed("invalid expression".to_string())),
+        Err(..) => Err(CalcError::ParseFailed("invalid expression".to_string())),
+    }
+}
+
+// text of a token: numbers are caseinsensitive, string literals keep their
+// case, and names are resolved by the calculator(see `Calc::ident_name`).
+// `^` becomes XOR `^^` if the calculator options say so
+fn token_str(pair: &Pair<'_, Rule>, calc: &Calc) -> String {
+    let s = pair.as_span().as_str();
+    match pair.as_rule() {
+        Rule::string => s.to_string(),
+        Rule::ident => calc.ident_name(s),
+        Rule::operator if s == "^" && calc.caret == Caret::Xor => "^^".to_string(),
+        _ => s.to_lowercase(),
+    }
+}
+
+// replaces the argument separator and brackets chosen in the calculator
+// options with the standard ones, and math symbols pasted from documents
+// with their ASCII forms. String literals are not changed
+pub(crate) fn normalize_syntax<'a>(expr: &'a str, calc: &Calc) -> Cow<'a, str> {
+    let comma = calc.arg_sep == ArgSeparator::Comma;
+    let curly = calc.brackets != Brackets::Round;
+    let square = calc.brackets == Brackets::All;
+    if !comma && !curly && expr.is_ascii() {
+        return Cow::Borrowed(expr);
+    }
+    let mut in_str = false;
+    let mut in_power = false;
+    let mut s = String::with_capacity(expr.len());
+    for c in expr.chars() {
+        // a run of superscript digits is an exponent: `x²` is `x**2`
+        let sup = if in_str { None } else { superscript(c) };
+        if let Some(d) = sup {
+            if !in_power {
+                s.push_str("**");
+                in_power = true;
+            }
+            s.push(d);
+            continue;
+        }
+        in_power = false;
+        match c {
+            '"' => {
+                in_str = !in_str;
+                s.push(c);
+            }
+            _ if in_str => s.push(c),
+            ',' if comma => s.push(';'),
+            '{' if curly => s.push('('),
+            '}' if curly => s.push(')'),
+            '[' if square => s.push('('),
+            ']' if square => s.push(')'),
+            '×' => s.push('*'),
+            '÷' => s.push('/'),
+            '−' => s.push('-'),
+            '≤' => s.push_str("<="),
+            '≥' => s.push_str(">="),
+            '≠' => s.push_str("!="),
+            // spaces keep the function name apart from its neighbours:
+            // `2√x` is `2 sqrt x`
+            '√' => s.push_str(" sqrt "),
+            // brackets keep a constant a separate value, so `2πr` is
+            // `2(pi)r` and `sin 2π` is `sin(2*pi)`
+            'π' => s.push_str("(pi)"),
+            'τ' => s.push_str("(2*pi)"),
+            'φ' => s.push_str("(phi)"),
+            'ℯ' => s.push_str("(e)"),
+            '∞' => s.push_str("(inf)"),
+            _ => match vulgar_fraction(c) {
+                // a fraction after an integer is its fractional part: `1½`
+                // is `1\1\2`
+                Some(frac) if ends_with_int(&s) => {
+                    s.push('\\');
+                    s.push_str(frac);
+                }
+                // brackets separate it from a preceding name or number
+                Some(frac) if s.ends_with(|c: char| c.is_alphanumeric() || "_.,)".contains(c)) => {
+                    s.push('(');
+                    s.push_str(frac);
+                    s.push(')');
+                }
+                Some(frac) => s.push_str(frac),
+                None => s.push(c),
+            },
+        }
+    }
+    Cow::Owned(s)
+}
+
+fn vulgar_fraction(c: char) -> Option<&'static str> {
+    let frac = match c {
+        '½' => "1\\2",
+        '⅓' => "1\\3",
+        '⅔' => "2\\3",
+        '¼' => "1\\4",
+        '¾' => "3\\4",
+        '⅕' => "1\\5",
+        '⅖' => "2\\5",
+        '⅗' => "3\\5",
+        '⅘' => "4\\5",
+        '⅙' => "1\\6",
+        '⅚' => "5\\6",
+        '⅐' => "1\\7",
+        '⅛' => "1\\8",
+        '⅜' => "3\\8",
+        '⅝' => "5\\8",
+        '⅞' => "7\\8",
+        '⅑' => "1\\9",
+        '⅒' => "1\\10",
+        _ => return None,
     };
+    Some(frac)
+}
+
+// true if the text ends with an integer number, not with a part of a name
+// or of a float number
+fn ends_with_int(s: &str) -> bool {
+    let head = s.trim_end_matches(|c: char| c.is_ascii_digit());
+    head.len() < s.len()
+        && !head.ends_with(|c: char| c.is_alphanumeric() || c == '_' || c == '.' || c == ',' || c == '\\')
+}
+
+fn superscript(c: char) -> Option<char> {
+    match c {
+        '⁰' => Some('0'),
+        '¹' => Some('1'),
+        '²' => Some('2'),
+        '³' => Some('3'),
+        '⁴'..='⁹' => char::from_u32(c as u32 - '⁴' as u32 + '4' as u32),
+        '⁻' => Some('-'),
+        _ => None,
+    }
+}
+
+fn preprocess_expr(expr: &str, calc: &Calc) -> Result<Vec<PrepRule>, CalcError> {
+    let pairs = parse_tokens(expr)?;
     let mut is_last_prc = false;
     let mut preps: Vec<PrepRule> = Vec::new();
+    let mut last_end = usize::MAX;
     for pair in pairs {
         let rule = pair.as_rule();
-        let val = pair.as_span().as_str().to_lowercase();
+        let val = token_str(&pair, calc);
+        let joined = pair.as_span().start() == last_end;
+        last_end = pair.as_span().end();
         match rule {
-            Rule::close_b | Rule::arg_sep | Rule::operator => {
+            Rule::close_b | Rule::close_l | Rule::arg_sep | Rule::operator => {
                 let is_prc = val == "%";
                 if is_last_prc {
                     let _ = preps.pop();
                     if !fixup_last_prc_op(&mut preps) {
-                        preps.push(PrepRule { r: Rule::operator, v: "%".to_string() });
+                        preps.push(PrepRule { r: Rule::operator, v: "%".to_string(), joined: false });
                     }
                 }
-                preps.push(PrepRule { r: rule, v: val });
+                preps.push(PrepRule { r: rule, v: val, joined });
                 is_last_prc = is_prc;
             }
             _ => {
                 is_last_prc = val == "%";
-                preps.push(PrepRule { r: rule, v: val });
+                preps.push(PrepRule { r: rule, v: val, joined });
             }
         }
     }
     if is_last_prc {
         let _ = preps.pop();
         if !fixup_last_prc_op(&mut preps) {
-            preps.push(PrepRule { r: Rule::operator, v: "%".to_string() });
+            preps.push(PrepRule { r: Rule::operator, v: "%".to_string(), joined: false });
         };
     }
     Ok(preps)
@@ -227,119 +424,394 @@ fn preprocess_expr(expr: &str) -> Result<Vec<PrepRule>, CalcError> {
 
 /// evaluates a given expression and returns either result or error
 pub fn eval(expr: &str, state: &mut CalcState) -> CalcResult {
-    state.is_last_value = false;
-    state.is_last_func = false;
-    state.has_alt = false;
+    Calc::new().eval(expr, state)
+}
+
+// returns names of all variables used in an expression
+pub(crate) fn referenced_names(expr: &str, calc: &Calc, state: &CalcState) -> Result<Vec<String>, CalcError> {
+    let mut names: Vec<String> = Vec::new();
+    for pair in parse_tokens(expr)? {
+        if pair.as_rule() != Rule::ident {
+            continue;
+        }
+        let name = calc.ident_name(pair.as_span().as_str());
+        if find_func(&name).is_some() || state.constant(&name).is_some() || names.contains(&name) {
+            continue;
+        }
+        names.push(name);
+    }
+    Ok(names)
+}
 
-    let rules = preprocess_expr(expr)?;
+// returns true if the expression calls a function which result changes
+// from call to call, or a function that evaluates an expression passed as
+// a string. Aliases are resolved, so `jetzt()` is a call of `now`
+pub(crate) fn calls_volatile(expr: &str, calc: &Calc) -> Result<bool, CalcError> {
+    for pair in parse_tokens(expr)? {
+        if pair.as_rule() != Rule::ident {
+            continue;
+        }
+        let name = calc.ident_name(pair.as_span().as_str());
+        if VOLATILE_FUNCS.contains(&name.as_str()) || FORMULA_FUNCS.contains(&name.as_str()) {
+            return Ok(true);
+        }
+    }
+    Ok(false)
+}
+
+/// parses a given expression without evaluating it. The result can be
+/// evaluated later or dumped in postfix or S-expression form
+pub fn compile(expr: &str, state: &mut CalcState) -> Result<Expr, CalcError> {
+    Calc::new().compile(expr, state)
+}
+
+// converts an expression to postfix form. Variables are not resolved
+// at this stage: their values are requested during evaluation
+pub(crate) fn compile_infix(expr: &str, calc: &Calc) -> Result<Expr, CalcError> {
+    let mut flags = ParseFlags::default();
+    let sandbox = calc.sandbox.as_ref();
+    let limits = calc.parse_limits();
+    if expr.len() > limits.max_length {
+        return Err(CalcError::ExprTooLong(limits.max_length));
+    }
+
+    let expr = normalize_syntax(expr, calc);
+    let rules = preprocess_expr(&expr, calc)?;
+    if rules.len() > limits.max_tokens {
+        return Err(CalcError::TooManyTokens(limits.max_tokens));
+    }
     let mut stk = Stack::new();
-    for pair in rules {
+    let mut depth = 0usize;
+    // bracket depths of function arguments written without brackets
+    let mut implicit: Vec<usize> = Vec::new();
+    let mut last_rule: Option<Rule> = None;
+    let mut rules = rules.into_iter().peekable();
+    while let Some(pair) = rules.next() {
+        let prev_rule = last_rule.replace(pair.r);
+        let ends_arg = !implicit.is_empty() && ends_implicit_arg(&pair, &flags, stk.is_func(&pair.v));
         let rule = pair.r;
         let val = pair.v;
+        if let Some(sb) = sandbox {
+            if rule == Rule::fulluint {
+                sb.check_literal(&val)?;
+            }
+        }
+        if starts_implicit_arg(rule, &flags) {
+            stk.push("(", None)?;
+            implicit.push(depth);
+            flags.is_last_func = false;
+        } else if ends_arg {
+            while implicit.last() == Some(&depth) {
+                stk.push(")", None)?;
+                implicit.pop();
+            }
+        }
         match rule {
             Rule::int | Rule::fulluint | Rule::hex | Rule::bin | Rule::oct => {
-                process_value!(from_str_integer, stk, state, val);
+                process_value!(from_str_integer, stk, flags, val);
+            }
+            Rule::float if calc.exact => {
+                process_value!(from_str_decimal, stk, flags, val);
             }
             Rule::float => {
-                process_value!(from_str_float, stk, state, val);
+                process_value!(from_str_float, stk, flags, val);
             }
             Rule::ratio => {
-                process_value!(from_str_ratio, stk, state, val);
+                process_value!(from_str_ratio, stk, flags, val);
+            }
+            Rule::degreefloat | Rule::fulldegree if calc.angle == Angle::Deg => {
+                process_value!(from_str_degrees, stk, flags, val);
             }
             Rule::degreefloat | Rule::fulldegree => {
-                process_value!(from_str_angle, stk, state, val);
+                process_value!(from_str_angle, stk, flags, val);
+            }
+            Rule::duration => {
+                process_value!(from_str_duration, stk, flags, val);
+            }
+            Rule::string => {
+                process_value!(from_str_quoted, stk, flags, val);
             }
             Rule::complex => {
                 // distinguish between "1 - 2+i4" and "1 - -2+i4"
                 #[allow(clippy::branches_sharing_code)]
-                if state.is_last_value && val.starts_with('-') {
+                if flags.is_last_value && val.starts_with('-') {
                     stk.push("-", None)?;
                     let slice = val[1..].to_string();
-                    state.is_last_value = false;
-                    state.is_last_func = false;
-                    process_value!(from_str_complex, stk, state, slice);
+                    flags.is_last_value = false;
+                    flags.is_last_func = false;
+                    process_value!(from_str_complex, stk, flags, slice);
                 } else {
-                    process_value!(from_str_complex, stk, state, val);
+                    process_value!(from_str_complex, stk, flags, val);
                 }
             }
             Rule::open_b => {
-                if state.is_last_value {
+                depth += 1;
+                if depth > limits.max_depth {
+                    return Err(CalcError::ExprTooDeep(limits.max_depth));
+                }
+                // a function call without arguments: `now()`
+                if flags.is_last_func && rules.peek().is_some_and(|p| p.r == Rule::close_b) {
+                    let _ = rules.next();
+                    last_rule = Some(Rule::close_b);
+                    depth -= 1;
+                    flags.is_last_value = true;
+                    flags.is_last_func = false;
+                    continue;
+                }
+                if flags.is_last_value {
                     stk.push("*", None)?;
                 }
                 stk.push("(", None)?;
-                state.is_last_value = false;
-                state.is_last_func = false;
+                flags.is_last_value = false;
+                flags.is_last_func = false;
+            }
+            // an index after a variable or a list, string, or expression:
+            // `xs[1]` is `item(xs; 1)`
+            Rule::open_l
+                if flags.is_last_value
+                    && matches!(prev_rule, Some(Rule::ident | Rule::string | Rule::close_b | Rule::close_l)) =>
+            {
+                if rules.peek().is_some_and(|p| p.r == Rule::close_l) {
+                    return Err(CalcError::FunctionNotEnoughArgs(ITEM_FUNC.to_string(), 2));
+                }
+                depth += 1;
+                if depth > limits.max_depth {
+                    return Err(CalcError::ExprTooDeep(limits.max_depth));
+                }
+                // the index applies to the result of a function call
+                stk.pop_functions()?;
+                stk.push(ITEM_FUNC, None)?;
+                stk.increase_func_argc()?;
+                stk.push("(", None)?;
+                flags.is_last_value = false;
+                flags.is_last_func = false;
             }
-            Rule::close_b => {
+            // a list `[1; 2]` is a call of function `list(1; 2)`
+            Rule::open_l => {
+                if flags.is_last_func {
+                    stk.increase_func_argc()?;
+                } else if flags.is_last_value {
+                    stk.push("*", None)?;
+                }
+                if rules.peek().is_some_and(|p| p.r == Rule::close_l) {
+                    let _ = rules.next();
+                    last_rule = Some(Rule::close_l);
+                    stk.push("", Some(Value::List(Vec::new())))?;
+                    flags.is_last_value = true;
+                    flags.is_last_func = false;
+                    continue;
+                }
+                depth += 1;
+                if depth > limits.max_depth {
+                    return Err(CalcError::ExprTooDeep(limits.max_depth));
+                }
+                stk.push(LIST_FUNC, None)?;
+                stk.push("(", None)?;
+                flags.is_last_value = false;
+                flags.is_last_func = false;
+            }
+            Rule::close_b | Rule::close_l => {
+                // a slice without the end: `xs[2:]`
+                if prev_rule == Some(Rule::slice_sep) {
+                    stk.push("", Some(Value::Float(f64::INFINITY)))?;
+                }
+                depth = depth.saturating_sub(1);
                 stk.push(")", None)?;
-                state.is_last_value = true;
-                state.is_last_func = false;
+                flags.is_last_value = true;
+                flags.is_last_func = false;
             }
             Rule::arg_sep => {
                 stk.push(";", None)?;
-                state.is_last_value = false;
-                state.is_last_func = false;
+                flags.is_last_value = false;
+                flags.is_last_func = false;
+            }
+            // a slice without the start: `xs[:2]`
+            Rule::slice_sep => {
+                if !flags.is_last_value {
+                    stk.push("", Some(Value::from(0)))?;
+                }
+                stk.push_slice()?;
+                flags.is_last_value = false;
+                flags.is_last_func = false;
             }
             Rule::operator => {
-                if val == "+" && !state.is_last_value {
-                    state.is_last_value = false;
-                } else if val == "-" && (!state.is_last_value || state.is_last_func) {
-                    if state.is_last_func {
+                if val == "+" && !flags.is_last_value {
+                    flags.is_last_value = false;
+                } else if val == "-" && (!flags.is_last_value || flags.is_last_func) {
+                    if flags.is_last_func {
                         stk.push("(", None)?;
                         stk.push(")", None)?;
                         stk.push("-", None)?;
                     } else {
                         stk.push(UNARY_MINUS, None)?;
                     }
-                    state.is_last_value = false;
-                } else if val == "!" && state.is_last_value {
+                    flags.is_last_value = false;
+                } else if val == "!" && flags.is_last_value {
                     stk.push(FACTORIAL, None)?;
-                    state.is_last_value = true;
+                    flags.is_last_value = true;
                 } else {
                     stk.push(&val, None)?;
-                    state.is_last_value = false;
+                    flags.is_last_value = false;
                 }
-                state.is_last_func = false;
+                flags.is_last_func = false;
+            }
+            Rule::ident if val == STEP && flags.is_last_value && stk.in_range() => {
+                stk.push_step()?;
+                flags.is_last_value = false;
+                flags.is_last_func = false;
             }
             Rule::ident => {
                 if stk.is_func(&val) {
-                    if state.is_last_value {
+                    if flags.is_last_value {
                         stk.push("*", None)?;
-                    } else if state.is_last_func {
+                    } else if flags.is_last_func {
                         stk.increase_func_argc()?;
                     }
                     stk.push(&val, None)?;
-                    state.is_last_value = false;
-                    state.is_last_func = true;
+                    flags.is_last_value = false;
+                    flags.is_last_func = true;
+                    flags.takes_args = find_func(&val).is_some_and(|f| f.min_args > 0);
                 } else {
-                    if let Some(v) = state.constant(&val) {
-                        stk.push("", Some(v))?;
-                    } else if let Some(v) = state.variable(&val) {
+                    // a coefficient before a name: `2x`
+                    if flags.is_last_value {
+                        stk.push("*", None)?;
+                    }
+                    if let Some(v) = constant(&val) {
                         stk.push("", Some(v))?;
                     } else {
-                        return Err(CalcError::VarUndeclared(val.to_string()));
+                        stk.push_var(&val);
                     }
-                    state.is_last_value = true;
-                    state.is_last_func = false;
+                    flags.is_last_value = true;
+                    flags.is_last_func = false;
                 }
             }
             _ => return Err(CalcError::Unreachable),
         }
     }
-    let output = stk.calculate();
-    if let Ok(ref v) = output {
-        state.add_variable(LAST_RESULT, v.clone());
-        if stk.has_alt {
-            state.has_alt = true;
-            state.alt_result = stk.alt_result;
+    for _ in implicit {
+        stk.push(")", None)?;
+    }
+    let entries = stk.into_postfix()?;
+    if let Some(sb) = sandbox {
+        sb.check_entries(&entries)?;
+    }
+    Ok(Expr::new(entries))
+}
+
+/// evaluates an expression written in reverse Polish notation(e.g, `2 3 + 4 *`)
+/// and returns either result or error.
+///
+/// Values, constants, and variables are pushed to the value stack as is, and every
+/// operator or function is applied immediately to the values on top of the stack.
+/// Differences from the regular expressions:
+/// * brackets and argument separators are not allowed
+/// * `-` is always a binary operator. A minus sign written right before a number
+///   without spaces makes the number negative: `2 -3 *` = `-6`
+/// * `!` is factorial
+/// * a function takes a fixed number of arguments, e.g. `clamp` takes three and `gcd`
+///   takes two. `Calc::functions()` lists the number for every function(`rpn_args`)
+pub fn eval_rpn(expr: &str, state: &mut CalcState) -> CalcResult {
+    let e = compile_rpn(expr, state)?;
+    e.eval(state)
+}
+
+/// parses a given expression written in reverse Polish notation without evaluating it
+pub fn compile_rpn(expr: &str, state: &mut CalcState) -> Result<Expr, CalcError> {
+    Calc::new().compile_rpn(expr, state)
+}
+
+pub(crate) fn compile_postfix(expr: &str, calc: &Calc) -> Result<Expr, CalcError> {
+    let sandbox = calc.sandbox.as_ref();
+    let limits = calc.parse_limits();
+    if expr.len() > limits.max_length {
+        return Err(CalcError::ExprTooLong(limits.max_length));
+    }
+
+    let pairs: Vec<_> = parse_tokens(expr)?.collect();
+    if pairs.len() > limits.max_tokens {
+        return Err(CalcError::TooManyTokens(limits.max_tokens));
+    }
+    let mut stk = Stack::new();
+    let mut negate_next = false;
+    for (idx, pair) in pairs.iter().enumerate() {
+        let rule = pair.as_rule();
+        let val = token_str(pair, calc);
+        if let Some(sb) = sandbox {
+            if rule == Rule::fulluint {
+                sb.check_literal(&val)?;
+            }
         }
+        let v = match rule {
+            Rule::int | Rule::fulluint | Rule::hex | Rule::bin | Rule::oct => Value::from_str_integer(&val)?,
+            Rule::float if calc.exact => Value::from_str_decimal(&val)?,
+            Rule::float => Value::from_str_float(&val)?,
+            Rule::ratio => Value::from_str_ratio(&val)?,
+            Rule::degreefloat | Rule::fulldegree if calc.angle == Angle::Deg => Value::from_str_degrees(&val)?,
+            Rule::degreefloat | Rule::fulldegree => Value::from_str_angle(&val)?,
+            Rule::duration => Value::from_str_duration(&val)?,
+            Rule::complex => Value::from_str_complex(&val)?,
+            Rule::string => Value::from_str_quoted(&val)?,
+            Rule::open_b | Rule::close_b | Rule::open_l | Rule::close_l | Rule::arg_sep => {
+                return Err(CalcError::InvalidOp(val))
+            }
+            Rule::operator => {
+                let is_sign = val == "-"
+                    && pairs.get(idx + 1).is_some_and(|next| {
+                        next.as_span().start() == pair.as_span().end()
+                            && matches!(
+                                next.as_rule(),
+                                Rule::int
+                                    | Rule::fulluint
+                                    | Rule::hex
+                                    | Rule::bin
+                                    | Rule::oct
+                                    | Rule::float
+                                    | Rule::ratio
+                                    | Rule::degreefloat
+                                    | Rule::fulldegree
+                                    | Rule::duration
+                            )
+                    });
+                if is_sign {
+                    negate_next = true;
+                } else if val == "!" {
+                    stk.push_rpn(FACTORIAL, None)?;
+                } else {
+                    stk.push_rpn(&val, None)?;
+                }
+                continue;
+            }
+            Rule::ident => {
+                if stk.is_func(&val) {
+                    stk.push_rpn(&val, None)?;
+                    continue;
+                }
+                match constant(&val) {
+                    Some(v) => v,
+                    None => {
+                        stk.push_var(&val);
+                        continue;
+                    }
+                }
+            }
+            _ => return Err(CalcError::Unreachable),
+        };
+        let v = if negate_next { v.negate()? } else { v };
+        negate_next = false;
+        stk.push_rpn("", Some(v))?;
+    }
+    let entries = stk.into_postfix()?;
+    if let Some(sb) = sandbox {
+        sb.check_entries(&entries)?;
     }
-    output
+    Ok(Expr::new(entries))
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::expr::Token;
+    use crate::sandbox::Limits;
     use num_bigint::BigInt;
     use num_complex::Complex;
     use num_rational::BigRational;
@@ -383,6 +855,824 @@ mod tests {
         assert_eq!(v, Ok(Value::Float(200.0)));
     }
 
+    #[test]
+    fn test_arithmetic_functions() {
+        let mut state = CalcState::new();
+        let v = eval("clamp(15; 0; 10) + lerp(0; 10; 0.5) + wrap(-90; 0; 360)", &mut state);
+        assert_eq!(v, Ok(Value::Int(BigInt::from(285))));
+        let v = eval("ldexp(3; 2) + copysign(2; -0.0) + fma(2; 3; 4)", &mut state);
+        assert_eq!(v, Ok(Value::Int(BigInt::from(20))));
+        let v = eval("frexp(12)", &mut state).unwrap();
+        assert_eq!(v.to_string(), "[0.75; 4]");
+        let v = eval("-7 % 3 + mod(-7; 3)", &mut state);
+        assert_eq!(v, Ok(Value::Int(BigInt::from(1))));
+        let v = eval("divmod(-7; 3)", &mut state).unwrap();
+        assert_eq!(v.to_string(), "[-3; 2]");
+        let v = eval("as_fraction(0.333333) + as_fraction(pi; 100)", &mut state).unwrap();
+        assert_eq!(v.to_string(), "3\\47\\99");
+    }
+
+    #[test]
+    fn test_trig_functions() {
+        let mut state = CalcState::new();
+        let v = eval("round(cot(pi/4) + sec 0 + 1000 * acoth 2)", &mut state);
+        assert_eq!(v, Ok(Value::Int(BigInt::from(551))));
+        let v = eval("sinc 0 + sigmoid(0) + smoothstep(0; 4; 2)", &mut state);
+        assert_eq!(v, Ok(Value::Int(BigInt::from(2))));
+        let v = eval("dms(fromdms(12; 30.5) + fromdms(-0; 0; 15))", &mut state).unwrap();
+        assert_eq!(v.to_string(), "[12; 30; 45]");
+        assert_eq!(state.alt_result, "12°30'45\"");
+        let v = eval("dms(-1.75)", &mut state).unwrap();
+        assert_eq!(v.to_string(), "[-1; 45; 0]");
+        assert_eq!(state.alt_result, "-1°45'0\"");
+        let v = eval("arg(1+1i) * 4 - phase(-1)", &mut state);
+        assert_eq!(v, Ok(Value::Float(0.0)));
+        let v = eval("topolar(0; -2)", &mut state).unwrap();
+        assert_eq!(v.to_string(), "[2; -1.5707963267948966]");
+        let v = eval("tocart(2; 0d)", &mut state).unwrap();
+        assert_eq!(v.to_string(), "[2; 0.0]");
+        assert_eq!(eval("sin()", &mut state), Err(CalcError::FunctionNoArgs("sin".to_string())));
+    }
+
+    #[test]
+    fn test_integer_functions() {
+        let mut state = CalcState::new();
+        let v = eval("harmonic(4) + harmonic(0)", &mut state).unwrap();
+        assert_eq!(v.to_string(), "2\\1\\12");
+        let v = eval("stirling1(5; 2) + stirling2(5; 2) + bell(5)", &mut state);
+        assert_eq!(v, Ok(Value::Int(BigInt::from(17))));
+        let v = eval("primorial(10) + multifact(7; 2)", &mut state);
+        assert_eq!(v, Ok(Value::Int(BigInt::from(315))));
+        let v = eval("egcd(35; 15)", &mut state).unwrap();
+        assert_eq!(v.to_string(), "[5; 1; -2]");
+        let v = eval("modinv(3; 7) + modpow(2; 100; 13)", &mut state);
+        assert_eq!(v, Ok(Value::Int(BigInt::from(8))));
+        let v = eval("collatz(27)", &mut state);
+        assert_eq!(v, Ok(Value::Int(BigInt::from(111))));
+        let v = eval("digitsum(2**100) + numdigits(255; 16) + revdigits(120)", &mut state);
+        assert_eq!(v, Ok(Value::Int(BigInt::from(138))));
+        let v = eval("ilog2(2**100) + ilog10(10**50 - 1) + ilog(1000; 3) + nextpow2(100)", &mut state);
+        assert_eq!(v, Ok(Value::Int(BigInt::from(283))));
+        let v = eval("bell(-1)", &mut state);
+        assert_eq!(v, Err(CalcError::NotForNegativeInt("bell".to_string())));
+    }
+
+    #[test]
+    fn test_roman_and_words() {
+        let mut state = CalcState::new();
+        let v = eval("roman(1990)", &mut state);
+        assert_eq!(v, Ok(Value::Str("MCMXC".to_string())));
+        let v = eval("unroman(\"MMXXIV\") + unroman( \"xiv\" )", &mut state);
+        assert_eq!(v, Ok(Value::Int(BigInt::from(2038))));
+        let v = eval("roman(12) * 2", &mut state);
+        assert_eq!(v, Err(CalcError::StrToNumber("XII".to_string())));
+        let v = eval_rpn("\"MCMXC\" unroman", &mut state);
+        assert_eq!(v, Ok(Value::Int(BigInt::from(1990))));
+        let v = eval("spell(2**10)", &mut state);
+        assert_eq!(v, Ok(Value::Str("one thousand twenty-four".to_string())));
+        let v = eval("spell(1024; \"en-GB\")", &mut state);
+        assert_eq!(v, Ok(Value::Str("one thousand and twenty-four".to_string())));
+    }
+
+    #[test]
+    fn test_checksums() {
+        let mut state = CalcState::new();
+        let v = eval("crc32(\"123456789\")", &mut state);
+        assert_eq!(v, Ok(Value::Int(BigInt::from(0xCBF4_3926u32))));
+        assert_eq!(state.alt_result, "0xcbf43926");
+        let v = eval("murmur3(\"hello\")", &mut state);
+        assert_eq!(v, Ok(Value::Int(BigInt::from(0x248B_FA47u32))));
+        assert_eq!(state.alt_result, "0x248bfa47");
+        let v = eval("fnv1a(\"\")", &mut state);
+        assert_eq!(v, Ok(Value::Int(BigInt::from(0xCBF2_9CE4_8422_2325u64))));
+        assert_eq!(state.alt_result, "0xcbf29ce484222325");
+        let v = eval("luhn(\"4539 1488 0343 6467\") + luhn(123)", &mut state);
+        assert_eq!(v, Ok(Value::Int(BigInt::from(1))));
+    }
+
+    #[test]
+    fn test_strings() {
+        let mut state = CalcState::new();
+        let v = eval("\"x = \" + 2**3 + \", len = \" + (len(\"héllo\") + len([1; 2]))", &mut state);
+        assert_eq!(v, Ok(Value::Str("x = 8, len = 7".to_string())));
+        let v = eval("\"total: \" + format(1234.5; \",.2f\") + \" (\" + format(0.25; \".0%\") + \")\"", &mut state);
+        assert_eq!(v, Ok(Value::Str("total: 1,234.50 (25%)".to_string())));
+        let v = eval("fmt(1234.5; \"%.3e\") + fmt(2.5; \" |{:>6.2}|\")", &mut state);
+        assert_eq!(v, Ok(Value::Str("1.234e+03 |  2.50|".to_string())));
+    }
+
+    #[test]
+    fn test_encodings() {
+        let mut state = CalcState::new();
+        let v = eval("unb64(b64(\"Hi!\"))", &mut state);
+        assert_eq!(v, Ok(Value::Str("Hi!".to_string())));
+        let v = eval("hex(\"Hi\")", &mut state);
+        assert_eq!(v, Ok(Value::Str("4869".to_string())));
+        let v = eval("unhex(\"0xff\") + unb64(\"AQA=\")", &mut state);
+        assert_eq!(v, Ok(Value::Int(BigInt::from(511))));
+    }
+
+    #[test]
+    fn test_bit_functions() {
+        let mut state = CalcState::new();
+        let v = eval("signed(0xFFFE; 16) + unsigned(-1; 8)", &mut state);
+        assert_eq!(v, Ok(Value::Int(BigInt::from(253))));
+        let v = eval("bits(0xABCD; 11; 4) + setbits(0xFF00; 7; 4; 0b1010)", &mut state);
+        assert_eq!(v, Ok(Value::Int(BigInt::from(0xBC + 0xFFA0))));
+        let v = eval("bswap32(0x12345678) - bswap(0x7856; 16)", &mut state);
+        assert_eq!(v, Ok(Value::Int(BigInt::from(0x7856_3412 - 0x5678))));
+    }
+
+    #[test]
+    fn test_durations() {
+        let mut state = CalcState::new();
+        let v = eval("3 * 1h15m", &mut state);
+        assert_eq!(v, Ok(Value::Int(BigInt::from(13_500))));
+        // angles and implicit multiplication are not durations
+        let v = eval("1d30m", &mut state);
+        assert_eq!(v, Value::from_str_angle("1d30m"));
+        let v = eval("2sin(0)", &mut state);
+        assert_eq!(v, Ok(Value::Float(0.0)));
+        let v = eval("1.5h + 2.5m", &mut state);
+        assert_eq!(v, Ok(Value::Int(BigInt::from(5_550))));
+        // a unit right after a number is never a variable
+        state.add_variable("m", Value::Int(BigInt::from(4)));
+        let v = eval("2m", &mut state);
+        assert_eq!(v, Ok(Value::Int(BigInt::from(120))));
+        let v = eval("2 m", &mut state);
+        assert_eq!(v, Ok(Value::Int(BigInt::from(8))));
+        let v = eval("2*m", &mut state);
+        assert_eq!(v, Ok(Value::Int(BigInt::from(8))));
+    }
+
+    #[test]
+    fn test_regression() {
+        let mut state = CalcState::new();
+        let v = eval("linreg([0; 1; 2]; [1; 3; 5])", &mut state);
+        assert_eq!(v, Ok(Value::List(vec![Value::Float(2.0), Value::Float(1.0), Value::Float(1.0)])));
+        let v = eval("corr([1; 2; 3]; [2; 4; 6]) + cov([1; 2; 3]; [2; 4; 6])", &mut state);
+        assert_eq!(v, Ok(Value::Int(BigInt::from(3))));
+    }
+
+    #[test]
+    fn test_matrices() {
+        let mut state = CalcState::new();
+        let v = eval("solve([[2; 1]; [4; 3]]; [1; 5])", &mut state);
+        assert_eq!(v.map(|v| v.to_string()), Ok("[-1; 3]".to_string()));
+        let v = eval("det([[1; 2]; [3; 4]]) * 2", &mut state);
+        assert_eq!(v, Ok(Value::Int(BigInt::from(-4))));
+        let v = eval("rank(inv([[0; 1; 2]; [1; 0; 3]; [4; -3; 8]]))", &mut state);
+        assert_eq!(v, Ok(Value::Int(BigInt::from(3))));
+        let v = eval("dot([1; 2; 3]; cross([1; 0; 0]; [0; 1; 0])) + vnorm([3; 4])", &mut state);
+        assert_eq!(v, Ok(Value::Int(BigInt::from(8))));
+        let v = eval("angle([1; 0]; [1; 1]) * 4", &mut state);
+        assert!((v.unwrap().as_f64().unwrap() - std::f64::consts::PI).abs() < 1e-15);
+    }
+
+    #[test]
+    fn test_polynomials() {
+        let mut state = CalcState::new();
+        let v = eval("poly(1; -3; 2) * poly(1; 1) - 1", &mut state).unwrap();
+        assert_eq!(v.to_string(), "x**3-2*x**2-x+1");
+        state.add_variable("p", v);
+        let v = eval("polyval(p; 2) + polyval(-p; 3)", &mut state);
+        assert_eq!(v, Ok(Value::Int(BigInt::from(-8))));
+        let v = eval("roots(poly(1; -1; -2))", &mut state);
+        assert_eq!(v.map(|v| v.to_string()), Ok("[-1; 2]".to_string()));
+        let v = eval("roots(1; -3)", &mut state);
+        assert_eq!(v, Ok(Value::Int(BigInt::from(-3))));
+    }
+
+    #[test]
+    fn test_solve() {
+        let mut state = CalcState::new();
+        let list = |v: CalcResult| v.map(|v| v.to_string());
+        assert_eq!(list(eval("solve(\"2*x + 3 = 7\"; \"x\")", &mut state)), Ok("[2]".to_string()));
+        assert_eq!(list(eval("zeroes(\"x = 1\"; \"x\")", &mut state)), Ok("[1]".to_string()));
+        assert_eq!(list(eval("solve(\"X**2 = 2*x + 3\"; \"x\")", &mut state)), Ok("[-1; 3]".to_string()));
+        assert_eq!(list(eval("solve(\"(t - 1)**2\"; \"t\")", &mut state)), Ok("[1]".to_string()));
+        assert_eq!(list(eval("solve(\"x/2 + 1\\3 = 1\"; \"x\")", &mut state)), Ok("[1\\1\\3]".to_string()));
+        assert_eq!(list(eval("solve(\"x**2 + 4 = 0\"; \"x\")", &mut state)), Ok("[0.0-2.0i; 0.0+2.0i]".to_string()));
+        state.add_variable("a", Value::Int(BigInt::from(3)));
+        assert_eq!(list(eval("solve(\"a*x = 1\"; \"x\")", &mut state)), Ok("[1\\3]".to_string()));
+        let v = eval("solve(\"cos(x) = x\"; \"x\")", &mut state);
+        assert_eq!(v, Ok(Value::List(vec![Value::Float(0.739_085_133_215_160_7)])));
+        let v = eval("solve(\"x**5 - x - 1\"; \"x\")", &mut state);
+        assert_eq!(v, Ok(Value::List(vec![Value::Float(1.167_303_978_261_418_7)])));
+        let v = eval("solve(\"exp(x) = 10\"; \"x\")", &mut state);
+        match v {
+            Ok(Value::List(v)) if v.len() == 1 => assert!((v[0].as_f64().unwrap() - 10f64.ln()).abs() < 1e-15),
+            _ => panic!("{:?}", v),
+        }
+        assert_eq!(eval("solve(\"1/x = 0\"; \"x\")", &mut state), Err(CalcError::NoRoots));
+        assert_eq!(eval("solve(\"x**2 = -1 + x**2\"; \"x\")", &mut state), Err(CalcError::NoRoots));
+        let msg = "every value of x is a solution".to_string();
+        assert_eq!(
+            eval("solve(\"2*x = x + x\"; \"x\")", &mut state),
+            Err(CalcError::InvalidAgrument("solve".to_string(), msg))
+        );
+        let msg = "invalid variable name \"1\"".to_string();
+        assert_eq!(
+            eval("solve(\"x = 1\"; \"1\")", &mut state),
+            Err(CalcError::InvalidAgrument("solve".to_string(), msg))
+        );
+        assert_eq!(eval("solve(\"x = y\"; \"x\")", &mut state), Err(CalcError::VarUndeclared("y".to_string())));
+    }
+
+    #[test]
+    fn test_solvesys() {
+        let mut state = CalcState::new();
+        state.add_variable("a", Value::Int(BigInt::from(3)));
+        let list = |v: CalcResult| v.map(|v| v.to_string());
+        let v = eval("solvesys([\"x + y = 3\"; \"2*x - y = 0\"]; [\"x\"; \"y\"])", &mut state);
+        assert_eq!(list(v), Ok("[[x; 1]; [y; 2]]".to_string()));
+        let v = eval("solvesys([\"x/2 + 3*z = a\"; \"x = z\"]; [\"x\"; \"z\"])", &mut state);
+        assert_eq!(list(v), Ok("[[x; 6\\7]; [z; 6\\7]]".to_string()));
+        let v = eval("solvesys([\"0.5*x + y = 1\"; \"x - y = 0.5\"; \"3*x = 3\"]; [\"x\"; \"y\"])", &mut state);
+        assert_eq!(list(v), Ok("[[x; 1]; [y; 0.5]]".to_string()));
+        let v = eval("solvesys(\"2*x = 5\"; \"x\")", &mut state);
+        assert_eq!(list(v), Ok("[[x; 2\\1\\2]]".to_string()));
+        let v = eval("solvesys([\"x + y = 1\"; \"x + y = 2\"]; [\"x\"; \"y\"])", &mut state);
+        assert_eq!(v, Err(CalcError::NoRoots));
+        let msg = "the system has infinitely many solutions".to_string();
+        let v = eval("solvesys([\"x + y = 1\"; \"2*x + 2*y = 2\"]; [\"x\"; \"y\"])", &mut state);
+        assert_eq!(v, Err(CalcError::InvalidAgrument("solvesys".to_string(), msg)));
+        let msg = "equation 2 is not linear".to_string();
+        let v = eval("solvesys([\"x + y = 1\"; \"x*y = 2\"]; [\"x\"; \"y\"])", &mut state);
+        assert_eq!(v, Err(CalcError::InvalidAgrument("solvesys".to_string(), msg.clone())));
+        let v = eval("solvesys([\"x + y = 1\"; \"sin(x) = y\"]; [\"x\"; \"y\"])", &mut state);
+        assert_eq!(v, Err(CalcError::InvalidAgrument("solvesys".to_string(), msg)));
+        let msg = "duplicate variable \"X\"".to_string();
+        let v = eval("solvesys([\"x = 1\"; \"x = 2\"]; [\"x\"; \"X\"])", &mut state);
+        assert_eq!(v, Err(CalcError::InvalidAgrument("solvesys".to_string(), msg)));
+    }
+
+    #[test]
+    fn test_taylor() {
+        let mut state = CalcState::new();
+        let list = |v: CalcResult| v.map(|v| v.to_string());
+        state.add_variable("a", Value::Int(BigInt::from(3)));
+        let v = eval("taylor(\"1/(1 - x)\"; \"x\"; 0; 4)", &mut state);
+        assert_eq!(list(v), Ok("x**4+x**3+x**2+x+1".to_string()));
+        let v = eval("taylor(\"sqrt(x)\"; \"x\"; 4; 2)", &mut state);
+        assert_eq!(list(v), Ok("-1\\64*x**2+3\\8*x+3\\4".to_string()));
+        let v = eval("taylor(\"x**x\"; \"x\"; a - 2; 2)", &mut state);
+        assert_eq!(list(v), Ok("x**2-x+1".to_string()));
+        let v = eval("taylor(\"atan(x)\"; \"x\"; 0; 5)", &mut state);
+        assert_eq!(list(v), Ok("1\\5*x**5-1\\3*x**3+x".to_string()));
+        let v = eval("taylor(\"sin(x)\"; \"x\"; 0; 5)", &mut state);
+        assert_eq!(list(v), Ok("0.008333333333333334*x**5-0.16666666666666667*x**3+x".to_string()));
+        let msg = "'floor' is not supported in series".to_string();
+        let v = eval("taylor(\"floor(x)\"; \"x\"; 0; 2)", &mut state);
+        assert_eq!(v, Err(CalcError::InvalidAgrument("taylor".to_string(), msg)));
+    }
+
+    #[test]
+    fn test_limit() {
+        let mut state = CalcState::new();
+        state.add_variable("a", Value::Int(BigInt::from(3)));
+        let v = eval("limit(\"sin(x)/x\"; \"x\"; 0)", &mut state);
+        assert!((v.unwrap().as_f64().unwrap() - 1.0).abs() < 1e-12);
+        let v = eval("limit(\"(x**2 - a**2)/(x - a)\"; \"x\"; a)", &mut state);
+        assert!((v.unwrap().as_f64().unwrap() - 6.0).abs() < 1e-12);
+        assert_eq!(eval("limit(\"x**2\"; \"x\"; 3)", &mut state), Ok(Value::Int(BigInt::from(9))));
+        assert_eq!(eval("limit(\"sqrt(x)\"; \"x\"; 0)", &mut state), Ok(Value::Int(BigInt::from(0))));
+        assert_eq!(eval("limit(\"ln(x)\"; \"x\"; 0)", &mut state), Ok(Value::Float(f64::NEG_INFINITY)));
+        let v = eval("limit(\"1/x\"; \"x\"; 0)", &mut state);
+        assert_eq!(v, Err(CalcError::OneSidedLimits("-inf".to_string(), "inf".to_string())));
+        assert_eq!(eval("limit(\"1/x**2\"; \"x\"; 0)", &mut state).map(|v| v.to_string()), Ok("inf".to_string()));
+        let v = eval("limit(\"floor(x)\"; \"x\"; 1)", &mut state);
+        assert_eq!(v, Err(CalcError::OneSidedLimits("0.0".to_string(), "1.0".to_string())));
+        let v = eval("limit(\"sin(1/x)\"; \"x\"; 0)", &mut state);
+        assert_eq!(v, Err(CalcError::NoLimit("sin(1/x)".to_string())));
+    }
+
+    #[test]
+    fn test_sample() {
+        let mut state = CalcState::new();
+        let list = |v: CalcResult| v.map(|v| v.to_string());
+        let v = eval("sample(\"x**2\"; \"x\"; -1; 1; 5)", &mut state);
+        assert_eq!(list(v), Ok("[[-1.0; 1.0]; [-0.5; 0.25]; [0.0; 0.0]; [0.5; 0.25]; [1.0; 1.0]]".to_string()));
+        let v = eval("sample(\"sqrt(x)\"; \"x\"; -1; 1; 3)", &mut state);
+        assert_eq!(list(v), Ok("[[-1.0; NaN]; [0.0; 0.0]; [1.0; 1.0]]".to_string()));
+        let v = eval("sample(\"1/(x - 1\\2)\"; \"x\"; 0; 1; 3)", &mut state);
+        assert_eq!(list(v), Ok("[[0.0; -2.0]; [0.5; NaN]; [1.0; 2.0]]".to_string()));
+        let v = eval("sample(\"1/(x - 1\\2)\"; \"x\"; 0; 1; 2)", &mut state);
+        assert_eq!(list(v), Ok("[[0.0; -2.0]; [0.5; NaN]; [1.0; 2.0]]".to_string()));
+        let v = eval("sample(\"x\"; \"x\"; 0; 1; 1)", &mut state);
+        let r = CalcError::ArgumentOutOfRange("sample".to_string(), "1".to_string(), "[2..100000]".to_string());
+        assert_eq!(v, Err(r));
+    }
+
+    #[test]
+    fn test_nderiv() {
+        let mut state = CalcState::new();
+        state.add_variable("a", Value::Int(BigInt::from(3)));
+        let v = eval("nderiv(\"x**3\"; \"x\"; 2)", &mut state);
+        assert!((v.unwrap().as_f64().unwrap() - 12.0).abs() < 1e-10);
+        let v = eval("nderiv(\"ln(x)\"; \"x\"; 0.01)", &mut state);
+        assert!((v.unwrap().as_f64().unwrap() - 100.0).abs() < 1e-9);
+        let v = eval("nderiv(\"exp(a*x)\"; \"x\"; 0)", &mut state);
+        assert!((v.unwrap().as_f64().unwrap() - 3.0).abs() < 1e-12);
+        let v = eval("nderiv(\"sqrt(x)\"; \"x\"; 0)", &mut state);
+        assert_eq!(v, Err(CalcError::NotForComplex("nderiv".to_string())));
+    }
+
+    #[test]
+    fn test_minimize() {
+        let mut state = CalcState::new();
+        let list = |v: CalcResult| v.map(|v| v.to_string());
+        let v = eval("minimize(\"(x - 2)**2 + 1\"; \"x\"; 0; 5)", &mut state);
+        assert_eq!(list(v), Ok("[2.0; 1.0]".to_string()));
+        let v = eval("maximize(\"x**2\"; \"x\"; 3; -1)", &mut state);
+        assert_eq!(list(v), Ok("[3.0; 9.0]".to_string()));
+        match eval("maximize(\"sin(x)\"; \"x\"; 0; 3)", &mut state) {
+            Ok(Value::List(v)) => {
+                assert!((v[0].as_f64().unwrap() - std::f64::consts::FRAC_PI_2).abs() < 1e-7);
+                assert!((v[1].as_f64().unwrap() - 1.0).abs() < 1e-14);
+            }
+            v => panic!("{:?}", v),
+        }
+    }
+
+    #[test]
+    fn test_rk4() {
+        let mut state = CalcState::new();
+        let list = |v: CalcResult| v.map(|v| v.to_string());
+        let v = eval("rk4(\"t\"; \"y\"; 0; 0; 1; 2)", &mut state);
+        assert_eq!(list(v), Ok("[[0.0; 0.0]; [1.0; 0.5]; [2.0; 2.0]]".to_string()));
+        match eval("rk4(\"y\"; [\"s\"; \"y\"]; 0; 1; 0.1; 10)", &mut state) {
+            Ok(Value::List(v)) => {
+                assert_eq!(v.len(), 11);
+                assert_eq!(v[10], Value::List(vec![Value::Float(1.0), Value::Float(2.718_279_744_135_166)]));
+            }
+            v => panic!("{:?}", v),
+        }
+    }
+
+    #[test]
+    #[cfg(feature = "special")]
+    fn test_special_functions() {
+        let mut state = CalcState::new();
+        let v = eval("lambertw(e) + lambertw(-1/e; -1)", &mut state);
+        assert_eq!(v, Ok(Value::Float(0.0)));
+        let v = eval("lambertw(-1)", &mut state);
+        assert_eq!(v, Err(CalcError::InvalidAgrument("lambertw".to_string(), "-1".to_string())));
+        let v = eval("besselj(0; 0) + bessely(1; 1)", &mut state).unwrap();
+        assert_eq!(v.to_string(), "0.21878717869971132");
+        let v = eval("bessely(0; 0)", &mut state);
+        assert_eq!(v, Err(CalcError::InvalidAgrument("bessely".to_string(), "0".to_string())));
+        let v = eval("besselj(1.5; 2)", &mut state);
+        assert_eq!(v, Err(CalcError::OnlyInt("besselj".to_string())));
+        let v = eval("harmonic(0.5) + zeta(2)", &mut state).unwrap();
+        assert_eq!(v.to_string(), "2.2586397057283364");
+        let v = eval("binompmf(2; 4; 0.5) + binomcdf(1; 4; 0.5)", &mut state);
+        assert!((v.unwrap().as_f64().unwrap() - 0.6875).abs() < 1e-14);
+        let v = eval("poiscdf(1; 2) - poispmf(1; 2) - poispmf(0; 2)", &mut state);
+        assert!(v.unwrap().as_f64().unwrap().abs() < 1e-15);
+        let v = eval("tcdf(tinv(0.9; 4); 4) + chi2cdf(chi2inv(0.05; 3); 3)", &mut state);
+        assert!((v.unwrap().as_f64().unwrap() - 0.95).abs() < 1e-14);
+        let v = eval("zeta(1)", &mut state);
+        assert_eq!(v, Err(CalcError::InvalidAgrument("zeta".to_string(), "1".to_string())));
+    }
+
+    #[test]
+    #[cfg(feature = "units")]
+    fn test_units() {
+        let mut state = CalcState::new();
+        let v = eval("broadcast(\"172.16.5.4\"; 12) - network(ip(\"172.16.5.4\"); 12) + 1", &mut state);
+        assert_eq!(v, Ok(Value::Int(BigInt::from(1 << 20))));
+        let v = eval("network(\"172.31.5.4\"; 12)", &mut state);
+        assert_eq!(v, Ok(Value::Int(BigInt::from(0xAC10_0000u32))));
+        assert_eq!(state.alt_result, "172.16.0.0");
+        let v = eval("ipstr(netmask(20))", &mut state);
+        assert_eq!(v, Ok(Value::Str("255.255.240.0".to_string())));
+        let v = eval("hsize(gib(3) + mib(512))", &mut state);
+        assert_eq!(v, Ok(Value::Str("3.5 GiB".to_string())));
+        let v = eval("hsize(kb(1500); 1000)", &mut state);
+        assert_eq!(v, Ok(Value::Str("1.5 MB".to_string())));
+        let v = eval("tb(1) / tib(1)", &mut state);
+        assert_eq!(v, Ok(Value::Float(0.9094947017729282)));
+        let v = eval("duration(2d4h - 90s)", &mut state);
+        assert_eq!(v, Ok(Value::Str("51h58m30s".to_string())));
+    }
+
+    #[test]
+    #[cfg(feature = "datetime")]
+    fn test_dates() {
+        let mut state = CalcState::new();
+        let v = eval("fromunix(unix(\"2024-02-28 23:00\") + 3600*25)", &mut state);
+        assert_eq!(v, Ok(Value::Str("2024-03-01T00:00:00Z".to_string())));
+        let v = eval("now() > unix(\"2024-01-01\")", &mut state);
+        assert_eq!(v, Ok(Value::Int(BigInt::from(1))));
+        assert!(eval("now()", &mut state).is_ok());
+        assert!(state.has_alt && state.alt_result.ends_with('Z'));
+        let v = eval("fromunix(unix(\"2024-03-01\") + 36h)", &mut state);
+        assert_eq!(v, Ok(Value::Str("2024-03-02T12:00:00Z".to_string())));
+        let v = eval("tz(unix(\"2024-06-01 10:00\") + 2h; \"Europe/Stockholm\")", &mut state);
+        assert_eq!(v, Ok(Value::Str("2024-06-01T14:00:00+02:00".to_string())));
+        let v = eval("weekday(\"2024-06-01\") + isoweek(\"2024-06-01\") + daysin(6; 2024)", &mut state);
+        assert_eq!(v, Ok(Value::Int(BigInt::from(6 + 22 + 30))));
+    }
+
+    #[test]
+    fn test_rpn() {
+        let mut state: CalcState = CalcState::new();
+        let v = eval_rpn("2 3 + 4 *", &mut state);
+        assert_eq!(v, Ok(Value::Int(BigInt::from(20))));
+        let v = eval_rpn("2 -3 *", &mut state);
+        assert_eq!(v, Ok(Value::Int(BigInt::from(-6))));
+        let v = eval_rpn("2 3 - 5 -", &mut state);
+        assert_eq!(v, Ok(Value::Int(BigInt::from(-6))));
+        let v = eval_rpn("2 3 ** 2 **", &mut state);
+        assert_eq!(v, Ok(Value::Int(BigInt::from(64))));
+        let v = eval_rpn("4 ! ans +", &mut state);
+        assert_eq!(v, Ok(Value::Int(BigInt::from(88))));
+        let v = eval_rpn("12 18 gcd sqr", &mut state);
+        assert_eq!(v, Ok(Value::Int(BigInt::from(36))));
+        let v = eval_rpn("0 10 20 iif", &mut state);
+        assert_eq!(v, Ok(Value::Int(BigInt::from(20))));
+        let v = eval_rpn("370 0 360 wrap", &mut state);
+        assert_eq!(v, Ok(Value::Int(BigInt::from(10))));
+        let v = eval_rpn("pi 2 / sin", &mut state);
+        assert_eq!(v, Ok(Value::Float(1.0)));
+        let v = eval_rpn("2 3 4 +", &mut state);
+        assert_eq!(v, Err(CalcError::InsufficientOps));
+        let v = eval_rpn("2 +", &mut state);
+        assert_eq!(v, Err(CalcError::TooManyOps));
+        let v = eval_rpn("(2 3 +)", &mut state);
+        assert_eq!(v, Err(CalcError::InvalidOp("(".to_string())));
+        // a function needs as many values on the stack as it takes
+        let v = eval_rpn("1 2 roots", &mut state);
+        assert_eq!(v, Err(CalcError::FunctionNotEnoughArgs("roots".to_string(), 3)));
+        let v = eval_rpn("1 solve", &mut state);
+        assert_eq!(v, Err(CalcError::FunctionNotEnoughArgs("solve".to_string(), 3)));
+        let v = eval_rpn("5 gcd", &mut state);
+        assert_eq!(v, Err(CalcError::FunctionNotEnoughArgs("gcd".to_string(), 2)));
+        let short = [
+            ("\"x\" map", "map", 2),
+            ("1 taylor", "taylor", 4),
+            ("solvesys", "solvesys", 2),
+            ("1 2 iterate", "iterate", 4),
+        ];
+        for (expr, fname, args) in short.iter() {
+            assert_eq!(
+                eval_rpn(expr, &mut state),
+                Err(CalcError::FunctionNotEnoughArgs(fname.to_string(), *args)),
+                "{}",
+                expr
+            );
+        }
+    }
+
+    #[test]
+    fn test_dump() {
+        let mut state: CalcState = CalcState::new();
+        let e = compile("(2+3)*4", &mut state).unwrap();
+        assert_eq!(e.to_postfix(), "2 3 + 4 *");
+        assert_eq!(e.to_sexpr(), Ok("(* (+ 2 3) 4)".to_string()));
+        let e = compile("2 + 3 * 4 ** 2 ** 3", &mut state).unwrap();
+        assert_eq!(e.to_postfix(), "2 3 4 2 3 ** ** * +");
+        assert_eq!(e.to_sexpr(), Ok("(+ 2 (* 3 (** 4 (** 2 3))))".to_string()));
+        let e = compile("-3! + max(1;2;3) - sin 1", &mut state).unwrap();
+        assert_eq!(e.to_postfix(), "3 ! neg 1 2 3 max/3 + 1 sin -");
+        assert_eq!(e.to_sexpr(), Ok("(- (+ (neg (! 3)) (max 1 2 3)) (sin 1))".to_string()));
+        assert_eq!(
+            e.tokens()[..3].to_vec(),
+            vec![
+                Token::Value(Value::Int(BigInt::from(3))),
+                Token::Operator("!".to_string(), 1),
+                Token::Operator("neg".to_string(), 1)
+            ]
+        );
+        let e = compile("40 + 30 - 50 %", &mut state).unwrap();
+        assert_eq!(e.to_sexpr(), Ok("(+ 40 (-% 30 50))".to_string()));
+        let e = compile("unroman(\"XIV\") * 2", &mut state).unwrap();
+        assert_eq!(e.to_postfix(), "\"XIV\" unroman 2 *");
+        assert_eq!(e.to_sexpr(), Ok("(* (unroman \"XIV\") 2)".to_string()));
+        let e = compile_rpn("2 3 + 4 *", &mut state).unwrap();
+        assert_eq!(e.to_sexpr(), Ok("(* (+ 2 3) 4)".to_string()));
+        let e = compile_rpn("2 3 4 +", &mut state).unwrap();
+        assert_eq!(e.to_sexpr(), Err(CalcError::InsufficientOps));
+        let e = compile("", &mut state).unwrap();
+        assert_eq!(e.to_sexpr(), Err(CalcError::EmptyExpression));
+    }
+
+    #[test]
+    fn test_lists() {
+        let mut state: CalcState = CalcState::new();
+        let v = eval("[1; 2+3; sqrt 16]", &mut state).unwrap();
+        assert_eq!(v.to_string(), "[1; 5; 4]");
+        let v = eval("-[1; [2; 3\\4]]", &mut state).unwrap();
+        assert_eq!(v.to_string(), "[-1; [-2; -3\\4]]");
+        assert_eq!(eval("[]", &mut state), Ok(Value::List(Vec::new())));
+        assert_eq!(eval("gcd([12; 18; 24])", &mut state), Ok(Value::Int(BigInt::from(6))));
+        assert_eq!(eval("gcd(12; 18; 24)", &mut state), Ok(Value::Int(BigInt::from(6))));
+        assert_eq!(eval("lcm [2; 3; 4] + 1", &mut state), Ok(Value::Int(BigInt::from(13))));
+        assert_eq!(eval("max([1; 5; 3])", &mut state), Ok(Value::Int(BigInt::from(5))));
+        assert_eq!(eval("min [4; 2]", &mut state), Ok(Value::Int(BigInt::from(2))));
+        assert_eq!(eval("avg([1; 2; 3; 4])", &mut state), Ok(Value::Float(2.5)));
+        assert_eq!(eval("gcd([12])", &mut state), Err(CalcError::FunctionNotEnoughArgs("gcd".to_string(), 2)));
+        assert_eq!(eval("avg([])", &mut state), Err(CalcError::FunctionNoArgs("avg".to_string())));
+        assert_eq!(eval("[1; 2] + 1", &mut state), Err(CalcError::ListToNumber("[1; 2]".to_string())));
+        assert_eq!(eval("sqrt([4])", &mut state), Err(CalcError::NotForList("sqrt".to_string())));
+        let e = compile("max [1; 2] * 3", &mut state).unwrap();
+        assert_eq!(e.to_postfix(), "1 2 list/2 max/1 3 *");
+    }
+
+    #[test]
+    fn test_implicit_args() {
+        let mut state = CalcState::new();
+        state.add_variable("x", Value::from(3));
+        let cases = [
+            ("sqrt x", "sqrt(x)"),
+            ("sin 2x", "sin(2*x)"),
+            ("sin 2 x", "sin(2)*x"),
+            ("sin 2pi", "sin(2*pi)"),
+            ("ln -x^2", "ln(-x)^2"),
+            ("cos x + 1", "cos(x)+1"),
+            ("sin x cos x", "sin(x)*cos(x)"),
+            ("sqrt sqrt 16 x", "sqrt(sqrt(16))*x"),
+            ("max(sqrt 16; sin x)", "max(sqrt(16); sin(x))"),
+            ("(exp 2x)", "exp(2*x)"),
+        ];
+        for (expr, expected) in cases.iter() {
+            assert_eq!(eval(expr, &mut state), eval(expected, &mut state), "{}", expr);
+        }
+        assert_eq!(eval("2x", &mut state), Ok(Value::from(6)));
+    }
+
+    #[test]
+    fn test_unicode_symbols() {
+        let mut state = CalcState::new();
+        state.add_variable("x", Value::from(3));
+        let cases = [
+            ("6×2÷4", "6*2/4"),
+            ("5 − x", "5-x"),
+            ("(x ≤ 3) + (x ≥ 4) + (x ≠ 2)", "(x <= 3) + (x >= 4) + (x != 2)"),
+            ("√16 + 2√x", "sqrt(16) + 2*sqrt(x)"),
+            ("2πx²", "2*pi*x**2"),
+            ("sin 2π", "sin(2*pi)"),
+            ("x⁻¹ + x¹⁰", "x**-1 + x**10"),
+            ("−∞", "-inf"),
+        ];
+        for (expr, expected) in cases.iter() {
+            assert_eq!(eval(expr, &mut state), eval(expected, &mut state), "{}", expr);
+        }
+        assert_eq!(eval("\"π×²\"", &mut state), Ok(Value::Str("π×²".to_string())));
+    }
+
+    #[test]
+    fn test_unicode_literals() {
+        let mut state = CalcState::new();
+        state.add_variable("x", Value::from(3));
+        let cases = [
+            ("¾", "3\\4"),
+            ("1½ + ⅓", "1\\1\\2 + 1\\3"),
+            ("x½", "x*1\\2"),
+            ("2.5⅒", "2.5*1\\10"),
+            ("τ - 2π", "0.0"),
+            ("φ", "phi"),
+            ("ℯ²", "e**2"),
+            ("sin ¼τ", "sin(pi/2)"),
+            ("sin ½", "sin(0.5)"),
+        ];
+        for (expr, expected) in cases.iter() {
+            assert_eq!(eval(expr, &mut state), eval(expected, &mut state), "{}", expr);
+        }
+        assert_eq!(eval("1½", &mut state).map(|v| v.to_string()), Ok("1\\1\\2".to_string()));
+    }
+
+    #[test]
+    fn test_ranges() {
+        let mut state = CalcState::new();
+        state.add_variable("n", Value::from(3));
+        let cases = [
+            ("1..n", "[1; 2; 3]"),
+            ("1+1..n+2", "[2; 3; 4; 5]"),
+            ("1..2*n step 2", "[1; 3; 5]"),
+            ("n..0 step -2", "[3; 1]"),
+            ("avg(0..1 step 0.25)", "0.5"),
+            ("[1..2; 0]", "[[1; 2]; 0]"),
+        ];
+        for (expr, expected) in cases.iter() {
+            assert_eq!(eval(expr, &mut state).map(|v| v.to_string()), Ok(expected.to_string()), "{}", expr);
+        }
+        assert_eq!(eval("n step", &mut state), Err(CalcError::VarUndeclared("step".to_string())));
+        state.add_variable("step", Value::from(2));
+        assert_eq!(eval("n step", &mut state), Ok(Value::from(6)));
+        assert_eq!(eval("1..n step step", &mut state).map(|v| v.to_string()), Ok("[1; 3]".to_string()));
+    }
+
+    #[test]
+    fn test_indexes() {
+        let mut state = CalcState::new();
+        state.add_variable("n", Value::from(1));
+        state.add_variable("xs", eval("[10; 20; 30; 40]", &mut CalcState::new()).unwrap());
+        let cases = [
+            ("xs[0] + xs[-1]", "50"),
+            ("2*xs[n+1]^2", "1800"),
+            ("xs[1:3]", "[20; 30]"),
+            ("xs[:n]", "[10]"),
+            ("xs[-2:]", "[30; 40]"),
+            ("xs[3:1]", "[]"),
+            ("[[1; 2]; [3; 4]][1][0]", "3"),
+            ("[[1; 2]; [3; 4]][1; 0]", "3"),
+            ("list(1; 2; 3)[:-1]", "[1; 2]"),
+            ("(0..9)[2:4]", "[2; 3]"),
+            ("\"hello\"[1:]", "ello"),
+            ("sqr xs[2] + 1", "901"),
+        ];
+        for (expr, expected) in cases.iter() {
+            assert_eq!(eval(expr, &mut state).map(|v| v.to_string()), Ok(expected.to_string()), "{}", expr);
+        }
+        assert_eq!(
+            eval("xs[4]", &mut state),
+            Err(CalcError::ArgumentOutOfRange("index".to_string(), "4".to_string(), "-4..3".to_string()))
+        );
+        assert_eq!(eval("xs[1:2:3]", &mut state), Err(CalcError::InvalidOp(":".to_string())));
+        assert!(eval("xs[]", &mut state).is_err());
+        assert!(eval("[1:2]", &mut state).is_err());
+    }
+
+    #[test]
+    fn test_list_formulas() {
+        let mut state = CalcState::new();
+        state.add_variable("k", Value::from(10));
+        let cases = [
+            ("map(1..4; \"x**2\")", "[1; 4; 9; 16]"),
+            ("map([1; 2]; \"k*x + 1\")", "[11; 21]"),
+            ("map(map([[1; 2]; [3; 4]]; \"x[1]\"); \"-x\")", "[-2; -4]"),
+            ("filter(1..10; \"x % 3 == 0\")", "[3; 6; 9]"),
+            ("filter([0; 1; 2]; \"x\")", "[1; 2]"),
+            ("reduce(1..5; \"acc*x\"; 1)", "120"),
+            ("reduce([\"a\"; \"b\"]; \"acc + x\"; \"\")", "ab"),
+            ("reduce([]; \"acc + x\"; k)", "10"),
+            ("len(filter(1..100; \"x*x < k\"))", "3"),
+        ];
+        for (expr, expected) in cases.iter() {
+            assert_eq!(eval(expr, &mut state).map(|v| v.to_string()), Ok(expected.to_string()), "{}", expr);
+        }
+        assert_eq!(eval("map([1; 2]; \"y\")", &mut state), Err(CalcError::VarUndeclared("y".to_string())));
+        assert!(eval("map(5; \"x\")", &mut state).is_err());
+        assert!(eval("reduce([1; 2]; \"acc + x\")", &mut state).is_err());
+
+        // a formula can read another formula from a variable, but not endlessly
+        let nested = [
+            "map([1]; s)",
+            "iterate(s; \"x\"; 1; 1)",
+            "solve(s; \"x\")",
+            "sample(s; \"x\"; 0; 1; 2)",
+            "limit(s; \"x\"; 1)",
+        ];
+        for expr in nested.iter() {
+            state.add_variable("s", Value::Str(expr.to_string()));
+            assert_eq!(eval(expr, &mut state), Err(CalcError::TooDeepNesting(64)), "{}", expr);
+        }
+        state.add_variable("t", Value::Str("x + 1".to_string()));
+        let calc = Calc::new().with_limits(Limits { max_nesting: 1, ..Limits::default() });
+        assert_eq!(calc.eval("map([1]; t)", &mut state).map(|v| v.to_string()), Ok("[2]".to_string()));
+        assert_eq!(calc.eval("map([1]; \"map([x]; t)\")", &mut state), Err(CalcError::TooDeepNesting(1)));
+    }
+
+    #[test]
+    fn test_iterate() {
+        let mut state = CalcState::new();
+        state.add_variable("r", Value::from(2));
+        let cases = [
+            ("iterate(\"2*x\"; \"x\"; 1; 10)", "1024"),
+            ("iterate(\"x + 1\"; \"x\"; 5; 0)", "5"),
+            ("iterate(\"r*y*(1 - y)\"; \"y\"; 1\\4; 2)", "15\\32"),
+            ("iterate(\"cos(x)\"; \"x\"; 1; 100)", "0.7390851332151607"),
+            ("abs(iterate(\"(x + 2/x)/2\"; \"x\"; 1.0; 6) - sqrt(2)) < 0.000001", "1"),
+            ("iterate(\"[x[1]; x[0] + x[1]]\"; \"x\"; [0; 1]; 10)", "[55; 89]"),
+        ];
+        for (expr, expected) in cases.iter() {
+            assert_eq!(eval(expr, &mut state).map(|v| v.to_string()), Ok(expected.to_string()), "{}", expr);
+        }
+        assert_eq!(
+            eval("iterate(\"x\"; \"x\"; 0; 10**7)", &mut state),
+            Err(CalcError::ArgumentOutOfRange(
+                "iterate".to_string(),
+                "10000000".to_string(),
+                "[0..1000000]".to_string()
+            ))
+        );
+        assert_eq!(
+            eval("iterate(\"x\"; \"x\"; 0; -1)", &mut state),
+            Err(CalcError::NotForNegativeInt("iterate".to_string()))
+        );
+        assert_eq!(eval("iterate(\"x + z\"; \"x\"; 0; 2)", &mut state), Err(CalcError::VarUndeclared("z".to_string())));
+        assert!(eval("iterate(\"x\"; \"x\"; 0)", &mut state).is_err());
+    }
+
+    #[test]
+    fn test_piecewise() {
+        let mut state = CalcState::new();
+        state.add_variable("x", Value::from(0));
+        let cases = [
+            ("piecewise(x < 0; -1; x == 0; 0; 1)", "0"),
+            ("piecewise(x == 0; 1; sin(x)/x)", "1"),
+            ("piecewise(x == 0; 3; 1/0 == 0; 2; 1) + 1", "4"),
+            ("piecewise(x + 1; piecewise(1; 2; 3); 4) * 10", "20"),
+            ("piecewise(piecewise(0; 1; 0); 1; 2)", "2"),
+            ("2 * piecewise(x; y; 5)", "10"),
+        ];
+        for (expr, expected) in cases.iter() {
+            assert_eq!(eval(expr, &mut state).map(|v| v.to_string()), Ok(expected.to_string()), "{}", expr);
+        }
+        assert_eq!(
+            eval("piecewise(1; dms(12.5); 0)", &mut state).map(|v| v.to_string()),
+            Ok("[12; 30; 0]".to_string())
+        );
+        assert!(state.has_alt);
+        assert_eq!(eval("piecewise(x == 1; 1; 1/x)", &mut state), Err(CalcError::DividedByZero("1".to_string())));
+        assert_eq!(eval("piecewise(y; 1; 2)", &mut state), Err(CalcError::VarUndeclared("y".to_string())));
+        assert_eq!(
+            eval("piecewise(1; 2; 0; 3)", &mut state),
+            Err(CalcError::InvalidAgrument("piecewise".to_string(), "no default value".to_string()))
+        );
+        assert_eq!(
+            eval("piecewise(1; 2)", &mut state),
+            Err(CalcError::FunctionNotEnoughArgs("piecewise".to_string(), 3))
+        );
+        assert_eq!(eval_rpn("0 1 2 piecewise", &mut state), Ok(Value::from(2)));
+        assert_eq!(eval_rpn("0 1 0 / 2 piecewise", &mut state).map(|v| v.to_string()), Ok("2".to_string()));
+    }
+
+    #[test]
+    fn test_assert() {
+        let mut state = CalcState::new();
+        state.add_variable("x", Value::from(4));
+        assert_eq!(eval("assert(x > 0; \"x must be positive\") * x", &mut state), Ok(Value::from(4)));
+        assert_eq!(eval("sqrt(assert(x; \"x is zero\"))", &mut state), Ok(Value::from(2)));
+        assert_eq!(eval("assert(x % 2 == 0)", &mut state), Ok(Value::from(1)));
+        let err = eval("assert(x < 0; \"x must be negative\")", &mut state);
+        assert_eq!(err, Err(CalcError::AssertionFailed("x must be negative".to_string())));
+        assert_eq!(err.unwrap_err().to_string(), "Assertion failed: x must be negative");
+        let err = eval("1 + assert(x == 5)", &mut state);
+        assert_eq!(err.unwrap_err().to_string(), "Assertion failed");
+        assert_eq!(eval("assert(0; 42)", &mut state), Err(CalcError::AssertionFailed("42".to_string())));
+        assert_eq!(eval("piecewise(x > 0; x; assert(0; \"unreachable\"))", &mut state), Ok(Value::from(4)));
+        assert_eq!(state.result(), Some(Value::from(4)));
+        assert_eq!(eval_rpn("x 2 > \"too small\" assert", &mut state), Ok(Value::from(1)));
+        assert_eq!(eval("assert()", &mut state), Err(CalcError::FunctionNoArgs("assert".to_string())));
+    }
+
+    #[test]
+    fn test_list_utils() {
+        let mut state = CalcState::new();
+        let cases = [
+            ("sort([3; 1\\2; -1; 2.5])", "[-1; 1\\2; 2.5; 3]"),
+            ("sortdesc(map(1..4; \"x % 3\"))", "[2; 1; 1; 0]"),
+            ("sort([\"pear\"; \"apple\"])", "[apple; pear]"),
+            ("unique([2; 1; 2; 3; 1])", "[2; 1; 3]"),
+            ("reverse(1..3)", "[3; 2; 1]"),
+            ("reverse(\"abc\")", "cba"),
+            ("len(unique([1; 1; 1]))", "1"),
+            ("sort([])", "[]"),
+        ];
+        for (expr, expected) in cases.iter() {
+            assert_eq!(eval(expr, &mut state).map(|v| v.to_string()), Ok(expected.to_string()), "{}", expr);
+        }
+        assert_eq!(eval("sort([1; 2+3i])", &mut state), Err(CalcError::NotForComplex("sort".to_string())));
+        assert!(eval("sort(5)", &mut state).is_err());
+    }
+
+    #[test]
+    fn test_zip() {
+        let mut state = CalcState::new();
+        state.add_variable("xs", eval("[1; 2; 3]", &mut CalcState::new()).unwrap());
+        state.add_variable("ys", eval("[4; 5; 6]", &mut CalcState::new()).unwrap());
+        let cases = [
+            ("zip(xs; ys)", "[[1; 4]; [2; 5]; [3; 6]]"),
+            ("zip(zip(xs; ys))", "[[1; 2; 3]; [4; 5; 6]]"),
+            ("zip(xs; ys; xs)[1]", "[2; 5; 2]"),
+            ("col(zip(xs; ys); 1)", "[4; 5; 6]"),
+            ("col([[1; 2]; [3; 4]]; -2)", "[1; 3]"),
+            ("map(zip(xs; ys); \"x[0]*x[1]\")", "[4; 10; 18]"),
+        ];
+        for (expr, expected) in cases.iter() {
+            assert_eq!(eval(expr, &mut state).map(|v| v.to_string()), Ok(expected.to_string()), "{}", expr);
+        }
+        assert_eq!(eval("zip(5)", &mut state), Err(CalcError::FunctionNotEnoughArgs("zip".to_string(), 2)));
+        assert_eq!(
+            eval("zip(xs; [1])", &mut state),
+            Err(CalcError::DimensionMismatch("zip".to_string(), "lists of different lengths".to_string()))
+        );
+    }
+
+    #[test]
+    fn test_dependencies() {
+        let mut state: CalcState = CalcState::new();
+        let e = compile("sin(A1 * pi) + max(b1; a1; sqr(price.total)) * ans", &mut state).unwrap();
+        assert_eq!(e.variables(), vec!["a1", "b1", "price.total", "ans"]);
+        assert_eq!(e.functions(), vec!["sin", "sqr", "max"]);
+        let e = compile_rpn("2 3 + pi *", &mut state).unwrap();
+        assert!(e.variables().is_empty());
+        assert!(e.functions().is_empty());
+    }
+
     #[test]
     fn test_corner_cases() {
         let mut state: CalcState = CalcState::new();
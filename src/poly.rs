@@ -0,0 +1,396 @@
+//! Polynomials.
+//!
+//! A polynomial keeps its coefficients starting from the highest degree:
+//! `poly(1; -3; 2)` is `x**2-3*x+2`. Coefficients are integers, fractions,
+//! or float numbers, and the arithmetic with integer and fractional
+//! coefficients is exact. A number in an expression with a polynomial is a
+//! polynomial of zero degree: `poly(1; 0) + 2` is `x+2`.
+
+use num_bigint::BigInt;
+use num_complex::Complex;
+use num_traits::Zero;
+use std::cmp::Ordering;
+
+use crate::errors::*;
+use crate::fmath;
+use crate::matrix;
+use crate::value::*;
+
+// roots with imaginary part smaller than this value multiplied by the
+// root magnitude are real
+const IMAGINARY_EPS: f64 = 1e-10;
+// the maximum number of Newton iterations when a root is refined
+const POLISH_ITERATIONS: usize = 16;
+// float roots of polynomials with exact coefficients are checked for being
+// fractions with denominators up to this value
+const MAX_ROOT_DENOMINATOR: u32 = 1_000_000;
+// the maximum degree `roots` solves
+const MAX_ROOTS_DEGREE: usize = 4;
+// the maximum degree of a polynomial read from its text
+const MAX_PARSED_DEGREE: usize = 10_000;
+
+/// Removes leading zero coefficients. The zero polynomial keeps a single
+/// zero coefficient
+pub(crate) fn normalize(mut c: Vec<Value>) -> Vec<Value> {
+    let nonzero = c.iter().position(|v| !v.is_zero()).unwrap_or(c.len());
+    c.drain(..nonzero);
+    if c.is_empty() {
+        c.push(Value::Int(BigInt::zero()));
+    }
+    c
+}
+
+/// Coefficients of a polynomial, a list of coefficients, or a number
+pub(crate) fn coefficients(v: &Value, name: &str) -> Result<Vec<Value>, CalcError> {
+    match v {
+        Value::Poly(c) => Ok(c.clone()),
+        Value::List(..) => Ok(normalize(matrix::to_vector(v, name)?)),
+        _ => Ok(vec![matrix::element(v, name)?]),
+    }
+}
+
+// adds or subtracts coefficients of the same degree
+fn combine<F>(a: &Value, b: &Value, name: &str, f: F) -> CalcResult
+where
+    F: Fn(Value, Value) -> CalcResult,
+{
+    let (a, b) = (coefficients(a, name)?, coefficients(b, name)?);
+    let len = a.len().max(b.len());
+    let zero = Value::Int(BigInt::zero());
+    let (pa, pb) = (len - a.len(), len - b.len());
+    let mut res = Vec::with_capacity(len);
+    for i in 0..len {
+        let x = if i < pa { zero.clone() } else { a[i - pa].clone() };
+        let y = if i < pb { zero.clone() } else { b[i - pb].clone() };
+        res.push(f(x, y)?);
+    }
+    Ok(Value::Poly(normalize(res)))
+}
+
+/// Sum of two polynomials
+pub(crate) fn addition(a: Value, b: Value) -> CalcResult {
+    combine(&a, &b, "+", |x, y| x.addition(y))
+}
+
+/// Difference of two polynomials
+pub(crate) fn subtract(a: Value, b: Value) -> CalcResult {
+    combine(&a, &b, "-", |x, y| x.subtract(y))
+}
+
+/// Product of two polynomials
+pub(crate) fn multiply(a: Value, b: Value) -> CalcResult {
+    let (a, b) = (coefficients(&a, "*")?, coefficients(&b, "*")?);
+    let mut res = vec![Value::Int(BigInt::zero()); a.len() + b.len() - 1];
+    for (i, x) in a.iter().enumerate() {
+        for (j, y) in b.iter().enumerate() {
+            let p = x.clone().multiply(y.clone())?;
+            res[i + j] = res[i + j].clone().addition(p)?;
+        }
+    }
+    Ok(Value::Poly(normalize(res)))
+}
+
+/// Quotient of a polynomial and a number. Coefficients are divided exactly
+pub(crate) fn divide(a: Value, b: Value) -> CalcResult {
+    let b = match coefficients(&b, "/")?.as_slice() {
+        [k] => k.clone(),
+        _ => return Err(CalcError::PolyToNumber(b.to_string())),
+    };
+    let c = coefficients(&a, "/")?
+        .into_iter()
+        .map(|k| k.divide_exact(b.clone()))
+        .collect::<Result<Vec<Value>, CalcError>>()?;
+    Ok(Value::Poly(normalize(c)))
+}
+
+/// Polynomials do not support the remainder operation
+pub(crate) fn remainder(_a: Value, _b: Value) -> CalcResult {
+    Err(CalcError::NotForPoly("%".to_string()))
+}
+
+/// Value of a polynomial at `x`(Horner's method)
+pub(crate) fn eval(c: &[Value], x: &Value) -> CalcResult {
+    let mut res = Value::Int(BigInt::zero());
+    for k in c {
+        res = res.multiply(x.clone())?.addition(k.clone())?;
+    }
+    Ok(res)
+}
+
+/// Polynomial in the form of an expression: `x**2-3*x+2`
+pub(crate) fn to_string(c: &[Value]) -> String {
+    let degree = c.len() - 1;
+    let mut s = String::new();
+    for (i, k) in c.iter().enumerate() {
+        if k.is_zero() && !(i == 0 && degree == 0) {
+            continue;
+        }
+        let d = degree - i;
+        let coef = k.to_string();
+        let term = match (d, coef.as_str()) {
+            (0, _) => coef,
+            (1, "1") => "x".to_string(),
+            (1, "-1") => "-x".to_string(),
+            (1, _) => format!("{}*x", coef),
+            (_, "1") => format!("x**{}", d),
+            (_, "-1") => format!("-x**{}", d),
+            _ => format!("{}*x**{}", coef, d),
+        };
+        if !s.is_empty() && !term.starts_with('-') {
+            s.push('+');
+        }
+        s += &term;
+    }
+    s
+}
+
+/// Parses the text of a polynomial made by `to_string`: `x**2-3*x+2`.
+/// Returns `None` if the text is not a polynomial with number coefficients
+pub(crate) fn from_str(s: &str) -> Option<Vec<Value>> {
+    // terms are separated by signs that are not a part of a float exponent
+    let b = s.as_bytes();
+    let mut terms = Vec::new();
+    let mut start = 0;
+    for i in 1..b.len() {
+        if (b[i] == b'+' || b[i] == b'-') && !b"eE*".contains(&b[i - 1]) {
+            terms.push(&s[start..i]);
+            start = i;
+        }
+    }
+    terms.push(&s[start..]);
+
+    let mut parsed = Vec::with_capacity(terms.len());
+    for t in terms {
+        let t = t.strip_prefix('+').unwrap_or(t);
+        let (coef, degree) = match t.find('x') {
+            None => (t, 0),
+            Some(pos) => {
+                let degree = match &t[pos + 1..] {
+                    "" => 1,
+                    rest => rest.strip_prefix("**")?.parse::<usize>().ok()?,
+                };
+                let coef = match &t[..pos] {
+                    "" => "1",
+                    "-" => "-1",
+                    c => c.strip_suffix('*')?,
+                };
+                (coef, degree)
+            }
+        };
+        match coef.parse::<Value>().ok()? {
+            v @ (Value::Int(..) | Value::Ratio(..) | Value::Float(..)) if degree <= MAX_PARSED_DEGREE => {
+                parsed.push((degree, v))
+            }
+            _ => return None,
+        }
+    }
+    let max = parsed.iter().map(|(d, _)| *d).max()?;
+    let mut c = vec![Value::Int(BigInt::zero()); max + 1];
+    for (d, v) in parsed {
+        c[max - d] = v;
+    }
+    Some(c)
+}
+
+fn is_exact(v: &Value) -> bool {
+    matches!(v, Value::Int(..) | Value::Ratio(..))
+}
+
+/// All roots of a polynomial, including complex ones. Roots of
+/// multiplicity `k` are repeated `k` times. Real roots go first in
+/// ascending order
+pub(crate) fn roots(c: &[Value]) -> Result<Vec<Value>, CalcError> {
+    if c.len() == 1 {
+        if c[0].is_zero() {
+            return Err(CalcError::InvalidAgrument("roots".to_string(), "zero polynomial".to_string()));
+        }
+        return Err(CalcError::NoRoots);
+    }
+    // zero roots are found exactly by removing the trailing zeroes
+    let zeroes = c.iter().rev().take_while(|v| v.is_zero()).count();
+    let c = &c[..c.len() - zeroes];
+    let degree = c.len() - 1;
+    if degree > MAX_ROOTS_DEGREE {
+        return Err(CalcError::InvalidAgrument(
+            "roots".to_string(),
+            format!("degree {} is greater than {}", degree, MAX_ROOTS_DEGREE),
+        ));
+    }
+    let exact = c.iter().all(is_exact);
+    let mut res = vec![Value::Int(BigInt::zero()); zeroes];
+    match degree {
+        0 => {}
+        1 => res.push(c[1].clone().negate()?.divide_exact(c[0].clone())?),
+        2 if exact => res.extend(exact_quadratic(&c[0], &c[1], &c[2])?),
+        _ => {
+            let cc =
+                c.iter().map(|v| v.clone().into_raw_complex()).collect::<Result<Vec<Complex<f64>>, CalcError>>()?;
+            let found = match degree {
+                2 => quadratic(cc[0], cc[1], cc[2]).to_vec(),
+                3 => cubic(cc[0], cc[1], cc[2], cc[3]).to_vec(),
+                _ => quartic(cc[0], cc[1], cc[2], cc[3], cc[4]).to_vec(),
+            };
+            for z in found {
+                res.push(root_value(c, polish(&cc, z), exact)?);
+            }
+        }
+    }
+    res.sort_by(compare_roots);
+    Ok(res)
+}
+
+// roots of a quadratic polynomial with integer or fractional coefficients:
+// they are exact if the discriminant is a square of a fraction
+fn exact_quadratic(a: &Value, b: &Value, c: &Value) -> Result<Vec<Value>, CalcError> {
+    let four = Value::Int(BigInt::from(4));
+    let d = b.clone().sqr()?.subtract(four.multiply(a.clone())?.multiply(c.clone())?)?;
+    let d = d.sqrt()?;
+    let a2 = a.clone().multiply(Value::Int(BigInt::from(2)))?;
+    let b = b.clone().negate()?;
+    let x1 = b.clone().subtract(d.clone())?.divide_exact(a2.clone())?;
+    let x2 = b.addition(d)?.divide_exact(a2)?;
+    Ok(vec![x1, x2])
+}
+
+fn quadratic(a: Complex<f64>, b: Complex<f64>, c: Complex<f64>) -> [Complex<f64>; 2] {
+    let d = (b * b - a * c * 4.0).sqrt();
+    // the sign is chosen to avoid subtraction of close numbers
+    let q = if (b + d).norm() >= (b - d).norm() { (b + d) * -0.5 } else { (b - d) * -0.5 };
+    if q.is_zero() {
+        return [q, q];
+    }
+    [q / a, c / q]
+}
+
+// principal cube root of a complex number. The real functions go through
+// `fmath`, so the roots are the same on all platforms with feature `libm`
+fn complex_cbrt(z: Complex<f64>) -> Complex<f64> {
+    let r = fmath::cbrt((z.re * z.re + z.im * z.im).sqrt());
+    let theta = fmath::atan2(z.im, z.re) / 3.0;
+    Complex::new(r * fmath::cos(theta), r * fmath::sin(theta))
+}
+
+// Cardano's formula
+fn cubic(a: Complex<f64>, b: Complex<f64>, c: Complex<f64>, d: Complex<f64>) -> [Complex<f64>; 3] {
+    let d0 = b * b - a * c * 3.0;
+    let d1 = b * b * b * 2.0 - a * b * c * 9.0 + a * a * d * 27.0;
+    let s = (d1 * d1 - d0 * d0 * d0 * 4.0).sqrt();
+    let t = if (d1 + s).norm() >= (d1 - s).norm() { d1 + s } else { d1 - s };
+    let k = complex_cbrt(t * 0.5);
+    let a3 = a * -3.0;
+    if k.is_zero() {
+        let x = b / a3;
+        return [x, x, x];
+    }
+    let xi = Complex::new(-0.5, 3f64.sqrt() / 2.0);
+    let k2 = k * xi;
+    let k3 = k2 * xi;
+    [(b + k + d0 / k) / a3, (b + k2 + d0 / k2) / a3, (b + k3 + d0 / k3) / a3]
+}
+
+// Ferrari's method: the depressed quartic is factored into two quadratic
+// polynomials with a root of the resolvent cubic
+fn quartic(a: Complex<f64>, b: Complex<f64>, c: Complex<f64>, d: Complex<f64>, e: Complex<f64>) -> [Complex<f64>; 4] {
+    let (b, c, d, e) = (b / a, c / a, d / a, e / a);
+    let p = c - b * b * 0.375;
+    let q = d - b * c * 0.5 + b * b * b * 0.125;
+    let r = e - b * d * 0.25 + b * b * c / 16.0 - b * b * b * b * 3.0 / 256.0;
+    let shift = b * -0.25;
+    let one = Complex::new(1.0, 0.0);
+    if q.norm() <= f64::EPSILON * (p.norm() + r.norm().sqrt() + 1.0) {
+        // biquadratic: `y**4 + p*y**2 + r`
+        let [z1, z2] = quadratic(one, p, r);
+        let (y1, y2) = (z1.sqrt(), z2.sqrt());
+        return [shift + y1, shift - y1, shift + y2, shift - y2];
+    }
+    let m = cubic(one * 8.0, p * 8.0, p * p * 2.0 - r * 8.0, -q * q)
+        .iter()
+        .copied()
+        .fold(Complex::zero(), |best: Complex<f64>, m| if m.norm() > best.norm() { m } else { best });
+    let s = (m * 2.0).sqrt();
+    let mut res = [Complex::zero(); 4];
+    for (i, sign) in [1.0, -1.0].iter().enumerate() {
+        let t = (-(p * 2.0 + m * 2.0 + q * 2.0 * *sign / s)).sqrt();
+        res[2 * i] = shift + (s * *sign + t) * 0.5;
+        res[2 * i + 1] = shift + (s * *sign - t) * 0.5;
+    }
+    res
+}
+
+// refines a root with Newton's method while it improves the result
+fn polish(c: &[Complex<f64>], mut z: Complex<f64>) -> Complex<f64> {
+    let value = |z: Complex<f64>| {
+        c.iter().fold((Complex::zero(), Complex::zero()), |(p, dp): (Complex<f64>, Complex<f64>), k| {
+            (p * z + k, dp * z + p)
+        })
+    };
+    let (mut p, mut dp) = value(z);
+    for _ in 0..POLISH_ITERATIONS {
+        if p.is_zero() || dp.is_zero() {
+            break;
+        }
+        let next = z - p / dp;
+        let (np, ndp) = value(next);
+        if np.norm() >= p.norm() {
+            break;
+        }
+        z = next;
+        p = np;
+        dp = ndp;
+    }
+    z
+}
+
+// converts a float root to a value: a real number if the imaginary part is
+// negligible, and an exact fraction if it is a root of the polynomial with
+// exact coefficients
+fn root_value(c: &[Value], z: Complex<f64>, exact: bool) -> CalcResult {
+    if z.im.abs() > IMAGINARY_EPS * z.norm().max(1.0) {
+        return Ok(Value::Complex(z));
+    }
+    if exact {
+        let r = Value::Float(z.re).as_fraction(Value::Int(BigInt::from(MAX_ROOT_DENOMINATOR)))?;
+        if eval(c, &r)?.is_zero() {
+            return Ok(r);
+        }
+    }
+    Ok(Value::Float(z.re))
+}
+
+fn compare_roots(a: &Value, b: &Value) -> Ordering {
+    let key = |v: &Value| match v {
+        Value::Complex(c) => (1, c.re, c.im),
+        _ => (0, v.as_f64().unwrap_or(0.0), 0.0),
+    };
+    let (ka, kb) = (key(a), key(b));
+    ka.0.cmp(&kb.0)
+        .then(ka.1.partial_cmp(&kb.1).unwrap_or(Ordering::Equal))
+        .then(ka.2.partial_cmp(&kb.2).unwrap_or(Ordering::Equal))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_solvers() {
+        let c = |re: f64| Complex::new(re, 0.0);
+        let check = |roots: &[Complex<f64>], expected: &[f64]| {
+            let mut re: Vec<f64> = roots.iter().map(|z| z.re).collect();
+            re.sort_by(|a, b| a.partial_cmp(b).unwrap());
+            for (x, e) in re.iter().zip(expected.iter()) {
+                assert!((x - e).abs() < 1e-9, "{:?} != {:?}", re, expected);
+            }
+        };
+        check(&quadratic(c(1.0), c(-3.0), c(2.0)), &[1.0, 2.0]);
+        check(&cubic(c(1.0), c(-6.0), c(11.0), c(-6.0)), &[1.0, 2.0, 3.0]);
+        check(&cubic(c(2.0), c(0.0), c(0.0), c(-16.0)), &[-1.0, -1.0, 2.0]);
+        check(&quartic(c(1.0), c(-10.0), c(35.0), c(-50.0), c(24.0)), &[1.0, 2.0, 3.0, 4.0]);
+        check(&quartic(c(1.0), c(0.0), c(-5.0), c(0.0), c(4.0)), &[-2.0, -1.0, 1.0, 2.0]);
+
+        let one = Value::Int(BigInt::from(1));
+        assert_eq!(to_string(&[one.clone(), Value::Int(BigInt::from(-3)), Value::Int(BigInt::from(2))]), "x**2-3*x+2");
+        assert_eq!(to_string(&[Value::Float(-0.5), Value::Int(BigInt::zero()), one]), "-0.5*x**2+1");
+        assert_eq!(to_string(&[Value::Int(BigInt::zero())]), "0");
+    }
+}
@@ -0,0 +1,123 @@
+//! Python module `rcalc` (enabled with feature `python`).
+//!
+//! Values are converted to native Python types: integers to `int`, float
+//! numbers to `float`, common fractions to `fractions.Fraction`, complex
+//! numbers to `complex`, lists to `list`, strings to `str`, and polynomials
+//! to `list` of their coefficients. Calculation errors raise `ValueError`.
+
+// code generated by pyo3 macros triggers the lint
+#![allow(clippy::useless_conversion)]
+
+use pyo3::exceptions::PyValueError;
+use pyo3::prelude::*;
+use pyo3::types::{PyComplex, PyList};
+
+use crate::parse::{eval, CalcState};
+use crate::value::Value;
+
+fn to_py(py: Python<'_>, v: Value) -> PyResult<PyObject> {
+    let obj = match v {
+        Value::Int(i) => {
+            let int = py.import_bound("builtins")?.getattr("int")?;
+            int.call1((i.to_string(),))?.unbind()
+        }
+        Value::Float(f) => f.into_py(py),
+        Value::Ratio(r) => {
+            let int = py.import_bound("builtins")?.getattr("int")?;
+            let n = int.call1((r.numer().to_string(),))?;
+            let d = int.call1((r.denom().to_string(),))?;
+            let fraction = py.import_bound("fractions")?.getattr("Fraction")?;
+            fraction.call1((n, d))?.unbind()
+        }
+        Value::Complex(c) => PyComplex::from_doubles_bound(py, c.re, c.im).into_any().unbind(),
+        Value::List(l) | Value::Poly(l) => {
+            let items = l.into_iter().map(|v| to_py(py, v)).collect::<PyResult<Vec<PyObject>>>()?;
+            PyList::new_bound(py, items).into_any().unbind()
+        }
+        Value::Str(s) => s.into_py(py),
+        Value::Bool(b) => b.into_py(py),
+    };
+    Ok(obj)
+}
+
+fn eval_to_py(py: Python<'_>, expr: &str, state: &mut CalcState) -> PyResult<PyObject> {
+    match eval(expr, state) {
+        Ok(v) => to_py(py, v),
+        Err(e) => Err(PyValueError::new_err(e.to_string())),
+    }
+}
+
+/// Calculator session: keeps variables and the last result between evaluations
+#[pyclass]
+struct Session {
+    state: CalcState,
+}
+
+#[pymethods]
+impl Session {
+    #[new]
+    fn new() -> Self {
+        Session { state: CalcState::new() }
+    }
+
+    /// Evaluates an expression and returns its result
+    fn evaluate(&mut self, py: Python<'_>, expr: &str) -> PyResult<PyObject> {
+        eval_to_py(py, expr, &mut self.state)
+    }
+
+    /// Alternative representation of the last result(e.g, for `hex` function) or `None`
+    fn alt_result(&self) -> Option<String> {
+        if self.state.has_alt {
+            Some(self.state.alt_result.clone())
+        } else {
+            None
+        }
+    }
+}
+
+/// Evaluates an expression in a new empty session and returns its result
+#[pyfunction]
+fn evaluate(py: Python<'_>, expr: &str) -> PyResult<PyObject> {
+    let mut state = CalcState::new();
+    eval_to_py(py, expr, &mut state)
+}
+
+#[pymodule]
+fn rcalc(m: &Bound<'_, PyModule>) -> PyResult<()> {
+    m.add_function(wrap_pyfunction!(evaluate, m)?)?;
+    m.add_class::<Session>()?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_conversion() {
+        pyo3::prepare_freethreaded_python();
+        Python::with_gil(|py| {
+            let v = evaluate(py, "2**100").unwrap();
+            assert_eq!(v.bind(py).str().unwrap().to_string(), "1267650600228229401496703205376");
+            let v = evaluate(py, "1\\3 + 1\\3").unwrap();
+            assert_eq!(v.bind(py).repr().unwrap().to_string(), "Fraction(2, 3)");
+            let v = evaluate(py, "sqrt(-4)").unwrap();
+            assert_eq!(v.bind(py).repr().unwrap().to_string(), "2j");
+            let v = evaluate(py, "1/4").unwrap();
+            assert_eq!(v.extract::<f64>(py).unwrap(), 0.25);
+            assert!(evaluate(py, "1/0").is_err());
+            let v = evaluate(py, "[1; 1\\2]").unwrap();
+            assert_eq!(v.bind(py).repr().unwrap().to_string(), "[1, Fraction(1, 2)]");
+            let v = evaluate(py, "roman(14)").unwrap();
+            assert_eq!(v.extract::<String>(py).unwrap(), "XIV");
+            let v = evaluate(py, "poly(1; -3; 2)").unwrap();
+            assert_eq!(v.bind(py).repr().unwrap().to_string(), "[1, -3, 2]");
+
+            let mut s = Session::new();
+            s.evaluate(py, "5").unwrap();
+            let v = s.evaluate(py, "hex(ans*3)").unwrap();
+            assert_eq!(v.extract::<i64>(py).unwrap(), 15);
+            assert_eq!(s.alt_result(), Some("0xf".to_string()));
+        });
+    }
+}
@@ -0,0 +1,25 @@
+//! Host-supplied variables.
+//!
+//! A `Resolver` provides values for identifiers that are not defined in
+//! the session, e.g. spreadsheet cells (`A1`) or fields of host objects
+//! (`price.total`). An identifier may resolve to a value or to another
+//! formula that is evaluated on demand. Formulas can refer to other
+//! identifiers, and a loop of references is reported as an error. So is a
+//! chain of formulas longer than `Limits::max_nesting`.
+
+/// What a resolver returns for an identifier
+#[derive(Clone, Debug, PartialEq)]
+pub enum Resolved {
+    /// A ready value
+    Value(crate::value::Value),
+    /// An expression that is evaluated to get the value
+    Formula(String),
+}
+
+/// Source of values for identifiers unknown to the session. Identifier
+/// names passed to `resolve` are always lowercase
+pub trait Resolver {
+    /// Returns the value or formula for the identifier, or `None` if the
+    /// identifier is unknown
+    fn resolve(&self, name: &str) -> Option<Resolved>;
+}
@@ -0,0 +1,276 @@
+//! Restrictions for evaluating untrusted expressions.
+//!
+//! A calculator with a sandbox rejects expressions that are too long or
+//! too deeply nested, that use functions or operators outside of the allowed
+//! lists, and that produce integer numbers bigger than the limit. Sizes of
+//! results of power, factorials, shift, primorial, Fibonacci, harmonic,
+//! Stirling, and Bell numbers are estimated before the calculation starts,
+//! so a short expression like `9**9**9` cannot keep a server busy.
+//!
+//! Parser limits(`Limits`) are applied to every expression, even without
+//! a sandbox.
+
+use num_bigint::BigInt;
+use num_traits::{Signed, ToPrimitive};
+
+use crate::errors::*;
+use crate::stack::{Entry, FACTORIAL, PERCENT_ADD, PERCENT_DIV, PERCENT_MUL, PERCENT_SUB, UNARY_MINUS};
+use crate::value::{CalcErrorResult, Value};
+
+/// Parser limits that protect from pathological input, e.g. thousands of
+/// nested brackets or megabyte-long expressions. The limits are applied to
+/// all expressions, sandboxed or not. Use `usize::MAX` to disable a limit
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Limits {
+    /// Maximum length of an expression in bytes
+    pub max_length: usize,
+    /// Maximum number of nested brackets
+    pub max_depth: usize,
+    /// Maximum number of numbers, names, operators, and brackets
+    pub max_tokens: usize,
+    /// Maximum number of formulas evaluated one inside another, e.g. a
+    /// formula of `map` that calls `map` with another formula, and so on.
+    /// It also limits chains of formulas returned by a `Resolver`
+    pub max_nesting: usize,
+}
+
+impl Default for Limits {
+    fn default() -> Limits {
+        Limits { max_length: 65_536, max_depth: 256, max_tokens: 10_000, max_nesting: 64 }
+    }
+}
+
+/// Sandbox profile. By default, all functions and operators are allowed
+/// and the limits are strict enough for bots and web services
+#[derive(Clone, Debug)]
+pub struct Sandbox {
+    functions: Option<Vec<String>>,
+    operators: Option<Vec<String>>,
+    pub(crate) max_length: usize,
+    pub(crate) max_depth: usize,
+    pub(crate) max_int_bits: u64,
+}
+
+impl Default for Sandbox {
+    fn default() -> Sandbox {
+        Sandbox { functions: None, operators: None, max_length: 1024, max_depth: 32, max_int_bits: 8192 }
+    }
+}
+
+impl Sandbox {
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    /// Allows only the listed functions. Names are caseinsensitive
+    pub fn allow_functions(mut self, names: &[&str]) -> Self {
+        self.functions = Some(names.iter().map(|n| n.to_lowercase()).collect());
+        self
+    }
+
+    /// Allows only the listed operators. Allowing `-` allows unary minus as
+    /// well, and allowing `%` allows percentage operators
+    pub fn allow_operators(mut self, ops: &[&str]) -> Self {
+        self.operators = Some(ops.iter().map(|op| op.to_string()).collect());
+        self
+    }
+
+    /// Sets the maximum length of an expression in bytes. The calculator
+    /// uses the smallest of sandbox and parser limits
+    pub fn max_length(mut self, len: usize) -> Self {
+        self.max_length = len;
+        self
+    }
+
+    /// Sets the maximum number of nested brackets. The calculator uses the
+    /// smallest of sandbox and parser limits
+    pub fn max_depth(mut self, depth: usize) -> Self {
+        self.max_depth = depth;
+        self
+    }
+
+    /// Sets the maximum size of integer numbers(numerator and denominator
+    /// for fractions) in bits
+    pub fn max_int_bits(mut self, bits: u64) -> Self {
+        self.max_int_bits = bits;
+        self
+    }
+
+    // checks that a compiled expression uses only allowed functions and operators
+    pub(crate) fn check_entries(&self, entries: &[Entry]) -> CalcErrorResult {
+        for e in entries {
+            match e {
+                Entry::Func(name, ..) => self.check_function(name)?,
+                Entry::Op(op, ..) => self.check_operator(op)?,
+                _ => {}
+            }
+        }
+        Ok(())
+    }
+
+    pub(crate) fn check_function(&self, name: &str) -> CalcErrorResult {
+        match &self.functions {
+            Some(names) if !names.iter().any(|n| n == name) => Err(CalcError::NotAllowed(name.to_string())),
+            _ => Ok(()),
+        }
+    }
+
+    pub(crate) fn check_operator(&self, op: &str) -> CalcErrorResult {
+        let symbol = match op {
+            UNARY_MINUS => "-",
+            FACTORIAL => "!",
+            PERCENT_ADD | PERCENT_SUB | PERCENT_MUL | PERCENT_DIV => "%",
+            _ => op,
+        };
+        match &self.operators {
+            Some(ops) if !ops.iter().any(|o| o == symbol) => Err(CalcError::NotAllowed(symbol.to_string())),
+            _ => Ok(()),
+        }
+    }
+
+    // integers with exponent(`1e100`) are expanded when an expression is
+    // parsed, so their size is checked before conversion
+    pub(crate) fn check_literal(&self, s: &str) -> CalcErrorResult {
+        let exp = match s.find(['e', 'E']) {
+            Some(pos) => s[pos + 1..].trim_start_matches('+').replace('_', ""),
+            None => return Ok(()),
+        };
+        let bits = match exp.parse::<u64>() {
+            Ok(e) => e.saturating_mul(10) / 3,
+            Err(..) => u64::MAX,
+        };
+        if bits > self.max_int_bits {
+            return Err(CalcError::NumberTooBig(self.max_int_bits));
+        }
+        Ok(())
+    }
+
+    // estimates the size of the result of an operation that may generate
+    // a huge integer. `args` are the operation arguments
+    pub(crate) fn check_result_size(&self, op: &str, args: &[Value]) -> CalcErrorResult {
+        let bits = match (op, args) {
+            ("**" | "^", [base, Value::Int(pow)]) => {
+                // the estimation can be up to two times less than the real
+                // size, the final result is checked after calculation anyway
+                let base_bits = int_bits(base);
+                if base_bits <= 1 {
+                    return Ok(());
+                }
+                (base_bits - 1).saturating_mul(bigint_to_u64(pow))
+            }
+            ("<<" | "ldexp", [v, Value::Int(shift)]) if shift.is_positive() => {
+                int_bits(v).saturating_add(bigint_to_u64(shift))
+            }
+            // Stirling and Bell numbers do not exceed `n!`, `n^n` respectively
+            (FACTORIAL, [Value::Int(n)])
+            | ("stirling1" | "stirling2", [Value::Int(n), _])
+            | ("bell", [Value::Int(n), ..]) => {
+                let n = bigint_to_u64(n);
+                n.saturating_mul(64 - n.leading_zeros() as u64)
+            }
+            ("fib", [Value::Int(n), ..]) => bigint_to_u64(n).saturating_mul(7) / 10,
+            ("exp2", [Value::Int(n), ..]) => bigint_to_u64(n),
+            // the denominator is close to `lcm(1..n)` ~ `exp(n)`, and the
+            // primorial is close to `exp(n)` as well
+            ("harmonic" | "primorial", [Value::Int(n), ..]) => bigint_to_u64(n).saturating_mul(3) / 2,
+            // about `k`-th root of `n!`
+            ("multifact", [Value::Int(n), Value::Int(k), ..]) if k.is_positive() => {
+                let n = bigint_to_u64(n);
+                n.saturating_mul(64 - n.leading_zeros() as u64) / bigint_to_u64(k)
+            }
+            _ => return Ok(()),
+        };
+        if bits > self.max_int_bits {
+            return Err(CalcError::NumberTooBig(self.max_int_bits));
+        }
+        Ok(())
+    }
+
+    pub(crate) fn check_value(&self, v: &Value) -> CalcErrorResult {
+        if int_bits(v) > self.max_int_bits {
+            return Err(CalcError::NumberTooBig(self.max_int_bits));
+        }
+        Ok(())
+    }
+}
+
+fn bigint_to_u64(i: &BigInt) -> u64 {
+    i.abs().to_u64().unwrap_or(u64::MAX)
+}
+
+// size of an integer or the biggest part of a fraction
+fn int_bits(v: &Value) -> u64 {
+    match v {
+        Value::Int(i) => i.bits() as u64,
+        Value::Ratio(r) => r.numer().bits().max(r.denom().bits()) as u64,
+        _ => 0,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::calc::Calc;
+    use crate::parse::CalcState;
+
+    #[test]
+    fn test_sandbox() {
+        let calc = Calc::new().with_sandbox(
+            Sandbox::new().allow_functions(&["sqrt", "MAX", "fib"]).allow_operators(&["+", "-", "*", "**", "!"]),
+        );
+        let mut state = CalcState::new();
+        assert_eq!(calc.eval("max(2; 3) * -sqrt(4)", &mut state), Ok(Value::Int(BigInt::from(-6))));
+        assert_eq!(calc.eval("sin(1)", &mut state), Err(CalcError::NotAllowed("sin".to_string())));
+        assert_eq!(calc.eval("7 / 2", &mut state), Err(CalcError::NotAllowed("/".to_string())));
+        assert_eq!(calc.eval("50 - 10 %", &mut state), Err(CalcError::NotAllowed("%".to_string())));
+        assert_eq!(calc.eval("9**9**9", &mut state), Err(CalcError::NumberTooBig(8192)));
+        assert_eq!(calc.eval("100000!", &mut state), Err(CalcError::NumberTooBig(8192)));
+        assert_eq!(calc.eval("fib(50000)", &mut state), Err(CalcError::NumberTooBig(8192)));
+        assert_eq!(calc.eval("1e100000", &mut state), Err(CalcError::NumberTooBig(8192)));
+        assert_eq!(calc.eval("(2**4000)*(2**4000)*(2**4000)", &mut state), Err(CalcError::NumberTooBig(8192)));
+        assert!(calc.eval("2**8000 + 1e2000 + 100!", &mut state).is_ok());
+        assert_eq!(calc.eval("1**1000000000", &mut state), Ok(Value::Int(BigInt::from(1))));
+
+        let long = "1+".repeat(600) + "1";
+        assert_eq!(calc.eval(&long, &mut state), Err(CalcError::ExprTooLong(1024)));
+        let deep = "(".repeat(33) + "1" + &")".repeat(33);
+        assert_eq!(calc.eval(&deep, &mut state), Err(CalcError::ExprTooDeep(32)));
+        let deep = "(".repeat(32) + "1" + &")".repeat(32);
+        assert_eq!(calc.eval(&deep, &mut state), Ok(Value::Int(BigInt::from(1))));
+        assert_eq!(calc.eval_rpn("2 3 <<", &mut state), Err(CalcError::NotAllowed("<<".to_string())));
+
+        let calc = Calc::new().with_sandbox(Sandbox::new().max_int_bits(64));
+        assert_eq!(calc.eval("1 << 70", &mut state), Err(CalcError::NumberTooBig(64)));
+        assert_eq!(calc.eval("ldexp(1; 70)", &mut state), Err(CalcError::NumberTooBig(64)));
+        assert_eq!(calc.eval("exp2(70)", &mut state), Err(CalcError::NumberTooBig(64)));
+        assert_eq!(calc.eval("harmonic(50)", &mut state), Err(CalcError::NumberTooBig(64)));
+        assert_eq!(calc.eval("bell(30)", &mut state), Err(CalcError::NumberTooBig(64)));
+        assert_eq!(calc.eval("primorial(100)", &mut state), Err(CalcError::NumberTooBig(64)));
+        assert_eq!(calc.eval("multifact(100; 2)", &mut state), Err(CalcError::NumberTooBig(64)));
+        assert!(calc.eval("multifact(100; 50)", &mut state).is_ok());
+        assert_eq!(calc.eval("100000000000000000000", &mut state), Err(CalcError::NumberTooBig(64)));
+        assert_eq!(calc.eval("1\\100000000000000000000", &mut state), Err(CalcError::NumberTooBig(64)));
+    }
+
+    #[test]
+    fn test_limits() {
+        let calc = Calc::new();
+        let mut state = CalcState::new();
+        let deep = "(".repeat(300) + "1";
+        assert_eq!(calc.eval(&deep, &mut state), Err(CalcError::ExprTooDeep(256)));
+        let long = "1".repeat(1 << 20);
+        assert_eq!(calc.eval(&long, &mut state), Err(CalcError::ExprTooLong(65_536)));
+        let many = "1+".repeat(6000) + "1";
+        assert_eq!(calc.eval(&many, &mut state), Err(CalcError::TooManyTokens(10_000)));
+        assert_eq!(calc.eval_rpn(&"1 ".repeat(10_001), &mut state), Err(CalcError::TooManyTokens(10_000)));
+
+        let calc = Calc::new().with_limits(Limits { max_depth: 3, max_tokens: 20, ..Limits::default() });
+        assert_eq!(calc.eval("((1+2)*3)", &mut state), Ok(Value::Int(BigInt::from(9))));
+        assert_eq!(calc.eval("sqrt((((1))))", &mut state), Err(CalcError::ExprTooDeep(3)));
+        let many = "1+".repeat(10) + "1";
+        assert_eq!(calc.eval(&many, &mut state), Err(CalcError::TooManyTokens(20)));
+        // the smallest limit wins
+        let calc = calc.with_sandbox(Sandbox::new().max_depth(1));
+        assert_eq!(calc.eval("((1+2)*3)", &mut state), Err(CalcError::ExprTooDeep(1)));
+    }
+}
@@ -0,0 +1,153 @@
+//! Serialization support (enabled with feature `serde`).
+//!
+//! Wire representation of values does not depend on the internals of
+//! big number crates, so it stays stable between library versions:
+//! * `Int` - a string with a decimal integer number: `{"Int":"-12345"}`
+//! * `Float` - a number: `{"Float":1.5}`
+//! * `Ratio` - two strings with decimal numerator and denominator: `{"Ratio":["-1","3"]}`
+//! * `Complex` - two numbers, real and imaginary parts: `{"Complex":[1.0,-2.5]}`
+//! * `List` - an array of values: `{"List":[{"Int":"1"},{"Float":0.5}]}`
+//! * `Str` - a string: `{"Str":"MMXXIV"}`
+//! * `Poly` - an array of coefficients starting from the highest degree: `{"Poly":[{"Int":"1"},{"Int":"-3"}]}`
+//!
+//! Big integers and fractions inside errors use the same string formats.
+
+use num_bigint::BigInt;
+use num_complex::Complex;
+use num_rational::BigRational;
+use num_traits::Zero;
+use serde::de::Error;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+use crate::value::Value;
+
+#[derive(Serialize, Deserialize)]
+enum ValueRepr {
+    Int(String),
+    Float(f64),
+    Ratio(String, String),
+    Complex(f64, f64),
+    List(Vec<Value>),
+    Str(String),
+    Poly(Vec<Value>),
+    Bool(bool),
+}
+
+fn parse_bigint<E: Error>(s: &str) -> Result<BigInt, E> {
+    s.parse::<BigInt>().map_err(|_| E::custom(format!("invalid integer '{}'", s)))
+}
+
+fn parse_ratio<E: Error>(n: &str, d: &str) -> Result<BigRational, E> {
+    let n = parse_bigint(n)?;
+    let d = parse_bigint(d)?;
+    if d.is_zero() {
+        return Err(E::custom("zero denominator"));
+    }
+    Ok(BigRational::new(n, d))
+}
+
+impl Serialize for Value {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let repr = match self {
+            Value::Int(i) => ValueRepr::Int(i.to_string()),
+            Value::Float(f) => ValueRepr::Float(*f),
+            Value::Ratio(r) => ValueRepr::Ratio(r.numer().to_string(), r.denom().to_string()),
+            Value::Complex(c) => ValueRepr::Complex(c.re, c.im),
+            Value::List(l) => ValueRepr::List(l.clone()),
+            Value::Str(s) => ValueRepr::Str(s.clone()),
+            Value::Poly(c) => ValueRepr::Poly(c.clone()),
+            Value::Bool(b) => ValueRepr::Bool(*b),
+        };
+        repr.serialize(serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for Value {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Value, D::Error> {
+        let v = match ValueRepr::deserialize(deserializer)? {
+            ValueRepr::Int(s) => Value::Int(parse_bigint(&s)?),
+            ValueRepr::Float(f) => Value::Float(f),
+            ValueRepr::Ratio(n, d) => Value::Ratio(parse_ratio(&n, &d)?),
+            ValueRepr::Complex(re, im) => Value::Complex(Complex::new(re, im)),
+            ValueRepr::List(l) => Value::List(l),
+            ValueRepr::Str(s) => Value::Str(s),
+            ValueRepr::Poly(c) => Value::Poly(c),
+            ValueRepr::Bool(b) => Value::Bool(b),
+        };
+        Ok(v)
+    }
+}
+
+pub(crate) mod bigint_str {
+    use super::*;
+
+    pub(crate) fn serialize<S: Serializer>(i: &BigInt, serializer: S) -> Result<S::Ok, S::Error> {
+        i.to_string().serialize(serializer)
+    }
+
+    pub(crate) fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<BigInt, D::Error> {
+        let s = String::deserialize(deserializer)?;
+        parse_bigint(&s)
+    }
+}
+
+pub(crate) mod ratio_str {
+    use super::*;
+
+    pub(crate) fn serialize<S: Serializer>(r: &BigRational, serializer: S) -> Result<S::Ok, S::Error> {
+        (r.numer().to_string(), r.denom().to_string()).serialize(serializer)
+    }
+
+    pub(crate) fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<BigRational, D::Error> {
+        let (n, d) = <(String, String)>::deserialize(deserializer)?;
+        parse_ratio(&n, &d)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::errors::CalcError;
+    use crate::parse::{compile, CalcState};
+
+    #[test]
+    fn test_value_wire_format() {
+        let v = Value::Int(BigInt::from(-12345));
+        assert_eq!(serde_json::to_string(&v).unwrap(), r#"{"Int":"-12345"}"#);
+        let v = Value::Ratio(BigRational::new(BigInt::from(2), BigInt::from(-6)));
+        assert_eq!(serde_json::to_string(&v).unwrap(), r#"{"Ratio":["-1","3"]}"#);
+        let v = Value::Complex(Complex::new(1.0, -2.5));
+        assert_eq!(serde_json::to_string(&v).unwrap(), r#"{"Complex":[1.0,-2.5]}"#);
+        let v = Value::Float(1.5);
+        assert_eq!(serde_json::to_string(&v).unwrap(), r#"{"Float":1.5}"#);
+        let v = Value::List(vec![Value::Int(BigInt::from(1)), Value::Float(0.5)]);
+        assert_eq!(serde_json::to_string(&v).unwrap(), r#"{"List":[{"Int":"1"},{"Float":0.5}]}"#);
+        let v = Value::Str("XIV".to_string());
+        assert_eq!(serde_json::to_string(&v).unwrap(), r#"{"Str":"XIV"}"#);
+        let v = Value::Poly(vec![Value::Int(BigInt::from(1)), Value::Int(BigInt::from(-3))]);
+        assert_eq!(serde_json::to_string(&v).unwrap(), r#"{"Poly":[{"Int":"1"},{"Int":"-3"}]}"#);
+        assert_eq!(serde_json::to_string(&Value::Bool(true)).unwrap(), r#"{"Bool":true}"#);
+
+        let v: Value = serde_json::from_str(r#"{"Int":"123456789012345678901234567890"}"#).unwrap();
+        assert_eq!(v, Value::Int("123456789012345678901234567890".parse::<BigInt>().unwrap()));
+        let v: Value = serde_json::from_str(r#"{"Ratio":["3","6"]}"#).unwrap();
+        assert_eq!(v, Value::Ratio(BigRational::new(BigInt::from(1), BigInt::from(2))));
+        assert!(serde_json::from_str::<Value>(r#"{"Ratio":["3","0"]}"#).is_err());
+        assert!(serde_json::from_str::<Value>(r#"{"Int":"1.5"}"#).is_err());
+    }
+
+    #[test]
+    fn test_error_and_expr() {
+        let e = CalcError::IntToFloat(BigInt::from(7));
+        let s = serde_json::to_string(&e).unwrap();
+        assert_eq!(s, r#"{"IntToFloat":"7"}"#);
+        let e2: CalcError = serde_json::from_str(&s).unwrap();
+        assert_eq!(e, e2);
+
+        let mut state = CalcState::new();
+        let expr = compile("(2+3)*sqr(4)", &mut state).unwrap();
+        let s = serde_json::to_string(&expr).unwrap();
+        let expr: crate::expr::Expr = serde_json::from_str(&s).unwrap();
+        assert_eq!(expr.eval(&mut state), Ok(Value::Int(BigInt::from(80))));
+    }
+}
@@ -0,0 +1,345 @@
+//! Truncated power series.
+//!
+//! A series keeps the coefficients of `c0 + c1*t + c2*t**2 + ...` starting
+//! from the lowest degree up to a fixed order. Every operator and function
+//! applied to a series returns the series of its result, so evaluating an
+//! expression with the series `a + t` in place of its variable gives the
+//! Taylor series of the expression at `a`: the coefficient `ck` is the
+//! `k`-th derivative at `a` divided by `k!`. Operations on integer and
+//! fractional coefficients are exact.
+
+use num_bigint::{BigInt, Sign};
+use num_rational::BigRational;
+use num_traits::{Signed, ToPrimitive, Zero};
+
+use crate::calc::Calc;
+use crate::errors::*;
+use crate::expr::new_stack;
+use crate::formula::Lookup;
+use crate::stack::{op_args, Entry, UNARY_MINUS};
+use crate::value::*;
+
+pub(crate) type Series = Vec<Value>;
+
+fn int(i: usize) -> Value {
+    Value::Int(BigInt::from(i))
+}
+
+fn zero() -> Value {
+    Value::Int(BigInt::zero())
+}
+
+fn half() -> Value {
+    Value::Ratio(BigRational::new(BigInt::from(1), BigInt::from(2)))
+}
+
+/// Series of a number: all coefficients except the first one are zeroes
+pub(crate) fn constant(v: Value, len: usize) -> Series {
+    let mut s = vec![zero(); len];
+    s[0] = v;
+    s
+}
+
+/// Series of the variable at point `a`: `a + t`
+pub(crate) fn variable(a: Value, len: usize) -> Series {
+    let mut s = constant(a, len);
+    if len > 1 {
+        s[1] = int(1);
+    }
+    s
+}
+
+fn is_constant(s: &[Value]) -> bool {
+    s[1..].iter().all(|v| v.is_zero())
+}
+
+fn add(a: &[Value], b: &[Value]) -> Result<Series, CalcError> {
+    a.iter().zip(b.iter()).map(|(x, y)| x.clone().addition(y.clone())).collect()
+}
+
+fn sub(a: &[Value], b: &[Value]) -> Result<Series, CalcError> {
+    a.iter().zip(b.iter()).map(|(x, y)| x.clone().subtract(y.clone())).collect()
+}
+
+fn neg(a: &[Value]) -> Result<Series, CalcError> {
+    a.iter().map(|x| x.clone().negate()).collect()
+}
+
+fn mul(a: &[Value], b: &[Value]) -> Result<Series, CalcError> {
+    let mut res = Vec::with_capacity(a.len());
+    for k in 0..a.len() {
+        let mut sum = zero();
+        for j in 0..=k {
+            sum = sum.addition(a[j].clone().multiply(b[k - j].clone())?)?;
+        }
+        res.push(sum);
+    }
+    Ok(res)
+}
+
+// `h = a / b`: `b0*hk = ak - (b1*h(k-1) + ... + bk*h0)`
+fn div(a: &[Value], b: &[Value]) -> Result<Series, CalcError> {
+    let mut res: Series = Vec::with_capacity(a.len());
+    for k in 0..a.len() {
+        let mut sum = a[k].clone();
+        for j in 1..=k {
+            sum = sum.subtract(b[j].clone().multiply(res[k - j].clone())?)?;
+        }
+        res.push(sum.divide_exact(b[0].clone())?);
+    }
+    Ok(res)
+}
+
+// the series of the derivative: its last coefficient is unknown and it is
+// set to zero, so the result is correct up to the order minus one
+fn derivative(a: &[Value]) -> Result<Series, CalcError> {
+    let mut res = Vec::with_capacity(a.len());
+    for (k, v) in a.iter().enumerate().skip(1) {
+        res.push(v.clone().multiply(int(k))?);
+    }
+    res.push(zero());
+    Ok(res)
+}
+
+// the series of the antiderivative with the given constant term
+fn integral(a: &[Value], c: Value) -> Result<Series, CalcError> {
+    let mut res = Vec::with_capacity(a.len());
+    res.push(c);
+    for k in 1..a.len() {
+        res.push(a[k - 1].clone().divide_exact(int(k))?);
+    }
+    Ok(res)
+}
+
+// `h = exp(a)`: `k*hk = 1*a1*h(k-1) + 2*a2*h(k-2) + ... + k*ak*h0`
+fn exp(a: &[Value]) -> Result<Series, CalcError> {
+    let mut res: Series = Vec::with_capacity(a.len());
+    res.push(a[0].clone().exp()?);
+    for k in 1..a.len() {
+        let mut sum = zero();
+        for j in 1..=k {
+            sum = sum.addition(a[j].clone().multiply(int(j))?.multiply(res[k - j].clone())?)?;
+        }
+        res.push(sum.divide_exact(int(k))?);
+    }
+    Ok(res)
+}
+
+// `ln(a)` is the antiderivative of `a' / a`
+fn ln(a: &[Value]) -> Result<Series, CalcError> {
+    integral(&div(&derivative(a)?, a)?, a[0].clone().ln()?)
+}
+
+// `h = a**p` for a number `p`: `k*a0*hk = sum((p*j - (k - j))*aj*h(k-j))`
+// for `j` from 1 to `k`. `h0` is passed by the caller, so `sqrt` can use
+// the exact square root
+fn pow_num(a: &[Value], p: &Value, h0: Value) -> Result<Series, CalcError> {
+    let mut res: Series = Vec::with_capacity(a.len());
+    res.push(h0);
+    for k in 1..a.len() {
+        let mut sum = zero();
+        for j in 1..=k {
+            let f = p.clone().multiply(int(j))?.subtract(int(k - j))?;
+            sum = sum.addition(f.multiply(a[j].clone())?.multiply(res[k - j].clone())?)?;
+        }
+        res.push(sum.divide_exact(a[0].clone().multiply(int(k))?)?);
+    }
+    Ok(res)
+}
+
+fn pow(a: &[Value], b: &[Value]) -> Result<Series, CalcError> {
+    if !is_constant(b) {
+        // a**b = exp(b*ln(a))
+        return exp(&mul(b, &ln(a)?)?);
+    }
+    let p = &b[0];
+    if let Value::Int(n) = p {
+        // integer powers work for any `a0`, including zero
+        let mut e = match n.abs().to_u64() {
+            Some(e) => e,
+            None => return Err(CalcError::InvalidAgrument("**".to_string(), n.to_string())),
+        };
+        let mut res = constant(int(1), a.len());
+        let mut base = a.to_vec();
+        while e != 0 {
+            if e & 1 == 1 {
+                res = mul(&res, &base)?;
+            }
+            base = mul(&base, &base)?;
+            e >>= 1;
+        }
+        if n.sign() == Sign::Minus {
+            return div(&constant(int(1), a.len()), &res);
+        }
+        return Ok(res);
+    }
+    pow_num(a, p, a[0].clone().power(p.clone())?)
+}
+
+// `sin(a)` and `cos(a)` together: `s' = a'*c` and `c' = -a'*s`. The same
+// recurrence with `c' = a'*s` gives `sinh(a)` and `cosh(a)`
+fn sin_cos(a: &[Value], hyper: bool) -> Result<(Series, Series), CalcError> {
+    let (mut s, mut c): (Series, Series) = if hyper {
+        (vec![a[0].clone().sinh()?], vec![a[0].clone().cosh()?])
+    } else {
+        (vec![a[0].clone().sin()?], vec![a[0].clone().cos()?])
+    };
+    for k in 1..a.len() {
+        let (mut ss, mut cs) = (zero(), zero());
+        for j in 1..=k {
+            let f = a[j].clone().multiply(int(j))?;
+            ss = ss.addition(f.clone().multiply(c[k - j].clone())?)?;
+            cs = cs.addition(f.multiply(s[k - j].clone())?)?;
+        }
+        s.push(ss.divide_exact(int(k))?);
+        let cs = cs.divide_exact(int(k))?;
+        c.push(if hyper { cs } else { cs.negate()? });
+    }
+    Ok((s, c))
+}
+
+// `asin(a)` and `acos(a)`: their derivatives are `a' / sqrt(1 - a**2)` with
+// different signs
+fn asin(a: &[Value]) -> Result<Series, CalcError> {
+    let one = constant(int(1), a.len());
+    let d = sub(&one, &mul(a, a)?)?;
+    let root = pow_num(&d, &half(), d[0].clone().sqrt()?)?;
+    integral(&div(&derivative(a)?, &root)?, a[0].clone().asin()?)
+}
+
+// `atan(a)`: its derivative is `a' / (1 + a**2)`
+fn atan(a: &[Value]) -> Result<Series, CalcError> {
+    let d = add(&constant(int(1), a.len()), &mul(a, a)?)?;
+    integral(&div(&derivative(a)?, &d)?, a[0].clone().atan()?)
+}
+
+fn apply_func(name: &str, args: &[Series]) -> Option<Result<Series, CalcError>> {
+    let a = match args {
+        [a] => a,
+        _ => return None,
+    };
+    let res = match name {
+        "exp" => exp(a),
+        "ln" => ln(a),
+        "sqrt" => a[0].clone().sqrt().and_then(|h0| pow_num(a, &half(), h0)),
+        "sin" => sin_cos(a, false).map(|(s, _)| s),
+        "cos" => sin_cos(a, false).map(|(_, c)| c),
+        "tan" => sin_cos(a, false).and_then(|(s, c)| div(&s, &c)),
+        "sinh" => sin_cos(a, true).map(|(s, _)| s),
+        "cosh" => sin_cos(a, true).map(|(_, c)| c),
+        "tanh" => sin_cos(a, true).and_then(|(s, c)| div(&s, &c)),
+        "asin" => asin(a),
+        "acos" => asin(a).and_then(|s| {
+            let mut s = neg(&s)?;
+            s[0] = a[0].clone().acos()?;
+            Ok(s)
+        }),
+        "atan" => atan(a),
+        _ => return None,
+    };
+    Some(res)
+}
+
+fn apply_op(op: &str, args: &[Series]) -> Option<Result<Series, CalcError>> {
+    let res = match (op, args) {
+        ("+", [a, b]) => add(a, b),
+        ("-", [a, b]) => sub(a, b),
+        ("*", [a, b]) => mul(a, b),
+        ("/", [a, b]) => div(a, b),
+        ("**", [a, b]) | ("^", [a, b]) => pow(a, b),
+        (UNARY_MINUS, [a]) => neg(a),
+        _ => return None,
+    };
+    Some(res)
+}
+
+/// Evaluates an expression in postfix form with the series `x` in place
+/// of the variable `var`. Operators and functions that do not support
+/// series(e.g, `floor`) work only if their arguments do not depend on the
+/// variable
+pub(crate) fn eval(
+    entries: &[Entry],
+    var: &str,
+    x: &[Value],
+    calc: &Calc,
+    lookup: &mut Lookup,
+) -> Result<Series, CalcError> {
+    let len = x.len();
+    let mut values: Vec<Series> = Vec::new();
+    for e in entries {
+        let (name, args) = match e {
+            Entry::Val(v) => {
+                values.push(constant(v.clone(), len));
+                continue;
+            }
+            Entry::Var(name) if name.to_lowercase() == var => {
+                values.push(x.to_vec());
+                continue;
+            }
+            Entry::Var(name) => {
+                values.push(constant(lookup(name)?, len));
+                continue;
+            }
+            Entry::Op(op, ..) => (op, op_args(op)),
+            Entry::Func(name, args) => (name, *args),
+            _ => return Err(CalcError::Unreachable),
+        };
+        if values.len() < args {
+            return Err(CalcError::InsufficientOps);
+        }
+        let args = values.split_off(values.len() - args);
+        let res = if args.iter().all(|a| is_constant(a)) {
+            // the stack evaluates functions of numbers, so the calculator
+            // options are applied as usual
+            let mut entries: Vec<Entry> = args.iter().map(|a| Entry::Val(a[0].clone())).collect();
+            entries.push(e.clone());
+            let v = new_stack(entries, calc).calculate_with(lookup)?;
+            Ok(constant(v, len))
+        } else {
+            let res = match e {
+                Entry::Op(..) => apply_op(name, &args),
+                _ => apply_func(name, &args),
+            };
+            match res {
+                Some(r) => r,
+                None => {
+                    return Err(CalcError::InvalidAgrument(
+                        "taylor".to_string(),
+                        format!("'{}' is not supported in series", name),
+                    ))
+                }
+            }
+        }?;
+        values.push(res);
+    }
+    match values.pop() {
+        Some(v) if values.is_empty() => Ok(v),
+        _ => Err(CalcError::InsufficientOps),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_series() {
+        let len = 5;
+        let x = variable(int(0), len);
+        // 1/(1-x) = 1 + x + x**2 + ...
+        let s = div(&constant(int(1), len), &sub(&constant(int(1), len), &x).unwrap()).unwrap();
+        assert_eq!(s, vec![int(1); len]);
+        // exp(x) = 1 + x + x**2/2 + x**3/6 + x**4/24
+        let s = exp(&x).unwrap();
+        let f: Vec<f64> = s.iter().map(|v| v.as_f64().unwrap()).collect();
+        assert_eq!(f, vec![1.0, 1.0, 0.5, 1.0 / 6.0, 1.0 / 24.0]);
+        // (1+x)**-2 = 1 - 2x + 3x**2 - 4x**3 + 5x**4
+        let s = pow(&add(&constant(int(1), len), &x).unwrap(), &constant(Value::Int(BigInt::from(-2)), len)).unwrap();
+        let expected: Series = [1, -2, 3, -4, 5].iter().map(|i| Value::Int(BigInt::from(*i))).collect();
+        assert_eq!(s, expected);
+        // sqrt(1+x) = 1 + x/2 - x**2/8 + ...
+        let s = apply_func("sqrt", &[add(&constant(int(1), len), &x).unwrap()]).unwrap().unwrap();
+        assert_eq!(s[2].to_string(), "-1\\8");
+        assert_eq!(derivative(&x).unwrap(), constant(int(1), len));
+    }
+}
@@ -0,0 +1,676 @@
+//! Special functions for real arguments: Lambert W, Bessel, Riemann zeta,
+//! digamma, regularized incomplete gamma and beta functions, and
+//! distribution functions based on them.
+//!
+//! The functions work with float numbers only and return `None` if an
+//! argument is outside of the function domain. `Value` wrappers convert
+//! arguments and turn `None` into calculation errors.
+//!
+//! Without feature `special` the calculator uses only digamma function(for
+//! harmonic numbers of float arguments), the rest is not called.
+#![cfg_attr(not(feature = "special"), allow(dead_code))]
+
+use std::convert::TryFrom;
+use std::f64::consts;
+
+use crate::fmath;
+
+const MAX_ITERATIONS: usize = 64;
+
+/// Lambert W function: the solution of `w * exp(w) = x`. Branch `0` is
+/// the principal one and is defined for `x >= -1/e`, branch `-1` is
+/// defined for `-1/e <= x < 0`
+pub(crate) fn lambertw(x: f64, branch: i32) -> Option<f64> {
+    let branch_point = -fmath::exp(-1.0f64);
+    if !x.is_finite() || x < branch_point || (branch == -1 && x >= 0.0) || (branch != 0 && branch != -1) {
+        return None;
+    }
+    if x == 0.0 {
+        return Some(0.0);
+    }
+    // near the branch point both branches are close to `-1`, and the
+    // iteration below divides by `w + 1`
+    if x - branch_point < 1e-15 {
+        return Some(-1.0);
+    }
+
+    let mut w = if x < -0.3 {
+        // series expansion around the branch point
+        let p = (2.0 * (consts::E * x + 1.0)).sqrt();
+        let p = if branch == 0 { p } else { -p };
+        -1.0 + p - p * p / 3.0 + 11.0 / 72.0 * p * p * p
+    } else if branch == -1 {
+        let l = fmath::ln(-x);
+        l - fmath::ln(-l)
+    } else if x < 3.0 {
+        fmath::ln_1p(x)
+    } else {
+        let l = fmath::ln(x);
+        l - fmath::ln(l)
+    };
+
+    // Halley's method
+    for _ in 0..MAX_ITERATIONS {
+        let ew = fmath::exp(w);
+        let f = w * ew - x;
+        let next = w - f / (ew * (w + 1.0) - (w + 2.0) * f / (2.0 * w + 2.0));
+        if (next - w).abs() <= f64::EPSILON * next.abs().max(1.0) {
+            return Some(next);
+        }
+        w = next;
+    }
+    Some(w)
+}
+
+// Bessel functions of integer order. Small arguments use power series,
+// big arguments use Hankel asymptotic expansion for orders 0 and 1 and
+// forward recurrence for higher orders
+const BESSEL_ASYMPTOTIC_FROM: f64 = 17.0;
+
+// Euler-Mascheroni constant
+const EULER_GAMMA: f64 = 0.577_215_664_901_532_9;
+
+fn factorial(n: u32) -> f64 {
+    (1..=n).fold(1.0, |acc, k| acc * k as f64)
+}
+
+fn bessel_j_series(n: u32, x: f64) -> f64 {
+    let q = -x * x / 4.0;
+    let mut term = (x / 2.0).powi(n as i32) / factorial(n);
+    let mut sum = term;
+    for k in 0..MAX_ITERATIONS * 4 {
+        term *= q / ((k + 1) as f64 * (k as f64 + n as f64 + 1.0));
+        sum += term;
+        if term.abs() <= f64::EPSILON * sum.abs() * 1e-2 {
+            break;
+        }
+    }
+    sum
+}
+
+// A&S 9.1.11
+fn bessel_y_series(n: u32, x: f64) -> f64 {
+    let q = x * x / 4.0;
+    let mut finite = 0.0;
+    for k in 0..n {
+        finite += factorial(n - k - 1) / factorial(k) * q.powi(k as i32);
+    }
+    let finite = -finite * (x / 2.0).powi(-(n as i32)) / consts::PI;
+
+    let mut term = 1.0 / factorial(n);
+    // digamma(k + 1) and digamma(n + k + 1)
+    let mut psi1 = -EULER_GAMMA;
+    let mut psi2 = -EULER_GAMMA + (1..=n).map(|k| 1.0 / k as f64).sum::<f64>();
+    let mut sum = (psi1 + psi2) * term;
+    for k in 0..MAX_ITERATIONS * 4 {
+        let k1 = (k + 1) as f64;
+        term *= -q / (k1 * (k1 + n as f64));
+        psi1 += 1.0 / k1;
+        psi2 += 1.0 / (k1 + n as f64);
+        let add = (psi1 + psi2) * term;
+        sum += add;
+        if add.abs() <= f64::EPSILON * sum.abs() * 1e-2 {
+            break;
+        }
+    }
+    let series = -sum * (x / 2.0).powi(n as i32) / consts::PI;
+
+    finite + 2.0 / consts::PI * fmath::ln(x / 2.0) * bessel_j_series(n, x) + series
+}
+
+// returns `(J_n(x), Y_n(x))` for big `x`
+fn bessel_asymptotic(n: u32, x: f64) -> (f64, f64) {
+    let mu = 4.0 * (n * n) as f64;
+    let (mut p, mut q) = (1.0, 0.0);
+    let mut a = 1.0;
+    let mut prev = f64::INFINITY;
+    for k in 1..MAX_ITERATIONS {
+        let odd = (2 * k - 1) as f64;
+        a *= (mu - odd * odd) / (k as f64 * 8.0 * x);
+        // the series diverges: stop at the smallest term
+        if a.abs() >= prev || a == 0.0 {
+            break;
+        }
+        prev = a.abs();
+        let sign = if (k / 2) % 2 == 0 { 1.0 } else { -1.0 };
+        if k % 2 == 0 {
+            p += sign * a;
+        } else {
+            q += sign * a;
+        }
+    }
+    let chi = x - (n as f64 / 2.0 + 0.25) * consts::PI;
+    let scale = (2.0 / (consts::PI * x)).sqrt();
+    (scale * (p * fmath::cos(chi) - q * fmath::sin(chi)), scale * (p * fmath::sin(chi) + q * fmath::cos(chi)))
+}
+
+// `J_n(x)` and `Y_n(x)` for big `x` using the recurrence
+// `B_(k+1) = 2k/x * B_k - B_(k-1)`, it is stable while `k < x`
+fn bessel_recurrence(n: u32, x: f64) -> (f64, f64) {
+    let (mut j0, mut y0) = bessel_asymptotic(0, x);
+    if n == 0 {
+        return (j0, y0);
+    }
+    let (mut j1, mut y1) = bessel_asymptotic(1, x);
+    for k in 1..n {
+        let c = 2.0 * k as f64 / x;
+        (j0, j1) = (j1, c * j1 - j0);
+        (y0, y1) = (y1, c * y1 - y0);
+    }
+    (j1, y1)
+}
+
+/// Bessel function of the first kind of integer order `n`
+pub(crate) fn besselj(n: i64, x: f64) -> Option<f64> {
+    if !x.is_finite() {
+        return None;
+    }
+    let order = u32::try_from(n.unsigned_abs()).ok()?;
+    let v = if x.abs() > BESSEL_ASYMPTOTIC_FROM && (order as f64) < x.abs() {
+        bessel_recurrence(order, x.abs()).0
+    } else {
+        bessel_j_series(order, x.abs())
+    };
+    // J_(-n)(x) = (-1)^n J_n(x) and J_n(-x) = (-1)^n J_n(x)
+    let odd = order % 2 == 1;
+    let neg = (n < 0 && odd) != (x < 0.0 && odd);
+    Some(if neg { -v } else { v })
+}
+
+/// Bessel function of the second kind of integer order `n`. It is defined
+/// only for positive arguments
+pub(crate) fn bessely(n: i64, x: f64) -> Option<f64> {
+    if !x.is_finite() || x <= 0.0 {
+        return None;
+    }
+    let order = u32::try_from(n.unsigned_abs()).ok()?;
+    let v = if x > BESSEL_ASYMPTOTIC_FROM && (order as f64) < x {
+        bessel_recurrence(order, x).1
+    } else {
+        bessel_y_series(order, x)
+    };
+    // Y_(-n)(x) = (-1)^n Y_n(x)
+    Some(if n < 0 && order % 2 == 1 { -v } else { v })
+}
+
+// Lanczos approximation coefficients(g = 7, n = 9)
+const LANCZOS_G: f64 = 7.0;
+const LANCZOS: [f64; 9] = [
+    0.999_999_999_999_809_9,
+    676.520_368_121_885_1,
+    -1_259.139_216_722_402_8,
+    771.323_428_777_653_1,
+    -176.615_029_162_140_6,
+    12.507_343_278_686_905,
+    -0.138_571_095_265_720_12,
+    9.984_369_578_019_572e-6,
+    1.505_632_735_149_311_6e-7,
+];
+
+// logarithm of gamma function for `x >= 0.5`
+fn ln_gamma(x: f64) -> f64 {
+    let x = x - 1.0;
+    let mut sum = LANCZOS[0];
+    for (i, c) in LANCZOS.iter().enumerate().skip(1) {
+        sum += c / (x + i as f64);
+    }
+    let t = x + LANCZOS_G + 0.5;
+    0.5 * fmath::ln(2.0 * consts::PI) + (x + 0.5) * fmath::ln(t) - t + fmath::ln(sum)
+}
+
+// the number of terms in Borwein's algorithm for zeta function: the error
+// is about `3 / (3 + sqrt(8))^n`
+const ZETA_TERMS: usize = 40;
+
+/// Riemann zeta function. It has a pole at `s = 1`
+pub(crate) fn zeta(s: f64) -> Option<f64> {
+    if !s.is_finite() || s == 1.0 {
+        return None;
+    }
+    if s == 0.0 {
+        return Some(-0.5);
+    }
+    if s < 0.5 {
+        // trivial zeros
+        if s < 0.0 && s % 2.0 == 0.0 {
+            return Some(0.0);
+        }
+        // functional equation: zeta(s) = 2^s pi^(s-1) sin(pi s/2) gamma(1-s) zeta(1-s),
+        // the factors are combined in logarithms to avoid overflow
+        let m = fmath::exp(s * consts::LN_2 + (s - 1.0) * fmath::ln(consts::PI) + ln_gamma(1.0 - s));
+        return Some(m * fmath::sin(consts::FRAC_PI_2 * s) * zeta(1.0 - s)?);
+    }
+
+    // Borwein's algorithm for alternating series of Dirichlet eta function
+    let n = ZETA_TERMS;
+    let mut d = Vec::with_capacity(n + 1);
+    let (mut term, mut sum) = (1.0, 1.0);
+    d.push(sum);
+    for i in 0..n {
+        let i = i as f64;
+        let nf = n as f64;
+        term *= 4.0 * (nf + i) * (nf - i) / ((2.0 * i + 1.0) * (2.0 * i + 2.0));
+        sum += term;
+        d.push(sum);
+    }
+    let dn = d[n];
+    let mut eta = 0.0;
+    for (k, dk) in d.iter().take(n).enumerate() {
+        let v = (dk - dn) / fmath::powf((k + 1) as f64, s);
+        eta += if k % 2 == 0 { v } else { -v };
+    }
+    let eta = -eta / dn;
+    // 1 - 2^(1-s) without cancellation for `s` close to 1
+    Some(eta / -fmath::exp_m1((1.0 - s) * consts::LN_2))
+}
+
+/// Digamma function: the logarithmic derivative of gamma function. It has
+/// poles at zero and negative integers
+pub(crate) fn digamma(x: f64) -> Option<f64> {
+    if !x.is_finite() || (x <= 0.0 && x == x.floor()) {
+        return None;
+    }
+    if x < 0.5 {
+        // reflection formula
+        return Some(digamma(1.0 - x)? - consts::PI / fmath::tan(consts::PI * x));
+    }
+    let mut x = x;
+    let mut res = 0.0;
+    while x < 10.0 {
+        res -= 1.0 / x;
+        x += 1.0;
+    }
+    // asymptotic expansion with Bernoulli numbers
+    let x2 = 1.0 / (x * x);
+    let series = x2
+        * (1.0 / 12.0
+            - x2 * (1.0 / 120.0 - x2 * (1.0 / 252.0 - x2 * (1.0 / 240.0 - x2 * (1.0 / 132.0 - x2 * 691.0 / 32760.0)))));
+    Some(res + fmath::ln(x) - 0.5 / x - series)
+}
+
+/// Harmonic number extended to real numbers: `H(x) = digamma(x + 1) + gamma`
+pub(crate) fn harmonic(x: f64) -> Option<f64> {
+    Some(digamma(x + 1.0)? + EULER_GAMMA)
+}
+
+// continued fractions of incomplete gamma and beta functions converge
+// slower for big arguments: about `sqrt(max(a, b))` iterations
+const INCOMPLETE_ITERATIONS: usize = 10_000;
+const INCOMPLETE_EPS: f64 = 1e-15;
+// a number close to the smallest float number to avoid division by zero
+// in Lentz's algorithm
+const TINY: f64 = 1e-300;
+
+/// Regularized lower incomplete gamma function `P(a, x)`, `a > 0`, `x >= 0`
+pub(crate) fn gamma_p(a: f64, x: f64) -> Option<f64> {
+    if !a.is_finite() || a <= 0.0 || x.is_nan() || x < 0.0 {
+        return None;
+    }
+    if x == 0.0 {
+        return Some(0.0);
+    }
+    if x.is_infinite() {
+        return Some(1.0);
+    }
+    if x < a + 1.0 {
+        gamma_series(a, x)
+    } else {
+        Some(1.0 - gamma_fraction(a, x)?)
+    }
+}
+
+/// Regularized upper incomplete gamma function `Q(a, x)`, `a > 0`, `x >= 0`
+pub(crate) fn gamma_q(a: f64, x: f64) -> Option<f64> {
+    if !a.is_finite() || a <= 0.0 || x.is_nan() || x < 0.0 {
+        return None;
+    }
+    if x == 0.0 {
+        return Some(1.0);
+    }
+    if x.is_infinite() {
+        return Some(0.0);
+    }
+    if x < a + 1.0 {
+        Some(1.0 - gamma_series(a, x)?)
+    } else {
+        gamma_fraction(a, x)
+    }
+}
+
+// lower function `P(a, x) = 1 - Q(a, x)` by its series, converges fast
+// for `x < a + 1`
+fn gamma_series(a: f64, x: f64) -> Option<f64> {
+    let mut ap = a;
+    let mut term = 1.0 / a;
+    let mut sum = term;
+    for _ in 0..INCOMPLETE_ITERATIONS {
+        ap += 1.0;
+        term *= x / ap;
+        sum += term;
+        if term.abs() < sum.abs() * INCOMPLETE_EPS {
+            return Some(sum * fmath::exp(a * fmath::ln(x) - x - ln_gamma(a)));
+        }
+    }
+    None
+}
+
+// `Q(a, x)` by its continued fraction(modified Lentz's method), converges
+// fast for `x >= a + 1`
+fn gamma_fraction(a: f64, x: f64) -> Option<f64> {
+    let mut b = x + 1.0 - a;
+    let mut c = 1.0 / TINY;
+    let mut d = 1.0 / b;
+    let mut h = d;
+    for i in 1..INCOMPLETE_ITERATIONS {
+        let an = -(i as f64) * (i as f64 - a);
+        b += 2.0;
+        d = an * d + b;
+        if d.abs() < TINY {
+            d = TINY;
+        }
+        c = b + an / c;
+        if c.abs() < TINY {
+            c = TINY;
+        }
+        d = 1.0 / d;
+        let delta = d * c;
+        h *= delta;
+        if (delta - 1.0).abs() < INCOMPLETE_EPS {
+            return Some(fmath::exp(a * fmath::ln(x) - x - ln_gamma(a)) * h);
+        }
+    }
+    None
+}
+
+/// Regularized incomplete beta function `I_x(a, b)`, `a > 0`, `b > 0`,
+/// `0 <= x <= 1`
+pub(crate) fn beta_inc(a: f64, b: f64, x: f64) -> Option<f64> {
+    if !a.is_finite() || a <= 0.0 || !b.is_finite() || b <= 0.0 || !(0.0..=1.0).contains(&x) {
+        return None;
+    }
+    if x == 0.0 || x == 1.0 {
+        return Some(x);
+    }
+    let front = fmath::exp(ln_gamma(a + b) - ln_gamma(a) - ln_gamma(b) + a * fmath::ln(x) + b * fmath::ln(1.0 - x));
+    // the continued fraction converges fast only for `x < (a + 1) / (a + b + 2)`,
+    // otherwise the symmetry `I_x(a, b) = 1 - I_(1-x)(b, a)` is used
+    if x < (a + 1.0) / (a + b + 2.0) {
+        Some(front * beta_fraction(a, b, x)? / a)
+    } else {
+        Some(1.0 - front * beta_fraction(b, a, 1.0 - x)? / b)
+    }
+}
+
+// continued fraction for incomplete beta function(modified Lentz's method)
+fn beta_fraction(a: f64, b: f64, x: f64) -> Option<f64> {
+    let qab = a + b;
+    let qap = a + 1.0;
+    let qam = a - 1.0;
+    let mut c = 1.0;
+    let mut d = 1.0 - qab * x / qap;
+    if d.abs() < TINY {
+        d = TINY;
+    }
+    d = 1.0 / d;
+    let mut h = d;
+    for m in 1..INCOMPLETE_ITERATIONS {
+        let m = m as f64;
+        let m2 = 2.0 * m;
+        // even step
+        let aa = m * (b - m) * x / ((qam + m2) * (a + m2));
+        d = 1.0 + aa * d;
+        if d.abs() < TINY {
+            d = TINY;
+        }
+        c = 1.0 + aa / c;
+        if c.abs() < TINY {
+            c = TINY;
+        }
+        d = 1.0 / d;
+        h *= d * c;
+        // odd step
+        let aa = -(a + m) * (qab + m) * x / ((a + m2) * (qap + m2));
+        d = 1.0 + aa * d;
+        if d.abs() < TINY {
+            d = TINY;
+        }
+        c = 1.0 + aa / c;
+        if c.abs() < TINY {
+            c = TINY;
+        }
+        d = 1.0 / d;
+        let delta = d * c;
+        h *= delta;
+        if (delta - 1.0).abs() < INCOMPLETE_EPS {
+            return Some(h);
+        }
+    }
+    None
+}
+
+/// Cumulative distribution function of Student's t-distribution with `df`
+/// degrees of freedom, `df > 0`
+pub(crate) fn t_cdf(x: f64, df: f64) -> Option<f64> {
+    if x.is_nan() || !df.is_finite() || df <= 0.0 {
+        return None;
+    }
+    if x.is_infinite() {
+        return Some(if x > 0.0 { 1.0 } else { 0.0 });
+    }
+    let tail = 0.5 * beta_inc(0.5 * df, 0.5, df / (df + x * x))?;
+    Some(if x > 0.0 { 1.0 - tail } else { tail })
+}
+
+/// Cumulative distribution function of chi-squared distribution with `df`
+/// degrees of freedom, `df > 0`
+pub(crate) fn chi2_cdf(x: f64, df: f64) -> Option<f64> {
+    if x.is_nan() || !df.is_finite() || df <= 0.0 {
+        return None;
+    }
+    if x <= 0.0 {
+        return Some(0.0);
+    }
+    gamma_p(0.5 * df, 0.5 * x)
+}
+
+/// Quantile function(inverse CDF) of Student's t-distribution,
+/// `0 < p < 1`, `df > 0`
+pub(crate) fn t_inv(p: f64, df: f64) -> Option<f64> {
+    if p <= 0.0 || p >= 1.0 || p.is_nan() || !df.is_finite() || df <= 0.0 {
+        return None;
+    }
+    if p == 0.5 {
+        return Some(0.0);
+    }
+    // the distribution is symmetric: find the positive quantile
+    let q = if p > 0.5 { p } else { 1.0 - p };
+    let x = invert_cdf(q, 0.0, 1.0, |x| t_cdf(x, df))?;
+    Some(if p > 0.5 { x } else { -x })
+}
+
+/// Quantile function(inverse CDF) of chi-squared distribution,
+/// `0 <= p < 1`, `df > 0`
+pub(crate) fn chi2_inv(p: f64, df: f64) -> Option<f64> {
+    if !(0.0..1.0).contains(&p) || !df.is_finite() || df <= 0.0 {
+        return None;
+    }
+    if p == 0.0 {
+        return Some(0.0);
+    }
+    invert_cdf(p, 0.0, df.max(1.0), |x| chi2_cdf(x, df))
+}
+
+// finds `x >= lo` such that `cdf(x) = p` for an increasing function `cdf`:
+// the upper bound `hi` is doubled until it is big enough, and then the
+// range is halved until it is as narrow as the float number precision
+fn invert_cdf(p: f64, mut lo: f64, mut hi: f64, cdf: impl Fn(f64) -> Option<f64>) -> Option<f64> {
+    while cdf(hi)? < p {
+        lo = hi;
+        hi *= 2.0;
+        if hi.is_infinite() {
+            return None;
+        }
+    }
+    for _ in 0..INCOMPLETE_ITERATIONS {
+        let mid = 0.5 * (lo + hi);
+        if mid <= lo || mid >= hi {
+            break;
+        }
+        if cdf(mid)? < p {
+            lo = mid;
+        } else {
+            hi = mid;
+        }
+    }
+    Some(0.5 * (lo + hi))
+}
+
+/// Natural logarithm of `n!`, `n >= 0`
+pub(crate) fn ln_factorial(n: f64) -> f64 {
+    // small factorials fit float numbers and the product is more precise
+    if n.fract() == 0.0 && n <= 170.0 {
+        return fmath::ln((2..=n as u32).fold(1.0f64, |acc, i| acc * f64::from(i)));
+    }
+    ln_gamma(n + 1.0)
+}
+
+/// Natural logarithm of the binomial coefficient `C(n, k)`, `0 <= k <= n`
+pub(crate) fn ln_choose(n: f64, k: f64) -> f64 {
+    ln_factorial(n) - ln_factorial(k) - ln_factorial(n - k)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_lambertw() {
+        for &x in &[-0.35, -0.1, 0.5, 1.0, 2.5, 10.0, 1e6] {
+            let w = lambertw(x, 0).unwrap();
+            assert!((w * w.exp() - x).abs() <= 1e-12 * x.abs().max(1.0), "W0({})", x);
+        }
+        for &x in &[-0.36, -0.2, -0.01, -1e-6] {
+            let w = lambertw(x, -1).unwrap();
+            assert!(w <= -1.0);
+            assert!((w * w.exp() - x).abs() <= 1e-12, "W-1({})", x);
+        }
+        assert_eq!(lambertw(1.0, 0), Some(0.5671432904097838));
+        assert_eq!(lambertw(consts::E, 0), Some(1.0));
+        assert_eq!(lambertw(-(-1.0f64).exp(), -1), Some(-1.0));
+        assert_eq!(lambertw(-0.5, 0), None);
+        assert_eq!(lambertw(0.5, -1), None);
+        assert_eq!(lambertw(0.5, 1), None);
+    }
+
+    #[test]
+    fn test_bessel() {
+        // (n, x, J_n(x), Y_n(x))
+        let cases = [
+            (0, 1.0, 0.7651976865579666, 0.08825696421567696),
+            (1, 1.0, 0.4400505857449335, -0.7812128213002887),
+            (2, 5.0, 0.046565116277752214, 0.36766288260552454),
+            (3, 0.1, 2.0820315754756265e-05, -5099.332378612904),
+            (10, 12.0, 0.3004760352712693, -0.0228763140704997),
+            (1, 16.9, -0.08074925425014197, 0.17663144309012718),
+            (0, 17.5, -0.10311039822868592, -0.16041119250501118),
+            (0, 20.0, 0.16702466434058316, 0.06264059680938383),
+            (1, 30.0, -0.11875106261662294, 0.08442557066174723),
+            (5, 30.0, -0.14324029551207706, 0.03162735928926443),
+            (0, 100.0, 0.019985850304223122, -0.07724431336508315),
+        ];
+        for (n, x, j, y) in cases {
+            let rj = besselj(n, x).unwrap();
+            let ry = bessely(n, x).unwrap();
+            assert!((rj - j).abs() <= 1e-9 * j.abs().max(1.0), "J{}({}) = {}", n, x, rj);
+            assert!((ry - y).abs() <= 1e-9 * y.abs().max(1.0), "Y{}({}) = {}", n, x, ry);
+        }
+        assert!((besselj(4, -2.0).unwrap() - 0.033995719807568436).abs() < 1e-12);
+        assert!((besselj(-1, 1.0).unwrap() + 0.4400505857449335).abs() < 1e-12);
+        assert_eq!(besselj(0, 0.0), Some(1.0));
+        assert_eq!(bessely(0, 0.0), None);
+        assert_eq!(bessely(0, -1.0), None);
+    }
+
+    #[test]
+    fn test_zeta() {
+        let cases = [
+            (2.0, consts::PI * consts::PI / 6.0),
+            (3.0, 1.2020569031595942),
+            (0.5, -1.4603545088095868),
+            (1.0 + 1e-9, 999999917.8368515),
+            (0.0, -0.5),
+            (-1.0, -1.0 / 12.0),
+            (-2.5, 0.00851692877785033),
+            (-31.0, 472384867.7216299),
+            (50.0, 1.0000000000000009),
+        ];
+        for (s, z) in cases {
+            let r = zeta(s).unwrap();
+            assert!((r - z).abs() <= 1e-12 * z.abs().max(1.0), "zeta({}) = {}", s, r);
+        }
+        assert_eq!(zeta(-4.0), Some(0.0));
+        assert_eq!(zeta(1.0), None);
+    }
+
+    #[test]
+    fn test_digamma() {
+        let cases = [
+            (1.0, -EULER_GAMMA),
+            (0.5, -1.9635100260214235),
+            (2.5, 0.7031566406452432),
+            (-0.5, 0.03648997397857652),
+            (100.0, 4.600161852738087),
+        ];
+        for (x, d) in cases {
+            let r = digamma(x).unwrap();
+            assert!((r - d).abs() <= 1e-13 * d.abs().max(1.0), "digamma({}) = {}", x, r);
+        }
+        assert!((harmonic(0.5).unwrap() - 0.6137056388801094).abs() < 1e-14);
+        assert!((harmonic(10.0).unwrap() - 7381.0 / 2520.0).abs() < 1e-14);
+        assert_eq!(digamma(0.0), None);
+        assert_eq!(digamma(-3.0), None);
+    }
+
+    #[test]
+    fn test_incomplete() {
+        let close = |r: Option<f64>, v: f64| (r.unwrap() - v).abs() <= 1e-12 * v.abs().max(1.0);
+        assert!(close(gamma_q(1.0, 2.0), (-2.0f64).exp()));
+        assert!(close(gamma_q(0.5, 2.0), 1.0 - 0.9544997361036416));
+        assert!(close(gamma_q(5.0, 3.0), 0.8152632445237722));
+        assert!(close(gamma_q(100.0, 90.0), 0.8417790108135698));
+        assert!(close(gamma_q(3.0, 0.1), 1.0 - 0.00015465307026467168));
+        assert_eq!(gamma_q(0.0, 1.0), None);
+        assert_eq!(gamma_q(1.0, -1.0), None);
+
+        assert!(close(beta_inc(2.0, 3.0, 0.4), 0.5248));
+        assert!(close(beta_inc(0.5, 0.5, 0.25), 1.0 / 3.0));
+        assert!(close(beta_inc(10.0, 20.0, 0.6), 0.9984777778269129));
+        // the error of `ln_gamma` grows with arguments
+        assert!((beta_inc(200.0, 300.0, 0.4).unwrap() - 0.502428616319932).abs() < 1e-11);
+        assert_eq!(beta_inc(1.0, 1.0, 1.5), None);
+        assert!((ln_choose(10.0, 3.0) - 120f64.ln()).abs() < 1e-13);
+        assert!((ln_factorial(5.0) - 120f64.ln()).abs() < 1e-13);
+    }
+
+    #[test]
+    fn test_continuous_distributions() {
+        let close = |r: Option<f64>, v: f64| (r.unwrap() - v).abs() <= 1e-12 * v.abs().max(1.0);
+        assert!(close(t_cdf(2.0, 10.0), 0.9633059826146298));
+        assert!(close(t_cdf(-1.5, 3.0), 0.11529193262241151));
+        // Cauchy distribution
+        assert!(close(t_cdf(0.5, 1.0), 0.5 + 0.5f64.atan() / consts::PI));
+        assert!(close(t_inv(0.975, 10.0), 2.2281388519862742));
+        assert!(close(t_inv(0.05, 2.5), -2.5582186141359366));
+        assert_eq!(t_inv(0.5, 7.0), Some(0.0));
+        assert_eq!(t_inv(1.0, 7.0), None);
+        assert_eq!(t_cdf(1.0, 0.0), None);
+
+        assert!(close(chi2_cdf(3.0, 5.0), 0.3000141641213725));
+        assert!(close(chi2_cdf(4.0, 2.0), 1.0 - (-2.0f64).exp()));
+        assert_eq!(chi2_cdf(-1.0, 2.0), Some(0.0));
+        assert!(close(chi2_inv(0.95, 5.0), 11.070497693516352));
+        assert!(close(chi2_inv(0.01, 100.0), 70.0648949253998));
+        assert_eq!(chi2_inv(0.0, 3.0), Some(0.0));
+        assert_eq!(chi2_inv(1.0, 3.0), None);
+    }
+}
@@ -1,17 +1,31 @@
+use std::collections::HashMap;
 use std::f64::consts;
+use std::ops::Range;
 
 use num_bigint::BigInt;
+use num_complex::Complex;
 use num_rational::BigRational;
-use num_traits::{One, Zero};
+use num_traits::{One, Signed, Zero};
 
+use crate::calc::{
+    Angle, Associativity, Calc, Category, ExtraArgs, FloatWidth, FunctionInfo, LogBase, OperatorInfo, Truth,
+};
+#[cfg(feature = "datetime")]
+use crate::datetime;
 use crate::errors::*;
+use crate::expr::new_stack;
+use crate::format;
+use crate::formula::{self, Lookup};
 use crate::value::*;
 
 use lazy_static::lazy_static;
 
 #[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub(crate) enum Entry {
     Val(Value),
+    // a variable: its value is looked up at evaluation time
+    Var(String),
     Op(String, i32, bool),
     OpenB,
     Func(String, usize),
@@ -24,6 +38,10 @@ pub(crate) struct Stack {
     pub result: Value,
     pub(crate) has_alt: bool,
     pub(crate) alt_result: String,
+    // options of the calculator that evaluates the expression. Expressions
+    // passed to functions are compiled and evaluated with them as well
+    pub(crate) calc: Calc,
+    pub(crate) warnings: Vec<String>,
 }
 
 const PRI_IMMEDIATE: i32 = 99;
@@ -33,58 +51,788 @@ pub(crate) const PERCENT_SUB: &str = "-%%";
 pub(crate) const PERCENT_MUL: &str = "*%%";
 pub(crate) const PERCENT_DIV: &str = "/%%";
 pub(crate) const UNARY_MINUS: &str = "---";
+pub(crate) const RANGE: &str = "..";
+// `a..b step s` is a range operator with three operands
+pub(crate) const RANGE_STEP: &str = "..step";
+// the word that adds a step to a range
+pub(crate) const STEP: &str = "step";
+// list literal `[1; 2]` is converted to a call of this function
+pub(crate) const LIST_FUNC: &str = "list";
+// indexing `xs[i]` and slicing `xs[i:j]` are calls of these functions
+pub(crate) const ITEM_FUNC: &str = "item";
+pub(crate) const SLICE_FUNC: &str = "slice";
+// `as_fraction` without the maximum denominator
+const DEFAULT_MAX_DENOM: u64 = 10_000;
+
+// the handler of a built-in function. It gets the function name(a handler
+// can serve several functions), the number of arguments, and the lookup of
+// session variables for functions that evaluate expressions
+type Handler = fn(&mut Stack, &str, usize, &mut Lookup) -> CalcErrorResult;
+
+/// A built-in function: its name, the number of arguments, the handler, and
+/// description. All knowledge about a function is kept in one entry of the
+/// registry, so adding a function does not require updating any other list
+#[derive(Clone)]
+pub(crate) struct Func {
+    pub(crate) name: &'static str,
+    // the name with argument names, e.g. `clamp(x; lo; hi)`
+    pub(crate) signature: &'static str,
+    // the least and the greatest number of arguments, `None` - any number
+    pub(crate) min_args: usize,
+    pub(crate) max_args: Option<usize>,
+    // the number of arguments a function takes from the value stack when it is
+    // used in RPN expression: RPN has no brackets, so the argument count cannot
+    // be detected from the expression itself
+    pub(crate) rpn_args: usize,
+    handler: Handler,
+    pub(crate) category: Category,
+    pub(crate) help: &'static str,
+}
+
+impl Func {
+    // the category is assigned by the group the function is listed in
+    fn new(
+        signature: &'static str,
+        min_args: usize,
+        max_args: Option<usize>,
+        handler: Handler,
+        help: &'static str,
+    ) -> Self {
+        let name = signature.split('(').next().unwrap_or(signature);
+        Func { name, signature, min_args, max_args, rpn_args: min_args, handler, category: Category::Arithmetic, help }
+    }
+
+    fn rpn_args(mut self, args: usize) -> Self {
+        self.rpn_args = args;
+        self
+    }
+
+    pub(crate) fn info(&self) -> FunctionInfo {
+        FunctionInfo {
+            name: self.name,
+            signature: self.signature,
+            min_args: self.min_args,
+            max_args: self.max_args,
+            rpn_args: self.rpn_args,
+            category: self.category,
+            description: self.help,
+        }
+    }
+}
+
+// a registry entry. The handler is either a method of `Stack` that takes the
+// number of arguments, or a closure with the signature of `Handler`
+macro_rules! func {
+    ($sig:expr, $min:expr, $max:expr, $method:ident, $help:expr) => {
+        Func::new($sig, $min, $max, |stk, _, args, _| stk.$method(args), $help)
+    };
+    ($sig:expr, $min:expr, $max:expr, $handler:expr, $help:expr) => {
+        Func::new($sig, $min, $max, $handler, $help)
+    };
+}
+
+fn builtin_funcs() -> Vec<Func> {
+    let groups = vec![
+        (
+            Category::Arithmetic,
+            vec![
+                func!("sqr(x)", 1, Some(1), sqr, "square of a number"),
+                func!("sqrt(x)", 1, Some(1), sqrt, "square root, negative numbers have complex roots"),
+                func!("cbrt(x)", 1, Some(1), cbrt, "cube root"),
+                func!("abs(x)", 1, Some(1), abs, "absolute value"),
+                func!("signum(x)", 1, Some(1), signum, "sign of a number: -1, 0, or 1"),
+                func!("fract(x)", 1, Some(1), fract, "fractional part of a number"),
+                func!("iif(cond; a; b)", 3, Some(3), iif, "a if the condition is not zero, and b otherwise"),
+                func!(
+                    "piecewise(cond1; a1; cond2; a2; ...; default)",
+                    3,
+                    None,
+                    piecewise,
+                    "the value after the first condition that is not zero, or the default value"
+                ),
+                func!(
+                    "assert(cond; message)",
+                    1,
+                    Some(2),
+                    assert,
+                    "the condition, or an error with the message if it is zero"
+                )
+                .rpn_args(2),
+                func!("min(a; b; ...)", 1, None, min, "the least of the arguments").rpn_args(2),
+                func!("max(a; b; ...)", 1, None, max, "the greatest of the arguments").rpn_args(2),
+                func!("avg(a; b; ...)", 1, None, avg, "arithmetic mean of the arguments").rpn_args(2),
+                func!("mod(a; b)", 2, Some(2), mod_euclid, "Euclidean remainder that is never negative"),
+                func!("divmod(a; b)", 2, Some(2), divmod, "Euclidean division, returns a list [quotient; remainder]"),
+                func!("copysign(x; y)", 2, Some(2), copysign, "magnitude of x with the sign of y"),
+                func!("fma(a; b; c)", 3, Some(3), fma, "fused multiply-add a*b+c"),
+                func!("ldexp(x; e)", 2, Some(2), ldexp, "x*2**e"),
+                func!("frexp(x)", 1, Some(1), frexp, "splits a float number into a list [mantissa; exponent]"),
+                func!("clamp(x; lo; hi)", 3, Some(3), clamp, "limits a number to the range"),
+                func!("lerp(a; b; t)", 3, Some(3), lerp, "linear interpolation between a and b"),
+                func!("wrap(x; lo; hi)", 3, Some(3), wrap, "wraps a number around the range"),
+                func!("sinc(x)", 1, Some(1), sinc, "sinc function sin(x)/x"),
+                func!("sincn(x)", 1, Some(1), sincn, "normalized sinc: sin(PI*x)/(PI*x)"),
+                func!("sigmoid(x)", 1, Some(1), sigmoid, "logistic function 1/(1+exp(-x))"),
+                func!("smoothstep(a; b; x)", 3, Some(3), smoothstep, "smooth Hermite interpolation between 0 and 1"),
+            ],
+        ),
+        (
+            Category::Rounding,
+            vec![
+                func!(
+                    "round(x)",
+                    1,
+                    Some(1),
+                    round,
+                    "rounds to the nearest integer using the calculator rounding mode"
+                ),
+                func!("ceil(x)", 1, Some(1), ceil, "rounds up"),
+                func!("trunc(x)", 1, Some(1), trunc, "rounds towards zero"),
+                func!("floor(x)", 1, Some(1), floor, "rounds down"),
+                func!(
+                    "ratio(n; d)",
+                    1,
+                    Some(2),
+                    ratio,
+                    "converts a float number to a fraction, or makes the fraction n/d"
+                ),
+                func!("approx(x)", 1, Some(1), approx, "converts a fraction to a float number"),
+                func!(
+                    "as_fraction(x; max_denom)",
+                    1,
+                    Some(2),
+                    as_fraction,
+                    "best rational approximation, the default maximum denominator is 10000"
+                )
+                .rpn_args(2),
+            ],
+        ),
+        (
+            Category::Trigonometry,
+            vec![
+                func!("sin(x)", 1, Some(1), sin, "sine"),
+                func!("cos(x)", 1, Some(1), cos, "cosine"),
+                func!("tan(x)", 1, Some(1), tan, "tangent"),
+                func!("cot(x)", 1, Some(1), cot, "cotangent"),
+                func!("sec(x)", 1, Some(1), sec, "secant"),
+                func!("csc(x)", 1, Some(1), csc, "cosecant"),
+                func!("asin(x)", 1, Some(1), asin, "arcsine"),
+                func!("acos(x)", 1, Some(1), acos, "arccosine"),
+                func!("atan(x)", 1, Some(1), atan, "arctangent"),
+                func!("acot(x)", 1, Some(1), acot, "arccotangent"),
+                func!("asec(x)", 1, Some(1), asec, "arcsecant"),
+                func!("acsc(x)", 1, Some(1), acsc, "arccosecant"),
+                func!("deg(x)", 1, Some(1), deg, "converts radians to degrees"),
+                func!("rad(x)", 1, Some(1), rad, "converts degrees to radians"),
+                func!("dms(degrees)", 1, Some(1), dms, "splits degrees into a list [degrees; minutes; seconds]"),
+                func!("fromdms(d; m; s)", 1, Some(3), fromdms, "converts degrees, minutes, and seconds to degrees")
+                    .rpn_args(3),
+                func!("topolar(x; y)", 2, Some(2), topolar, "polar coordinates [r; theta]"),
+                func!("tocart(r; theta)", 2, Some(2), tocart, "Cartesian coordinates [x; y]"),
+            ],
+        ),
+        (
+            Category::Hyperbolic,
+            vec![
+                func!("sinh(x)", 1, Some(1), sinh, "hyperbolic sine"),
+                func!("cosh(x)", 1, Some(1), cosh, "hyperbolic cosine"),
+                func!("tanh(x)", 1, Some(1), tanh, "hyperbolic tangent"),
+                func!("coth(x)", 1, Some(1), coth, "hyperbolic cotangent"),
+                func!("sech(x)", 1, Some(1), sech, "hyperbolic secant"),
+                func!("csch(x)", 1, Some(1), csch, "hyperbolic cosecant"),
+                func!("asinh(x)", 1, Some(1), asinh, "inverse hyperbolic sine"),
+                func!("acosh(x)", 1, Some(1), acosh, "inverse hyperbolic cosine"),
+                func!("atanh(x)", 1, Some(1), atanh, "inverse hyperbolic tangent"),
+                func!("acoth(x)", 1, Some(1), acoth, "inverse hyperbolic cotangent"),
+                func!("asech(x)", 1, Some(1), asech, "inverse hyperbolic secant"),
+                func!("acsch(x)", 1, Some(1), acsch, "inverse hyperbolic cosecant"),
+            ],
+        ),
+        (
+            Category::Exponential,
+            vec![
+                func!("exp(x)", 1, Some(1), exp, "exponent"),
+                func!("ln(x)", 1, Some(1), ln, "natural logarithm"),
+                func!("log10(x)", 1, Some(1), log10, "base 10 logarithm"),
+                func!("exp2(x)", 1, Some(1), exp2, "2 raised to the power"),
+                func!("expm1(x)", 1, Some(1), expm1, "exp(x)-1, accurate near zero"),
+                func!("ln1p(x)", 1, Some(1), ln1p, "ln(1+x), accurate near zero"),
+            ],
+        ),
+        (
+            Category::Complex,
+            vec![
+                func!("norm(z)", 1, Some(1), norm, "absolute value of a complex number"),
+                func!("conj(z)", 1, Some(1), conj, "complex conjugate"),
+                func!("re(z)", 1, Some(1), re, "real part of a complex number"),
+                func!("im(z)", 1, Some(1), im, "imaginary part of a complex number"),
+                func!("arg(z)", 1, Some(1), arg, "argument(angle) of a complex number"),
+                func!("phase(z)", 1, Some(1), arg, "the same as arg"),
+                func!("proj(z)", 1, Some(1), proj, "projection onto the Riemann sphere"),
+            ],
+        ),
+        (
+            Category::NumberTheory,
+            vec![
+                func!("gcd(a; b; ...)", 1, None, gcd, "greatest common divisor").rpn_args(2),
+                func!("lcm(a; b; ...)", 1, None, lcm, "least common multiple").rpn_args(2),
+                func!("egcd(a; b)", 2, Some(2), egcd, "extended GCD: [g; x; y] where a*x+b*y=g"),
+                func!("modinv(a; m)", 2, Some(2), modinv, "inverse of a modulo m"),
+                func!("modpow(a; e; m)", 3, Some(3), modpow, "a**e modulo m"),
+                func!("is_prime(n)", 1, Some(1), prime, "1 if the number is prime, and 0 otherwise"),
+                func!("next_prime(n)", 1, Some(1), next_prime, "the least prime greater than the number"),
+                func!("fib(n)", 1, Some(1), fib, "Fibonacci number"),
+                func!("harmonic(n)", 1, Some(1), harmonic, "harmonic number"),
+                func!("stirling1(n; k)", 2, Some(2), stirling1, "signed Stirling number of the first kind"),
+                func!("stirling2(n; k)", 2, Some(2), stirling2, "Stirling number of the second kind"),
+                func!("bell(n)", 1, Some(1), bell, "Bell number"),
+                func!("primorial(n)", 1, Some(1), primorial, "product of all primes up to the number"),
+                func!("multifact(n; k)", 2, Some(2), multifact, "multifactorial n*(n-k)*(n-2k)*..."),
+                func!("collatz(n)", 1, Some(1), collatz, "number of steps of Collatz sequence to reach 1"),
+                func!("digitsum(n; base)", 1, Some(2), digitsum, "sum of digits, the default base is 10"),
+                func!("numdigits(n; base)", 1, Some(2), numdigits, "number of digits, the default base is 10"),
+                func!("revdigits(n; base)", 1, Some(2), revdigits, "digits in reverse order, the default base is 10"),
+                func!("ilog(n; base)", 2, Some(2), ilog, "exact integer logarithm"),
+                func!("ilog2(n)", 1, Some(1), ilog2, "exact integer base 2 logarithm"),
+                func!("ilog10(n)", 1, Some(1), ilog10, "exact integer base 10 logarithm"),
+                func!("nextpow2(n)", 1, Some(1), nextpow2, "the least power of two not less than the number"),
+            ],
+        ),
+        (
+            Category::Special,
+            vec![
+                func!("gamma(x)", 1, Some(1), gamma, "gamma function"),
+                #[cfg(feature = "special")]
+                func!("lambertw(x; branch)", 1, Some(2), lambertw, "Lambert W function, branch -1 is the lower one"),
+                #[cfg(feature = "special")]
+                func!("besselj(n; x)", 2, Some(2), besselj, "Bessel function of the first kind of integer order"),
+                #[cfg(feature = "special")]
+                func!("bessely(n; x)", 2, Some(2), bessely, "Bessel function of the second kind of integer order"),
+                #[cfg(feature = "special")]
+                func!("zeta(s)", 1, Some(1), zeta, "Riemann zeta function"),
+            ],
+        ),
+        (
+            Category::Statistics,
+            vec![
+                func!("linreg(xs; ys)", 2, Some(2), linreg, "least squares line [slope; intercept; r2]"),
+                func!("corr(xs; ys)", 2, Some(2), corr, "Pearson correlation coefficient"),
+                func!("cov(xs; ys)", 2, Some(2), cov, "sample covariance"),
+                #[cfg(feature = "special")]
+                func!("binompmf(k; n; p)", 3, Some(3), binompmf, "binomial probability mass"),
+                #[cfg(feature = "special")]
+                func!("binomcdf(k; n; p)", 3, Some(3), binomcdf, "binomial cumulative probability"),
+                #[cfg(feature = "special")]
+                func!("poispmf(k; lambda)", 2, Some(2), poispmf, "Poisson probability mass"),
+                #[cfg(feature = "special")]
+                func!("poiscdf(k; lambda)", 2, Some(2), poiscdf, "Poisson cumulative probability"),
+                #[cfg(feature = "special")]
+                func!("tcdf(x; df)", 2, Some(2), tcdf, "Student's t cumulative probability"),
+                #[cfg(feature = "special")]
+                func!("tinv(p; df)", 2, Some(2), tinv, "inverse of Student's t cumulative probability"),
+                #[cfg(feature = "special")]
+                func!("chi2cdf(x; df)", 2, Some(2), chi2cdf, "chi-squared cumulative probability"),
+                #[cfg(feature = "special")]
+                func!("chi2inv(p; df)", 2, Some(2), chi2inv, "inverse of chi-squared cumulative probability"),
+            ],
+        ),
+        (
+            Category::Matrix,
+            vec![
+                func!("list(a; b; ...)", 0, None, list, "a list of the arguments, the same as [a; b; ...]").rpn_args(1),
+                func!("item(xs; i; ...)", 2, None, item, "item of a list or character of a string, the same as xs[i]")
+                    .rpn_args(2),
+                func!("slice(xs; i; j)", 3, Some(3), slice, "items from i up to j, the same as xs[i:j]"),
+                func!("zip(xs; ys; ...)", 1, None, zip, "list of lists of items with the same index").rpn_args(2),
+                func!("col(rows; i)", 2, Some(2), col, "list of items with index i of every row"),
+                func!("sort(xs)", 1, Some(1), sort, "items of a list in ascending order"),
+                func!("sortdesc(xs)", 1, Some(1), sortdesc, "items of a list in descending order"),
+                func!("unique(xs)", 1, Some(1), unique, "items of a list without repetitions"),
+                func!("reverse(xs)", 1, Some(1), reverse, "a list or a string in reverse order"),
+                func!(
+                    "map(xs; expr)",
+                    2,
+                    Some(2),
+                    Stack::process_formula_function,
+                    "values of an expression of x for every item x of a list"
+                ),
+                func!(
+                    "filter(xs; cond)",
+                    2,
+                    Some(2),
+                    Stack::process_formula_function,
+                    "items x of a list for which a condition is true"
+                ),
+                func!(
+                    "reduce(xs; expr; init)",
+                    3,
+                    Some(3),
+                    Stack::process_formula_function,
+                    "combines items x of a list: acc = expr for every item, starting from acc = init"
+                ),
+                func!("det(m)", 1, Some(1), det, "determinant of a matrix"),
+                func!("inv(m)", 1, Some(1), inv, "inverse matrix"),
+                func!("rank(m)", 1, Some(1), rank, "rank of a matrix"),
+                func!("dot(u; v)", 2, Some(2), dot, "dot product of vectors"),
+                func!("cross(u; v)", 2, Some(2), cross, "cross product of 3D vectors"),
+                func!("vnorm(u)", 1, Some(1), vnorm, "Euclidean length of a vector"),
+                func!("angle(u; v)", 2, Some(2), angle, "angle between vectors in radians"),
+            ],
+        ),
+        (
+            Category::Algebra,
+            vec![
+                func!(
+                    "solve(a; b; c)",
+                    2,
+                    Some(3),
+                    Stack::solve_equation,
+                    "roots of a linear or square equation, or solves an equation given as a string"
+                )
+                .rpn_args(3),
+                func!("zeroes(a; b; c)", 2, Some(3), Stack::solve_equation, "the same as solve").rpn_args(3),
+                func!("roots(p)", 1, Some(3), roots, "roots of a polynomial or an equation").rpn_args(3),
+                func!("poly(a; b; ...)", 1, None, poly, "polynomial, the coefficients start from the highest power"),
+                func!("polyval(p; x)", 2, Some(2), polyval, "value of a polynomial"),
+                func!(
+                    "solvesys(eqs; vars)",
+                    2,
+                    Some(2),
+                    Stack::process_formula_function,
+                    "solves a system of linear equations given as strings"
+                ),
+            ],
+        ),
+        (
+            Category::Calculus,
+            vec![
+                func!(
+                    "taylor(expr; var; x0; n)",
+                    4,
+                    Some(4),
+                    Stack::process_formula_function,
+                    "Taylor series of an expression as a polynomial"
+                ),
+                func!(
+                    "limit(expr; var; x0)",
+                    3,
+                    Some(3),
+                    Stack::process_formula_function,
+                    "numeric limit of an expression"
+                ),
+                func!(
+                    "nderiv(expr; var; x)",
+                    3,
+                    Some(3),
+                    Stack::process_formula_function,
+                    "numeric derivative of an expression"
+                ),
+                func!(
+                    "minimize(expr; var; a; b)",
+                    4,
+                    Some(4),
+                    Stack::process_formula_function,
+                    "minimum of an expression on the interval as a pair [x; value]"
+                ),
+                func!(
+                    "maximize(expr; var; a; b)",
+                    4,
+                    Some(4),
+                    Stack::process_formula_function,
+                    "maximum of an expression on the interval as a pair [x; value]"
+                ),
+                func!(
+                    "rk4(expr; var; t0; y0; h; n)",
+                    6,
+                    Some(6),
+                    Stack::process_formula_function,
+                    "integrates y'=f(t, y) with Runge-Kutta method"
+                ),
+                func!(
+                    "sample(expr; var; a; b; n)",
+                    5,
+                    Some(5),
+                    Stack::process_formula_function,
+                    "list of n points [x; y] of an expression"
+                ),
+                func!(
+                    "iterate(expr; var; init; n)",
+                    4,
+                    Some(4),
+                    Stack::process_formula_function,
+                    "applies an expression n times: var = expr, starting from var = init"
+                ),
+            ],
+        ),
+        (
+            Category::Bits,
+            vec![
+                func!("hex(x)", 1, Some(1), hex, "hexadecimal form of an integer or string bytes"),
+                func!("oct(x)", 1, Some(1), oct, "octal form of an integer"),
+                func!("bin(x)", 1, Some(1), bin, "binary form of an integer"),
+                func!("signed(x; width)", 2, Some(2), signed, "the lowest bits as a two's-complement signed number"),
+                func!("unsigned(x; width)", 2, Some(2), unsigned, "the lowest bits as an unsigned number"),
+                func!("bits(x; hi; lo)", 3, Some(3), bits, "extracts bits from hi down to lo"),
+                func!("setbits(x; hi; lo; v)", 4, Some(4), setbits, "replaces bits from hi down to lo with v"),
+                func!("bswap(x; width)", 2, Some(2), bswap, "reverses the order of bytes"),
+                func!("bswap16(x)", 1, Some(1), bswap16, "reverses the order of bytes of a 16-bit number"),
+                func!("bswap32(x)", 1, Some(1), bswap32, "reverses the order of bytes of a 32-bit number"),
+                func!("bswap64(x)", 1, Some(1), bswap64, "reverses the order of bytes of a 64-bit number"),
+            ],
+        ),
+        (
+            Category::Strings,
+            vec![
+                func!("len(s)", 1, Some(1), len, "length of a string or a list"),
+                func!(
+                    "format(x; spec)",
+                    2,
+                    Some(2),
+                    |stk, fname, args, _| stk.format(fname, args),
+                    "formats a value with a format specification"
+                ),
+                func!(
+                    "fmt(x; template)",
+                    2,
+                    Some(2),
+                    |stk, fname, args, _| stk.format(fname, args),
+                    "formats a value with a printf-style or a brace template"
+                ),
+                func!("roman(n)", 1, Some(1), roman, "Roman numeral of an integer"),
+                func!("unroman(s)", 1, Some(1), unroman, "converts a Roman numeral to an integer"),
+                func!("spell(n; lang)", 1, Some(2), spell, "number in English words, lang is \"en\" or \"en-gb\""),
+            ],
+        ),
+        (
+            Category::Encoding,
+            vec![
+                func!("unhex(s)", 1, Some(1), unhex, "converts a hexadecimal string to an integer"),
+                func!("b64(x)", 1, Some(1), b64, "Base64 form of a string or an integer"),
+                func!("unb64(s)", 1, Some(1), unb64, "decodes a Base64 string"),
+                func!("crc32(x)", 1, Some(1), |stk, fname, args, _| stk.hash(fname, args), "CRC-32 checksum"),
+                func!("luhn(n)", 1, Some(1), luhn, "1 if the number passes Luhn check"),
+                func!("fnv1a(x)", 1, Some(1), |stk, fname, args, _| stk.hash(fname, args), "64-bit FNV-1a hash"),
+                func!("murmur3(x)", 1, Some(1), |stk, fname, args, _| stk.hash(fname, args), "32-bit MurmurHash3"),
+                #[cfg(feature = "sha256")]
+                func!("sha256(x)", 1, Some(1), |stk, fname, args, _| stk.hash(fname, args), "SHA-256 digest"),
+            ],
+        ),
+        (
+            Category::DateTime,
+            vec![
+                #[cfg(feature = "datetime")]
+                func!("now()", 0, Some(0), now, "the current Unix timestamp"),
+                #[cfg(feature = "datetime")]
+                func!("unix(date)", 1, Some(1), unix, "converts an ISO 8601 date to Unix timestamp"),
+                #[cfg(feature = "datetime")]
+                func!("fromunix(seconds)", 1, Some(1), fromunix, "converts Unix timestamp to a UTC date"),
+                #[cfg(feature = "datetime")]
+                func!("tz(date; zone)", 2, Some(2), tz, "local time of a date in the time zone"),
+                #[cfg(feature = "datetime")]
+                func!("weekday(date)", 1, Some(1), weekday, "ISO day of the week, 1 is Monday"),
+                #[cfg(feature = "datetime")]
+                func!("isoweek(date)", 1, Some(1), isoweek, "ISO week number"),
+                #[cfg(feature = "datetime")]
+                func!("isoyear(date)", 1, Some(1), isoyear, "year of ISO week"),
+                #[cfg(feature = "datetime")]
+                func!("daysin(month; year)", 2, Some(2), daysin, "number of days in a month"),
+            ],
+        ),
+        (
+            Category::Units,
+            vec![
+                #[cfg(feature = "units")]
+                func!("kib(n)", 1, Some(1), kib, "kibibytes to bytes"),
+                #[cfg(feature = "units")]
+                func!("mib(n)", 1, Some(1), mib, "mebibytes to bytes"),
+                #[cfg(feature = "units")]
+                func!("gib(n)", 1, Some(1), gib, "gibibytes to bytes"),
+                #[cfg(feature = "units")]
+                func!("tib(n)", 1, Some(1), tib, "tebibytes to bytes"),
+                #[cfg(feature = "units")]
+                func!("kb(n)", 1, Some(1), kb, "kilobytes to bytes"),
+                #[cfg(feature = "units")]
+                func!("mb(n)", 1, Some(1), mb, "megabytes to bytes"),
+                #[cfg(feature = "units")]
+                func!("gb(n)", 1, Some(1), gb, "gigabytes to bytes"),
+                #[cfg(feature = "units")]
+                func!("tb(n)", 1, Some(1), tb, "terabytes to bytes"),
+                #[cfg(feature = "units")]
+                func!("hsize(bytes; base)", 1, Some(2), hsize, "size with a human-readable unit, base is 1024 or 1000"),
+                #[cfg(feature = "units")]
+                func!("duration(seconds)", 1, Some(1), duration, "formats seconds as a duration, e.g. 1h30m"),
+                #[cfg(feature = "units")]
+                func!(
+                    "ip(addr)",
+                    1,
+                    Some(1),
+                    |stk, _, args, _| stk.ip(args).and_then(|_| stk.ipv4_alt()),
+                    "converts an IPv4 address to an integer"
+                ),
+                #[cfg(feature = "units")]
+                func!("ipstr(n)", 1, Some(1), ipstr, "converts an integer to an IPv4 address string"),
+                #[cfg(feature = "units")]
+                func!(
+                    "netmask(prefix)",
+                    1,
+                    Some(1),
+                    |stk, _, args, _| stk.netmask(args).and_then(|_| stk.ipv4_alt()),
+                    "network mask of a prefix length"
+                ),
+                #[cfg(feature = "units")]
+                func!(
+                    "network(ip; prefix)",
+                    2,
+                    Some(2),
+                    |stk, _, args, _| stk.network(args).and_then(|_| stk.ipv4_alt()),
+                    "network address"
+                ),
+                #[cfg(feature = "units")]
+                func!(
+                    "broadcast(ip; prefix)",
+                    2,
+                    Some(2),
+                    |stk, _, args, _| stk.broadcast(args).and_then(|_| stk.ipv4_alt()),
+                    "broadcast address"
+                ),
+            ],
+        ),
+    ];
+    groups.into_iter().flat_map(|(category, funcs)| funcs.into_iter().map(move |f| Func { category, ..f })).collect()
+}
 
 lazy_static! {
-    pub(crate) static ref STD_FUNCS: Vec<&'static str> = [
-        "sqr",
-        "sqrt",
-        "cbrt",
-        "exp",
-        "ln",
-        "abs",
-        "signum",
-        "round",
-        "ceil",
-        "trunc",
-        "floor",
-        "ratio",
-        "sin",
-        "cos",
-        "tan",
-        "asin",
-        "acos",
-        "atan",
-        "sinh",
-        "cosh",
-        "tanh",
-        "asinh",
-        "acosh",
-        "atanh",
-        "norm",
-        "conj",
-        "im",
-        "re",
-        "fract",
-        "iif",
-        "gcd",
-        "lcm",
-        "deg",
-        "rad",
-        "fib",
-        "min",
-        "max",
-        "avg",
-        "is_prime",
-        "next_prime",
-        "hex",
-        "oct",
-        "bin",
-        "gamma",
-        "solve",
-        "zeroes",
-        "roots",
-    ]
-    .to_vec();
+    pub(crate) static ref FUNCS: Vec<Func> = builtin_funcs();
+    static ref FUNC_INDEX: HashMap<&'static str, usize> =
+        FUNCS.iter().enumerate().map(|(idx, f)| (f.name, idx)).collect();
+}
+
+/// Returns the registry entry of a built-in function
+pub(crate) fn find_func(name: &str) -> Option<&'static Func> {
+    FUNC_INDEX.get(name).map(|idx| &FUNCS[*idx])
+}
+
+// alternative names of built-in functions. `log` is not listed: it is
+// `ln` or `log10` depending on the calculator options
+pub(crate) const ALIASES: [(&str, &str); 10] = [
+    ("arcsin", "asin"),
+    ("arccos", "acos"),
+    ("arctan", "atan"),
+    ("arcsinh", "asinh"),
+    ("arccosh", "acosh"),
+    ("arctanh", "atanh"),
+    ("arsinh", "asinh"),
+    ("arcosh", "acosh"),
+    ("lg", "log10"),
+    ("polar", "topolar"),
+];
+
+/// Returns the name of the function an alias refers to
+pub(crate) fn resolve_alias(name: &str, log: LogBase) -> Option<&'static str> {
+    if name == "log" {
+        return Some(match log {
+            LogBase::E => "ln",
+            LogBase::Ten => "log10",
+        });
+    }
+    ALIASES.iter().find(|(alias, _)| *alias == name).map(|(_, func)| *func)
+}
+
+// operators which results are booleans
+const LOGIC_OPS: [&str; 9] = ["==", "!=", "<", ">", "<=", ">=", "!", "&&", "||"];
+
+// functions that return different results for the same arguments: the
+// results of expressions that use them are never cached
+pub(crate) const VOLATILE_FUNCS: [&str; 1] = ["now"];
+
+// functions that can take an expression as a string argument. The variables
+// of the expression are not known until it is evaluated, so the results of
+// expressions that use these functions are never cached as well
+pub(crate) const FORMULA_FUNCS: [&str; 14] = [
+    "solve", "zeroes", "solvesys", "taylor", "limit", "sample", "nderiv", "minimize", "maximize", "rk4", "map",
+    "filter", "reduce", "iterate",
+];
+
+// functions which arguments are evaluated only when they are needed: a
+// branch that is not selected can fail, e.g. `piecewise(x == 0; 1; sin(x)/x)`
+pub(crate) const LAZY_FUNCS: [&str; 1] = ["piecewise"];
+
+// trigonometric functions which argument is an angle, and inverse ones
+// which result is an angle. They use degrees if the calculator is in degree mode
+const TRIG_FUNCS: [&str; 6] = ["sin", "cos", "tan", "cot", "sec", "csc"];
+const INV_TRIG_FUNCS: [&str; 6] = ["asin", "acos", "atan", "acot", "asec", "acsc"];
+
+// the least non-negative residue of an integer modulo `m`. Other values are
+// returned as is
+fn residue(v: Value, m: &BigInt) -> Value {
+    match v {
+        Value::Int(i) => Value::Int(((i % m) + m) % m),
+        v => v,
+    }
+}
+
+// rounds float numbers, parts of complex numbers, and items of lists and
+// polynomials to single precision. Other values are returned as is
+fn single_precision(v: Value) -> Value {
+    match v {
+        Value::Float(f) => Value::Float(f64::from(f as f32)),
+        Value::Complex(c) => Value::Complex(Complex::new(f64::from(c.re as f32), f64::from(c.im as f32))),
+        Value::List(l) => Value::List(l.into_iter().map(single_precision).collect()),
+        Value::Poly(c) => Value::Poly(c.into_iter().map(single_precision).collect()),
+        v => v,
+    }
+}
+
+// the number of arguments a function takes from the value stack in RPN
+// expression
+pub(crate) fn rpn_func_args(fname: &str) -> usize {
+    find_func(fname).map_or(1, |f| f.rpn_args)
+}
+
+// an operator of the calculator. Internal operators use special names to
+// distinguish them from the regular ones with the same symbol
+pub(crate) struct Op {
+    pub(crate) name: &'static str,
+    // the operator as a user types it
+    pub(crate) symbol: &'static str,
+    pub(crate) priority: i32,
+    pub(crate) right_assoc: bool,
+    // the number of operands
+    pub(crate) args: usize,
+    pub(crate) help: &'static str,
+}
+
+impl Op {
+    pub(crate) fn info(&self) -> OperatorInfo {
+        OperatorInfo {
+            symbol: self.symbol,
+            precedence: self.priority,
+            associativity: if self.right_assoc { Associativity::Right } else { Associativity::Left },
+            operands: self.args,
+            description: self.help,
+        }
+    }
+}
+
+// all operators starting from the highest priority
+pub(crate) const OPERATORS: [Op; 32] = [
+    Op { name: FACTORIAL, symbol: "!", priority: PRI_IMMEDIATE, right_assoc: false, args: 1, help: "factorial" },
+    Op { name: UNARY_MINUS, symbol: "-", priority: 20, right_assoc: true, args: 1, help: "unary minus" },
+    Op { name: "~", symbol: "~", priority: 20, right_assoc: true, args: 1, help: "bitwise NOT" },
+    Op { name: "!", symbol: "!", priority: 20, right_assoc: true, args: 1, help: "logical NOT" },
+    Op { name: "**", symbol: "**", priority: 17, right_assoc: true, args: 2, help: "power" },
+    Op { name: "^", symbol: "^", priority: 17, right_assoc: true, args: 2, help: "power, the same as **" },
+    Op { name: "<<", symbol: "<<", priority: 15, right_assoc: false, args: 2, help: "bit shift left" },
+    Op { name: ">>", symbol: ">>", priority: 15, right_assoc: false, args: 2, help: "bit shift right" },
+    Op { name: "*", symbol: "*", priority: 12, right_assoc: false, args: 2, help: "multiplication" },
+    Op { name: "/", symbol: "/", priority: 12, right_assoc: false, args: 2, help: "division" },
+    Op { name: "//", symbol: "//", priority: 12, right_assoc: false, args: 2, help: "integer division" },
+    Op { name: "%", symbol: "%", priority: 12, right_assoc: false, args: 2, help: "remainder" },
+    Op { name: PERCENT_ADD, symbol: "+%", priority: 9, right_assoc: false, args: 2, help: "add a percentage" },
+    Op { name: PERCENT_SUB, symbol: "-%", priority: 9, right_assoc: false, args: 2, help: "subtract a percentage" },
+    Op { name: PERCENT_MUL, symbol: "*%", priority: 9, right_assoc: false, args: 2, help: "a percentage of" },
+    Op { name: PERCENT_DIV, symbol: "/%", priority: 9, right_assoc: false, args: 2, help: "ratio in percent" },
+    Op { name: "+", symbol: "+", priority: 8, right_assoc: false, args: 2, help: "addition" },
+    Op { name: "-", symbol: "-", priority: 8, right_assoc: false, args: 2, help: "subtraction" },
+    Op { name: "&", symbol: "&", priority: 7, right_assoc: false, args: 2, help: "bitwise AND" },
+    Op { name: "@", symbol: "@", priority: 7, right_assoc: false, args: 2, help: "bitwise XOR" },
+    Op { name: "^^", symbol: "^^", priority: 7, right_assoc: false, args: 2, help: "bitwise XOR, the same as @" },
+    Op { name: RANGE, symbol: "..", priority: 6, right_assoc: false, args: 2, help: "list of numbers from a to b" },
+    Op {
+        name: RANGE_STEP,
+        symbol: STEP,
+        priority: 6,
+        right_assoc: false,
+        args: 3,
+        help: "range with a step: a..b step s",
+    },
+    Op { name: "|", symbol: "|", priority: 5, right_assoc: false, args: 2, help: "bitwise OR" },
+    Op { name: "&&", symbol: "&&", priority: 4, right_assoc: false, args: 2, help: "logical AND" },
+    Op { name: "||", symbol: "||", priority: 3, right_assoc: false, args: 2, help: "logical OR" },
+    Op { name: "==", symbol: "==", priority: 2, right_assoc: false, args: 2, help: "equal" },
+    Op { name: "!=", symbol: "!=", priority: 2, right_assoc: false, args: 2, help: "not equal" },
+    Op { name: "<", symbol: "<", priority: 2, right_assoc: false, args: 2, help: "less than" },
+    Op { name: ">", symbol: ">", priority: 2, right_assoc: false, args: 2, help: "greater than" },
+    Op { name: "<=", symbol: "<=", priority: 2, right_assoc: false, args: 2, help: "less than or equal" },
+    Op { name: ">=", symbol: ">=", priority: 2, right_assoc: false, args: 2, help: "greater than or equal" },
+];
+
+fn find_op(op: &str) -> Option<&'static Op> {
+    OPERATORS.iter().find(|o| o.name == op)
+}
+
+// the number of operands an operator takes
+pub(crate) fn op_args(op: &str) -> usize {
+    find_op(op).map_or(2, |o| o.args)
+}
+
+// index of the first entry of the sub-expression that ends at every entry.
+// `None` if the expression is incorrect: the evaluation reports the error
+fn subexpr_starts(entries: &[Entry]) -> Option<Vec<usize>> {
+    let mut starts = Vec::with_capacity(entries.len());
+    let mut slots: Vec<usize> = Vec::new();
+    for (idx, e) in entries.iter().enumerate() {
+        let cnt = match e {
+            Entry::Val(..) | Entry::Var(..) => 0,
+            Entry::Op(op, ..) => op_args(op),
+            Entry::Func(_, cnt) => *cnt,
+            Entry::OpenB => return None,
+        };
+        if slots.len() < cnt {
+            return None;
+        }
+        let start = slots.split_off(slots.len() - cnt).first().copied().unwrap_or(idx);
+        slots.push(start);
+        starts.push(start);
+    }
+    Some(starts)
+}
+
+// calls of lazy functions by the index of their first entry: the index of
+// the function entry and the entries of every argument. An outer call
+// starts at the same entry as its first argument, and replaces it. Empty
+// if the expression does not call lazy functions
+fn lazy_calls(entries: &[Entry]) -> Vec<Option<(usize, Vec<Range<usize>>)>> {
+    let is_lazy = |e: &Entry| matches!(e, Entry::Func(name, _) if LAZY_FUNCS.contains(&name.as_str()));
+    if !entries.iter().any(is_lazy) {
+        return Vec::new();
+    }
+    let Some(starts) = subexpr_starts(entries) else {
+        return Vec::new();
+    };
+    let mut calls = vec![None; entries.len()];
+    for (idx, e) in entries.iter().enumerate() {
+        let Entry::Func(name, cnt) = e else {
+            continue;
+        };
+        if !LAZY_FUNCS.contains(&name.as_str()) {
+            continue;
+        }
+        let mut spans = Vec::with_capacity(*cnt);
+        let mut end = idx;
+        for _ in 0..*cnt {
+            let start = starts[end - 1];
+            spans.push(start..end);
+            end = start;
+        }
+        spans.reverse();
+        calls[starts[idx]] = Some((idx, spans));
+    }
+    calls
+}
+
+// user-friendly name of an operator: internal operators use special names to
+// distinguish them from the regular ones with the same symbol
+pub(crate) fn op_display_name(op: &str) -> &str {
+    match op {
+        FACTORIAL => "!",
+        UNARY_MINUS => "neg",
+        PERCENT_ADD => "+%",
+        PERCENT_SUB => "-%",
+        PERCENT_MUL => "*%",
+        PERCENT_DIV => "/%",
+        _ => op,
+    }
 }
 
 macro_rules! one_arg_op {
@@ -138,32 +886,81 @@ macro_rules! function_op {
     };
 }
 
+// a function that takes exactly two arguments. Extra arguments are dropped.
+// The optional second argument is the function name if it differs from
+// the name of the value method
+macro_rules! function_op2 {
+    ($id:ident) => {
+        function_op2!($id, stringify!($id));
+    };
+    ($id:ident, $name:expr) => {
+        fn $id(&mut self, args: usize) -> CalcErrorResult {
+            if args < 2 || self.values.len() < 2 {
+                return Err(CalcError::FunctionNotEnoughArgs($name.to_string(), 2));
+            }
+
+            for _i in 0..args - 2 {
+                let _ = self.values.pop().unwrap();
+            }
+            let v2 = self.values.pop().unwrap();
+            let v1 = self.values.pop().unwrap();
+            let v = v1.$id(v2)?;
+            self.values.push(v);
+            Ok(())
+        }
+    };
+}
+
+// a function that takes exactly three arguments. Extra arguments are dropped
+macro_rules! function_op3 {
+    ($id:ident) => {
+        fn $id(&mut self, args: usize) -> CalcErrorResult {
+            if args < 3 || self.values.len() < 3 {
+                return Err(CalcError::FunctionNotEnoughArgs(stringify!($id).to_string(), 3));
+            }
+
+            for _i in 0..args - 3 {
+                let _ = self.values.pop().unwrap();
+            }
+            let v3 = self.values.pop().unwrap();
+            let v2 = self.values.pop().unwrap();
+            let v1 = self.values.pop().unwrap();
+            let v = v1.$id(v2, v3)?;
+            self.values.push(v);
+            Ok(())
+        }
+    };
+}
+
+// a function that takes exactly four arguments. Extra arguments are dropped
+macro_rules! function_op4 {
+    ($id:ident) => {
+        fn $id(&mut self, args: usize) -> CalcErrorResult {
+            if args < 4 || self.values.len() < 4 {
+                return Err(CalcError::FunctionNotEnoughArgs(stringify!($id).to_string(), 4));
+            }
+
+            for _i in 0..args - 4 {
+                let _ = self.values.pop().unwrap();
+            }
+            let v4 = self.values.pop().unwrap();
+            let v3 = self.values.pop().unwrap();
+            let v2 = self.values.pop().unwrap();
+            let v1 = self.values.pop().unwrap();
+            let v = v1.$id(v2, v3, v4)?;
+            self.values.push(v);
+            Ok(())
+        }
+    };
+}
+
 impl Stack {
     fn priority(op: &str) -> (i32, bool) {
-        match op {
-            FACTORIAL => (PRI_IMMEDIATE, false),   // immediate - factorial
-            UNARY_MINUS | "~" | "!" => (20, true), // negate, bit NOT
-            "**" | "^" => (17, true),              // power
-            "<<" | ">>" => (15, false),            // bit shifts
-            "*" | "/" | "//" | "%" => (12, false), // mult, div, int div, mod
-            "+" | "-" => (8, false),               // add, sub
-            PERCENT_ADD | PERCENT_SUB | PERCENT_MUL | PERCENT_DIV => (8, false), // percent operations
-            "&" | "@" => (7, false),               // bit AND/XOR
-            "|" => (5, false),                     // bit OR
-            "&&" => (4, false),                    // bit AND
-            "||" => (3, false),                    // bit AND
-            "==" | "!=" | "<" | ">" | "<=" | ">=" => (2, false), // logical ops
-            _ => (0, false),                       // invalid op
-        }
+        find_op(op).map_or((0, false), |o| (o.priority, o.right_assoc)) // 0 - invalid op
     }
 
     pub(crate) fn is_func(&self, s: &str) -> bool {
-        for fname in STD_FUNCS.iter() {
-            if *fname == s {
-                return true;
-            }
-        }
-        false
+        find_func(s).is_some()
     }
 
     // move operators from the queue to output while the top operator in the
@@ -223,7 +1020,7 @@ impl Stack {
             // unwrap is ok - vector is not empty
             let e = self.queue.pop().unwrap();
             match &e {
-                Entry::Val(..) | Entry::Op(..) | Entry::Func(..) => self.output.push(e),
+                Entry::Val(..) | Entry::Var(..) | Entry::Op(..) | Entry::Func(..) => self.output.push(e),
                 Entry::OpenB => {
                     self.update_func_args();
                     if keep_bracket {
@@ -236,7 +1033,7 @@ impl Stack {
     }
 
     // move functions from the queue to output
-    fn pop_functions(&mut self) -> CalcErrorResult {
+    pub(crate) fn pop_functions(&mut self) -> CalcErrorResult {
         loop {
             if self.queue.is_empty() {
                 return Ok(());
@@ -279,9 +1076,23 @@ impl Stack {
             result: Value::Float(0.0),
             has_alt: false,
             alt_result: "".to_owned(),
+            calc: Calc::new(),
+            warnings: Vec::new(),
         }
     }
 
+    pub(crate) fn from_postfix(entries: Vec<Entry>) -> Self {
+        let mut stk = Stack::new();
+        stk.output = entries;
+        stk
+    }
+
+    /// Finishes the expression and returns it in postfix form
+    pub(crate) fn into_postfix(mut self) -> Result<Vec<Entry>, CalcError> {
+        self.pop_all()?;
+        Ok(self.output)
+    }
+
     pub(crate) fn push(&mut self, op: &str, val: Option<Value>) -> CalcErrorResult {
         if op.is_empty() {
             if let Some(v) = val {
@@ -326,6 +1137,66 @@ impl Stack {
         Ok(())
     }
 
+    /// Pushes a value, a function, or an operator directly to the output
+    /// queue, bypassing the shunting-yard stage. Used for RPN expressions
+    pub(crate) fn push_rpn(&mut self, op: &str, val: Option<Value>) -> CalcErrorResult {
+        if op.is_empty() {
+            if let Some(v) = val {
+                self.output.push(Entry::Val(v));
+                return Ok(());
+            }
+            return Err(CalcError::EmptyValue);
+        }
+
+        if self.is_func(op) {
+            self.output.push(Entry::Func(op.to_owned(), rpn_func_args(op)));
+            return Ok(());
+        }
+
+        let (pri, right_assoc) = Stack::priority(op);
+        if pri == 0 {
+            return Err(CalcError::InvalidOp(op.to_owned()));
+        }
+        self.output.push(Entry::Op(op.to_owned(), pri, right_assoc));
+        Ok(())
+    }
+
+    // returns true if `step` after a value adds a step to a range: the
+    // range is the closest operator in the queue, except ones that bind
+    // tighter, e.g. in `1..2*n step 2`
+    pub(crate) fn in_range(&self) -> bool {
+        let (pri, _) = Stack::priority(RANGE);
+        for e in self.queue.iter().rev() {
+            match e {
+                Entry::Op(op, ..) if op == RANGE => return true,
+                Entry::Op(_, p, _) if *p > pri => {}
+                Entry::Func(..) => {}
+                _ => return false,
+            }
+        }
+        false
+    }
+
+    // turns the range operator in the queue into the range with a step. The
+    // step becomes its third operand
+    pub(crate) fn push_step(&mut self) -> CalcErrorResult {
+        let (pri, _) = Stack::priority(RANGE);
+        self.pop_while_priority(pri + 1);
+        match self.queue.pop() {
+            Some(Entry::Op(op, p, right)) if op == RANGE => {
+                self.queue.push(Entry::Op(RANGE_STEP.to_string(), p, right));
+                Ok(())
+            }
+            _ => Err(CalcError::InvalidOp(STEP.to_string())),
+        }
+    }
+
+    /// Pushes a variable to the output queue. Its value is requested when
+    /// the expression is calculated
+    pub(crate) fn push_var(&mut self, name: &str) {
+        self.output.push(Entry::Var(name.to_owned()));
+    }
+
     pub(crate) fn increase_func_argc(&mut self) -> CalcErrorResult {
         if let Some(e) = self.queue.pop() {
             match &e {
@@ -338,7 +1209,29 @@ impl Stack {
         Ok(())
     }
 
+    // `:` inside index brackets turns indexing into slicing. The start of
+    // the slice is already in the output
+    pub(crate) fn push_slice(&mut self) -> CalcErrorResult {
+        self.pop_until_bracket(true)?;
+        let bracket = self.queue.pop();
+        match self.queue.pop() {
+            Some(Entry::Func(name, 2)) if name == ITEM_FUNC => {
+                self.queue.push(Entry::Func(SLICE_FUNC.to_string(), 2));
+                self.queue.extend(bracket);
+                Ok(())
+            }
+            _ => Err(CalcError::InvalidOp(":".to_string())),
+        }
+    }
+
+    #[cfg(test)]
     pub(crate) fn calculate(&mut self) -> CalcResult {
+        self.calculate_with(&mut |name| Err(CalcError::VarUndeclared(name.to_string())))
+    }
+
+    /// Calculates the expression. `lookup` is called every time the
+    /// expression needs a variable value
+    pub(crate) fn calculate_with(&mut self, lookup: &mut dyn FnMut(&str) -> CalcResult) -> CalcResult {
         self.pop_all()?;
         if self.output.is_empty() {
             return Err(CalcError::EmptyExpression);
@@ -346,22 +1239,52 @@ impl Stack {
 
         self.result = Value::Float(0.0);
         self.values = Vec::new();
+        if let Some(m) = &self.calc.modulus {
+            if !m.is_positive() {
+                return Err(CalcError::ArgumentOutOfRange("modulus".to_string(), m.to_string(), "[1..]".to_string()));
+            }
+        }
 
-        for i in 0..self.output.len() {
+        let mut lazy = lazy_calls(&self.output);
+        let mut i = 0;
+        while i < self.output.len() {
             self.has_alt = false;
-            let o = self.output[i].clone();
-            match o {
-                Entry::Val(v) => {
-                    self.values.push(v.clone());
-                }
-                Entry::Op(op, ..) => {
-                    self.process_operator(&op)?;
+            if let Some((end, spans)) = lazy.get_mut(i).and_then(Option::take) {
+                let Entry::Func(fname, _) = self.output[end].clone() else {
+                    return Err(CalcError::Unreachable);
+                };
+                self.process_lazy_function(&fname, &spans, lookup)?;
+                i = end;
+            } else {
+                let o = self.output[i].clone();
+                match o {
+                    Entry::Val(v) => {
+                        self.values.push(v.clone());
+                    }
+                    Entry::Var(name) => {
+                        let v = lookup(&name)?;
+                        self.values.push(v);
+                    }
+                    Entry::Op(op, ..) => {
+                        self.check_result_size(&op, op_args(&op))?;
+                        self.process_operator(&op)?;
+                    }
+                    Entry::Func(fname, args) => {
+                        self.check_result_size(&fname, args)?;
+                        self.process_function(&fname, args, lookup)?;
+                    }
+                    _ => return Err(CalcError::Unreachable),
                 }
-                Entry::Func(fname, args) => {
-                    self.process_function(&fname, args)?;
+            }
+            if self.calc.float_width == FloatWidth::F32 {
+                if let Some(v) = self.values.pop() {
+                    self.values.push(single_precision(v));
                 }
-                _ => return Err(CalcError::Unreachable),
             }
+            if let (Some(sandbox), Some(v)) = (&self.calc.sandbox, self.values.last()) {
+                sandbox.check_value(v)?;
+            }
+            i += 1;
         }
 
         if self.values.len() != 1 {
@@ -370,16 +1293,105 @@ impl Stack {
 
         // values is never empty after calculation - unwrap is fine
         self.result = self.values.pop().unwrap();
+        if let Some(m) = &self.calc.modulus {
+            self.result = residue(self.result.clone(), m);
+        }
         Ok(self.result.clone())
     }
 
+    // in sandbox mode, rejects operations that would generate too big numbers
+    fn check_result_size(&self, op: &str, args: usize) -> CalcErrorResult {
+        if self.calc.modulus.is_some() && (op == "**" || op == "^") {
+            // modular power never generates numbers bigger than the modulus
+            return Ok(());
+        }
+        match &self.calc.sandbox {
+            Some(sandbox) if self.values.len() >= args => {
+                sandbox.check_result_size(op, &self.values[self.values.len() - args..])
+            }
+            _ => Ok(()),
+        }
+    }
+
+    // in modular mode integers are residues: division multiplies by the
+    // modular inverse, power uses `modpow`, and equality compares residues.
+    // Other operators work as usual, and their results are reduced at the
+    // end, so exponents that are results of arithmetic are not reduced.
+    // Returns true if the operator has been processed
+    fn modular_op(&mut self, op: &str) -> Result<bool, CalcError> {
+        let m = match &self.calc.modulus {
+            Some(m) => m.clone(),
+            None => return Ok(false),
+        };
+        let n = self.values.len();
+        if n < 2 || !matches!((&self.values[n - 2], &self.values[n - 1]), (Value::Int(..), Value::Int(..))) {
+            return Ok(false);
+        }
+        let v = match op {
+            "/" => {
+                let v2 = self.values.pop().unwrap();
+                let v1 = self.values.pop().unwrap();
+                residue(v1.multiply(v2.modinv(Value::Int(m.clone()))?)?, &m)
+            }
+            "**" | "^" => {
+                let v2 = self.values.pop().unwrap();
+                let v1 = self.values.pop().unwrap();
+                v1.modpow(v2, Value::Int(m))?
+            }
+            "==" | "!=" => {
+                for v in self.values[n - 2..].iter_mut() {
+                    *v = residue(v.clone(), &m);
+                }
+                return Ok(false);
+            }
+            _ => return Ok(false),
+        };
+        self.values.push(v);
+        Ok(true)
+    }
+
     fn process_operator(&mut self, op: &str) -> CalcErrorResult {
+        let is_logic = LOGIC_OPS.contains(&op);
+        let takes_bool = matches!(op, "==" | "!=" | "!" | "&&" | "||");
+        self.check_bools(op, op_args(op), takes_bool)?;
+        self.apply_operator(op)?;
+        if is_logic && self.calc.truth != Truth::Int {
+            if let Some(v) = self.values.pop() {
+                self.values.push(Value::Bool(!v.is_zero()));
+            }
+        }
+        Ok(())
+    }
+
+    // booleans are `1` and `0` in arithmetic. A strict calculator allows
+    // them only as arguments of logical operators and functions that make
+    // lists
+    fn check_bools(&mut self, name: &str, args: usize, takes_bool: bool) -> CalcErrorResult {
+        let start = self.values.len().saturating_sub(args);
+        for v in self.values[start..].iter_mut() {
+            if let Value::Bool(b) = v {
+                if takes_bool || name == LIST_FUNC {
+                    continue;
+                }
+                if self.calc.truth == Truth::Strict {
+                    return Err(CalcError::NotForBool(name.to_string()));
+                }
+                *v = Value::Int(BigInt::from(u8::from(*b)));
+            }
+        }
+        Ok(())
+    }
+
+    fn apply_operator(&mut self, op: &str) -> CalcErrorResult {
+        if self.modular_op(op)? {
+            return Ok(());
+        }
         match op {
             "/" => self.divide(),
             "*" => self.multiply(),
             "+" => self.addition(),
             "-" => self.subtract(),
-            "//" => self.div_int(),
+            "//" => self.div_round(),
             "%" => self.remainder(),
             "**" | "^" => self.power(),
             UNARY_MINUS => self.negate(),
@@ -395,66 +1407,169 @@ impl Stack {
             ">=" => self.greatereq(),
             "<" => self.less(),
             "<=" => self.lesseq(),
-            "@" => self.bit_xor(),
+            "@" | "^^" => self.bit_xor(),
             "&" => self.bit_and(),
             "|" => self.bit_or(),
             "&&" => self.logical_and(),
             "||" => self.logical_or(),
+            RANGE => self.range(false),
+            RANGE_STEP => self.range(true),
             _ => Err(CalcError::InvalidOp(op.to_string())),
         }
     }
 
-    fn process_function(&mut self, fname: &str, args: usize) -> CalcErrorResult {
+    // checks the number of arguments of a function and applies the calculator
+    // policy to the arguments the function does not use. The arguments are
+    // dropped by the function itself
+    fn check_args(&mut self, func: &Func, args: usize) -> CalcErrorResult {
+        if args < func.min_args {
+            return Err(if func.min_args == 1 {
+                CalcError::FunctionNoArgs(func.name.to_string())
+            } else {
+                CalcError::FunctionNotEnoughArgs(func.name.to_string(), func.min_args)
+            });
+        }
+        let max = match func.max_args {
+            Some(max) if args > max => max,
+            _ => return Ok(()),
+        };
+        match self.calc.extra_args {
+            ExtraArgs::Ignore => {}
+            ExtraArgs::Warn => self.warnings.push(format!(
+                "Function '{}' uses {} argument(s), {} extra dropped",
+                func.name,
+                max,
+                args - max
+            )),
+            ExtraArgs::Error => return Err(CalcError::FunctionTooManyArgs(func.name.to_string(), max)),
+        }
+        Ok(())
+    }
+
+    // returns true if `solve` is called with an equation as a string instead
+    // of coefficients
+    fn solve_takes_formula(&self, args: usize) -> bool {
+        args == 2 && self.values.len() >= 2 && matches!(self.values[self.values.len() - 2], Value::Str(..))
+    }
+
+    // functions that evaluate expressions need the session variables
+    fn process_formula_function(&mut self, fname: &str, args: usize, lookup: &mut Lookup) -> CalcErrorResult {
+        let mut calc = self.calc.clone();
+        calc.nesting += 1;
+        let max_nesting = calc.parse_limits().max_nesting;
+        if calc.nesting > max_nesting {
+            return Err(CalcError::TooDeepNesting(max_nesting));
+        }
+        let start = match self.values.len().checked_sub(args) {
+            Some(start) => start,
+            None => return Err(CalcError::FunctionNotEnoughArgs(fname.to_string(), args)),
+        };
+        let mut vals = self.values.split_off(start);
+        let v = match fname {
+            "solve" | "zeroes" => {
+                let var = vals.pop().unwrap();
+                formula::solve(&vals[0], &var, &calc, lookup)?
+            }
+            "solvesys" => formula::solvesys(&vals[0], &vals[1], &calc, lookup)?,
+            "taylor" => formula::taylor(&vals[0], &vals[1], &vals[2], &vals[3], &calc, lookup)?,
+            "limit" | "nderiv" => {
+                if fname == "limit" {
+                    formula::limit(&vals[0], &vals[1], &vals[2], &calc, lookup)?
+                } else {
+                    formula::nderiv(&vals[0], &vals[1], &vals[2], &calc, lookup)?
+                }
+            }
+            "minimize" | "maximize" => {
+                formula::minimize(&vals[0], &vals[1], &vals[2], &vals[3], fname == "maximize", &calc, lookup)?
+            }
+            "rk4" => formula::rk4(&vals, &calc, lookup)?,
+            "sample" => formula::sample(&vals[0], &vals[1], &vals[2], &vals[3], &vals[4], &calc, lookup)?,
+            "map" | "filter" => {
+                if fname == "map" {
+                    formula::map(&vals[0], &vals[1], &calc, lookup)?
+                } else {
+                    formula::filter(&vals[0], &vals[1], &calc, lookup)?
+                }
+            }
+            "reduce" => formula::reduce(&vals[0], &vals[1], &vals[2], &calc, lookup)?,
+            "iterate" => formula::iterate(&vals[0], &vals[1], &vals[2], &vals[3], &calc, lookup)?,
+            _ => return Err(CalcError::Unreachable),
+        };
+        self.values.push(v);
+        Ok(())
+    }
+
+    // evaluates a part of the expression on its own stack
+    fn eval_span(&mut self, span: Range<usize>, lookup: &mut Lookup) -> CalcResult {
+        let mut stk = new_stack(self.output[span].to_vec(), &self.calc);
+        let res = stk.calculate_with(lookup);
+        self.warnings.append(&mut stk.warnings);
+        if stk.has_alt {
+            self.has_alt = true;
+            self.alt_result = stk.alt_result;
+        }
+        res
+    }
+
+    // `spans` are the parts of the expression that calculate the arguments.
+    // Only the arguments the function needs are evaluated, and then the
+    // function gets them as regular values
+    fn process_lazy_function(&mut self, fname: &str, spans: &[Range<usize>], lookup: &mut Lookup) -> CalcErrorResult {
+        let func = match find_func(fname) {
+            Some(func) => func,
+            None => return Err(CalcError::InvalidOp(fname.to_string())),
+        };
+        self.check_args(func, spans.len())?;
         match fname {
-            "sin" => self.sin(args),
-            "cos" => self.cos(args),
-            "tan" => self.tan(args),
-            "asin" => self.asin(args),
-            "acos" => self.acos(args),
-            "atan" => self.atan(args),
-            "sinh" => self.sinh(args),
-            "cosh" => self.cosh(args),
-            "tanh" => self.tanh(args),
-            "asinh" => self.asinh(args),
-            "acosh" => self.acosh(args),
-            "atanh" => self.atanh(args),
-            "ln" => self.ln(args),
-            "exp" => self.exp(args),
-            "norm" => self.norm(args),
-            "re" => self.re(args),
-            "im" => self.im(args),
-            "conj" => self.conj(args),
-            "round" => self.round(args),
-            "ceil" => self.ceil(args),
-            "floor" => self.floor(args),
-            "trunc" => self.trunc(args),
-            "abs" => self.abs(args),
-            "signum" => self.signum(args),
-            "sqr" => self.sqr(args),
-            "sqrt" => self.sqrt(args),
-            "cbrt" => self.cbrt(args),
-            "ratio" => self.ratio(args),
-            "fract" => self.fract(args),
-            "iif" => self.iif(args),
-            "gcd" => self.gcd(args),
-            "lcm" => self.lcm(args),
-            "deg" => self.deg(args),
-            "rad" => self.rad(args),
-            "fib" => self.fib(args),
-            "min" => self.min(args),
-            "max" => self.max(args),
-            "avg" => self.avg(args),
-            "is_prime" => self.prime(args),
-            "next_prime" => self.next_prime(args),
-            "hex" => self.hex(args),
-            "oct" => self.oct(args),
-            "bin" => self.bin(args),
-            "gamma" => self.gamma(args),
-            "solve" | "zeroes" | "roots" => self.solve(args),
-            _ => Err(CalcError::InvalidOp(fname.to_string())),
+            "piecewise" => {
+                if spans.len().is_multiple_of(2) {
+                    return Err(CalcError::InvalidAgrument(fname.to_string(), "no default value".to_string()));
+                }
+                let mut selected = spans.len() - 1;
+                for idx in (0..spans.len() - 1).step_by(2) {
+                    if !self.eval_span(spans[idx].clone(), lookup)?.is_zero() {
+                        selected = idx + 1;
+                        break;
+                    }
+                }
+                self.has_alt = false;
+                let v = self.eval_span(spans[selected].clone(), lookup)?;
+                self.values.push(v);
+                (func.handler)(self, fname, 1, lookup)
+            }
+            _ => Err(CalcError::Unreachable),
         }
     }
 
+    fn process_function(&mut self, fname: &str, args: usize, lookup: &mut Lookup) -> CalcErrorResult {
+        let func = match find_func(fname) {
+            Some(func) => func,
+            None => return Err(CalcError::InvalidOp(fname.to_string())),
+        };
+        self.check_args(func, args)?;
+        // in reverse Polish notation a function can be applied to a stack
+        // that has fewer values than the function takes
+        if self.values.len() < args {
+            return Err(CalcError::FunctionNotEnoughArgs(fname.to_string(), args));
+        }
+        self.check_bools(fname, args, false)?;
+        if self.calc.angle == Angle::Rad || args == 0 {
+            return (func.handler)(self, fname, args, lookup);
+        }
+        if TRIG_FUNCS.contains(&fname) {
+            let idx = self.values.len() - args;
+            let v = self.values[idx].clone();
+            self.values[idx] = v.multiply(Value::Float(consts::PI / 180.0))?;
+        }
+        (func.handler)(self, fname, args, lookup)?;
+        if INV_TRIG_FUNCS.contains(&fname) {
+            if let Some(v) = self.values.pop() {
+                self.values.push(v.multiply(Value::Float(180.0 / consts::PI))?);
+            }
+        }
+        Ok(())
+    }
+
     one_arg_op!(negate);
     one_arg_op!(logical_not);
     one_arg_op!(fact);
@@ -470,17 +1585,92 @@ impl Stack {
     two_arg_op!(logical_or);
     two_arg_op!(bit_or);
     two_arg_op!(bit_xor);
+
+    fn range(&mut self, with_step: bool) -> CalcErrorResult {
+        let args = if with_step { 3 } else { 2 };
+        if self.values.len() < args {
+            return Err(CalcError::TooManyOps);
+        }
+        let step = if with_step { self.values.pop() } else { None };
+        let end = self.values.pop().unwrap();
+        let start = self.values.pop().unwrap();
+        let v = start.range(end, step)?;
+        self.values.push(v);
+        Ok(())
+    }
     two_arg_op!(bit_and);
     two_arg_op!(bit_shl);
     two_arg_op!(bit_shr);
-    two_arg_op!(power);
-    two_arg_op!(divide);
     two_arg_op!(remainder);
-    two_arg_op!(div_int);
     two_arg_op!(addition);
     two_arg_op!(subtract);
     two_arg_op!(multiply);
 
+    // in exact mode division of integers and fractions is never converted
+    // to float numbers
+    fn div_values(&self, v1: Value, v2: Value) -> CalcResult {
+        if self.calc.exact {
+            v1.divide_exact(v2)
+        } else {
+            v1.divide(v2)
+        }
+    }
+
+    fn divide(&mut self) -> CalcErrorResult {
+        if self.values.len() < 2 {
+            return Err(CalcError::TooManyOps);
+        }
+
+        let v2 = self.values.pop().unwrap();
+        let v1 = self.values.pop().unwrap();
+        let v = self.div_values(v1, v2)?;
+        self.values.push(v);
+        Ok(())
+    }
+
+    fn power(&mut self) -> CalcErrorResult {
+        if self.values.len() < 2 {
+            return Err(CalcError::TooManyOps);
+        }
+
+        let v2 = self.values.pop().unwrap();
+        let v1 = self.values.pop().unwrap();
+        let v = if self.calc.exact { v1.power_exact(v2)? } else { v1.power(v2)? };
+        self.values.push(v);
+        Ok(())
+    }
+
+    // `round` uses the rounding mode of the calculator
+    fn round(&mut self, args: usize) -> CalcErrorResult {
+        if args == 0 {
+            return Err(CalcError::FunctionNoArgs("round".to_string()));
+        }
+        if self.values.len() < args {
+            return Err(CalcError::FunctionUnfinished("round".to_string()));
+        }
+
+        let mut v = self.values.pop().unwrap();
+        for _i in 0..args - 1 {
+            v = self.values.pop().unwrap();
+        }
+        let v = v.round_with(self.calc.rounding)?;
+        self.values.push(v);
+        Ok(())
+    }
+
+    // integer division rounds the result as the calculator is configured
+    fn div_round(&mut self) -> CalcErrorResult {
+        if self.values.len() < 2 {
+            return Err(CalcError::TooManyOps);
+        }
+
+        let v2 = self.values.pop().unwrap();
+        let v1 = self.values.pop().unwrap();
+        let v = v1.div_round(v2, self.calc.int_div)?;
+        self.values.push(v);
+        Ok(())
+    }
+
     function_op!(sin);
     function_op!(cos);
     function_op!(tan);
@@ -493,23 +1683,191 @@ impl Stack {
     function_op!(asinh);
     function_op!(acosh);
     function_op!(atanh);
+    function_op!(cot);
+    function_op!(sec);
+    function_op!(csc);
+    function_op!(acot);
+    function_op!(asec);
+    function_op!(acsc);
+    function_op!(coth);
+    function_op!(sech);
+    function_op!(csch);
+    function_op!(acoth);
+    function_op!(asech);
+    function_op!(acsch);
+
+    function_op3!(clamp);
+    function_op3!(lerp);
+    function_op3!(wrap);
+    function_op3!(fma);
+    function_op2!(modinv);
+    function_op3!(modpow);
+    function_op2!(copysign);
+    function_op2!(ldexp);
+    function_op!(frexp);
+    function_op2!(mod_euclid, "mod");
+    function_op2!(divmod);
+    function_op!(approx);
+    function_op!(sinc);
+    function_op!(sincn);
+    function_op!(sigmoid);
+    function_op3!(smoothstep);
+    #[cfg(feature = "special")]
+    function_op3!(binompmf);
+    #[cfg(feature = "special")]
+    function_op3!(binomcdf);
+    function_op2!(topolar);
+    function_op2!(tocart);
+    #[cfg(feature = "special")]
+    function_op2!(besselj);
+    #[cfg(feature = "special")]
+    function_op2!(bessely);
+    function_op2!(stirling1);
+    function_op2!(stirling2);
+    function_op2!(multifact);
+    function_op2!(egcd);
+    function_op2!(ilog);
+    #[cfg(feature = "units")]
+    function_op2!(network);
+    #[cfg(feature = "units")]
+    function_op2!(broadcast);
+    #[cfg(feature = "datetime")]
+    function_op2!(tz);
+    #[cfg(feature = "datetime")]
+    function_op2!(daysin);
+    function_op2!(linreg);
+    function_op2!(corr);
+    function_op2!(cov);
+    #[cfg(feature = "special")]
+    function_op2!(poispmf);
+    #[cfg(feature = "special")]
+    function_op2!(poiscdf);
+    #[cfg(feature = "special")]
+    function_op2!(tcdf);
+    #[cfg(feature = "special")]
+    function_op2!(tinv);
+    #[cfg(feature = "special")]
+    function_op2!(chi2cdf);
+    #[cfg(feature = "special")]
+    function_op2!(chi2inv);
+    // `item(m; i; j)` is `m[i][j]`
+    fn item(&mut self, args: usize) -> CalcErrorResult {
+        if args < 2 || self.values.len() < args {
+            return Err(CalcError::FunctionNotEnoughArgs(ITEM_FUNC.to_string(), 2));
+        }
+        let idx = self.values.split_off(self.values.len() - args + 1);
+        let mut v = self.values.pop().unwrap();
+        for i in idx {
+            v = v.item(i)?;
+        }
+        self.values.push(v);
+        Ok(())
+    }
+
+    function_op3!(slice);
+    // `zip(pairs)` with a single list unzips the pairs
+    fn zip(&mut self, args: usize) -> CalcErrorResult {
+        let vals = self.variadic_args("zip", args)?;
+        if vals.len() < 2 {
+            return Err(CalcError::FunctionNotEnoughArgs("zip".to_string(), 2));
+        }
+        self.values.push(Value::zip(vals)?);
+        Ok(())
+    }
+
+    function_op2!(col);
+    function_op!(sort);
+    function_op!(sortdesc);
+    function_op!(unique);
+    function_op!(reverse);
+    function_op!(det);
+    function_op!(inv);
+    function_op!(rank);
+    function_op2!(dot);
+    function_op2!(cross);
+    function_op!(vnorm);
+    function_op2!(angle);
+    function_op2!(polyval);
 
     function_op!(norm);
     function_op!(conj);
     function_op!(im);
     function_op!(re);
+    function_op!(arg);
+    function_op!(proj);
 
     function_op!(fract);
     function_op!(abs);
     function_op!(floor);
     function_op!(ceil);
-    function_op!(round);
     function_op!(trunc);
     function_op!(sqr);
     function_op!(sqrt);
     function_op!(cbrt);
     function_op!(exp);
+    function_op!(exp2);
+    function_op!(expm1);
+    function_op!(ln1p);
+    #[cfg(feature = "special")]
+    function_op!(zeta);
+    function_op!(harmonic);
+    function_op!(bell);
+    function_op!(primorial);
+    function_op!(collatz);
+    function_op!(roman);
+    function_op!(unroman);
+    function_op!(ilog2);
+    function_op!(ilog10);
+    function_op!(nextpow2);
+    function_op!(luhn);
+    function_op!(unhex);
+    function_op!(b64);
+    function_op!(unb64);
+    function_op!(len);
+    function_op2!(signed);
+    function_op2!(unsigned);
+    function_op3!(bits);
+    function_op4!(setbits);
+    function_op2!(bswap);
+    function_op!(bswap16);
+    function_op!(bswap32);
+    function_op!(bswap64);
+    #[cfg(feature = "units")]
+    function_op!(ip);
+    #[cfg(feature = "units")]
+    function_op!(ipstr);
+    #[cfg(feature = "units")]
+    function_op!(netmask);
+    #[cfg(feature = "datetime")]
+    function_op!(unix);
+    #[cfg(feature = "datetime")]
+    function_op!(fromunix);
+    #[cfg(feature = "units")]
+    function_op!(duration);
+    #[cfg(feature = "datetime")]
+    function_op!(weekday);
+    #[cfg(feature = "datetime")]
+    function_op!(isoweek);
+    #[cfg(feature = "datetime")]
+    function_op!(isoyear);
+    #[cfg(feature = "units")]
+    function_op!(kib);
+    #[cfg(feature = "units")]
+    function_op!(mib);
+    #[cfg(feature = "units")]
+    function_op!(gib);
+    #[cfg(feature = "units")]
+    function_op!(tib);
+    #[cfg(feature = "units")]
+    function_op!(kb);
+    #[cfg(feature = "units")]
+    function_op!(mb);
+    #[cfg(feature = "units")]
+    function_op!(gb);
+    #[cfg(feature = "units")]
+    function_op!(tb);
     function_op!(ln);
+    function_op!(log10);
     function_op!(signum);
 
     fn percent_op(&mut self, op: &str) -> CalcErrorResult {
@@ -525,22 +1883,24 @@ impl Stack {
                 let v2 = v2.re()?;
                 let v2 = v2.addition(v100.clone())?;
                 let v2 = v1.multiply(v2)?;
-                v1 = v2.divide(v100)?;
+                v1 = self.div_values(v2, v100)?;
             }
             PERCENT_SUB => {
                 let v2 = v2.re()?;
                 let v2 = v100.clone().subtract(v2)?;
                 let v2 = v1.multiply(v2)?;
-                v1 = v2.divide(v100)?;
+                v1 = self.div_values(v2, v100)?;
             }
             PERCENT_MUL => {
-                let v2 = v2.divide(v100)?;
+                let v2 = self.div_values(v2, v100)?;
                 v1 = v1.multiply(v2)?;
             }
             PERCENT_DIV => {
-                v1 = v1.divide(v2)?;
+                v1 = self.div_values(v1, v2)?;
                 v1 = v1.multiply(v100)?;
-                v1 = v1.into_float()?;
+                if !self.calc.exact {
+                    v1 = v1.into_float()?;
+                }
             }
             _ => return Err(CalcError::Unreachable),
         }
@@ -548,6 +1908,43 @@ impl Stack {
         Ok(())
     }
 
+    // the arguments are evaluated lazily, so usually only the selected
+    // value is left: it is the default one
+    fn piecewise(&mut self, args: usize) -> CalcErrorResult {
+        if args.is_multiple_of(2) {
+            return Err(CalcError::InvalidAgrument("piecewise".to_string(), "no default value".to_string()));
+        }
+        if self.values.len() < args {
+            return Err(CalcError::FunctionUnfinished("piecewise".to_string()));
+        }
+        let mut vals = self.values.split_off(self.values.len() - args);
+        let default = vals.pop().unwrap();
+        let v = vals.chunks(2).find(|pair| !pair[0].is_zero()).map_or(default, |pair| pair[1].clone());
+        self.values.push(v);
+        Ok(())
+    }
+
+    // the condition is returned as is, so a checked expression can be a part
+    // of a bigger one: `assert(x > 0; "x must be positive") * x`
+    fn assert(&mut self, args: usize) -> CalcErrorResult {
+        if args == 0 || self.values.len() < args {
+            return Err(CalcError::FunctionNoArgs("assert".to_string()));
+        }
+        let mut vals = self.values.split_off(self.values.len() - args);
+        vals.truncate(2);
+        let msg = match vals.get(1) {
+            Some(Value::Str(s)) => s.clone(),
+            Some(v) => v.to_string(),
+            None => String::new(),
+        };
+        let cond = vals.swap_remove(0);
+        if cond.is_zero() {
+            return Err(CalcError::AssertionFailed(msg));
+        }
+        self.values.push(cond);
+        Ok(())
+    }
+
     fn iif(&mut self, args: usize) -> CalcErrorResult {
         if args < 3 || self.values.len() < 3 {
             return Err(CalcError::FunctionNotEnoughArgs("iif".to_string(), 3));
@@ -568,29 +1965,77 @@ impl Stack {
         Ok(())
     }
 
-    fn gcd(&mut self, args: usize) -> CalcErrorResult {
-        if args < 2 || self.values.len() < 2 {
-            return Err(CalcError::FunctionNotEnoughArgs("gcd".to_string(), 2));
+    // pops the arguments of a function that accepts any number of them.
+    // A single list argument is expanded: `gcd([12; 18])` == `gcd(12; 18)`
+    fn variadic_args(&mut self, fname: &str, args: usize) -> Result<Vec<Value>, CalcError> {
+        if args == 0 || self.values.is_empty() {
+            return Err(CalcError::FunctionNoArgs(fname.to_string()));
         }
-        let mut v = self.values.pop().unwrap();
-        for _i in 0..args - 1 {
-            let tmp = self.values.pop().unwrap();
-            v = v.gcd(tmp)?;
+        if self.values.len() < args {
+            return Err(CalcError::FunctionUnfinished(fname.to_string()));
+        }
+        let mut vals = self.values.split_off(self.values.len() - args);
+        if let [Value::List(..)] = vals.as_slice() {
+            if let Some(Value::List(l)) = vals.pop() {
+                return Ok(l);
+            }
+        }
+        Ok(vals)
+    }
+
+    // applies `f` to the arguments of a variadic function one by one,
+    // starting from the last argument
+    fn reduce_args<F>(&mut self, fname: &str, args: usize, min_args: usize, f: F) -> CalcErrorResult
+    where
+        F: Fn(Value, Value) -> CalcResult,
+    {
+        let vals = self.variadic_args(fname, args)?;
+        if vals.len() < min_args {
+            return Err(CalcError::FunctionNotEnoughArgs(fname.to_string(), min_args));
+        }
+        let mut vals = vals.into_iter().rev();
+        let mut v = match vals.next() {
+            Some(v) => v,
+            None => return Err(CalcError::FunctionNoArgs(fname.to_string())),
+        };
+        for a in vals {
+            v = f(v, a)?;
         }
         self.values.push(v);
         Ok(())
     }
 
+    fn gcd(&mut self, args: usize) -> CalcErrorResult {
+        self.reduce_args("gcd", args, 2, |v1, v2| v1.gcd(v2))
+    }
+
     fn lcm(&mut self, args: usize) -> CalcErrorResult {
-        if args < 2 || self.values.len() < 2 {
-            return Err(CalcError::FunctionNotEnoughArgs("lcm".to_string(), 2));
+        self.reduce_args("lcm", args, 2, |v1, v2| v1.lcm(v2))
+    }
+
+    fn poly(&mut self, args: usize) -> CalcErrorResult {
+        let vals = self.variadic_args("poly", args)?;
+        self.values.push(Value::poly(vals)?);
+        Ok(())
+    }
+
+    // a single argument is a polynomial, otherwise the function is the
+    // same as `solve`
+    fn roots(&mut self, args: usize) -> CalcErrorResult {
+        if args != 1 || self.values.is_empty() {
+            return self.solve(args);
         }
-        let mut v = self.values.pop().unwrap();
-        for _i in 0..args - 1 {
-            let tmp = self.values.pop().unwrap();
-            v = v.lcm(tmp)?;
+        let v = self.values.pop().unwrap();
+        self.values.push(v.roots()?);
+        Ok(())
+    }
+
+    fn list(&mut self, args: usize) -> CalcErrorResult {
+        if self.values.len() < args {
+            return Err(CalcError::FunctionUnfinished(LIST_FUNC.to_string()));
         }
-        self.values.push(v);
+        let items = self.values.split_off(self.values.len() - args);
+        self.values.push(Value::List(items));
         Ok(())
     }
 
@@ -686,57 +2131,137 @@ impl Stack {
         Ok(())
     }
 
-    fn min_max<F>(&mut self, args: usize, fname: &'static str, f: F) -> CalcErrorResult
-    where
-        F: Fn(Value, Value) -> Value,
-    {
-        if args == 0 || self.values.is_empty() {
-            return Err(CalcError::FunctionNoArgs(fname.to_string()));
+    // the maximum denominator is optional
+    fn as_fraction(&mut self, args: usize) -> CalcErrorResult {
+        if args == 0 {
+            return Err(CalcError::FunctionNoArgs("as_fraction".to_string()));
+        }
+        if self.values.len() < args {
+            return Err(CalcError::FunctionUnfinished("as_fraction".to_string()));
         }
 
-        let mut v = self.values.pop().unwrap();
-        for _i in 1..args {
-            let cmp = self.values.pop().unwrap();
-            v = f(v, cmp);
+        let max_den = if args >= 2 {
+            for _i in 0..args - 2 {
+                let _ = self.values.pop().unwrap();
+            }
+            self.values.pop().unwrap()
+        } else {
+            Value::Int(BigInt::from(DEFAULT_MAX_DENOM))
+        };
+        let v = self.values.pop().unwrap();
+        let v = v.as_fraction(max_den)?;
+        self.values.push(v);
+        Ok(())
+    }
+
+    // the branch is optional, the principal one is used by default
+    #[cfg(feature = "special")]
+    fn lambertw(&mut self, args: usize) -> CalcErrorResult {
+        if args == 0 {
+            return Err(CalcError::FunctionNoArgs("lambertw".to_string()));
+        }
+        if self.values.len() < args {
+            return Err(CalcError::FunctionUnfinished("lambertw".to_string()));
+        }
+
+        let branch = if args >= 2 {
+            for _i in 0..args - 2 {
+                let _ = self.values.pop().unwrap();
+            }
+            self.values.pop().unwrap()
+        } else {
+            Value::Int(BigInt::zero())
+        };
+        let v = self.values.pop().unwrap();
+        let v = v.lambertw(branch)?;
+        self.values.push(v);
+        Ok(())
+    }
+
+    // a function with an optional second argument: `default` is used when
+    // the argument is omitted
+    fn optional_arg_func(
+        &mut self,
+        fname: &str,
+        args: usize,
+        default: Value,
+        f: fn(Value, Value) -> CalcResult,
+    ) -> CalcErrorResult {
+        if args == 0 {
+            return Err(CalcError::FunctionNoArgs(fname.to_string()));
+        }
+        if self.values.len() < args {
+            return Err(CalcError::FunctionUnfinished(fname.to_string()));
         }
+
+        let arg = if args >= 2 {
+            for _i in 0..args - 2 {
+                let _ = self.values.pop().unwrap();
+            }
+            self.values.pop().unwrap()
+        } else {
+            default
+        };
+        let v = self.values.pop().unwrap();
+        let v = f(v, arg)?;
         self.values.push(v);
         Ok(())
     }
 
+    fn digitsum(&mut self, args: usize) -> CalcErrorResult {
+        self.optional_arg_func("digitsum", args, Value::Int(BigInt::from(10)), Value::digitsum)
+    }
+
+    fn numdigits(&mut self, args: usize) -> CalcErrorResult {
+        self.optional_arg_func("numdigits", args, Value::Int(BigInt::from(10)), Value::numdigits)
+    }
+
+    fn revdigits(&mut self, args: usize) -> CalcErrorResult {
+        self.optional_arg_func("revdigits", args, Value::Int(BigInt::from(10)), Value::revdigits)
+    }
+
+    fn spell(&mut self, args: usize) -> CalcErrorResult {
+        self.optional_arg_func("spell", args, Value::Str("en".to_string()), Value::spell)
+    }
+
+    #[cfg(feature = "units")]
+    fn hsize(&mut self, args: usize) -> CalcErrorResult {
+        self.optional_arg_func("hsize", args, Value::Int(BigInt::from(1024)), Value::hsize)
+    }
+
     fn min(&mut self, args: usize) -> CalcErrorResult {
-        self.min_max(args, "min", |v1, v2| {
+        self.reduce_args("min", args, 1, |v1, v2| {
             let r = if let Ok(v) = v1.clone().less(v2.clone()) { v } else { Value::Int(BigInt::zero()) };
             if r.is_zero() {
-                v2
+                Ok(v2)
             } else {
-                v1
+                Ok(v1)
             }
         })
     }
 
     fn max(&mut self, args: usize) -> CalcErrorResult {
-        self.min_max(args, "max", |v1, v2| {
+        self.reduce_args("max", args, 1, |v1, v2| {
             let r = if let Ok(v) = v1.clone().greater(v2.clone()) { v } else { Value::Int(BigInt::zero()) };
             if r.is_zero() {
-                v2
+                Ok(v2)
             } else {
-                v1
+                Ok(v1)
             }
         })
     }
 
     fn avg(&mut self, args: usize) -> CalcErrorResult {
-        if args < 2 || self.values.len() < 2 {
-            return Ok(());
+        let vals = self.variadic_args("avg", args)?;
+        let cnt = vals.len();
+        if cnt == 0 {
+            return Err(CalcError::FunctionNoArgs("avg".to_string()));
         }
-
-        let mut v = self.values.pop().unwrap();
-        for _i in 1..args {
-            let a = self.values.pop().unwrap();
+        let mut v = Value::Int(BigInt::zero());
+        for a in vals.into_iter().rev() {
             v = v.addition(a)?;
         }
-        let av = Value::Int(BigInt::from(args));
-        v = v.divide(av)?;
+        v = self.div_values(v, Value::Int(BigInt::from(cnt)))?;
         self.values.push(v);
         Ok(())
     }
@@ -801,7 +2326,141 @@ impl Stack {
         Ok(())
     }
 
+    // formats a value with a format specification(`format`) or a template
+    // (`fmt`), numbers are rounded using the rounding mode of the calculator
+    fn format(&mut self, fname: &str, args: usize) -> CalcErrorResult {
+        if args < 2 || self.values.len() < 2 {
+            return Err(CalcError::FunctionNotEnoughArgs(fname.to_string(), 2));
+        }
+        for _i in 0..args - 2 {
+            let _ = self.values.pop().unwrap();
+        }
+
+        let spec = self.values.pop().unwrap();
+        let v = self.values.pop().unwrap();
+        let s = match &spec {
+            Value::Str(s) if fname == "fmt" => format::format_template(&v, s, self.calc.rounding)?,
+            Value::Str(s) => format::format_value(&v, &format::parse_spec(s)?, self.calc.rounding)?,
+            _ => return Err(CalcError::InvalidAgrument(fname.to_string(), spec.to_string())),
+        };
+        self.values.push(Value::Str(s));
+        Ok(())
+    }
+
+    // the current time as Unix timestamp, the alternative result is the
+    // same time as UTC date
+    #[cfg(feature = "datetime")]
+    fn now(&mut self, args: usize) -> CalcErrorResult {
+        if self.values.len() < args {
+            return Err(CalcError::FunctionUnfinished("now".to_string()));
+        }
+        for _i in 0..args {
+            let _ = self.values.pop().unwrap();
+        }
+
+        let v = Value::Int(BigInt::from(datetime::now()));
+        if let Value::Str(s) = v.clone().fromunix()? {
+            self.has_alt = true;
+            self.alt_result = s;
+        }
+        self.values.push(v);
+        Ok(())
+    }
+
+    #[cfg(feature = "units")]
+    // sets the alternative result to the IPv4 address on the top of the stack
+    // in dotted-quad form
+    fn ipv4_alt(&mut self) -> CalcErrorResult {
+        if let Some(Value::Str(s)) = self.values.last().map(|v| v.clone().ipstr()).transpose()? {
+            self.has_alt = true;
+            self.alt_result = s;
+        }
+        Ok(())
+    }
+
+    // checksums and hashes: the result is an integer, and the alternative
+    // result is the same number in hexadecimal form with all its digits
+    fn hash(&mut self, fname: &str, args: usize) -> CalcErrorResult {
+        if args == 0 || self.values.is_empty() {
+            return Err(CalcError::FunctionNoArgs(fname.to_string()));
+        }
+        for _i in 0..args - 1 {
+            let _ = self.values.pop().unwrap();
+        }
+
+        let v = self.values.pop().unwrap();
+        let (v, digits) = match fname {
+            "fnv1a" => (v.fnv1a()?, 16),
+            "murmur3" => (v.murmur3()?, 8),
+            #[cfg(feature = "sha256")]
+            "sha256" => (v.sha256()?, 64),
+            _ => (v.crc32()?, 8),
+        };
+        self.has_alt = true;
+        self.alt_result = format!("0x{:0>width$}", v.clone().into_raw_big_int()?.to_str_radix(16), width = digits);
+        self.values.push(v);
+        Ok(())
+    }
+
+    // the result is a list, and the alternative result is the angle in
+    // the form `12°30'45"`
+    fn dms(&mut self, args: usize) -> CalcErrorResult {
+        if args == 0 || self.values.is_empty() {
+            return Err(CalcError::FunctionNoArgs("dms".to_string()));
+        }
+        for _i in 0..args - 1 {
+            let _ = self.values.pop().unwrap();
+        }
+
+        let v = self.values.pop().unwrap();
+        let v = v.dms()?;
+        if let Value::List(parts) = &v {
+            let neg = parts.iter().any(|p| !p.is_positive());
+            let parts = parts.iter().map(|p| p.clone().abs()).collect::<Result<Vec<Value>, CalcError>>()?;
+            self.has_alt = true;
+            self.alt_result = format!("{}{}°{}'{}\"", if neg { "-" } else { "" }, parts[0], parts[1], parts[2]);
+        }
+        self.values.push(v);
+        Ok(())
+    }
+
+    // minutes and seconds are optional
+    fn fromdms(&mut self, args: usize) -> CalcErrorResult {
+        if args == 0 {
+            return Err(CalcError::FunctionNoArgs("fromdms".to_string()));
+        }
+        if self.values.len() < args {
+            return Err(CalcError::FunctionUnfinished("fromdms".to_string()));
+        }
+
+        for _i in 3..args {
+            let _ = self.values.pop().unwrap();
+        }
+        let mut parts = vec![Value::Int(BigInt::zero()); 3];
+        for i in (0..args.min(3)).rev() {
+            parts[i] = self.values.pop().unwrap();
+        }
+        let sec = parts.pop().unwrap();
+        let min = parts.pop().unwrap();
+        let deg = parts.pop().unwrap();
+        let v = deg.fromdms(min, sec)?;
+        self.values.push(v);
+        Ok(())
+    }
+
+    // a string is converted to the hexadecimal form of its bytes, an integer
+    // is kept, and the alternative result shows it in hexadecimal form
     fn hex(&mut self, args: usize) -> CalcErrorResult {
+        if args != 0 && self.values.len() >= args {
+            if let Value::Str(..) = self.values[self.values.len() - args] {
+                for _i in 0..args - 1 {
+                    let _ = self.values.pop().unwrap();
+                }
+                let v = self.values.pop().unwrap().hexstr()?;
+                self.values.push(v);
+                return Ok(());
+            }
+        }
         self.int_to_base(args, 16u32, "0x")
     }
 
@@ -894,11 +2553,31 @@ impl Stack {
         Ok(())
     }
 
+    // `solve` takes either coefficients of an equation, or an equation as
+    // a string and a variable name
+    fn solve_equation(&mut self, fname: &str, args: usize, lookup: &mut Lookup) -> CalcErrorResult {
+        if self.solve_takes_formula(args) {
+            self.process_formula_function(fname, args, lookup)
+        } else {
+            self.solve(args)
+        }
+    }
+
     fn solve(&mut self, args: usize) -> CalcErrorResult {
         if args < 2 || self.values.is_empty() {
             return Err(CalcError::FunctionNotEnoughArgs("solve".to_string(), 2));
         }
 
+        // system of linear equations: a matrix and a list of right sides
+        if args == 2 && self.values.len() >= 2 {
+            if let Value::List(..) = self.values[self.values.len() - 2] {
+                let b = self.values.pop().unwrap();
+                let a = self.values.pop().unwrap();
+                self.values.push(a.linsolve(b)?);
+                return Ok(());
+            }
+        }
+
         // linear
         if args == 2 {
             let c = self.values.pop().unwrap();
@@ -934,6 +2613,22 @@ impl Stack {
 mod tests {
     use super::*;
     use num_bigint::BigInt;
+    #[test]
+    fn test_registry() {
+        for (idx, f) in FUNCS.iter().enumerate() {
+            assert!(FUNCS[..idx].iter().all(|other| other.name != f.name), "duplicate {}", f.name);
+            assert!(f.max_args.is_none_or(|max| f.min_args <= max && f.rpn_args <= max), "{}", f.name);
+            assert!(f.rpn_args >= f.min_args && !f.help.is_empty(), "{}", f.name);
+            assert_eq!(find_func(f.name).map(|found| found.name), Some(f.name));
+        }
+        assert!(find_func("nosuchfunc").is_none());
+        for (alias, func) in ALIASES.iter() {
+            assert!(find_func(alias).is_none() && find_func(func).is_some(), "{}", alias);
+        }
+        assert_eq!(rpn_func_args("gcd"), 2);
+        assert_eq!(rpn_func_args("sin"), 1);
+    }
+
     #[test]
     fn test_simple_order() {
         let mut stack = Stack::new();
@@ -0,0 +1,213 @@
+//! Statements of a session.
+//!
+//! A statement is either an expression or an assignment of its result to
+//! session variables: `x = 2*pi`. Functions that return several values
+//! return a list, and the list can be unpacked into several variables at
+//! once: `(q; r) = divmod(17; 5)` sets `q` to `3` and `r` to `2`.
+//!
+//! Compound assignments `+=`, `-=`, `*=`, and `/=` update an existing
+//! variable: `total *= 1.25` is `total = total * (1.25)`. A tally is
+//! counted with `n++` and `n--`, or `inc(n)` and `dec(n)`. The functions take
+//! an optional step: `inc(n; 5)` is `n += 5`.
+
+use crate::calc::{ArgSeparator, Calc};
+use crate::errors::*;
+use crate::parse::CalcState;
+use crate::value::*;
+
+pub(crate) enum Statement<'a> {
+    Expr(&'a str),
+    // names of the variables, and the expression which result they get
+    Assign(Vec<String>, &'a str),
+    // a variable, an operator, and its second operand
+    Update(String, char, &'a str),
+}
+
+// operators of compound assignments
+const UPDATE_OPS: [char; 4] = ['+', '-', '*', '/'];
+
+// returns the position of `=` that is neither a part of a comparison
+// operator nor inside a string literal
+fn assign_pos(stmt: &str) -> Option<usize> {
+    let bytes = stmt.as_bytes();
+    let mut in_str = false;
+    for (idx, &b) in bytes.iter().enumerate() {
+        match b {
+            b'"' => in_str = !in_str,
+            b'=' if !in_str => {
+                let prev = if idx == 0 { 0 } else { bytes[idx - 1] };
+                let next = bytes.get(idx + 1).copied().unwrap_or(0);
+                if !b"=!<>".contains(&prev) && next != b'=' {
+                    return Some(idx);
+                }
+            }
+            _ => {}
+        }
+    }
+    None
+}
+
+// `x` is a single variable, `(a; b)` or `[a; b]` unpacks a list
+fn target_names(target: &str, calc: &Calc) -> Vec<String> {
+    let target = target.trim();
+    let inner = target
+        .strip_prefix('(')
+        .and_then(|s| s.strip_suffix(')'))
+        .or_else(|| target.strip_prefix('[').and_then(|s| s.strip_suffix(']')));
+    match inner {
+        None => vec![target.to_string()],
+        Some(s) => {
+            let comma = calc.arg_sep == ArgSeparator::Comma;
+            s.split(|c: char| c == ';' || (comma && c == ',')).map(|name| name.trim().to_string()).collect()
+        }
+    }
+}
+
+// `x++`, `x--`, `inc(x)`, `dec(x)`, and `inc(x; step)`: returns the name of
+// the variable, the operator, and the step
+fn increment<'a>(stmt: &'a str, calc: &Calc) -> Option<(&'a str, char, &'a str)> {
+    let stmt = stmt.trim();
+    if let Some(name) = stmt.strip_suffix("++") {
+        return Some((name, '+', "1"));
+    }
+    if let Some(name) = stmt.strip_suffix("--") {
+        return Some((name, '-', "1"));
+    }
+    let op = match stmt.get(..4) {
+        Some(f) if f.eq_ignore_ascii_case("inc(") => '+',
+        Some(f) if f.eq_ignore_ascii_case("dec(") => '-',
+        _ => return None,
+    };
+    let args = stmt[4..].strip_suffix(')')?;
+    let comma = calc.arg_sep == ArgSeparator::Comma;
+    match args.split_once(|c: char| c == ';' || (comma && c == ',')) {
+        Some((name, step)) => Some((name, op, step)),
+        None => Some((args, op, "1")),
+    }
+}
+
+fn validate_name(name: &str, state: &CalcState) -> Result<(), CalcError> {
+    state.variable_name_validate(name).map_err(|e| CalcError::InvalidVariable(name.to_string(), e.to_string()))
+}
+
+// only an existing variable can be updated
+fn update<'a>(name: &str, op: char, expr: &'a str, state: &CalcState) -> Result<Statement<'a>, CalcError> {
+    let name = name.trim();
+    validate_name(name, state)?;
+    if state.variable(name).is_none() {
+        return Err(CalcError::VarUndeclared(name.to_lowercase()));
+    }
+    Ok(Statement::Update(name.to_string(), op, expr))
+}
+
+/// Splits a statement into the variable names and the expression. All
+/// names are validated before the expression is evaluated, so a failed
+/// assignment never changes the session
+pub(crate) fn parse_statement<'a>(stmt: &'a str, calc: &Calc, state: &CalcState) -> Result<Statement<'a>, CalcError> {
+    let Some(pos) = assign_pos(stmt) else {
+        return match increment(stmt, calc) {
+            Some((name, op, step)) => update(name, op, step, state),
+            None => Ok(Statement::Expr(stmt)),
+        };
+    };
+    let target = stmt[..pos].trim_end();
+    let op = target.chars().last().filter(|c| UPDATE_OPS.contains(c));
+    let target = if op.is_some() { &target[..target.len() - 1] } else { target };
+    let names = target_names(target, calc);
+    let expr = &stmt[pos + 1..];
+    match (op, names.as_slice()) {
+        (None, _) => {
+            for name in &names {
+                validate_name(name, state)?;
+            }
+            Ok(Statement::Assign(names, expr))
+        }
+        (Some(op), [name]) => update(name, op, expr, state),
+        (Some(op), _) => Err(CalcError::InvalidOp(format!("{}=", op))),
+    }
+}
+
+/// Stores the value in the session variables. Several variables take the
+/// items of a list of the same length
+pub(crate) fn assign(names: &[String], v: Value, state: &mut CalcState) -> Result<(), CalcError> {
+    if let [name] = names {
+        state.add_variable(name, v);
+        return Ok(());
+    }
+    let items = match v {
+        Value::List(items) if items.len() == names.len() => items,
+        Value::List(items) => return Err(CalcError::ValueCountMismatch(names.len(), items.len())),
+        _ => return Err(CalcError::ValueCountMismatch(names.len(), 1)),
+    };
+    for (name, item) in names.iter().zip(items) {
+        state.add_variable(name, item);
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::calc::CalcBuilder;
+
+    #[test]
+    fn test_assign() {
+        let calc = Calc::new();
+        let mut state = CalcState::new();
+        assert_eq!(calc.exec("x = 2 + 3", &mut state), Ok(Value::from(5)));
+        assert_eq!(state.variable("x"), Some(Value::from(5)));
+        assert_eq!(calc.exec("x == 5", &mut state), Ok(Value::from(1)));
+        assert_eq!(calc.exec("y = x >= 5", &mut state), Ok(Value::from(1)));
+        assert_eq!(calc.exec("s = \"a = b\"", &mut state), Ok(Value::Str("a = b".to_string())));
+        assert_eq!(calc.exec("(q; r) = divmod(17; 5)", &mut state).unwrap().to_string(), "[3; 2]");
+        assert_eq!(state.variable("q"), Some(Value::from(3)));
+        assert_eq!(state.variable("r"), Some(Value::from(2)));
+        assert_eq!(calc.exec("[m; ex] = frexp(8)", &mut state).unwrap().to_string(), "[0.5; 4]");
+        assert_eq!(state.variable("ex"), Some(Value::from(4)));
+        assert_eq!(calc.exec("(rho; theta) = polar(0; 2)", &mut state).unwrap().to_string(), "[2; 1.5707963267948966]");
+        assert_eq!(calc.exec("q*5 + r", &mut state), Ok(Value::from(17)));
+
+        assert_eq!(calc.exec("(a; b; c) = divmod(17; 5)", &mut state), Err(CalcError::ValueCountMismatch(3, 2)));
+        assert_eq!(calc.exec("(a; b) = 5", &mut state), Err(CalcError::ValueCountMismatch(2, 1)));
+        assert_eq!(state.variable("a"), None);
+        assert_eq!(
+            calc.exec("(a; sin) = divmod(17; 5)", &mut state),
+            Err(CalcError::InvalidVariable(
+                "sin".to_string(),
+                "Function name cannot be used as a variable".to_string()
+            ))
+        );
+        assert!(calc.exec("pi = 3", &mut state).is_err());
+        assert!(calc.exec("x = 1 +", &mut state).is_err());
+        assert_eq!(state.variable("x"), Some(Value::from(5)));
+
+        assert_eq!(calc.exec("x += 5", &mut state), Ok(Value::from(10)));
+        assert_eq!(calc.exec("x -= 1 + 2", &mut state), Ok(Value::from(7)));
+        assert_eq!(calc.exec("x *= 2", &mut state), Ok(Value::from(14)));
+        assert_eq!(calc.exec("x/=4", &mut state).unwrap().to_string(), "3.5");
+        assert_eq!(state.variable("x").unwrap().to_string(), "3.5");
+        assert_eq!(calc.exec("s += \"c\"", &mut state), Ok(Value::Str("a = bc".to_string())));
+        assert_eq!(calc.exec("total *= 1.25", &mut state), Err(CalcError::VarUndeclared("total".to_string())));
+        assert_eq!(calc.exec("(q; r) += 1", &mut state), Err(CalcError::InvalidOp("+=".to_string())));
+        assert_eq!(calc.exec("x == 3.5", &mut state), Ok(Value::from(1)));
+        assert!(calc.exec("pi += 1", &mut state).is_err());
+
+        assert_eq!(calc.exec("n = 0", &mut state), Ok(Value::from(0)));
+        assert_eq!(calc.exec("n++", &mut state), Ok(Value::from(1)));
+        assert_eq!(calc.exec(" n ++ ", &mut state), Ok(Value::from(2)));
+        assert_eq!(calc.exec("inc(n)", &mut state), Ok(Value::from(3)));
+        assert_eq!(calc.exec("INC(n; 2*5)", &mut state), Ok(Value::from(13)));
+        assert_eq!(calc.exec("dec(n; 3)", &mut state), Ok(Value::from(10)));
+        assert_eq!(calc.exec("n--", &mut state), Ok(Value::from(9)));
+        assert_eq!(calc.exec("dec(n)", &mut state), Ok(Value::from(8)));
+        assert_eq!(state.variable("n"), Some(Value::from(8)));
+        assert_eq!(calc.exec("k++", &mut state), Err(CalcError::VarUndeclared("k".to_string())));
+        assert!(calc.exec("2++", &mut state).is_err());
+        assert_eq!(calc.exec("n - -1", &mut state), Ok(Value::from(9)));
+
+        let calc = CalcBuilder::new().arg_separator(ArgSeparator::Comma).build();
+        assert_eq!(calc.exec("(q, r) = divmod(23, 5)", &mut state).unwrap().to_string(), "[4; 3]");
+        assert_eq!(calc.exec("inc(q, 0.5)", &mut state).unwrap().to_string(), "4.5");
+        assert_eq!(state.variable("r"), Some(Value::from(3)));
+    }
+}
@@ -1,18 +1,99 @@
+#[cfg(feature = "datetime")]
+use chrono::{DateTime, Datelike, FixedOffset};
 use dtoa;
 use num_bigint::{BigInt, Sign};
 use num_complex::Complex;
 use num_rational::BigRational;
-use num_traits::{FromPrimitive, Num, One, ToPrimitive, Zero};
-use std::f64::{consts, EPSILON};
+use num_traits::{FromPrimitive, Num, One, Pow, Signed, ToPrimitive, Zero};
+use std::cmp::Ordering;
+use std::convert::TryFrom;
+use std::f64::consts;
 use std::fmt;
+#[cfg(feature = "units")]
+use std::net::Ipv4Addr;
+use std::ops::{Add, Div, Mul, Neg, Sub};
 use std::str;
 
+#[cfg(feature = "datetime")]
+use crate::datetime;
+use crate::encoding;
 use crate::errors::*;
+use crate::fmath;
+use crate::format;
+use crate::list;
+use crate::matrix;
+use crate::poly;
+use crate::special;
 
 /// Expression calculation result: either value or error
 pub type CalcResult = Result<Value, CalcError>;
 pub(crate) type CalcErrorResult = Result<(), CalcError>;
 
+/// How a number is rounded to an integer
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum Rounding {
+    /// Toward zero: `-7 // 2` = `-3`
+    #[default]
+    Trunc,
+    /// Toward negative infinity: `-7 // 2` = `-4`
+    Floor,
+    /// Toward positive infinity: `7 // 2` = `4`
+    Ceil,
+    /// To the nearest integer, ties go to the even one(banker's rounding): `2.5` -> `2`
+    HalfEven,
+    /// To the nearest integer, ties go toward positive infinity: `-2.5` -> `-2`
+    HalfUp,
+    /// To the nearest integer, ties go away from zero: `-2.5` -> `-3`
+    HalfAwayFromZero,
+}
+
+/// How `Value::to_string_with` prints complex numbers
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum ComplexStyle {
+    /// Real and imaginary parts joined with a sign: `1.5-2i`
+    #[default]
+    Algebraic,
+    /// A pair of parts in brackets: `(1.5, -2)`
+    Pair,
+}
+
+/// Options of `Value::to_string_with`. The default options print a value
+/// exactly as `Display` does
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct FormatOptions {
+    /// Number of digits after the decimal point for float numbers and parts
+    /// of complex numbers. `None` prints the shortest text that is parsed
+    /// back to the same number
+    pub precision: Option<usize>,
+    /// Rounding mode used when `precision` is set. Default is
+    /// `Rounding::HalfEven`
+    pub rounding: Rounding,
+    /// Removes trailing zeros of the fractional part: `2.50` -> `2.5`,
+    /// `3.0` -> `3`
+    pub trim_zeros: bool,
+    /// Format of complex numbers
+    pub complex: ComplexStyle,
+    /// Separator inserted between groups of three digits of integer numbers
+    /// and integer parts of float ones: `1_000_000`
+    pub group_sep: Option<char>,
+    /// Base of integer numbers: `16`, `8`, and `2` print them with prefixes
+    /// `0x`, `0o`, and `0b`. Any other value means decimal numbers
+    pub radix: u32,
+}
+
+impl Default for FormatOptions {
+    fn default() -> FormatOptions {
+        FormatOptions {
+            precision: None,
+            rounding: Rounding::HalfEven,
+            trim_zeros: false,
+            complex: ComplexStyle::Algebraic,
+            group_sep: None,
+            radix: 10,
+        }
+    }
+}
+
 /// Supported value types (Float is used for angles in degrees)
 #[derive(Clone)]
 pub enum Value {
@@ -24,10 +105,25 @@ pub enum Value {
     Ratio(BigRational),
     /// Complex number
     Complex(Complex<f64>),
+    /// List of values
+    List(Vec<Value>),
+    /// Text, e.g. a Roman numeral. Strings are not numbers, and arithmetic
+    /// does not work with them, except `+` that concatenates strings
+    Str(String),
+    /// Polynomial: coefficients starting from the highest degree. See module
+    /// `poly` for details
+    Poly(Vec<Value>),
+    /// Result of a comparison or a logical operator if the calculator
+    /// returns booleans(see `Truth`). In arithmetic it is `1` or `0`
+    Bool(bool),
 }
 
 const F64_BUF_LEN: usize = 48;
 fn format_f64(g: f64) -> String {
+    // dtoa prints NaN and infinities as huge numbers
+    if !g.is_finite() {
+        return format!("{}", g);
+    }
     let mut buf = [b'\0'; F64_BUF_LEN];
     match dtoa::write(&mut buf[..], g) {
         Ok(len) => match str::from_utf8(&buf[..len]) {
@@ -39,7 +135,7 @@ fn format_f64(g: f64) -> String {
 }
 
 pub(crate) fn f64_equal(f1: f64, f2: f64) -> bool {
-    (f1 - f2).abs() <= EPSILON
+    (f1 - f2).abs() <= f64::EPSILON
 }
 
 impl fmt::Display for Value {
@@ -66,7 +162,139 @@ impl fmt::Display for Value {
                     write!(f, "{}{}i", format_f64(c.re), format_f64(c.im))
                 }
             }
+            Value::List(ref l) => {
+                let items: Vec<String> = l.iter().map(|v| v.to_string()).collect();
+                write!(f, "[{}]", items.join("; "))
+            }
+            Value::Str(ref s) => write!(f, "{}", s),
+            Value::Bool(b) => write!(f, "{}", b),
+            Value::Poly(ref c) => write!(f, "{}", poly::to_string(c)),
+        }
+    }
+}
+
+// removes trailing zeros of the fractional part and the decimal point if
+// the fractional part becomes empty. Numbers in exponential form are kept
+fn trim_fraction_zeros(s: String) -> String {
+    if !s.contains('.') || s.contains(['e', 'E']) {
+        return s;
+    }
+    s.trim_end_matches('0').trim_end_matches('.').to_string()
+}
+
+// groups digits of the integer part of a number; infinities, NaN and
+// numbers in exponential form are kept as is
+fn group_number(s: String, sep: char) -> String {
+    let (sign, digits) = match s.strip_prefix('-') {
+        Some(d) => ("-", d),
+        None => ("", &s[..]),
+    };
+    if !digits.starts_with(|c: char| c.is_ascii_digit()) || digits.contains(['e', 'E']) {
+        return s;
+    }
+    format!("{}{}", sign, format::group_digits(digits, sep, 3))
+}
+
+// formats one float number(or a part of a complex one) with the options
+fn f64_with(f: f64, opts: &FormatOptions) -> String {
+    let s = match opts.precision {
+        Some(p) if f.is_finite() => match f64_to_decimal_ratio(f) {
+            Ok(r) => ratio_to_decimal(&r, p, opts.rounding),
+            Err(..) => format_f64(f),
+        },
+        _ => format_f64(f),
+    };
+    let s = if opts.trim_zeros { trim_fraction_zeros(s) } else { s };
+    match opts.group_sep {
+        Some(sep) => group_number(s, sep),
+        None => s,
+    }
+}
+
+// characters of the text of a polynomial with number coefficients
+const POLY_CHARS: &str = "0123456789x*+-\\.eE";
+
+// splits the items of a list text(without brackets) separated with `;`
+// at the top level
+fn split_list_items(s: &str) -> Vec<&str> {
+    let mut items = Vec::new();
+    let mut depth = 0;
+    let mut start = 0;
+    for (i, c) in s.char_indices() {
+        match c {
+            '[' => depth += 1,
+            ']' => depth -= 1,
+            ';' if depth == 0 => {
+                items.push(s[start..i].trim());
+                start = i + 1;
+            }
+            _ => {}
+        }
+    }
+    items.push(s[start..].trim());
+    items
+}
+
+/// Reads a value from the text produced by `Display`, so a value printed
+/// by the calculator can be read back: `"[1\\2; 1.5; 2+3i]".parse::<Value>()`.
+///
+/// For every value `v`, `v.to_string().parse::<Value>() == Ok(v)` except:
+/// * NaN, because it is not equal to itself
+/// * strings that look like other values, e.g. `"12"` is read as an integer
+///   and `"true"` as a boolean, and strings inside lists that contain `;` or brackets
+/// * polynomials of zero degree are read as numbers
+///
+/// Any text that is not a number, a list, or a polynomial is read as a
+/// string, so parsing never fails. Arithmetic expressions are not
+/// evaluated: `"1+2"` is a string
+impl str::FromStr for Value {
+    type Err = CalcError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let t = s.trim();
+        match t {
+            "NaN" => return Ok(Value::Float(f64::NAN)),
+            "inf" => return Ok(Value::Float(f64::INFINITY)),
+            "-inf" => return Ok(Value::Float(f64::NEG_INFINITY)),
+            "true" => return Ok(Value::Bool(true)),
+            "false" => return Ok(Value::Bool(false)),
+            _ => {}
+        }
+        let digits = t.strip_prefix('-').unwrap_or(t);
+        if !digits.is_empty() && digits.chars().all(|c| c.is_ascii_digit()) {
+            return Value::from_str_integer(t);
+        }
+        if digits.starts_with(|c: char| c.is_ascii_digit()) {
+            if digits.chars().all(|c| c.is_ascii_digit() || "+-.eE".contains(c)) {
+                if let Ok(f) = t.parse::<f64>() {
+                    return Ok(Value::Float(f));
+                }
+            }
+            if digits.chars().all(|c| c.is_ascii_digit() || c == '\\') && digits.matches('\\').count() <= 2 {
+                // the sign applies to the whole mixed number: `-3\\1\\2` is `-7/2`
+                if let Ok(v) = Value::from_str_ratio(digits) {
+                    return if t.starts_with('-') { v.negate() } else { Ok(v) };
+                }
+            }
+            if t.ends_with('i') && t.chars().all(|c| c.is_ascii_digit() || "+-.eEi".contains(c)) {
+                if let Ok(v) = Value::from_str_complex(t) {
+                    return Ok(v);
+                }
+            }
+        }
+        if let Some(inner) = t.strip_prefix('[').and_then(|t| t.strip_suffix(']')) {
+            if inner.trim().is_empty() {
+                return Ok(Value::List(Vec::new()));
+            }
+            let items = split_list_items(inner);
+            return Ok(Value::List(items.into_iter().map(|item| item.parse()).collect::<Result<Vec<Value>, _>>()?));
+        }
+        if t.contains('x') && t.chars().all(|c| POLY_CHARS.contains(c)) {
+            if let Some(c) = poly::from_str(t) {
+                return Ok(Value::Poly(c));
+            }
         }
+        Ok(Value::Str(s.to_string()))
     }
 }
 
@@ -77,6 +305,10 @@ impl fmt::Debug for Value {
             Value::Float(ref g) => write!(f, " Float({:?})", g),
             Value::Ratio(ref r) => write!(f, " Ratio({:?})", r),
             Value::Complex(ref c) => write!(f, " Complex({:?})", c),
+            Value::List(ref l) => write!(f, " List({:?})", l),
+            Value::Str(ref s) => write!(f, " Str({:?})", s),
+            Value::Bool(b) => write!(f, " Bool({:?})", b),
+            Value::Poly(ref c) => write!(f, " Poly({:?})", c),
         }
     }
 }
@@ -88,6 +320,10 @@ impl PartialEq for Value {
             (Value::Float(ref f1), Value::Float(ref f2)) => f1 == f2,
             (Value::Ratio(ref r1), Value::Ratio(ref r2)) => r1 == r2,
             (Value::Complex(ref c1), Value::Complex(ref c2)) => c1 == c2,
+            (Value::List(ref l1), Value::List(ref l2)) => l1 == l2,
+            (Value::Str(ref s1), Value::Str(ref s2)) => s1 == s2,
+            (Value::Poly(ref c1), Value::Poly(ref c2)) => c1 == c2,
+            (Value::Bool(b1), Value::Bool(b2)) => b1 == b2,
             (_, _) => false,
         }
     }
@@ -142,6 +378,611 @@ fn ratio_to_f64(r: &BigRational) -> Result<f64, CalcError> {
     Ok(i + n / d)
 }
 
+fn round_f64(f: f64, mode: Rounding) -> f64 {
+    match mode {
+        Rounding::Trunc => f.trunc(),
+        Rounding::Floor => f.floor(),
+        Rounding::Ceil => f.ceil(),
+        Rounding::HalfEven => f.round_ties_even(),
+        Rounding::HalfUp => {
+            let fl = f.floor();
+            if f - fl >= 0.5 {
+                fl + 1.0
+            } else {
+                fl
+            }
+        }
+        Rounding::HalfAwayFromZero => f.round(),
+    }
+}
+
+fn round_ratio(r: &BigRational, mode: Rounding) -> BigInt {
+    match mode {
+        Rounding::Trunc => r.trunc().to_integer(),
+        Rounding::Floor => r.floor().to_integer(),
+        Rounding::Ceil => r.ceil().to_integer(),
+        Rounding::HalfEven => {
+            let fl = r.floor().to_integer();
+            let diff = (r - BigRational::from_integer(fl.clone())) * BigInt::from(2);
+            match diff.cmp(&BigRational::one()) {
+                Ordering::Less => fl,
+                Ordering::Greater => fl + 1,
+                Ordering::Equal if (&fl % BigInt::from(2)).is_zero() => fl,
+                Ordering::Equal => fl + 1,
+            }
+        }
+        Rounding::HalfUp => (r + BigRational::new(BigInt::one(), BigInt::from(2))).floor().to_integer(),
+        Rounding::HalfAwayFromZero => r.round().to_integer(),
+    }
+}
+
+// converts a decimal number(`12.5`, `1.5e-3`) to an exact fraction
+fn decimal_str_to_ratio(s: &str) -> Option<BigRational> {
+    let (mantissa, exp) = match s.find(['e', 'E']) {
+        Some(pos) => (&s[..pos], s[pos + 1..].parse::<i32>().ok()?),
+        None => (s, 0),
+    };
+    let (int_part, frac_part) = match mantissa.find('.') {
+        Some(pos) => (&mantissa[..pos], &mantissa[pos + 1..]),
+        None => (mantissa, ""),
+    };
+    let numer = BigInt::from_str_radix(&format!("{}{}", int_part, frac_part), 10).ok()?;
+    let exp = exp.checked_sub(i32::try_from(frac_part.len()).ok()?)?;
+    // huge powers of ten take too much time and memory
+    if exp.unsigned_abs() > 1000 {
+        return None;
+    }
+    let pow = BigInt::from(10).pow(exp.unsigned_abs());
+    if exp >= 0 {
+        Some(BigRational::from_integer(numer * pow))
+    } else {
+        Some(BigRational::new(numer, pow))
+    }
+}
+
+// converts a float number to a fraction using its shortest decimal
+// representation, so `2.675` is exactly `2675/1000` and not the closest
+// binary number `2.67499999...`
+fn f64_to_decimal_ratio(f: f64) -> Result<BigRational, CalcError> {
+    if !f.is_finite() {
+        return Err(CalcError::FloatToRatio(f));
+    }
+    match decimal_str_to_ratio(&format_f64(f)) {
+        Some(r) => Ok(r),
+        None => f64_to_ratio(f),
+    }
+}
+
+// whether a float number is a multiple of `step` taking into account
+// the precision of float numbers
+fn near_multiple(f: f64, step: f64) -> bool {
+    let n = (f / step).round();
+    f64_equal(n * step, f)
+}
+
+// the closest fraction with the denominator not greater than `max_den`:
+// continued fraction convergents and the best semiconvergent
+fn best_fraction(r: &BigRational, max_den: &BigInt) -> BigRational {
+    if r.denom() <= max_den {
+        return r.clone();
+    }
+    let neg = r.is_negative();
+    let x = r.abs();
+    let (mut p, mut q) = (x.numer().clone(), x.denom().clone());
+    let (mut h0, mut h1) = (BigInt::zero(), BigInt::one());
+    let (mut k0, mut k1) = (BigInt::one(), BigInt::zero());
+    let res = loop {
+        let a = &p / &q;
+        let k2 = &a * &k1 + &k0;
+        if &k2 > max_den {
+            // the last convergent or the semiconvergent, whichever is closer
+            let t = (max_den - &k0) / &k1;
+            let semi = BigRational::new(&t * &h1 + &h0, &t * &k1 + &k0);
+            let conv = BigRational::new(h1, k1);
+            if (&semi - &x).abs() < (&conv - &x).abs() {
+                break semi;
+            }
+            break conv;
+        }
+        let h2 = &a * &h1 + &h0;
+        let rem = &p - &a * &q;
+        h0 = std::mem::replace(&mut h1, h2);
+        k0 = std::mem::replace(&mut k1, k2);
+        p = std::mem::replace(&mut q, rem);
+        if q.is_zero() {
+            break BigRational::new(h1, k1);
+        }
+    };
+    if neg {
+        -res
+    } else {
+        res
+    }
+}
+
+// the biggest integer argument of `harmonic`: the denominator of the result
+// grows as fast as `exp(n)`
+const HARMONIC_MAX: u64 = 10_000;
+
+// the sum `1/a + 1/(a+1) + ... + 1/(b-1)` as a numerator and a denominator.
+// Binary splitting keeps the intermediate numbers small
+fn harmonic_sum(a: u64, b: u64) -> (BigInt, BigInt) {
+    if b - a == 1 {
+        return (BigInt::one(), BigInt::from(a));
+    }
+    let m = (a + b) / 2;
+    let (p1, q1) = harmonic_sum(a, m);
+    let (p2, q2) = harmonic_sum(m, b);
+    (p1 * &q2 + p2 * &q1, q1 * q2)
+}
+
+// the biggest argument of combinatorial functions(Stirling and Bell
+// numbers): they are calculated in `O(n^2)` operations on big integers
+const COMBINATORICS_MAX: usize = 1_000;
+
+// the biggest argument of functions that generate huge integers in
+// a loop, e.g. primorial
+const INT_SEQ_MAX: usize = 100_000;
+
+// the maximum number of steps `collatz` makes before giving up
+const COLLATZ_MAX_STEPS: u64 = 1_000_000;
+
+// the maximum bit width of integers processed by bit functions, e.g. `signed`
+const MAX_BIT_WIDTH: usize = 65_536;
+
+// a bit width must be positive and not greater than `MAX_BIT_WIDTH`
+fn width_arg(v: &Value, name: &str) -> Result<usize, CalcError> {
+    match bounded_int_arg(v, name, MAX_BIT_WIDTH)? {
+        0 => Err(CalcError::ArgumentOutOfRange(name.to_string(), "0".to_string(), format!("[1..{}]", MAX_BIT_WIDTH))),
+        w => Ok(w),
+    }
+}
+
+// the lowest `width` bits of an integer. Negative integers are in
+// two's-complement form, so the result is never negative
+fn low_bits(i: &BigInt, width: usize) -> BigInt {
+    i & ((BigInt::one() << width) - 1u32)
+}
+
+// a range of bits from `hi` down to `lo`: both are bit positions and `hi`
+// is not less than `lo`
+fn bit_range_args(hi: &Value, lo: &Value, name: &str) -> Result<(usize, usize), CalcError> {
+    let hi = bounded_int_arg(hi, name, MAX_BIT_WIDTH - 1)?;
+    let lo = bounded_int_arg(lo, name, MAX_BIT_WIDTH - 1)?;
+    if hi < lo {
+        return Err(CalcError::InvalidAgrument(name.to_string(), format!("bit {} is lower than bit {}", hi, lo)));
+    }
+    Ok((hi, lo))
+}
+
+// a modulus must be a positive integer
+fn modulus_arg(v: &Value, name: &str) -> Result<BigInt, CalcError> {
+    match v {
+        Value::Int(m) if m.is_positive() => Ok(m.clone()),
+        Value::Int(m) => Err(CalcError::ArgumentOutOfRange(name.to_string(), m.to_string(), "[1..]".to_string())),
+        _ => Err(CalcError::OnlyInt(name.to_string())),
+    }
+}
+
+// argument of a function that works with non-negative integers: the
+// integer must not be greater than `max`
+pub(crate) fn bounded_int_arg(v: &Value, name: &str, max: usize) -> Result<usize, CalcError> {
+    match v {
+        Value::Int(i) => {
+            if i.is_negative() {
+                return Err(CalcError::NotForNegativeInt(name.to_string()));
+            }
+            match i.to_usize() {
+                Some(n) if n <= max => Ok(n),
+                _ => Err(CalcError::ArgumentOutOfRange(name.to_string(), i.to_string(), format!("[0..{}]", max))),
+            }
+        }
+        Value::Complex(..) => Err(CalcError::NotForComplex(name.to_string())),
+        Value::List(..) => Err(CalcError::NotForList(name.to_string())),
+        Value::Str(..) => Err(CalcError::NotForString(name.to_string())),
+        Value::Poly(..) => Err(CalcError::NotForPoly(name.to_string())),
+        _ => Err(CalcError::OnlyInt(name.to_string())),
+    }
+}
+
+// digits of an integer(its absolute value) in the given base, the least
+// significant digit first. Zero has one digit
+fn int_digits(n: &BigInt, base: &BigInt) -> Vec<BigInt> {
+    let mut n = n.abs();
+    let mut digits = Vec::new();
+    loop {
+        digits.push(&n % base);
+        n /= base;
+        if n.is_zero() {
+            return digits;
+        }
+    }
+}
+
+// the number and the base of digit functions: both are integers, and the
+// base must be greater than 1
+fn digits_args(n: Value, base: Value, name: &str) -> Result<(BigInt, BigInt), CalcError> {
+    match (n, base) {
+        (Value::Int(n), Value::Int(b)) => {
+            if b <= BigInt::one() {
+                return Err(CalcError::InvalidAgrument(name.to_string(), format!("base {}", b)));
+            }
+            Ok((n, b))
+        }
+        _ => Err(CalcError::OnlyInt(name.to_string())),
+    }
+}
+
+// Roman numerals with their values in descending order, including
+// subtractive pairs
+const ROMAN_DIGITS: [(&str, u32); 13] = [
+    ("M", 1000),
+    ("CM", 900),
+    ("D", 500),
+    ("CD", 400),
+    ("C", 100),
+    ("XC", 90),
+    ("L", 50),
+    ("XL", 40),
+    ("X", 10),
+    ("IX", 9),
+    ("V", 5),
+    ("IV", 4),
+    ("I", 1),
+];
+
+fn to_roman(mut n: u32) -> String {
+    let mut s = String::new();
+    for (r, v) in ROMAN_DIGITS.iter() {
+        while n >= *v {
+            s += r;
+            n -= v;
+        }
+    }
+    s
+}
+
+const SMALL_NUMBER_NAMES: [&str; 20] = [
+    "zero",
+    "one",
+    "two",
+    "three",
+    "four",
+    "five",
+    "six",
+    "seven",
+    "eight",
+    "nine",
+    "ten",
+    "eleven",
+    "twelve",
+    "thirteen",
+    "fourteen",
+    "fifteen",
+    "sixteen",
+    "seventeen",
+    "eighteen",
+    "nineteen",
+];
+const TENS_NAMES: [&str; 10] = ["", "", "twenty", "thirty", "forty", "fifty", "sixty", "seventy", "eighty", "ninety"];
+// short scale: every next name is a thousand times bigger
+const SCALE_NAMES: [&str; 12] = [
+    "",
+    "thousand",
+    "million",
+    "billion",
+    "trillion",
+    "quadrillion",
+    "quintillion",
+    "sextillion",
+    "septillion",
+    "octillion",
+    "nonillion",
+    "decillion",
+];
+
+// English words for a number from 1 to 999. British English puts `and`
+// before tens and units: `one hundred and five`
+fn spell_hundreds(n: u32, british: bool) -> String {
+    let mut words = Vec::new();
+    let (hundreds, rest) = (n / 100, n % 100);
+    if hundreds > 0 {
+        words.push(format!("{} hundred", SMALL_NUMBER_NAMES[hundreds as usize]));
+        if rest > 0 && british {
+            words.push("and".to_string());
+        }
+    }
+    if rest >= 20 {
+        let (tens, units) = (rest / 10, rest % 10);
+        if units > 0 {
+            words.push(format!("{}-{}", TENS_NAMES[tens as usize], SMALL_NUMBER_NAMES[units as usize]));
+        } else {
+            words.push(TENS_NAMES[tens as usize].to_string());
+        }
+    } else if rest > 0 {
+        words.push(SMALL_NUMBER_NAMES[rest as usize].to_string());
+    }
+    words.join(" ")
+}
+
+// the exact integer logarithm `floor(log_b(n))` of a positive integer
+fn int_log(n: &BigInt, base: &BigInt) -> u64 {
+    if *base == BigInt::from(2) {
+        return n.bits() as u64 - 1;
+    }
+    // the estimation from the number of bits is never too big, so only
+    // a few multiplications are needed to fix it
+    let base_bits = base.bits() as u64;
+    let mut k = (n.bits() as u64 - 1) / base_bits;
+    let mut p = base.pow(k as u32);
+    while &p * base <= *n {
+        p *= base;
+        k += 1;
+    }
+    k
+}
+
+// CRC-32(IEEE 802.3, the one used by zip and PNG) of a byte sequence
+fn crc32_bytes(bytes: &[u8]) -> u32 {
+    const POLY: u32 = 0xEDB8_8320;
+    let mut crc = !0u32;
+    for b in bytes {
+        crc ^= u32::from(*b);
+        for _ in 0..8 {
+            crc = if crc & 1 == 1 { (crc >> 1) ^ POLY } else { crc >> 1 };
+        }
+    }
+    !crc
+}
+
+// MurmurHash3 x86 32-bit with zero seed
+fn murmur3_bytes(bytes: &[u8]) -> u32 {
+    const C1: u32 = 0xCC9E_2D51;
+    const C2: u32 = 0x1B87_3593;
+    let mix = |k: u32| k.wrapping_mul(C1).rotate_left(15).wrapping_mul(C2);
+    let mut h = 0u32;
+    let mut chunks = bytes.chunks_exact(4);
+    for c in &mut chunks {
+        let k = u32::from_le_bytes([c[0], c[1], c[2], c[3]]);
+        h = (h ^ mix(k)).rotate_left(13).wrapping_mul(5).wrapping_add(0xE654_6B64);
+    }
+    let tail = chunks.remainder();
+    if !tail.is_empty() {
+        let k = tail.iter().rev().fold(0u32, |k, b| (k << 8) | u32::from(*b));
+        h ^= mix(k);
+    }
+    h ^= bytes.len() as u32;
+    h ^= h >> 16;
+    h = h.wrapping_mul(0x85EB_CA6B);
+    h ^= h >> 13;
+    h = h.wrapping_mul(0xC2B2_AE35);
+    h ^ (h >> 16)
+}
+
+// the bytes a hash, a checksum, or an encoding is calculated for: UTF-8
+// bytes of a string, or bytes of a non-negative integer in big-endian order
+fn hash_bytes(v: &Value, name: &str) -> Result<Vec<u8>, CalcError> {
+    match v {
+        Value::Str(s) => Ok(s.as_bytes().to_vec()),
+        Value::Int(i) if i.is_negative() => Err(CalcError::NotForNegativeInt(name.to_string())),
+        Value::Int(i) => Ok(i.to_bytes_be().1),
+        _ => Err(CalcError::OnlyInt(name.to_string())),
+    }
+}
+
+// Unix timestamp as seconds and nanoseconds
+#[cfg(feature = "datetime")]
+fn timestamp_arg(v: &Value, name: &str) -> Result<(i64, u32), CalcError> {
+    match v {
+        Value::Int(i) => match i.to_i64() {
+            Some(secs) => Ok((secs, 0)),
+            None => Err(CalcError::InvalidAgrument(name.to_string(), i.to_string())),
+        },
+        Value::List(..) => Err(CalcError::NotForList(name.to_string())),
+        _ => {
+            let f = v.as_f64()?;
+            let secs = f.floor();
+            if !secs.is_finite() || secs.abs() >= i64::MAX as f64 {
+                return Err(CalcError::InvalidAgrument(name.to_string(), v.to_string()));
+            }
+            // rounding can push fractional part up to a whole second
+            let nanos = (((f - secs) * 1e9).round() as u32).min(999_999_999);
+            Ok((secs as i64, nanos))
+        }
+    }
+}
+
+// a date: either a string or Unix timestamp
+#[cfg(feature = "datetime")]
+fn date_arg(v: &Value, name: &str) -> Result<DateTime<FixedOffset>, CalcError> {
+    let dt = match v {
+        Value::Str(s) => datetime::parse(s),
+        _ => {
+            let (secs, nanos) = timestamp_arg(v, name)?;
+            datetime::from_timestamp(secs, nanos)
+        }
+    };
+    match dt {
+        Some(dt) => Ok(dt),
+        None => Err(CalcError::InvalidAgrument(name.to_string(), v.to_string())),
+    }
+}
+
+// a real number
+fn real_arg(v: &Value, name: &str) -> Result<f64, CalcError> {
+    match v {
+        Value::Complex(..) => Err(CalcError::NotForComplex(name.to_string())),
+        Value::Str(..) => Err(CalcError::NotForString(name.to_string())),
+        Value::Poly(..) => Err(CalcError::NotForPoly(name.to_string())),
+        Value::List(..) => Err(CalcError::NotForList(name.to_string())),
+        _ => v.clone().into_raw_f64(),
+    }
+}
+
+// probability: a real number in the range `[0..1]`
+#[cfg(feature = "special")]
+fn prob_arg(v: &Value, name: &str) -> Result<f64, CalcError> {
+    let p = real_arg(v, name)?;
+    if !(0.0..=1.0).contains(&p) {
+        return Err(CalcError::ArgumentOutOfRange(name.to_string(), v.to_string(), "[0..1]".to_string()));
+    }
+    Ok(p)
+}
+
+// the number of events or trials: an integer, big ones are saturated
+#[cfg(feature = "special")]
+fn count_arg(v: &Value, name: &str) -> Result<i64, CalcError> {
+    match v {
+        Value::Int(i) => Ok(i.to_i64().unwrap_or(if i.is_negative() { i64::MIN } else { i64::MAX })),
+        Value::Complex(..) => Err(CalcError::NotForComplex(name.to_string())),
+        Value::List(..) => Err(CalcError::NotForList(name.to_string())),
+        Value::Str(..) => Err(CalcError::NotForString(name.to_string())),
+        Value::Poly(..) => Err(CalcError::NotForPoly(name.to_string())),
+        _ => Err(CalcError::OnlyInt(name.to_string())),
+    }
+}
+
+// the expected number of events of Poisson distribution
+#[cfg(feature = "special")]
+fn poisson_lambda(v: &Value, name: &str) -> Result<f64, CalcError> {
+    let l = real_arg(v, name)?;
+    if !l.is_finite() || l < 0.0 {
+        return Err(CalcError::InvalidAgrument(name.to_string(), format!("lambda {}", v)));
+    }
+    Ok(l)
+}
+
+// degrees of freedom of a distribution: a positive real number
+#[cfg(feature = "special")]
+fn dof_arg(v: &Value, name: &str) -> Result<f64, CalcError> {
+    let df = real_arg(v, name)?;
+    if !df.is_finite() || df <= 0.0 {
+        return Err(CalcError::InvalidAgrument(name.to_string(), format!("df {}", v)));
+    }
+    Ok(df)
+}
+
+// two lists of real numbers of the same length, at least two numbers each
+fn paired_lists(xs: &Value, ys: &Value, name: &str) -> Result<(Vec<f64>, Vec<f64>), CalcError> {
+    let (xs, ys) = match (xs, ys) {
+        (Value::List(xs), Value::List(ys)) => (xs, ys),
+        (Value::List(..), v) | (v, ..) => return Err(CalcError::InvalidAgrument(name.to_string(), v.to_string())),
+    };
+    if xs.len() != ys.len() {
+        return Err(CalcError::InvalidAgrument(
+            name.to_string(),
+            format!("lists of different lengths {} and {}", xs.len(), ys.len()),
+        ));
+    }
+    if xs.len() < 2 {
+        return Err(CalcError::InvalidAgrument(name.to_string(), "less than two points".to_string()));
+    }
+    let xs = xs.iter().map(|v| real_arg(v, name)).collect::<Result<Vec<f64>, CalcError>>()?;
+    let ys = ys.iter().map(|v| real_arg(v, name)).collect::<Result<Vec<f64>, CalcError>>()?;
+    Ok((xs, ys))
+}
+
+// sums of squared deviations from the mean: `(sxx, syy, sxy)`
+fn deviation_sums(xs: &[f64], ys: &[f64]) -> (f64, f64, f64) {
+    let n = xs.len() as f64;
+    let mx = xs.iter().sum::<f64>() / n;
+    let my = ys.iter().sum::<f64>() / n;
+    xs.iter().zip(ys.iter()).fold((0.0, 0.0, 0.0), |(sxx, syy, sxy), (x, y)| {
+        (sxx + (x - mx) * (x - mx), syy + (y - my) * (y - my), sxy + (x - mx) * (y - my))
+    })
+}
+
+// units for human-readable data sizes: binary(powers of 1024) and
+// decimal(powers of 1000)
+#[cfg(feature = "units")]
+const BINARY_SIZE_UNITS: [&str; 7] = ["B", "KiB", "MiB", "GiB", "TiB", "PiB", "EiB"];
+#[cfg(feature = "units")]
+const DECIMAL_SIZE_UNITS: [&str; 7] = ["B", "kB", "MB", "GB", "TB", "PB", "EB"];
+
+// converts a number of data units to bytes
+#[cfg(feature = "units")]
+fn data_size(v: Value, name: &str, unit: u32, power: u32) -> CalcResult {
+    match &v {
+        Value::Str(..) => Err(CalcError::NotForString(name.to_string())),
+        Value::Poly(..) => Err(CalcError::NotForPoly(name.to_string())),
+        Value::Complex(..) => Err(CalcError::NotForComplex(name.to_string())),
+        _ => v.multiply(Value::Int(BigInt::from(unit).pow(power))),
+    }
+}
+
+#[cfg(feature = "units")]
+macro_rules! data_unit {
+    ($id:ident, $unit:expr, $power:expr, $doc:expr) => {
+        #[doc = $doc]
+        pub fn $id(self) -> CalcResult {
+            data_size(self, stringify!($id), $unit, $power)
+        }
+    };
+}
+
+// IPv4 address: an integer in the range `[0..2^32-1]` or a string in
+// dotted-quad form
+#[cfg(feature = "units")]
+fn ipv4_arg(v: &Value, name: &str) -> Result<u32, CalcError> {
+    match v {
+        Value::Int(i) => match i.to_u32() {
+            Some(a) => Ok(a),
+            None => Err(CalcError::ArgumentOutOfRange(name.to_string(), i.to_string(), "[0..4294967295]".to_string())),
+        },
+        Value::Str(s) => match s.trim().parse::<Ipv4Addr>() {
+            Ok(a) => Ok(u32::from(a)),
+            Err(..) => Err(CalcError::InvalidAgrument(name.to_string(), s.to_string())),
+        },
+        _ => Err(CalcError::OnlyInt(name.to_string())),
+    }
+}
+
+// network prefix length(the number of bits in the network mask)
+#[cfg(feature = "units")]
+fn ipv4_prefix(v: &Value, name: &str) -> Result<u32, CalcError> {
+    match v {
+        Value::Int(i) => match i.to_u32() {
+            Some(p) if p <= 32 => Ok(p),
+            _ => Err(CalcError::ArgumentOutOfRange(name.to_string(), i.to_string(), "[0..32]".to_string())),
+        },
+        _ => Err(CalcError::OnlyInt(name.to_string())),
+    }
+}
+
+#[cfg(feature = "units")]
+fn ipv4_mask(prefix: u32) -> u32 {
+    u32::MAX.checked_shl(32 - prefix).unwrap_or(0)
+}
+
+// product of numbers with binary splitting: it is much faster than
+// multiplying one by one when the product is huge
+fn range_product(nums: &[u64]) -> BigInt {
+    match nums.len() {
+        0 => BigInt::one(),
+        1 => BigInt::from(nums[0]),
+        n => range_product(&nums[..n / 2]) * range_product(&nums[n / 2..]),
+    }
+}
+
+// formats a fraction as a decimal number with the fixed number of digits
+// after the decimal point
+fn ratio_to_decimal(r: &BigRational, digits: usize, mode: Rounding) -> String {
+    let scale = BigInt::from(10).pow(digits as u32);
+    let n = round_ratio(&(r * BigRational::from_integer(scale)), mode);
+    let neg = n.sign() == Sign::Minus;
+    let mut s = n.abs().to_string();
+    if digits > 0 {
+        if s.len() <= digits {
+            s = "0".repeat(digits + 1 - s.len()) + &s;
+        }
+        s.insert(s.len() - digits, '.');
+    }
+    if neg {
+        s.insert(0, '-');
+    }
+    s
+}
+
 fn str_to_bigint(s: &str) -> Result<BigInt, CalcError> {
     let s = s.replace('_', "");
     let s = s.replace(' ', "");
@@ -163,7 +1004,7 @@ fn str_to_bigint(s: &str) -> Result<BigInt, CalcError> {
         return Err(CalcError::StrToInt(s));
     }
 
-    let pos = s.find(|c: char| c == 'e' || c == 'E').unwrap_or(0);
+    let pos = s.find(['e', 'E']).unwrap_or(0);
     if pos == 0 {
         if let Ok(i) = s.parse() {
             return Ok(i);
@@ -172,7 +1013,7 @@ fn str_to_bigint(s: &str) -> Result<BigInt, CalcError> {
         }
     }
     let (s1, s2) = s.split_at(pos);
-    let s2 = s2.trim_start_matches(|c| c == 'E' || c == 'e' || c == '+');
+    let s2 = s2.trim_start_matches(['E', 'e', '+']);
 
     let base = s1.parse();
     let pow = s2.parse();
@@ -197,10 +1038,16 @@ fn str_to_f64(s: &str) -> Result<f64, CalcError> {
     }
 }
 
+// the optional second identifier is the name of polynomial operation if
+// it differs from the name of the generated function
 macro_rules! basic_op {
     ($id:ident, $op:tt, $cond:ident) => {
+        basic_op!($id, $id, $op, $cond);
+    };
+    ($id:ident, $poly:ident, $op:tt, $cond:ident) => {
         pub fn $id(self, rhs: Value) -> CalcResult {
             match (&self, &rhs) {
+                (Value::Poly(..), ..) | (.., Value::Poly(..)) => poly::$poly(self, rhs),
                 (Value::Complex(..), ..) | (.., Value::Complex(..)) => {
                     let c1 = self.into_raw_complex()?;
                     let c2 = rhs.into_raw_complex()?;
@@ -261,6 +1108,7 @@ macro_rules! div_op {
                 return Err(CalcError::DividedByZero(format!("{}", self)));
             }
             match (&self, &rhs) {
+                (Value::Poly(..), ..) | (.., Value::Poly(..)) => poly::$id(self, rhs),
                 (Value::Complex(..), ..) | (.., Value::Complex(..)) => {
                     let c2 = rhs.into_raw_complex()?;
                     let c1 = self.into_raw_complex()?;
@@ -399,6 +1247,10 @@ macro_rules! round_op {
                     Ok(v)
                 }
                 Value::Int(i) => Ok(Value::Int(i.clone())),
+                Value::List(..) => Err(CalcError::NotForList(stringify!($id).to_string())),
+                Value::Str(..) => Err(CalcError::NotForString(stringify!($id).to_string())),
+                Value::Bool(b) => Ok(bool_int(*b)),
+                Value::Poly(..) => Err(CalcError::NotForPoly(stringify!($id).to_string())),
             }
         }
     };
@@ -441,10 +1293,10 @@ macro_rules! sin_cos {
         pub fn $id(self) -> CalcResult {
             match &self {
                 Value::Complex(c) => Ok(Value::Complex(c.$id())),
-                Value::Float(f) => Ok(Value::Float(f.$id())),
+                Value::Float(f) => Ok(Value::Float(fmath::$id(*f))),
                 _ => {
                     let f = self.into_raw_f64()?;
-                    Ok(Value::Float(f.$id()))
+                    Ok(Value::Float(fmath::$id(f)))
                 }
             }
         }
@@ -458,7 +1310,7 @@ macro_rules! asin_cos {
                 _ => {
                     let f = self.into_raw_f64()?;
                     if (-1.0..=1.0).contains(&f) {
-                        Ok(Value::Float(f.$id()))
+                        Ok(Value::Float(fmath::$id(f)))
                     } else {
                         let cm = Complex::new(f, 0.0);
                         Ok(Value::Complex(cm.$id()))
@@ -476,7 +1328,7 @@ macro_rules! fn_hyper {
                 Value::Complex(c) => Ok(Value::Complex(c.$id())),
                 _ => {
                     let f = self.clone().into_raw_f64()?;
-                    Ok(Value::Float(f.$id()))
+                    Ok(Value::Float(fmath::$id(f)))
                 }
             }
         }
@@ -489,6 +1341,104 @@ impl Default for Value {
     }
 }
 
+// a boolean as a number
+fn bool_int(b: bool) -> Value {
+    if b {
+        Value::Int(BigInt::one())
+    } else {
+        Value::Int(BigInt::zero())
+    }
+}
+
+macro_rules! value_from {
+    ($t:ty, $id:ident, $conv:expr) => {
+        impl From<$t> for Value {
+            fn from(v: $t) -> Value {
+                Value::$id($conv(v))
+            }
+        }
+    };
+}
+
+value_from!(i32, Int, BigInt::from);
+value_from!(i64, Int, BigInt::from);
+value_from!(u64, Int, BigInt::from);
+value_from!(BigInt, Int, |v| v);
+value_from!(f64, Float, |v| v);
+value_from!(BigRational, Ratio, |v| v);
+value_from!(Complex<f64>, Complex, |v| v);
+
+macro_rules! value_try_into {
+    ($t:ty, $id:ident) => {
+        impl TryFrom<Value> for $t {
+            type Error = CalcError;
+            fn try_from(v: Value) -> Result<$t, CalcError> {
+                v.$id()
+            }
+        }
+    };
+}
+
+value_try_into!(i64, as_i64);
+value_try_into!(u64, as_u64);
+value_try_into!(f64, as_f64);
+value_try_into!(BigInt, as_bigint);
+
+macro_rules! std_op {
+    ($tr:ident, $id:ident, $method:ident) => {
+        /// Panics if the operation fails(e.g, division by zero or value conversion
+        /// error). Use the corresponding `Value` method to get `Result` instead
+        impl $tr for Value {
+            type Output = Value;
+            fn $id(self, rhs: Value) -> Value {
+                match self.$method(rhs) {
+                    Ok(v) => v,
+                    Err(e) => panic!("{}", e),
+                }
+            }
+        }
+    };
+}
+
+std_op!(Add, add, addition);
+std_op!(Sub, sub, subtract);
+std_op!(Mul, mul, multiply);
+std_op!(Div, div, divide);
+
+/// Panics if the value is a string or a list or polynomial containing a string.
+/// Use `Value::negate` to get `Result` instead
+impl Neg for Value {
+    type Output = Value;
+    fn neg(self) -> Value {
+        match self.negate() {
+            Ok(v) => v,
+            Err(e) => panic!("{}", e),
+        }
+    }
+}
+
+/// Values of different types are compared as numbers, e.g `Int(3) < Float(3.5)`.
+/// But values that are numerically equal and have different types are
+/// not comparable(to be consistent with `PartialEq`): `Int(3)` and `Float(3.0)`.
+/// A complex number is less than another one only if both its real and
+/// imaginary parts are less than the parts of the other number
+impl PartialOrd for Value {
+    fn partial_cmp(&self, other: &Value) -> Option<Ordering> {
+        if self == other {
+            return Some(Ordering::Equal);
+        }
+        let less = self.clone().less(other.clone()).ok()?;
+        if !less.is_zero() {
+            return Some(Ordering::Less);
+        }
+        let greater = other.clone().less(self.clone()).ok()?;
+        if !greater.is_zero() {
+            return Some(Ordering::Greater);
+        }
+        None
+    }
+}
+
 impl Value {
     pub fn new() -> Self {
         Default::default()
@@ -508,6 +1458,10 @@ impl Value {
                 let i = f64_to_int(c.re.floor())?;
                 Ok(Value::Int(i))
             }
+            Value::List(..) => Err(CalcError::ListToNumber(self.to_string())),
+            Value::Str(s) => Err(CalcError::StrToNumber(s)),
+            Value::Bool(b) => Ok(bool_int(b)),
+            Value::Poly(..) => Err(CalcError::PolyToNumber(self.to_string())),
         }
     }
 
@@ -523,6 +1477,10 @@ impl Value {
                 Ok(Value::Float(f))
             }
             Value::Complex(c) => Ok(Value::Float(c.re)),
+            Value::List(..) => Err(CalcError::ListToNumber(self.to_string())),
+            Value::Str(s) => Err(CalcError::StrToNumber(s)),
+            Value::Bool(b) => bool_int(b).into_float(),
+            Value::Poly(..) => Err(CalcError::PolyToNumber(self.to_string())),
         }
     }
 
@@ -538,6 +1496,10 @@ impl Value {
                 let r = f64_to_ratio(c.re)?;
                 Ok(Value::Ratio(r))
             }
+            Value::List(..) => Err(CalcError::ListToNumber(self.to_string())),
+            Value::Str(s) => Err(CalcError::StrToNumber(s)),
+            Value::Bool(b) => bool_int(b).into_ratio(),
+            Value::Poly(..) => Err(CalcError::PolyToNumber(self.to_string())),
         }
     }
 
@@ -553,6 +1515,10 @@ impl Value {
                 let f = ratio_to_f64(&r)?;
                 Ok(Value::Complex(Complex::new(f, 0.0)))
             }
+            Value::List(..) => Err(CalcError::ListToNumber(self.to_string())),
+            Value::Str(s) => Err(CalcError::StrToNumber(s)),
+            Value::Bool(b) => bool_int(b).into_complex(),
+            Value::Poly(..) => Err(CalcError::PolyToNumber(self.to_string())),
         }
     }
 
@@ -625,6 +1591,20 @@ impl Value {
         Ok(Value::Float(f))
     }
 
+    /// Convert &str with a decimal number to an exact rational number:
+    /// `0.1` is `1\10`. If the number has no fractional part, the result
+    /// is an integer number. Numbers with huge exponents are converted to
+    /// float numbers
+    pub fn from_str_decimal(s: &str) -> CalcResult {
+        let s = s.replace(['_', ' '], "");
+        let s = s.replace(',', ".");
+        match decimal_str_to_ratio(&s) {
+            Some(r) if r.is_integer() => Ok(Value::Int(r.to_integer())),
+            Some(r) => Ok(Value::Ratio(r)),
+            None => Value::from_str_float(&s),
+        }
+    }
+
     /// Convert &str to rational number
     /// Supported formats:
     /// * Numerator and denominator only: `1\2` = one half
@@ -667,6 +1647,13 @@ impl Value {
     /// For convenience digits can be separated with underscores:
     /// `3_005.245_1d` is the same as `3005.2451d`
     pub fn from_str_angle(s: &str) -> CalcResult {
+        let deg = Value::from_str_degrees(s)?.into_raw_f64()?;
+        Ok(Value::Float(deg * consts::PI / 180.0))
+    }
+
+    /// Converts &str that represents angle in degrees to float number of
+    /// degrees: `30d30m` -> `30.5`. Supports the same formats as `from_str_angle`
+    pub(crate) fn from_str_degrees(s: &str) -> CalcResult {
         let s = s.replace('_', "");
         let s = s.replace(',', ".");
         let parts: Vec<&str> = s
@@ -684,9 +1671,9 @@ impl Value {
             .filter(|s| !(*s).is_empty())
             .collect();
 
-        let deg_ex = s.find(|c: char| c == 'd' || c == 'D' || c == '°').is_some();
-        let min_ex = s.find(|c: char| c == 'm' || c == 'M' || c == '\'').is_some();
-        let sec_ex = s.find(|c: char| c == 's' || c == 'S' || c == '"').is_some();
+        let deg_ex = s.find(['d', 'D', '°']).is_some();
+        let min_ex = s.find(['m', 'M', '\'']).is_some();
+        let sec_ex = s.find(['s', 'S', '"']).is_some();
         let mut cnt = 0usize;
         if deg_ex {
             cnt += 1;
@@ -716,20 +1703,65 @@ impl Value {
             let sec = str_to_f64(parts[idx])?;
             deg += sec / 3600.0;
         }
-        Ok(Value::Float(deg * consts::PI / 180.0))
+        Ok(Value::Float(deg))
     }
 
-    /// Convert &str to complex number
-    /// Supported formats:
-    /// * "post" marker - `1+2i`
-    /// * *pre" marker - `1+i2`
-    ///
-    /// `i` can be capital one. Instead of `i` letter `j` can be used.
+    /// Convert &str that represents duration to the number of seconds:
+    /// `1h30m` = `5400`. Units are `d`(days), `h`, `m`, and `s`; every unit
+    /// can be used once, and units go from the biggest to the smallest one.
+    /// Letters can be either lowercase or capital ones
     ///
-    /// Note: while omitting real part is supported by this converter(e.g,
-    /// it parses `2i` to `0+2i`), it is discouraged. Because a parser that
-    /// parses the entire expression cannot detect such cases and may generate
-    /// an error.
+    /// Numbers can be fractional: `1.5h` = `5400`. If the duration is not
+    /// a whole number of seconds, the result is a fraction: `0.25s` = `1\4`
+    ///
+    /// For convenience digits can be separated with underscores: `1_000s`
+    pub fn from_str_duration(s: &str) -> CalcResult {
+        let mut secs = BigRational::zero();
+        let mut num = String::new();
+        for c in s.chars() {
+            let unit = match c.to_ascii_lowercase() {
+                '_' => continue,
+                ',' => {
+                    num.push('.');
+                    continue;
+                }
+                'd' => 86_400,
+                'h' => 3_600,
+                'm' => 60,
+                's' => 1,
+                _ => {
+                    num.push(c);
+                    continue;
+                }
+            };
+            let n = match decimal_str_to_ratio(&num) {
+                Some(n) => n,
+                None => return Err(CalcError::StrToInt(s.to_owned())),
+            };
+            secs += n * BigInt::from(unit);
+            num.clear();
+        }
+        if !num.is_empty() {
+            return Err(CalcError::StrToInt(s.to_owned()));
+        }
+        if secs.is_integer() {
+            Ok(Value::Int(secs.to_integer()))
+        } else {
+            Ok(Value::Ratio(secs))
+        }
+    }
+
+    /// Convert &str to complex number
+    /// Supported formats:
+    /// * "post" marker - `1+2i`
+    /// * *pre" marker - `1+i2`
+    ///
+    /// `i` can be capital one. Instead of `i` letter `j` can be used.
+    ///
+    /// Note: while omitting real part is supported by this converter(e.g,
+    /// it parses `2i` to `0+2i`), it is discouraged. Because a parser that
+    /// parses the entire expression cannot detect such cases and may generate
+    /// an error.
     ///
     /// Comma(,) can be used instead of decimal point(.):
     /// `1.25d` is the same as `1,25d`
@@ -739,7 +1771,7 @@ impl Value {
     pub fn from_str_complex(s: &str) -> CalcResult {
         let s = s.replace('_', "");
         let s = s.replace(',', ".");
-        if let Some(pos) = s.find(|c: char| c == 'i' || c == 'I' || c == 'j' || c == 'J') {
+        if let Some(pos) = s.find(['i', 'I', 'j', 'J']) {
             if pos == 0 {
                 // only imaginary case: -i3.2e-5
                 let f = str_to_f64(&s[pos + 1..])?;
@@ -752,18 +1784,20 @@ impl Value {
                 }
                 Ok(Value::Complex(Complex::new(0.0, f)))
             } else if pos == s.len() - 1 {
-                // harder case: -2.1e-4-3.2-e5i
-                let epos = s.rfind(|c: char| c == 'e' || c == 'E').unwrap_or(s.len());
-                let mut spos = s.rfind(|c: char| c == '-' || c == '+').unwrap_or(s.len());
-                if spos > epos {
-                    // case when the imaginary number has 'e' power: '..-2.3e-4i'.
-                    // need to look for the next '-' or '+' from the end
-                    spos = s[..epos].rfind(|c: char| c == '-' || c == '+').unwrap_or(s.len());
-                }
-                if spos >= epos || spos == 0 {
-                    let f = str_to_f64(&s[..s.len() - 1])?;
-                    return Ok(Value::Complex(Complex::new(0.0, f)));
-                }
+                // harder case: -2.1e-4-3.2e-5i. The parts are separated by
+                // the last sign that is not a sign of an exponent
+                let spos = s[..pos].rfind(['-', '+']);
+                let spos = match spos {
+                    Some(p) if p > 0 && s[..p].ends_with(['e', 'E']) => s[..p - 1].rfind(['-', '+']),
+                    p => p,
+                };
+                let spos = match spos {
+                    Some(p) if p > 0 => p,
+                    _ => {
+                        let f = str_to_f64(&s[..s.len() - 1])?;
+                        return Ok(Value::Complex(Complex::new(0.0, f)));
+                    }
+                };
                 let r = str_to_f64(&s[..spos])?;
                 let i = str_to_f64(&s[spos..s.len() - 1])?;
                 Ok(Value::Complex(Complex::new(r, i)))
@@ -782,8 +1816,86 @@ impl Value {
         }
     }
 
+    /// Converts a string literal in double quotes to a string value:
+    /// `"MMXXIV"`. The text between quotes is kept as is, including its
+    /// case. Quotes inside a string literal are not supported
+    pub fn from_str_quoted(s: &str) -> CalcResult {
+        match s.strip_prefix('"').and_then(|t| t.strip_suffix('"')) {
+            Some(t) => Ok(Value::Str(t.to_string())),
+            None => Err(CalcError::ParseFailed(s.to_string())),
+        }
+    }
+
     //---------------------------------------------
 
+    /// Returns the value as a float number.
+    /// Complex numbers with non-zero imaginary part generate an error
+    pub fn as_f64(&self) -> Result<f64, CalcError> {
+        match self {
+            Value::Complex(c) if c.im != 0.0 => Err(CalcError::NotForComplex("as_f64".to_string())),
+            _ => self.clone().into_raw_f64(),
+        }
+    }
+
+    /// Returns the value as a big integer number.
+    /// Only integers, fractions with denominator `1`, and float numbers
+    /// without fractional part can be converted
+    pub fn as_bigint(&self) -> Result<BigInt, CalcError> {
+        match self {
+            Value::Int(i) => Ok(i.clone()),
+            Value::Ratio(r) if r.is_integer() => Ok(r.to_integer()),
+            Value::Float(f) if f.fract() == 0.0 => f64_to_int(*f),
+            Value::Complex(c) if c.im == 0.0 && c.re.fract() == 0.0 => f64_to_int(c.re),
+            _ => Err(CalcError::OnlyInt("as_bigint".to_string())),
+        }
+    }
+
+    /// Returns the value as i64 number. See `as_bigint` for the list of
+    /// values that can be converted
+    pub fn as_i64(&self) -> Result<i64, CalcError> {
+        let i = self.as_bigint()?;
+        match i.to_i64() {
+            Some(v) => Ok(v),
+            None => Err(CalcError::ArgumentOutOfRange("as_i64".to_string(), i.to_string(), "i64".to_string())),
+        }
+    }
+
+    /// Returns the value as u64 number. See `as_bigint` for the list of
+    /// values that can be converted
+    pub fn as_u64(&self) -> Result<u64, CalcError> {
+        let i = self.as_bigint()?;
+        match i.to_u64() {
+            Some(v) => Ok(v),
+            None => Err(CalcError::ArgumentOutOfRange("as_u64".to_string(), i.to_string(), "u64".to_string())),
+        }
+    }
+
+    /// Short name of the value type: `int`, `ratio`, `float`, `complex`,
+    /// `list`, `string`, `poly`, or `bool`
+    pub fn type_name(&self) -> &'static str {
+        match self {
+            Value::Int(..) => "int",
+            Value::Ratio(..) => "ratio",
+            Value::Float(..) => "float",
+            Value::Complex(..) => "complex",
+            Value::List(..) => "list",
+            Value::Str(..) => "string",
+            Value::Bool(..) => "bool",
+            Value::Poly(..) => "poly",
+        }
+    }
+
+    /// Returns true if the value is exact: an integer, a fraction, a string,
+    /// a boolean, or a list and a polynomial that contain only exact values. Float
+    /// and complex numbers are approximate
+    pub fn is_exact(&self) -> bool {
+        match self {
+            Value::Int(..) | Value::Ratio(..) | Value::Str(..) | Value::Bool(..) => true,
+            Value::Float(..) | Value::Complex(..) => false,
+            Value::List(l) | Value::Poly(l) => l.iter().all(|v| v.is_exact()),
+        }
+    }
+
     /// Returns true if the value is zero
     pub fn is_zero(&self) -> bool {
         match self {
@@ -791,6 +1903,10 @@ impl Value {
             Value::Float(ref f) => *f == 0.0,
             Value::Ratio(ref r) => r.is_zero(),
             Value::Complex(ref c) => c.is_zero(),
+            Value::List(ref l) => l.is_empty(),
+            Value::Str(ref s) => s.is_empty(),
+            Value::Bool(b) => !b,
+            Value::Poly(ref c) => c.iter().all(|v| v.is_zero()),
         }
     }
 
@@ -801,6 +1917,8 @@ impl Value {
             Value::Float(ref f) => *f >= 0.0f64,
             Value::Ratio(ref r) => *r >= BigRational::zero(),
             Value::Complex(ref c) => c.re >= 0.0f64,
+            Value::Bool(..) => true,
+            Value::List(..) | Value::Str(..) | Value::Poly(..) => false,
         }
     }
 
@@ -808,7 +1926,7 @@ impl Value {
     // to BigInt
     fn is_like_int(&self) -> bool {
         match self {
-            Value::Int(..) => true,
+            Value::Int(..) | Value::Bool(..) => true,
             Value::Float(f) => {
                 let fa: f64 = f.abs();
                 // f64 precision is about 19-20 digits,
@@ -823,10 +1941,79 @@ impl Value {
                 let fa: f64 = c.re.abs();
                 (1.0..=1e22).contains(&fa) && f64_equal(fa.floor(), fa)
             }
+            Value::List(..) | Value::Str(..) | Value::Poly(..) => false,
+        }
+    }
+
+    basic_op!(add_numbers, addition, +, false);
+
+    /// Adds two values. If any of them is a string, the result is
+    /// a string concatenation: `"x = " + 2` is `"x = 2"`
+    pub fn addition(self, rhs: Value) -> CalcResult {
+        match (&self, &rhs) {
+            (Value::Str(..), ..) | (.., Value::Str(..)) => Ok(Value::Str(format!("{}{}", self, rhs))),
+            _ => self.add_numbers(rhs),
+        }
+    }
+
+    /// The number of characters in a string or the number of items in
+    /// a list
+    pub fn len(self) -> CalcResult {
+        match &self {
+            Value::Str(s) => Ok(Value::Int(BigInt::from(s.chars().count()))),
+            Value::List(l) => Ok(Value::Int(BigInt::from(l.len()))),
+            _ => Err(CalcError::InvalidAgrument("len".to_string(), self.to_string())),
         }
     }
+    /// Numbers from `self` to `end` inclusive with the given step, or one if
+    /// the step is `None`: `1..5` is `[1; 2; 3; 4; 5]`
+    pub fn range(self, end: Value, step: Option<Value>) -> CalcResult {
+        list::range(self, end, step)
+    }
+
+    /// Item of a list or character of a string. The first item has index
+    /// `0`, and negative indexes count from the end
+    pub fn item(self, idx: Value) -> CalcResult {
+        list::item(self, &idx)
+    }
+
+    /// Part of a list or string from `start` up to `end`, not including the
+    /// item at `end`. Indexes are the same as in `item`
+    pub fn slice(self, start: Value, end: Value) -> CalcResult {
+        list::slice(self, &start, &end)
+    }
+
+    /// Lists of items with the same index from several lists of the same
+    /// length: `zip([1; 2]; [3; 4])` is `[[1; 3]; [2; 4]]`
+    pub fn zip(lists: Vec<Value>) -> CalcResult {
+        list::zip(lists)
+    }
+
+    /// Items with the same index from every row of a list
+    pub fn col(self, idx: Value) -> CalcResult {
+        list::col(self, &idx)
+    }
+
+    /// Items of a list in ascending order
+    pub fn sort(self) -> CalcResult {
+        list::sort(self, false, "sort")
+    }
+
+    /// Items of a list in descending order
+    pub fn sortdesc(self) -> CalcResult {
+        list::sort(self, true, "sortdesc")
+    }
+
+    /// Items of a list without repetitions
+    pub fn unique(self) -> CalcResult {
+        list::unique(self)
+    }
+
+    /// A list or a string in reverse order
+    pub fn reverse(self) -> CalcResult {
+        list::reverse(self)
+    }
 
-    basic_op!(addition, +, false);
     basic_op!(subtract, -, false);
     basic_op!(multiply, *, false);
 
@@ -838,6 +2025,12 @@ impl Value {
     /// two complex numbers are divided. It means in that the result for
     /// complex numbers may be incorrect or strange
     pub fn div_int(self, rhs: Value) -> CalcResult {
+        self.div_round(rhs, Rounding::Trunc)
+    }
+
+    /// Divides and rounds the result to an integer using the given mode.
+    /// For complex numbers only the real part of the result is rounded
+    pub fn div_round(self, rhs: Value, mode: Rounding) -> CalcResult {
         if rhs.is_zero() {
             return Err(CalcError::DividedByZero(format!("{}", self)));
         }
@@ -845,25 +2038,19 @@ impl Value {
             (Value::Complex(..), ..) | (.., Value::Complex(..)) => {
                 let c2 = rhs.into_raw_complex()?;
                 let c1 = self.into_raw_complex()?;
-                let c = Value::Complex(c1 / c2);
-                Value::into_int(c)
+                let i = f64_to_int(round_f64((c1 / c2).re, mode))?;
+                Ok(Value::Int(i))
             }
             (Value::Float(..), ..) | (.., Value::Float(..)) => {
                 let f2 = rhs.into_raw_f64()?;
                 let f1 = self.into_raw_f64()?;
-                let f = Value::Float(f1 / f2);
-                Value::into_int(f)
+                let i = f64_to_int(round_f64(f1 / f2, mode))?;
+                Ok(Value::Int(i))
             }
-            (Value::Ratio(..), ..) | (.., Value::Ratio(..)) => {
+            _ => {
                 let r2 = rhs.into_raw_ratio()?;
                 let r1 = self.into_raw_ratio()?;
-                let r = Value::Ratio(r1 / r2);
-                Value::into_int(r)
-            }
-            _ => {
-                let i2 = rhs.into_raw_big_int()?;
-                let i1 = self.into_raw_big_int()?;
-                Ok(Value::Int(i1 / i2))
+                Ok(Value::Int(round_ratio(&(r1 / r2), mode)))
             }
         }
     }
@@ -875,6 +2062,16 @@ impl Value {
             Value::Ratio(r) => Ok(Value::Ratio(-r)),
             Value::Float(f) => Ok(Value::Float(-f)),
             Value::Int(i) => Ok(Value::Int(-i)),
+            Value::List(l) => {
+                let l: Result<Vec<Value>, CalcError> = l.iter().map(|v| v.clone().negate()).collect();
+                Ok(Value::List(l?))
+            }
+            Value::Str(..) => Err(CalcError::NotForString("neg".to_string())),
+            Value::Bool(b) => bool_int(*b).negate(),
+            Value::Poly(c) => {
+                let c: Result<Vec<Value>, CalcError> = c.iter().map(|v| v.clone().negate()).collect();
+                Ok(Value::Poly(c?))
+            }
         }
     }
 
@@ -911,6 +2108,28 @@ impl Value {
         }
     }
 
+    /// Returns the argument(phase) of a complex number in radians in the
+    /// range `(-PI; PI]`. Negative real numbers have argument `PI`
+    pub fn arg(self) -> CalcResult {
+        let v = Value::into_complex(self)?;
+        match &v {
+            Value::Complex(c) => Ok(Value::Float(c.arg())),
+            _ => Err(CalcError::Unreachable),
+        }
+    }
+
+    /// Projects a complex number onto the Riemann sphere: all complex
+    /// infinities become the positive real infinity, other numbers are
+    /// returned as is
+    pub fn proj(self) -> CalcResult {
+        match &self {
+            Value::Complex(c) if c.re.is_infinite() || c.im.is_infinite() => {
+                Ok(Value::Complex(Complex::new(f64::INFINITY, 0.0f64.copysign(c.im))))
+            }
+            _ => Ok(self),
+        }
+    }
+
     cmp_op!(eq, ==);
     cmp_op!(less, <);
     cmp_op!(lesseq, <=);
@@ -954,6 +2173,10 @@ impl Value {
             Value::Ratio(r) => Ok(Value::Ratio(r.fract())),
             Value::Float(f) => Ok(Value::Float(f.fract())),
             Value::Int(..) => Ok(Value::Int(BigInt::zero())),
+            Value::List(..) => Err(CalcError::NotForList("fract".to_string())),
+            Value::Str(..) => Err(CalcError::NotForString("fract".to_string())),
+            Value::Bool(..) => Ok(Value::Int(BigInt::zero())),
+            Value::Poly(..) => Err(CalcError::NotForPoly("fract".to_string())),
         }
     }
 
@@ -977,12 +2200,69 @@ impl Value {
                     Ok(Value::Int(i.clone()))
                 }
             }
+            Value::List(..) => Err(CalcError::NotForList("abs".to_string())),
+            Value::Str(..) => Err(CalcError::NotForString("abs".to_string())),
+            Value::Bool(b) => Ok(bool_int(*b)),
+            Value::Poly(..) => Err(CalcError::NotForPoly("abs".to_string())),
         }
     }
 
     round_op!(floor);
     round_op!(ceil);
-    round_op!(round);
+
+    /// Rounds the number to the nearest integer, ties go away from zero
+    pub fn round(self) -> CalcResult {
+        self.round_with(Rounding::HalfAwayFromZero)
+    }
+
+    /// Rounds the number to an integer using the given mode. Real and
+    /// imaginary parts of a complex number are rounded separately
+    pub fn round_with(self, mode: Rounding) -> CalcResult {
+        match &self {
+            Value::Complex(c) => {
+                let v = Value::Complex(Complex::new(round_f64(c.re, mode), round_f64(c.im, mode)));
+                if Value::is_like_int(&v) {
+                    Value::into_int(v)
+                } else {
+                    Ok(v)
+                }
+            }
+            Value::Ratio(r) => Ok(Value::Int(round_ratio(r, mode))),
+            Value::Float(f) => Value::into_int(Value::Float(round_f64(*f, mode))),
+            Value::Int(..) => Ok(self),
+            Value::List(..) => Err(CalcError::NotForList("round".to_string())),
+            Value::Str(..) => Err(CalcError::NotForString("round".to_string())),
+            Value::Bool(b) => Ok(bool_int(*b)),
+            Value::Poly(..) => Err(CalcError::NotForPoly("round".to_string())),
+        }
+    }
+
+    /// Formats the number with the fixed number of digits after the decimal
+    /// point, e.g. `to_decimal(2.675, 2, Rounding::HalfEven)` = `2.68`. Float
+    /// numbers are rounded as they are displayed, not as they are stored
+    pub fn to_decimal(&self, digits: usize, mode: Rounding) -> Result<String, CalcError> {
+        match self {
+            Value::Int(i) => Ok(ratio_to_decimal(&BigRational::from_integer(i.clone()), digits, mode)),
+            Value::Ratio(r) => Ok(ratio_to_decimal(r, digits, mode)),
+            Value::Float(f) => Ok(ratio_to_decimal(&f64_to_decimal_ratio(*f)?, digits, mode)),
+            Value::Complex(c) => {
+                let re = ratio_to_decimal(&f64_to_decimal_ratio(c.re)?, digits, mode);
+                let im = ratio_to_decimal(&f64_to_decimal_ratio(c.im)?, digits, mode);
+                if im.starts_with('-') {
+                    Ok(format!("{}{}i", re, im))
+                } else {
+                    Ok(format!("{}+{}i", re, im))
+                }
+            }
+            Value::List(l) => {
+                let items = l.iter().map(|v| v.to_decimal(digits, mode)).collect::<Result<Vec<String>, CalcError>>()?;
+                Ok(format!("[{}]", items.join("; ")))
+            }
+            Value::Str(s) => Ok(s.clone()),
+            Value::Bool(b) => Ok(b.to_string()),
+            Value::Poly(..) => Ok(self.to_string()),
+        }
+    }
     round_op!(trunc);
 
     /// Squares the value.
@@ -1001,6 +2281,10 @@ impl Value {
             Value::Ratio(r) => Ok(Value::Ratio(r * r)),
             Value::Float(f) => Ok(Value::Float(f * f)),
             Value::Int(i) => Ok(Value::Int(i * i)),
+            Value::List(..) => Err(CalcError::NotForList("sqr".to_string())),
+            Value::Str(..) => Err(CalcError::NotForString("sqr".to_string())),
+            Value::Bool(b) => Ok(bool_int(*b)),
+            Value::Poly(..) => Err(CalcError::NotForPoly("sqr".to_string())),
         }
     }
 
@@ -1046,6 +2330,10 @@ impl Value {
                     Ok(Value::Int(-BigInt::one()))
                 }
             }
+            Value::List(..) => Err(CalcError::NotForList("signum".to_string())),
+            Value::Str(..) => Err(CalcError::NotForString("signum".to_string())),
+            Value::Bool(b) => Ok(bool_int(*b)),
+            Value::Poly(..) => Err(CalcError::NotForPoly("signum".to_string())),
         }
     }
 
@@ -1095,6 +2383,10 @@ impl Value {
                     }
                 }
             }
+            Value::List(..) => Err(CalcError::NotForList("sqrt".to_string())),
+            Value::Str(..) => Err(CalcError::NotForString("sqrt".to_string())),
+            Value::Bool(b) => Ok(bool_int(*b)),
+            Value::Poly(..) => Err(CalcError::NotForPoly("sqrt".to_string())),
         }
     }
 
@@ -1104,17 +2396,21 @@ impl Value {
             Value::Complex(..) => self.power(Value::Float(1.0f64 / 3.0f64)),
             Value::Ratio(r) => {
                 let f = ratio_to_f64(r)?;
-                Ok(Value::Float(f.cbrt()))
+                Ok(Value::Float(fmath::cbrt(f)))
             }
-            Value::Float(f) => Ok(Value::Float(f.cbrt())),
+            Value::Float(f) => Ok(Value::Float(fmath::cbrt(*f))),
             Value::Int(i) => {
                 let cb = i.cbrt();
                 if cb.clone() * cb.clone() * cb.clone() == *i {
                     return Ok(Value::Int(cb));
                 }
                 let f = int_to_f64(i)?;
-                Ok(Value::Float(f.cbrt()))
+                Ok(Value::Float(fmath::cbrt(f)))
             }
+            Value::List(..) => Err(CalcError::NotForList("cbrt".to_string())),
+            Value::Str(..) => Err(CalcError::NotForString("cbrt".to_string())),
+            Value::Bool(b) => Ok(bool_int(*b)),
+            Value::Poly(..) => Err(CalcError::NotForPoly("cbrt".to_string())),
         }
     }
 
@@ -1156,6 +2452,8 @@ impl Value {
     /// For float degrees the power is calculated using `exp`
     pub fn power(self, rhs: Value) -> CalcResult {
         match (&self, &rhs) {
+            (Value::Poly(..), Value::Int(i)) if !i.is_negative() => self.fast_power(i.clone()),
+            (.., Value::Poly(..)) => Err(CalcError::PolyToNumber(rhs.to_string())),
             (Value::Complex(..), ..) | (.., Value::Complex(..)) => {
                 let v = self.into_raw_complex()?;
                 let pow = rhs.into_raw_complex()?;
@@ -1169,7 +2467,7 @@ impl Value {
                     let c2 = Complex::new(f2, 0.0);
                     return Ok(Value::Complex(c1.powc(c2)));
                 }
-                let f1 = Value::Float(f1.powf(f2));
+                let f1 = Value::Float(fmath::powf(f1, f2));
                 if Value::is_like_int(&f1) {
                     return Value::into_int(f1);
                 }
@@ -1191,6 +2489,10 @@ impl Value {
 
         match &self {
             Value::Complex(..) => Err(CalcError::NotForComplex("factorial".to_owned())),
+            Value::List(..) => Err(CalcError::NotForList("factorial".to_owned())),
+            Value::Str(..) => Err(CalcError::NotForString("factorial".to_owned())),
+            Value::Bool(..) => Ok(Value::Int(BigInt::one())),
+            Value::Poly(..) => Err(CalcError::NotForPoly("factorial".to_owned())),
             Value::Ratio(..) | Value::Float(..) => {
                 if Value::is_like_int(&self) {
                     let i = Value::into_int(self)?;
@@ -1253,7 +2555,7 @@ impl Value {
                 if f64_equal(ipart * half, f) {
                     Err(CalcError::InvalidAgrument("tan".to_owned(), format!("{}", self)))
                 } else {
-                    Ok(Value::Float(f.tan()))
+                    Ok(Value::Float(fmath::tan(f)))
                 }
             }
         }
@@ -1266,7 +2568,7 @@ impl Value {
             Value::Complex(c) => Ok(Value::Complex(c.atan())),
             _ => {
                 let f = self.into_raw_f64()?;
-                Ok(Value::Float(f.atan()))
+                Ok(Value::Float(fmath::atan(f)))
             }
         }
     }
@@ -1283,7 +2585,7 @@ impl Value {
             _ => {
                 let f = self.clone().into_raw_f64()?;
                 if (-1.0..=1.0).contains(&f) {
-                    Ok(Value::Float(f.atanh()))
+                    Ok(Value::Float(fmath::atanh(f)))
                 } else {
                     Err(CalcError::InvalidAgrument("atanh".to_owned(), format!("{}", self)))
                 }
@@ -1291,6 +2593,135 @@ impl Value {
         }
     }
 
+    // `1/x` for reciprocal trigonometric functions: zero means the argument
+    // is a pole of the function
+    fn recip(self, fname: &str, arg: &Value) -> CalcResult {
+        if self.is_zero() {
+            return Err(CalcError::InvalidAgrument(fname.to_owned(), format!("{}", arg)));
+        }
+        match self {
+            Value::Complex(c) => Ok(Value::Complex(c.inv())),
+            _ => Ok(Value::Float(1.0 / self.into_raw_f64()?)),
+        }
+    }
+
+    /// Cotangent: `cos(x)/sin(x)`
+    pub fn cot(self) -> CalcResult {
+        match &self {
+            Value::Complex(c) => Value::Complex(c.tan()).recip("cot", &self),
+            _ => {
+                let f = self.clone().into_raw_f64()?;
+                if near_multiple(f, consts::PI) {
+                    return Err(CalcError::InvalidAgrument("cot".to_owned(), format!("{}", self)));
+                }
+                Ok(Value::Float(fmath::cos(f) / fmath::sin(f)))
+            }
+        }
+    }
+
+    /// Secant: `1/cos(x)`
+    pub fn sec(self) -> CalcResult {
+        match &self {
+            Value::Complex(c) => Value::Complex(c.cos()).recip("sec", &self),
+            _ => {
+                let f = self.clone().into_raw_f64()?;
+                if near_multiple(f - consts::FRAC_PI_2, consts::PI) {
+                    return Err(CalcError::InvalidAgrument("sec".to_owned(), format!("{}", self)));
+                }
+                Ok(Value::Float(1.0 / fmath::cos(f)))
+            }
+        }
+    }
+
+    /// Cosecant: `1/sin(x)`
+    pub fn csc(self) -> CalcResult {
+        match &self {
+            Value::Complex(c) => Value::Complex(c.sin()).recip("csc", &self),
+            _ => {
+                let f = self.clone().into_raw_f64()?;
+                if near_multiple(f, consts::PI) {
+                    return Err(CalcError::InvalidAgrument("csc".to_owned(), format!("{}", self)));
+                }
+                Ok(Value::Float(1.0 / fmath::sin(f)))
+            }
+        }
+    }
+
+    /// Inverse cotangent: `atan(1/x)`, `acot(0)` = `PI/2`
+    pub fn acot(self) -> CalcResult {
+        if self.is_zero() {
+            return Ok(Value::Float(consts::FRAC_PI_2));
+        }
+        let arg = self.clone();
+        self.recip("acot", &arg)?.atan()
+    }
+
+    /// Inverse secant: `acos(1/x)`. Arguments between `-1` and `1` produce
+    /// complex numbers
+    pub fn asec(self) -> CalcResult {
+        let arg = self.clone();
+        self.recip("asec", &arg)?.acos()
+    }
+
+    /// Inverse cosecant: `asin(1/x)`. Arguments between `-1` and `1` produce
+    /// complex numbers
+    pub fn acsc(self) -> CalcResult {
+        let arg = self.clone();
+        self.recip("acsc", &arg)?.asin()
+    }
+
+    /// Hyperbolic cotangent: `1/tanh(x)`
+    pub fn coth(self) -> CalcResult {
+        let arg = self.clone();
+        self.tanh()?.recip("coth", &arg)
+    }
+
+    /// Hyperbolic secant: `1/cosh(x)`
+    pub fn sech(self) -> CalcResult {
+        let arg = self.clone();
+        self.cosh()?.recip("sech", &arg)
+    }
+
+    /// Hyperbolic cosecant: `1/sinh(x)`
+    pub fn csch(self) -> CalcResult {
+        let arg = self.clone();
+        self.sinh()?.recip("csch", &arg)
+    }
+
+    /// Inverse hyperbolic cotangent: `atanh(1/x)`. Real arguments must be
+    /// greater than `1` by absolute value
+    pub fn acoth(self) -> CalcResult {
+        match &self {
+            Value::Complex(..) => {
+                let arg = self.clone();
+                self.recip("acoth", &arg)?.atanh()
+            }
+            _ => {
+                let f = self.clone().into_raw_f64()?;
+                if f.abs() <= 1.0 {
+                    return Err(CalcError::InvalidAgrument("acoth".to_owned(), format!("{}", self)));
+                }
+                Ok(Value::Float(fmath::atanh(1.0 / f)))
+            }
+        }
+    }
+
+    /// Inverse hyperbolic secant: `acosh(1/x)`. Real arguments outside of
+    /// the range `(0; 1]` produce complex numbers
+    pub fn asech(self) -> CalcResult {
+        let arg = self.clone();
+        match self.recip("asech", &arg)? {
+            Value::Float(f) if f < 1.0 => Ok(Value::Complex(Complex::new(f, 0.0).acosh())),
+            v => v.acosh(),
+        }
+    }
+
+    /// Inverse hyperbolic cosecant: `asinh(1/x)`
+    pub fn acsch(self) -> CalcResult {
+        let arg = self.clone();
+        self.recip("acsch", &arg)?.asinh()
+    }
+
     /// Returns natural logarithm of a number.
     /// Automatically converts a negative number to a complex one before calculation
     pub fn ln(self) -> CalcResult {
@@ -1302,7 +2733,7 @@ impl Value {
             _ => {
                 let f = self.clone().into_raw_f64()?;
                 if f > 0.0 {
-                    Ok(Value::Float(f.ln()))
+                    Ok(Value::Float(fmath::ln(f)))
                 } else {
                     let cm = Complex::new(f, 0.0);
                     Ok(Value::Complex(cm.ln()))
@@ -1311,6 +2742,209 @@ impl Value {
         }
     }
 
+    /// Returns the base 10 logarithm of the number. Negative numbers
+    /// produce complex numbers
+    pub fn log10(self) -> CalcResult {
+        if Value::is_zero(&self) {
+            return Err(CalcError::InvalidAgrument("log10".to_owned(), format!("{}", self)));
+        }
+        match &self {
+            Value::Complex(c) => Ok(Value::Complex(c.ln() / consts::LN_10)),
+            _ => {
+                let f = self.clone().into_raw_f64()?;
+                if f > 0.0 {
+                    Ok(Value::Float(fmath::log10(f)))
+                } else {
+                    Ok(Value::Complex(Complex::new(f, 0.0).ln() / consts::LN_10))
+                }
+            }
+        }
+    }
+
+    /// Returns `2` raised to power of the number. Non-negative integer
+    /// powers produce exact integer numbers
+    pub fn exp2(self) -> CalcResult {
+        match &self {
+            Value::Int(i) if i.sign() != Sign::Minus => Value::Int(BigInt::from(2)).power(self),
+            Value::Complex(c) => Ok(Value::Complex((c * consts::LN_2).exp())),
+            _ => {
+                let f = self.into_raw_f64()?;
+                Ok(Value::Float(fmath::exp2(f)))
+            }
+        }
+    }
+
+    /// Returns `exp(x) - 1`. The result is accurate even if the number is
+    /// close to zero
+    pub fn expm1(self) -> CalcResult {
+        match &self {
+            Value::Complex(c) => Ok(Value::Complex(c.exp() - 1.0)),
+            _ => {
+                let f = self.into_raw_f64()?;
+                Ok(Value::Float(fmath::exp_m1(f)))
+            }
+        }
+    }
+
+    /// Returns `ln(1 + x)`. The result is accurate even if the number is
+    /// close to zero. Numbers less than `-1` produce complex numbers
+    pub fn ln1p(self) -> CalcResult {
+        match &self {
+            Value::Complex(c) => {
+                if (c + 1.0).is_zero() {
+                    return Err(CalcError::InvalidAgrument("ln1p".to_owned(), format!("{}", self)));
+                }
+                Ok(Value::Complex((c + 1.0).ln()))
+            }
+            _ => {
+                let f = self.clone().into_raw_f64()?;
+                if f > -1.0 {
+                    Ok(Value::Float(fmath::ln_1p(f)))
+                } else if f < -1.0 {
+                    Ok(Value::Complex(Complex::new(1.0 + f, 0.0).ln()))
+                } else {
+                    Err(CalcError::InvalidAgrument("ln1p".to_owned(), format!("{}", self)))
+                }
+            }
+        }
+    }
+
+    /// Returns the best rational approximation of the number with the
+    /// denominator not greater than `max_den`, e.g. `0.333333` -> `1\3`.
+    /// Float numbers are approximated as they are displayed.
+    /// Complex numbers generate an error
+    pub fn as_fraction(self, max_den: Value) -> CalcResult {
+        let max_den = match max_den {
+            Value::Int(ref i) if i.sign() == Sign::Plus => i.clone(),
+            _ => return Err(CalcError::InvalidAgrument("as_fraction".to_string(), max_den.to_string())),
+        };
+        let r = match self {
+            Value::Int(..) => return Ok(self),
+            Value::Ratio(r) => r,
+            Value::Float(f) => f64_to_decimal_ratio(f)?,
+            Value::Complex(..) => return Err(CalcError::NotForComplex("as_fraction".to_string())),
+            Value::List(..) => return Err(CalcError::NotForList("as_fraction".to_string())),
+            Value::Str(..) => return Err(CalcError::NotForString("as_fraction".to_string())),
+            Value::Bool(b) => return Ok(bool_int(b)),
+            Value::Poly(..) => return Err(CalcError::NotForPoly("as_fraction".to_string())),
+        };
+        let r = best_fraction(&r, &max_den);
+        if r.is_integer() {
+            return Ok(Value::Int(r.to_integer()));
+        }
+        Ok(Value::Ratio(r))
+    }
+
+    /// Converts the value to a string using the options: the number of
+    /// digits after the decimal point, trimming trailing zeros, the style of
+    /// complex numbers, and grouping digits. Lists apply the options to
+    /// every item; strings, fractions and polynomials are printed as is
+    pub fn to_string_with(&self, opts: &FormatOptions) -> String {
+        match self {
+            Value::Int(i) => {
+                let (prefix, digits) = match opts.radix {
+                    16 => ("0x", i.abs().to_str_radix(16)),
+                    8 => ("0o", i.abs().to_str_radix(8)),
+                    2 => ("0b", i.abs().to_str_radix(2)),
+                    _ => ("", i.abs().to_string()),
+                };
+                let digits = match opts.group_sep {
+                    Some(sep) => format::group_digits(&digits, sep, 3),
+                    None => digits,
+                };
+                let sign = if i.is_negative() { "-" } else { "" };
+                format!("{}{}{}", sign, prefix, digits)
+            }
+            Value::Float(f) => f64_with(*f, opts),
+            Value::Complex(c) => {
+                let (re, im) = (f64_with(c.re, opts), f64_with(c.im, opts));
+                match opts.complex {
+                    ComplexStyle::Pair => format!("({}, {})", re, im),
+                    ComplexStyle::Algebraic if im.starts_with('-') => format!("{}{}i", re, im),
+                    ComplexStyle::Algebraic => format!("{}+{}i", re, im),
+                }
+            }
+            Value::List(l) => {
+                let items: Vec<String> = l.iter().map(|v| v.to_string_with(opts)).collect();
+                format!("[{}]", items.join("; "))
+            }
+            _ => self.to_string(),
+        }
+    }
+
+    /// Formats the number as a mixed number: `1\1\2` -> `1 1/2`, `-7\2` ->
+    /// `-3 1/2`. Float numbers are approximated with fractions which
+    /// denominators do not exceed `max_den`
+    pub fn to_mixed(&self, max_den: u64) -> Result<String, CalcError> {
+        let v = match self {
+            Value::List(l) => {
+                let items = l.iter().map(|v| v.to_mixed(max_den)).collect::<Result<Vec<String>, CalcError>>()?;
+                return Ok(format!("[{}]", items.join("; ")));
+            }
+            Value::Str(s) => return Ok(s.clone()),
+            _ => self.clone().as_fraction(Value::Int(BigInt::from(max_den)))?,
+        };
+        let r = match v {
+            Value::Ratio(r) => r,
+            _ => return Ok(v.to_string()),
+        };
+        let i = r.to_integer();
+        let fr = r.fract().abs();
+        if i.is_zero() {
+            let sign = if r.is_negative() { "-" } else { "" };
+            return Ok(format!("{}{}/{}", sign, fr.numer(), fr.denom()));
+        }
+        Ok(format!("{} {}/{}", i, fr.numer(), fr.denom()))
+    }
+
+    /// Converts an exact number(integer or fraction) to a float number.
+    /// Float and complex numbers are returned as is
+    pub fn approx(self) -> CalcResult {
+        match self {
+            Value::Int(..) | Value::Ratio(..) => self.into_float(),
+            Value::Float(..) | Value::Complex(..) => Ok(self),
+            Value::List(l) => {
+                let l: Result<Vec<Value>, CalcError> = l.into_iter().map(|v| v.approx()).collect();
+                Ok(Value::List(l?))
+            }
+            Value::Str(..) => Err(CalcError::NotForString("approx".to_string())),
+            Value::Bool(b) => bool_int(b).approx(),
+            Value::Poly(..) => Err(CalcError::NotForPoly("approx".to_string())),
+        }
+    }
+
+    /// Divides numbers keeping the result exact: unlike `divide`, division
+    /// of integers and fractions always produces an integer or a fraction
+    pub fn divide_exact(self, rhs: Value) -> CalcResult {
+        match (&self, &rhs) {
+            (Value::Int(..) | Value::Ratio(..), Value::Int(..) | Value::Ratio(..)) => {
+                if rhs.is_zero() {
+                    return Err(CalcError::DividedByZero(format!("{}", self)));
+                }
+                let r2 = rhs.into_raw_ratio()?;
+                let r1 = self.into_raw_ratio()?;
+                let r = r1 / r2;
+                if r.is_integer() {
+                    return Ok(Value::Int(r.to_integer()));
+                }
+                Ok(Value::Ratio(r))
+            }
+            _ => self.divide(rhs),
+        }
+    }
+
+    /// Raises a number into a power keeping the result exact: integers and
+    /// fractions raised into a negative integer power produce a fraction
+    pub fn power_exact(self, rhs: Value) -> CalcResult {
+        match (&self, &rhs) {
+            (Value::Int(..) | Value::Ratio(..), Value::Int(i)) if i.sign() == Sign::Minus => {
+                let pow = Value::Int(-i);
+                Value::Int(BigInt::one()).divide_exact(self.power(pow)?)
+            }
+            _ => self.power(rhs),
+        }
+    }
+
     /// Converts float or integer number into rational one.
     /// Complex numbers generate an error
     pub fn ratio(self) -> CalcResult {
@@ -1325,6 +2959,10 @@ impl Value {
                 }
             }
             Value::Complex(..) => Err(CalcError::NotForComplex("ratio".to_string())),
+            Value::List(..) => Err(CalcError::NotForList("ratio".to_string())),
+            Value::Str(..) => Err(CalcError::NotForString("ratio".to_string())),
+            Value::Bool(b) => bool_int(*b).ratio(),
+            Value::Poly(..) => Err(CalcError::NotForPoly("ratio".to_string())),
         }
     }
 
@@ -1372,6 +3010,1482 @@ impl Value {
         Ok(Value::Int(v1 / gcd * v2))
     }
 
+    /// Extended Euclidean algorithm: returns a list `[g; x; y]` where `g` is
+    /// the greatest common divisor and `a * x + b * y = g`. Both arguments
+    /// must be integers
+    pub fn egcd(self, rhs: Value) -> CalcResult {
+        let (mut r0, mut r1) = match (self, rhs) {
+            (Value::Int(a), Value::Int(b)) => (a, b),
+            _ => return Err(CalcError::OnlyInt("egcd".to_string())),
+        };
+        let (mut x0, mut x1) = (BigInt::one(), BigInt::zero());
+        let (mut y0, mut y1) = (BigInt::zero(), BigInt::one());
+        while !r1.is_zero() {
+            let q = &r0 / &r1;
+            let r2 = &r0 - &q * &r1;
+            r0 = std::mem::replace(&mut r1, r2);
+            let x2 = &x0 - &q * &x1;
+            x0 = std::mem::replace(&mut x1, x2);
+            let y2 = &y0 - &q * &y1;
+            y0 = std::mem::replace(&mut y1, y2);
+        }
+        if r0.is_negative() {
+            r0 = -r0;
+            x0 = -x0;
+            y0 = -y0;
+        }
+        Ok(Value::List(vec![Value::Int(r0), Value::Int(x0), Value::Int(y0)]))
+    }
+
+    /// Modular inverse: an integer `x` from `0` to `m - 1` such that
+    /// `self * x` is `1` modulo `m`. Fails if `self` and `m` are not coprime
+    pub fn modinv(self, m: Value) -> CalcResult {
+        let m = modulus_arg(&m, "modinv")?;
+        let a = match self {
+            Value::Int(a) => a,
+            _ => return Err(CalcError::OnlyInt("modinv".to_string())),
+        };
+        let res = Value::Int(a.clone()).egcd(Value::Int(m.clone()))?;
+        match res {
+            Value::List(v) if v[0] == Value::Int(BigInt::one()) => {
+                let x = v[1].clone().into_raw_big_int()?;
+                Ok(Value::Int(((x % &m) + &m) % &m))
+            }
+            _ => Err(CalcError::InvalidAgrument("modinv".to_string(), format!("{} has no inverse modulo {}", a, m))),
+        }
+    }
+
+    /// Modular power: `self ** e` modulo `m`. Negative exponents use the
+    /// modular inverse of `self`
+    pub fn modpow(self, e: Value, m: Value) -> CalcResult {
+        let m = modulus_arg(&m, "modpow")?;
+        let (a, e) = match (self, e) {
+            (Value::Int(a), Value::Int(e)) => (a, e),
+            _ => return Err(CalcError::OnlyInt("modpow".to_string())),
+        };
+        let a = if e.is_negative() { Value::Int(a).modinv(Value::Int(m.clone()))?.into_raw_big_int()? } else { a };
+        let a = ((a % &m) + &m) % &m;
+        Ok(Value::Int(a.modpow(&e.abs(), &m)))
+    }
+
+    /// Restricts a number to the range `[lo; hi]`.
+    /// Complex numbers generate an error
+    pub fn clamp(self, lo: Value, hi: Value) -> CalcResult {
+        if let (Value::Complex(..), ..) | (.., Value::Complex(..), _) | (.., Value::Complex(..)) = (&self, &lo, &hi) {
+            return Err(CalcError::NotForComplex("clamp".to_string()));
+        }
+        if !lo.clone().greater(hi.clone())?.is_zero() {
+            return Err(CalcError::InvalidAgrument("clamp".to_string(), format!("{} > {}", lo, hi)));
+        }
+        if !self.clone().less(lo.clone())?.is_zero() {
+            return Ok(lo);
+        }
+        if !self.clone().greater(hi.clone())?.is_zero() {
+            return Ok(hi);
+        }
+        Ok(self)
+    }
+
+    /// Linear interpolation between the number and `b`: `t = 0` returns
+    /// the number, `t = 1` returns `b`
+    pub fn lerp(self, b: Value, t: Value) -> CalcResult {
+        let d = b.subtract(self.clone())?;
+        self.addition(d.multiply(t)?)
+    }
+
+    /// Wraps a number into the range `[lo; hi)`, e.g. to normalize an angle:
+    /// `wrap(370; 0; 360)` = `10`, `wrap(-90; -180; 180)` = `-90`.
+    /// Complex numbers generate an error
+    pub fn wrap(self, lo: Value, hi: Value) -> CalcResult {
+        if let (Value::Complex(..), ..) | (.., Value::Complex(..), _) | (.., Value::Complex(..)) = (&self, &lo, &hi) {
+            return Err(CalcError::NotForComplex("wrap".to_string()));
+        }
+        let width = hi.clone().subtract(lo.clone())?;
+        if width.is_zero() || !width.is_positive() {
+            return Err(CalcError::InvalidAgrument("wrap".to_string(), format!("{} >= {}", lo, hi)));
+        }
+        let mut r = self.subtract(lo.clone())?.remainder(width.clone())?;
+        if !r.is_positive() {
+            r = r.addition(width)?;
+        }
+        lo.addition(r)
+    }
+
+    /// Returns the number with the magnitude of the number and the sign of
+    /// `sign`. Negative float zero counts as a negative sign.
+    /// Complex numbers generate an error
+    pub fn copysign(self, sign: Value) -> CalcResult {
+        if let (Value::Complex(..), ..) | (.., Value::Complex(..)) = (&self, &sign) {
+            return Err(CalcError::NotForComplex("copysign".to_string()));
+        }
+        let neg = match sign {
+            Value::Float(f) => f.is_sign_negative(),
+            _ => !sign.is_positive(),
+        };
+        match self {
+            Value::Float(f) => Ok(Value::Float(f.copysign(if neg { -1.0 } else { 1.0 }))),
+            _ => {
+                let v = self.abs()?;
+                if neg {
+                    v.negate()
+                } else {
+                    Ok(v)
+                }
+            }
+        }
+    }
+
+    /// Calculates `self * b + c`. For float numbers the result is rounded
+    /// only once, other numbers are calculated exactly
+    pub fn fma(self, b: Value, c: Value) -> CalcResult {
+        match (&self, &b, &c) {
+            (Value::Complex(..), ..) | (_, Value::Complex(..), _) | (.., Value::Complex(..)) => {
+                self.multiply(b)?.addition(c)
+            }
+            (Value::Float(..), ..) | (_, Value::Float(..), _) | (.., Value::Float(..)) => {
+                let f1 = self.into_raw_f64()?;
+                let f2 = b.into_raw_f64()?;
+                let f3 = c.into_raw_f64()?;
+                Ok(Value::Float(f1.mul_add(f2, f3)))
+            }
+            _ => self.multiply(b)?.addition(c),
+        }
+    }
+
+    /// Multiplies the number by `2` raised to power `exp`. The exponent must
+    /// be an integer. Integers and fractions stay exact
+    pub fn ldexp(self, exp: Value) -> CalcResult {
+        let exp = match exp {
+            Value::Int(i) => i,
+            _ => return Err(CalcError::OnlyInt("ldexp".to_string())),
+        };
+        match self {
+            Value::Float(..) | Value::Complex(..) => {
+                let e = exp.to_i32().unwrap_or(if exp.sign() == Sign::Plus { i32::MAX } else { i32::MIN });
+                self.multiply(Value::Float(2.0f64.powi(e)))
+            }
+            _ => {
+                // `fast_power` turns negative powers into float numbers
+                let (base, exp) = if exp.sign() == Sign::Minus {
+                    (Value::Ratio(BigRational::new(BigInt::one(), BigInt::from(2))), -exp)
+                } else {
+                    (Value::Int(BigInt::from(2)), exp)
+                };
+                self.multiply(base.fast_power(exp)?)
+            }
+        }
+    }
+
+    /// Splits a number into a mantissa and an exponent of `2`. Returns a list
+    /// `[m; e]` where `0.5 <= |m| < 1` and the number equals `m * 2**e`.
+    /// Zero, infinity, and NaN are returned with exponent `0`.
+    /// Complex numbers generate an error
+    pub fn frexp(self) -> CalcResult {
+        if let Value::Complex(..) = self {
+            return Err(CalcError::NotForComplex("frexp".to_string()));
+        }
+        let f = self.into_raw_f64()?;
+        if f == 0.0 || !f.is_finite() {
+            return Ok(Value::List(vec![Value::Float(f), Value::Int(BigInt::zero())]));
+        }
+        let mut e = fmath::log2(f.abs()).floor() as i32 + 1;
+        let mut m = f / 2.0f64.powi(e);
+        // log2 may be off by one for numbers close to a power of two
+        if m.abs() >= 1.0 {
+            m /= 2.0;
+            e += 1;
+        } else if m.abs() < 0.5 {
+            m *= 2.0;
+            e -= 1;
+        }
+        Ok(Value::List(vec![Value::Float(m), Value::Int(BigInt::from(e))]))
+    }
+
+    /// Unnormalized sinc function: `sin(x)/x`, `sinc(0)` = `1`
+    pub fn sinc(self) -> CalcResult {
+        if self.is_zero() {
+            return Ok(Value::Int(BigInt::one()));
+        }
+        match &self {
+            Value::Complex(c) => Ok(Value::Complex(c.sin() / c)),
+            _ => {
+                let f = self.into_raw_f64()?;
+                Ok(Value::Float(fmath::sin(f) / f))
+            }
+        }
+    }
+
+    /// Normalized sinc function: `sin(PI*x)/(PI*x)`. It is zero for all
+    /// non-zero integer arguments
+    pub fn sincn(self) -> CalcResult {
+        match &self {
+            Value::Int(i) if !i.is_zero() => Ok(Value::Int(BigInt::zero())),
+            Value::Complex(c) => Value::Complex(c * consts::PI).sinc(),
+            _ => Value::Float(self.into_raw_f64()? * consts::PI).sinc(),
+        }
+    }
+
+    /// Logistic function: `1/(1 + exp(-x))`
+    pub fn sigmoid(self) -> CalcResult {
+        match &self {
+            Value::Complex(c) => Ok(Value::Complex((Complex::new(1.0, 0.0) + (-c).exp()).inv())),
+            _ => {
+                let f = self.into_raw_f64()?;
+                Ok(Value::Float(1.0 / (1.0 + fmath::exp(-f))))
+            }
+        }
+    }
+
+    /// Smooth Hermite interpolation between `0` and `1` when `x` goes from
+    /// the number(the lower edge) to `hi`: `3t^2 - 2t^3`, where `t` is `x`
+    /// scaled to the range `[0; 1]`. Complex numbers generate an error
+    pub fn smoothstep(self, hi: Value, x: Value) -> CalcResult {
+        if self == hi {
+            return Err(CalcError::InvalidAgrument("smoothstep".to_string(), format!("{} == {}", self, hi)));
+        }
+        if let (Value::Complex(..), ..) | (.., Value::Complex(..), _) | (.., Value::Complex(..)) = (&self, &hi, &x) {
+            return Err(CalcError::NotForComplex("smoothstep".to_string()));
+        }
+        let width = hi.subtract(self.clone())?;
+        let t = x.subtract(self)?.divide(width)?;
+        let t = t.clamp(Value::Int(BigInt::zero()), Value::Int(BigInt::one()))?;
+        let t2 = t.clone().multiply(t.clone())?;
+        let v = Value::Int(BigInt::from(3)).subtract(t.multiply(Value::Int(BigInt::from(2)))?)?;
+        t2.multiply(v)
+    }
+
+    /// Splits an angle in degrees into degrees, minutes, and seconds:
+    /// `12.5125` -> `[12; 30; 45]`. The sign goes to the first non-zero
+    /// part: `-0.5` -> `[0; -30; 0]`. Seconds are rounded to microseconds.
+    /// Complex numbers generate an error
+    pub fn dms(self) -> CalcResult {
+        if let Value::Complex(..) = self {
+            return Err(CalcError::NotForComplex("dms".to_string()));
+        }
+        let f = self.into_raw_f64()?;
+        let total = (f.abs() * 3600.0 * 1e6).round() / 1e6;
+        let d = (total / 3600.0).floor();
+        let m = ((total - d * 3600.0) / 60.0).floor();
+        let sec = total - d * 3600.0 - m * 60.0;
+        let mut parts = [d, m, sec];
+        if f < 0.0 {
+            if let Some(p) = parts.iter_mut().find(|p| **p != 0.0) {
+                *p = -*p;
+            }
+        }
+        let mut res = Vec::new();
+        for p in parts {
+            if p.fract() == 0.0 {
+                res.push(Value::Int(f64_to_int(p)?));
+            } else {
+                res.push(Value::Float(p));
+            }
+        }
+        Ok(Value::List(res))
+    }
+
+    /// Joins degrees, minutes, and seconds into an angle in degrees:
+    /// `fromdms(12; 30; 45)` = `12.5125`. The angle is negative if any of
+    /// parts is negative. Complex numbers generate an error
+    pub fn fromdms(self, min: Value, sec: Value) -> CalcResult {
+        if let (Value::Complex(..), ..) | (.., Value::Complex(..), _) | (.., Value::Complex(..)) = (&self, &min, &sec) {
+            return Err(CalcError::NotForComplex("fromdms".to_string()));
+        }
+        let neg = !self.is_positive() || !min.is_positive() || !sec.is_positive();
+        let min = min.abs()?.divide(Value::Int(BigInt::from(60)))?;
+        let sec = sec.abs()?.divide(Value::Int(BigInt::from(3600)))?;
+        let v = self.abs()?.addition(min)?.addition(sec)?;
+        if neg {
+            v.negate()
+        } else {
+            Ok(v)
+        }
+    }
+
+    /// Converts cartesian coordinates(the number is `x`) to polar ones.
+    /// Returns a list `[r; theta]`, where the angle is in radians in the
+    /// range `(-PI; PI]`. Complex numbers generate an error
+    pub fn topolar(self, y: Value) -> CalcResult {
+        if let (Value::Complex(..), ..) | (.., Value::Complex(..)) = (&self, &y) {
+            return Err(CalcError::NotForComplex("topolar".to_string()));
+        }
+        let fx = self.clone().into_raw_f64()?;
+        let fy = y.clone().into_raw_f64()?;
+        let r = self.clone().multiply(self)?.addition(y.clone().multiply(y)?)?.sqrt()?;
+        Ok(Value::List(vec![r, Value::Float(fmath::atan2(fy, fx))]))
+    }
+
+    /// Simple linear regression `y = slope * x + intercept` by least
+    /// squares: `linreg(xs; ys)` returns a list `[slope; intercept; r2]`,
+    /// where `r2` is the coefficient of determination
+    pub fn linreg(self, ys: Value) -> CalcResult {
+        let (xs, ys) = paired_lists(&self, &ys, "linreg")?;
+        let (sxx, syy, sxy) = deviation_sums(&xs, &ys);
+        if sxx == 0.0 {
+            return Err(CalcError::InvalidAgrument("linreg".to_string(), "all x values are equal".to_string()));
+        }
+        let n = xs.len() as f64;
+        let slope = sxy / sxx;
+        let intercept = (ys.iter().sum::<f64>() - slope * xs.iter().sum::<f64>()) / n;
+        // all points are on a horizontal line
+        let r2 = if syy == 0.0 { 1.0 } else { sxy * sxy / (sxx * syy) };
+        Ok(Value::List(vec![Value::Float(slope), Value::Float(intercept), Value::Float(r2)]))
+    }
+
+    /// Pearson correlation coefficient of two lists of the same length
+    pub fn corr(self, ys: Value) -> CalcResult {
+        let (xs, ys) = paired_lists(&self, &ys, "corr")?;
+        let (sxx, syy, sxy) = deviation_sums(&xs, &ys);
+        if sxx == 0.0 || syy == 0.0 {
+            return Err(CalcError::InvalidAgrument("corr".to_string(), "all values in a list are equal".to_string()));
+        }
+        Ok(Value::Float(sxy / (sxx * syy).sqrt()))
+    }
+
+    /// Sample covariance of two lists of the same length(the sum is
+    /// divided by `n - 1`)
+    pub fn cov(self, ys: Value) -> CalcResult {
+        let (xs, ys) = paired_lists(&self, &ys, "cov")?;
+        let (_, _, sxy) = deviation_sums(&xs, &ys);
+        Ok(Value::Float(sxy / (xs.len() - 1) as f64))
+    }
+
+    #[cfg(feature = "special")]
+    /// Binomial distribution: the probability of exactly `k` successes in
+    /// `n` independent trials with success probability `p`.
+    /// `binompmf(k; n; p)`
+    pub fn binompmf(self, n: Value, p: Value) -> CalcResult {
+        let k = count_arg(&self, "binompmf")?;
+        let n = count_arg(&n, "binompmf")?;
+        if n < 0 {
+            return Err(CalcError::NotForNegativeInt("binompmf".to_string()));
+        }
+        let p = prob_arg(&p, "binompmf")?;
+        if k < 0 || k > n {
+            return Ok(Value::Float(0.0));
+        }
+        let (k, n) = (k as f64, n as f64);
+        // logarithm of `0^0` is `0`
+        let ln_p = if k == 0.0 { 0.0 } else { k * fmath::ln(p) };
+        let ln_q = if k == n { 0.0 } else { (n - k) * fmath::ln_1p(-p) };
+        Ok(Value::Float(fmath::exp(special::ln_choose(n, k) + ln_p + ln_q)))
+    }
+
+    #[cfg(feature = "special")]
+    /// Binomial distribution: the probability of at most `k` successes in
+    /// `n` independent trials with success probability `p`.
+    /// `binomcdf(k; n; p)`
+    pub fn binomcdf(self, n: Value, p: Value) -> CalcResult {
+        let k = count_arg(&self, "binomcdf")?;
+        let n = count_arg(&n, "binomcdf")?;
+        if n < 0 {
+            return Err(CalcError::NotForNegativeInt("binomcdf".to_string()));
+        }
+        let p = prob_arg(&p, "binomcdf")?;
+        if k < 0 {
+            return Ok(Value::Float(0.0));
+        }
+        if k >= n || p == 0.0 {
+            return Ok(Value::Float(1.0));
+        }
+        match special::beta_inc((n - k) as f64, k as f64 + 1.0, 1.0 - p) {
+            Some(v) => Ok(Value::Float(v)),
+            None => Err(CalcError::InvalidAgrument("binomcdf".to_string(), self.to_string())),
+        }
+    }
+
+    #[cfg(feature = "special")]
+    /// Poisson distribution: the probability of exactly `k` events when
+    /// `lambda` events are expected. `poispmf(k; lambda)`
+    pub fn poispmf(self, lambda: Value) -> CalcResult {
+        let k = count_arg(&self, "poispmf")?;
+        let l = poisson_lambda(&lambda, "poispmf")?;
+        if k < 0 {
+            return Ok(Value::Float(0.0));
+        }
+        if l == 0.0 {
+            return Ok(Value::Float(if k == 0 { 1.0 } else { 0.0 }));
+        }
+        let k = k as f64;
+        Ok(Value::Float(fmath::exp(k * fmath::ln(l) - l - special::ln_factorial(k))))
+    }
+
+    #[cfg(feature = "special")]
+    /// Poisson distribution: the probability of at most `k` events when
+    /// `lambda` events are expected. `poiscdf(k; lambda)`
+    pub fn poiscdf(self, lambda: Value) -> CalcResult {
+        let k = count_arg(&self, "poiscdf")?;
+        let l = poisson_lambda(&lambda, "poiscdf")?;
+        if k < 0 {
+            return Ok(Value::Float(0.0));
+        }
+        match special::gamma_q(k as f64 + 1.0, l) {
+            Some(v) => Ok(Value::Float(v)),
+            None => Err(CalcError::InvalidAgrument("poiscdf".to_string(), self.to_string())),
+        }
+    }
+
+    /// Creates a polynomial from its coefficients starting from the highest
+    /// degree: `poly(1; -3; 2)` is `x**2-3*x+2`
+    pub fn poly(coefs: Vec<Value>) -> CalcResult {
+        if coefs.is_empty() {
+            return Err(CalcError::FunctionNoArgs("poly".to_string()));
+        }
+        let coefs = coefs.iter().map(|v| matrix::element(v, "poly")).collect::<Result<Vec<Value>, CalcError>>()?;
+        Ok(Value::Poly(poly::normalize(coefs)))
+    }
+
+    /// Value of a polynomial at `x`. The polynomial can be a list of its
+    /// coefficients
+    pub fn polyval(self, x: Value) -> CalcResult {
+        let c = poly::coefficients(&self, "polyval")?;
+        poly::eval(&c, &x)
+    }
+
+    /// List of all roots of a polynomial of degree up to 4, including
+    /// complex ones. Real roots go first in ascending order. Roots of
+    /// polynomials with integer or fractional coefficients are exact when
+    /// they are fractions
+    pub fn roots(self) -> CalcResult {
+        let c = poly::coefficients(&self, "roots")?;
+        Ok(Value::List(poly::roots(&c)?))
+    }
+
+    /// Determinant of a square matrix. See module `matrix` for the matrix
+    /// format
+    pub fn det(self) -> CalcResult {
+        let mut m = matrix::to_matrix(&self, "det", false)?;
+        matrix::check_square(&m, "det")?;
+        let n = m.len();
+        let e = matrix::eliminate(&mut m, n)?;
+        if e.pivots.len() < n {
+            return Ok(Value::Int(BigInt::zero()));
+        }
+        Ok(e.det)
+    }
+
+    /// Inverse of a square matrix. Singular matrices generate an error
+    pub fn inv(self) -> CalcResult {
+        let mut m = matrix::to_matrix(&self, "inv", false)?;
+        matrix::check_square(&m, "inv")?;
+        let n = m.len();
+        matrix::augment(&mut m, matrix::identity(n));
+        if matrix::eliminate(&mut m, n)?.pivots.len() < n {
+            return Err(CalcError::SingularMatrix("inv".to_string()));
+        }
+        Ok(matrix::from_matrix(m.into_iter().map(|row| row[n..].to_vec()).collect()))
+    }
+
+    /// Rank of a matrix: the number of linearly independent rows
+    pub fn rank(self) -> CalcResult {
+        let mut m = matrix::to_matrix(&self, "rank", false)?;
+        let cols = m[0].len();
+        let e = matrix::eliminate(&mut m, cols)?;
+        Ok(Value::Int(BigInt::from(e.pivots.len())))
+    }
+
+    /// Solves a system of linear equations `A * x = b`: `A` is a square
+    /// matrix, `b` is a list of the right sides. The result is a list of
+    /// unknowns. If `b` is a matrix, every column of it is a separate
+    /// system, and the result is a matrix. Singular matrices generate an
+    /// error
+    pub fn linsolve(self, b: Value) -> CalcResult {
+        let mut m = matrix::to_matrix(&self, "solve", false)?;
+        matrix::check_square(&m, "solve")?;
+        let rhs = matrix::to_matrix(&b, "solve", true)?;
+        let n = m.len();
+        if rhs.len() != n {
+            return Err(CalcError::DimensionMismatch(
+                "solve".to_string(),
+                format!("{} equations and {} right sides", n, rhs.len()),
+            ));
+        }
+        matrix::augment(&mut m, rhs);
+        if matrix::eliminate(&mut m, n)?.pivots.len() < n {
+            return Err(CalcError::SingularMatrix("solve".to_string()));
+        }
+        let is_vector = matches!(&b, Value::List(v) if !matches!(v.first(), Some(Value::List(..))));
+        if is_vector {
+            return Ok(Value::List(m.into_iter().map(|row| row[n].clone()).collect()));
+        }
+        Ok(matrix::from_matrix(m.into_iter().map(|row| row[n..].to_vec()).collect()))
+    }
+
+    /// Dot product of two vectors of the same length
+    pub fn dot(self, v: Value) -> CalcResult {
+        let (a, b) = matrix::vector_pair(&self, &v, "dot")?;
+        matrix::dot(&a, &b)
+    }
+
+    /// Cross product of two 3-dimensional vectors
+    pub fn cross(self, v: Value) -> CalcResult {
+        let (a, b) = matrix::vector_pair(&self, &v, "cross")?;
+        if a.len() != 3 {
+            return Err(CalcError::DimensionMismatch(
+                "cross".to_string(),
+                format!("vectors must have 3 elements, not {}", a.len()),
+            ));
+        }
+        let mut res = Vec::with_capacity(3);
+        for i in 0..3 {
+            let (j, k) = ((i + 1) % 3, (i + 2) % 3);
+            let p = a[j].clone().multiply(b[k].clone())?;
+            res.push(p.subtract(a[k].clone().multiply(b[j].clone())?)?);
+        }
+        Ok(Value::List(res))
+    }
+
+    /// Euclidean length of a vector. It is exact if the square of the
+    /// length is a perfect square: `vnorm([3; 4])` = `5`
+    pub fn vnorm(self) -> CalcResult {
+        let a = matrix::to_vector(&self, "vnorm")?;
+        matrix::dot(&a, &a)?.sqrt()
+    }
+
+    /// Angle between two vectors in radians, from `0` to `PI`
+    pub fn angle(self, v: Value) -> CalcResult {
+        let (a, b) = matrix::vector_pair(&self, &v, "angle")?;
+        let la = matrix::dot(&a, &a)?.as_f64()?;
+        let lb = matrix::dot(&b, &b)?.as_f64()?;
+        if la == 0.0 || lb == 0.0 {
+            return Err(CalcError::InvalidAgrument("angle".to_string(), "zero vector".to_string()));
+        }
+        let cos = matrix::dot(&a, &b)?.as_f64()? / (la.sqrt() * lb.sqrt());
+        // rounding errors can move the cosine of parallel vectors out of [-1..1]
+        Ok(Value::Float(fmath::acos(cos.clamp(-1.0, 1.0))))
+    }
+
+    #[cfg(feature = "special")]
+    /// Cumulative distribution function of Student's t-distribution:
+    /// `tcdf(x; df)`, where `df` is the number of degrees of freedom
+    pub fn tcdf(self, df: Value) -> CalcResult {
+        let x = real_arg(&self, "tcdf")?;
+        let df = dof_arg(&df, "tcdf")?;
+        match special::t_cdf(x, df) {
+            Some(v) => Ok(Value::Float(v)),
+            None => Err(CalcError::InvalidAgrument("tcdf".to_string(), self.to_string())),
+        }
+    }
+
+    #[cfg(feature = "special")]
+    /// Inverse of `tcdf`: `tinv(p; df)` is `x` such that `tcdf(x; df) = p`.
+    /// The probability must be in the range `(0..1)`
+    pub fn tinv(self, df: Value) -> CalcResult {
+        let p = prob_arg(&self, "tinv")?;
+        let df = dof_arg(&df, "tinv")?;
+        match special::t_inv(p, df) {
+            Some(v) => Ok(Value::Float(v)),
+            None => Err(CalcError::ArgumentOutOfRange("tinv".to_string(), self.to_string(), "(0..1)".to_string())),
+        }
+    }
+
+    #[cfg(feature = "special")]
+    /// Cumulative distribution function of chi-squared distribution:
+    /// `chi2cdf(x; df)`, where `df` is the number of degrees of freedom
+    pub fn chi2cdf(self, df: Value) -> CalcResult {
+        let x = real_arg(&self, "chi2cdf")?;
+        let df = dof_arg(&df, "chi2cdf")?;
+        match special::chi2_cdf(x, df) {
+            Some(v) => Ok(Value::Float(v)),
+            None => Err(CalcError::InvalidAgrument("chi2cdf".to_string(), self.to_string())),
+        }
+    }
+
+    #[cfg(feature = "special")]
+    /// Inverse of `chi2cdf`: `chi2inv(p; df)` is `x` such that
+    /// `chi2cdf(x; df) = p`. The probability must be in the range `[0..1)`
+    pub fn chi2inv(self, df: Value) -> CalcResult {
+        let p = prob_arg(&self, "chi2inv")?;
+        let df = dof_arg(&df, "chi2inv")?;
+        match special::chi2_inv(p, df) {
+            Some(v) => Ok(Value::Float(v)),
+            None => Err(CalcError::ArgumentOutOfRange("chi2inv".to_string(), self.to_string(), "[0..1)".to_string())),
+        }
+    }
+
+    /// Converts polar coordinates(the number is the radius, `theta` is
+    /// the angle in radians) to cartesian ones. Returns a list `[x; y]`.
+    /// Complex numbers generate an error
+    pub fn tocart(self, theta: Value) -> CalcResult {
+        if let (Value::Complex(..), ..) | (.., Value::Complex(..)) = (&self, &theta) {
+            return Err(CalcError::NotForComplex("tocart".to_string()));
+        }
+        let x = self.clone().multiply(theta.clone().cos()?)?;
+        let y = self.multiply(theta.sin()?)?;
+        Ok(Value::List(vec![x, y]))
+    }
+
+    #[cfg(feature = "special")]
+    /// Lambert W function: the solution of `w * exp(w) = x`. `branch` is
+    /// either `0`(the principal branch, `x >= -1/e`) or `-1`(`-1/e <= x < 0`).
+    /// Complex numbers generate an error
+    pub fn lambertw(self, branch: Value) -> CalcResult {
+        if let Value::Complex(..) = self {
+            return Err(CalcError::NotForComplex("lambertw".to_string()));
+        }
+        let k = match branch.as_i64() {
+            Ok(k @ (0 | -1)) => k as i32,
+            _ => return Err(CalcError::InvalidAgrument("lambertw".to_string(), format!("branch {}", branch))),
+        };
+        let f = self.clone().into_raw_f64()?;
+        match special::lambertw(f, k) {
+            Some(w) => Ok(Value::Float(w)),
+            None => Err(CalcError::InvalidAgrument("lambertw".to_string(), format!("{}", self))),
+        }
+    }
+
+    #[cfg(feature = "special")]
+    /// Bessel function of the first kind `J_n(x)`: the number is the order
+    /// `n` that must be an integer. Complex numbers generate an error
+    pub fn besselj(self, x: Value) -> CalcResult {
+        self.bessel(x, "besselj", special::besselj)
+    }
+
+    #[cfg(feature = "special")]
+    /// Bessel function of the second kind `Y_n(x)`: the number is the order
+    /// `n` that must be an integer, `x` must be positive. Complex numbers
+    /// generate an error
+    pub fn bessely(self, x: Value) -> CalcResult {
+        self.bessel(x, "bessely", special::bessely)
+    }
+
+    #[cfg(feature = "special")]
+    fn bessel(self, x: Value, name: &str, f: fn(i64, f64) -> Option<f64>) -> CalcResult {
+        if let (Value::Complex(..), ..) | (.., Value::Complex(..)) = (&self, &x) {
+            return Err(CalcError::NotForComplex(name.to_string()));
+        }
+        let n = match self {
+            Value::Int(ref i) => match i.to_i64() {
+                Some(n) => n,
+                None => return Err(CalcError::InvalidAgrument(name.to_string(), format!("order {}", self))),
+            },
+            _ => return Err(CalcError::OnlyInt(name.to_string())),
+        };
+        let xf = x.clone().into_raw_f64()?;
+        match f(n, xf) {
+            Some(v) => Ok(Value::Float(v)),
+            None => Err(CalcError::InvalidAgrument(name.to_string(), format!("{}", x))),
+        }
+    }
+
+    #[cfg(feature = "special")]
+    /// Riemann zeta function of a real number. `zeta(1)` is a pole and
+    /// generates an error. Complex numbers generate an error
+    pub fn zeta(self) -> CalcResult {
+        if let Value::Complex(..) = self {
+            return Err(CalcError::NotForComplex("zeta".to_string()));
+        }
+        let f = self.clone().into_raw_f64()?;
+        match special::zeta(f) {
+            Some(z) => Ok(Value::Float(z)),
+            None => Err(CalcError::InvalidAgrument("zeta".to_string(), format!("{}", self))),
+        }
+    }
+
+    /// Harmonic number `H(n) = 1 + 1/2 + ... + 1/n`. For an integer the
+    /// result is an exact fraction, and `n` must be in the range
+    /// `[0..10_000]`. Other numbers are extended with digamma function:
+    /// `H(x) = digamma(x + 1) + gamma`, where `gamma` is Euler-Mascheroni
+    /// constant. Complex numbers generate an error
+    pub fn harmonic(self) -> CalcResult {
+        match &self {
+            Value::Complex(..) => Err(CalcError::NotForComplex("harmonic".to_string())),
+            Value::Int(i) => {
+                if i.is_negative() {
+                    return Err(CalcError::NotForNegativeInt("harmonic".to_string()));
+                }
+                let n = match i.to_u64() {
+                    Some(n) if n <= HARMONIC_MAX => n,
+                    _ => {
+                        let s = format!("{}", i);
+                        return Err(CalcError::ArgumentOutOfRange(
+                            "harmonic".to_string(),
+                            s,
+                            "[0..10_000]".to_string(),
+                        ));
+                    }
+                };
+                if n == 0 {
+                    return Ok(Value::Int(BigInt::zero()));
+                }
+                let (p, q) = harmonic_sum(1, n + 1);
+                let r = BigRational::new(p, q);
+                if r.is_integer() {
+                    return Ok(Value::Int(r.to_integer()));
+                }
+                Ok(Value::Ratio(r))
+            }
+            _ => {
+                let f = self.clone().into_raw_f64()?;
+                match special::harmonic(f) {
+                    Some(h) => Ok(Value::Float(h)),
+                    None => Err(CalcError::InvalidAgrument("harmonic".to_string(), format!("{}", self))),
+                }
+            }
+        }
+    }
+
+    /// Signed Stirling number of the first kind `s(n, k)`: the number is `n`.
+    /// Its absolute value is the number of permutations of `n` elements
+    /// with `k` cycles. Both arguments must be non-negative integers
+    pub fn stirling1(self, k: Value) -> CalcResult {
+        let n = bounded_int_arg(&self, "stirling1", COMBINATORICS_MAX)?;
+        let k = bounded_int_arg(&k, "stirling1", COMBINATORICS_MAX)?;
+        if k > n {
+            return Ok(Value::Int(BigInt::zero()));
+        }
+        // s(i+1, j) = s(i, j-1) - i * s(i, j)
+        let mut row = vec![BigInt::zero(); k + 1];
+        row[0] = BigInt::one();
+        for i in 0..n {
+            for j in (1..=k.min(i + 1)).rev() {
+                row[j] = &row[j - 1] - &row[j] * i;
+            }
+            row[0] = -&row[0] * i;
+        }
+        Ok(Value::Int(row.swap_remove(k)))
+    }
+
+    /// Stirling number of the second kind `S(n, k)`: the number is `n`. It is
+    /// the number of ways to split `n` elements into `k` non-empty subsets.
+    /// Both arguments must be non-negative integers
+    pub fn stirling2(self, k: Value) -> CalcResult {
+        let n = bounded_int_arg(&self, "stirling2", COMBINATORICS_MAX)?;
+        let k = bounded_int_arg(&k, "stirling2", COMBINATORICS_MAX)?;
+        if k > n {
+            return Ok(Value::Int(BigInt::zero()));
+        }
+        // S(i+1, j) = j * S(i, j) + S(i, j-1)
+        let mut row = vec![BigInt::zero(); k + 1];
+        row[0] = BigInt::one();
+        for i in 0..n {
+            for j in (1..=k.min(i + 1)).rev() {
+                row[j] = &row[j] * j + &row[j - 1];
+            }
+            row[0] = BigInt::zero();
+        }
+        Ok(Value::Int(row.swap_remove(k)))
+    }
+
+    /// Bell number: the number of ways to split a set of `n` elements into
+    /// non-empty subsets. The argument must be a non-negative integer
+    pub fn bell(self) -> CalcResult {
+        let n = bounded_int_arg(&self, "bell", COMBINATORICS_MAX)?;
+        // Bell triangle: every row starts with the last item of the previous
+        // row, the first items of rows are Bell numbers
+        let mut row = vec![BigInt::one()];
+        for _ in 0..n {
+            let mut next = Vec::with_capacity(row.len() + 1);
+            next.push(row[row.len() - 1].clone());
+            for (i, v) in row.iter().enumerate() {
+                let item = &next[i] + v;
+                next.push(item);
+            }
+            row = next;
+        }
+        Ok(Value::Int(row.swap_remove(0)))
+    }
+
+    /// Primorial `n#`: the product of all primes not greater than `n`. The
+    /// argument must be an integer in the range `[0..100000]`
+    pub fn primorial(self) -> CalcResult {
+        let n = bounded_int_arg(&self, "primorial", INT_SEQ_MAX)?;
+        // sieve of Eratosthenes
+        let mut composite = vec![false; n + 1];
+        let mut primes = Vec::new();
+        for i in 2..=n {
+            if composite[i] {
+                continue;
+            }
+            primes.push(i as u64);
+            for j in (i * i..=n).step_by(i) {
+                composite[j] = true;
+            }
+        }
+        Ok(Value::Int(range_product(&primes)))
+    }
+
+    /// Multifactorial `n!(k)`: the product `n * (n-k) * (n-2k) * ...` of
+    /// positive numbers, e.g. `multifact(n; 2)` is double factorial. `n` must
+    /// be an integer in the range `[0..100000]`, `k` must be a positive integer
+    pub fn multifact(self, k: Value) -> CalcResult {
+        let n = bounded_int_arg(&self, "multifact", INT_SEQ_MAX)?;
+        let step = match &k {
+            Value::Int(i) if i.is_positive() => i.to_usize().unwrap_or(usize::MAX),
+            Value::Int(..) => return Err(CalcError::InvalidAgrument("multifact".to_string(), format!("step {}", k))),
+            _ => return Err(CalcError::OnlyInt("multifact".to_string())),
+        };
+        let nums: Vec<u64> = (1..=n).rev().step_by(step).map(|i| i as u64).collect();
+        Ok(Value::Int(range_product(&nums)))
+    }
+
+    /// The number of steps of Collatz sequence(`n/2` for even numbers and
+    /// `3n+1` for odd ones) to reach `1`. The argument must be a positive
+    /// integer. An error is returned if the sequence does not reach `1` in
+    /// a million steps
+    pub fn collatz(self) -> CalcResult {
+        let mut n = match &self {
+            Value::Int(i) if i.is_positive() => i.clone(),
+            Value::Int(i) if i.is_negative() => return Err(CalcError::NotForNegativeInt("collatz".to_string())),
+            Value::Int(..) => return Err(CalcError::InvalidAgrument("collatz".to_string(), "0".to_string())),
+            _ => return Err(CalcError::OnlyInt("collatz".to_string())),
+        };
+        let mut steps = 0u64;
+        while !n.is_one() {
+            if steps >= COLLATZ_MAX_STEPS {
+                let detail = format!("{}(more than {} steps)", self, COLLATZ_MAX_STEPS);
+                return Err(CalcError::InvalidAgrument("collatz".to_string(), detail));
+            }
+            if (&n % 2u32).is_zero() {
+                n >>= 1;
+            } else {
+                n = n * 3u32 + 1u32;
+            }
+            steps += 1;
+        }
+        Ok(Value::Int(BigInt::from(steps)))
+    }
+
+    /// Sum of digits of an integer in the given base. The sign is ignored
+    pub fn digitsum(self, base: Value) -> CalcResult {
+        let (n, base) = digits_args(self, base, "digitsum")?;
+        let sum = int_digits(&n, &base).into_iter().fold(BigInt::zero(), |acc, d| acc + d);
+        Ok(Value::Int(sum))
+    }
+
+    /// The number of digits of an integer in the given base. Zero has one
+    /// digit, the sign is ignored
+    pub fn numdigits(self, base: Value) -> CalcResult {
+        let (n, base) = digits_args(self, base, "numdigits")?;
+        Ok(Value::Int(BigInt::from(int_digits(&n, &base).len())))
+    }
+
+    /// Reverses the order of digits of an integer in the given base, e.g.
+    /// `revdigits(-1230)` = `-321`. The sign is kept
+    pub fn revdigits(self, base: Value) -> CalcResult {
+        let (n, base) = digits_args(self, base, "revdigits")?;
+        let rev = int_digits(&n, &base).into_iter().fold(BigInt::zero(), |acc, d| acc * &base + d);
+        Ok(Value::Int(if n.is_negative() { -rev } else { rev }))
+    }
+
+    /// Converts an integer in the range `[1..3999]` to a string with
+    /// Roman numeral: `roman(2024)` = `"MMXXIV"`
+    pub fn roman(self) -> CalcResult {
+        let i = match &self {
+            Value::Int(i) => i,
+            Value::Str(..) => return Err(CalcError::NotForString("roman".to_string())),
+            Value::Poly(..) => return Err(CalcError::NotForPoly("roman".to_string())),
+            _ => return Err(CalcError::OnlyInt("roman".to_string())),
+        };
+        match i.to_u32() {
+            Some(n @ 1..=3999) => Ok(Value::Str(to_roman(n))),
+            _ => Err(CalcError::ArgumentOutOfRange("roman".to_string(), i.to_string(), "[1..3999]".to_string())),
+        }
+    }
+
+    /// Converts a string with Roman numeral to an integer. The numeral is
+    /// caseinsensitive, but it must be written in the standard form:
+    /// `"mcmxc"` is accepted, `"MCCCCC"` and `"IM"` are not
+    pub fn unroman(self) -> CalcResult {
+        let s = match &self {
+            Value::Str(s) => s.trim().to_uppercase(),
+            _ => return Err(CalcError::InvalidAgrument("unroman".to_string(), self.to_string())),
+        };
+        let mut rest = s.as_str();
+        let mut n = 0u32;
+        for (r, v) in ROMAN_DIGITS.iter() {
+            while let Some(tail) = rest.strip_prefix(r) {
+                n += v;
+                rest = tail;
+            }
+        }
+        // the round trip rejects non-standard forms like `IIII` or `VX`
+        if !rest.is_empty() || n == 0 || n > 3999 || to_roman(n) != s {
+            return Err(CalcError::InvalidAgrument("unroman".to_string(), self.to_string()));
+        }
+        Ok(Value::Int(BigInt::from(n)))
+    }
+
+    /// Returns English words for an integer: `spell(1234)` = `"one thousand
+    /// two hundred thirty-four"`. `locale` is either `"en"`(or `"en-us"`) or
+    /// `"en-gb"` that adds `and` after hundreds: `"one hundred and five"`.
+    /// Numbers up to decillions(`10^36 - 1`) are supported
+    pub fn spell(self, locale: Value) -> CalcResult {
+        let british = match &locale {
+            Value::Str(s) => match s.to_lowercase().replace('_', "-").as_str() {
+                "en" | "en-us" => false,
+                "en-gb" => true,
+                _ => return Err(CalcError::InvalidAgrument("spell".to_string(), format!("locale {}", s))),
+            },
+            _ => return Err(CalcError::InvalidAgrument("spell".to_string(), format!("locale {}", locale))),
+        };
+        let i = match &self {
+            Value::Int(i) => i,
+            Value::Str(..) => return Err(CalcError::NotForString("spell".to_string())),
+            Value::Poly(..) => return Err(CalcError::NotForPoly("spell".to_string())),
+            _ => return Err(CalcError::OnlyInt("spell".to_string())),
+        };
+        if i.is_zero() {
+            return Ok(Value::Str(SMALL_NUMBER_NAMES[0].to_string()));
+        }
+        let limit = BigInt::from(1000).pow(SCALE_NAMES.len() as u32);
+        if i.abs() >= limit {
+            let range = format!("[-{0}..{0}]", limit - 1);
+            return Err(CalcError::ArgumentOutOfRange("spell".to_string(), i.to_string(), range));
+        }
+
+        let mut groups = Vec::new();
+        let mut n = i.abs();
+        let thousand = BigInt::from(1000);
+        while !n.is_zero() {
+            groups.push((&n % &thousand).to_u32().unwrap_or(0));
+            n /= &thousand;
+        }
+        let mut words = Vec::new();
+        if i.is_negative() {
+            words.push("minus".to_string());
+        }
+        for (scale, &g) in groups.iter().enumerate().rev() {
+            if g == 0 {
+                continue;
+            }
+            // British English: `one thousand and five`
+            if scale == 0 && british && g < 100 && groups.len() > 1 {
+                words.push("and".to_string());
+            }
+            words.push(spell_hundreds(g, british));
+            if scale > 0 {
+                words.push(SCALE_NAMES[scale].to_string());
+            }
+        }
+        Ok(Value::Str(words.join(" ")))
+    }
+
+    /// Integer logarithm `floor(log_b(n))` calculated exactly, without float
+    /// numbers. Both arguments must be integers, `n > 0` and `b > 1`
+    pub fn ilog(self, base: Value) -> CalcResult {
+        self.ilog_named(base, "ilog")
+    }
+
+    /// Integer binary logarithm: the position of the highest set bit
+    pub fn ilog2(self) -> CalcResult {
+        self.ilog_named(Value::Int(BigInt::from(2)), "ilog2")
+    }
+
+    /// Integer decimal logarithm: the number of decimal digits minus one
+    pub fn ilog10(self) -> CalcResult {
+        self.ilog_named(Value::Int(BigInt::from(10)), "ilog10")
+    }
+
+    fn ilog_named(self, base: Value, name: &str) -> CalcResult {
+        let (n, b) = match (&self, &base) {
+            (Value::Int(n), Value::Int(b)) => (n, b),
+            _ => return Err(CalcError::OnlyInt(name.to_string())),
+        };
+        if !n.is_positive() {
+            return Err(CalcError::InvalidAgrument(name.to_string(), n.to_string()));
+        }
+        if *b <= BigInt::one() {
+            return Err(CalcError::InvalidAgrument(name.to_string(), format!("base {}", b)));
+        }
+        Ok(Value::Int(BigInt::from(int_log(n, b))))
+    }
+
+    /// The smallest power of two that is not less than the number:
+    /// `nextpow2(100)` = `128`, `nextpow2(64)` = `64`. The number must be
+    /// a non-negative integer
+    pub fn nextpow2(self) -> CalcResult {
+        let n = match &self {
+            Value::Int(n) if n.is_negative() => return Err(CalcError::NotForNegativeInt("nextpow2".to_string())),
+            Value::Int(n) => n,
+            _ => return Err(CalcError::OnlyInt("nextpow2".to_string())),
+        };
+        if *n <= BigInt::one() {
+            return Ok(Value::Int(BigInt::one()));
+        }
+        let bits = (n - 1u32).bits();
+        Ok(Value::Int(BigInt::one() << bits))
+    }
+
+    /// Two's-complement interpretation of the lowest `width` bits of the
+    /// integer as a signed number: `signed(0xFF; 8)` = `-1`,
+    /// `signed(0x7F; 8)` = `127`
+    pub fn signed(self, width: Value) -> CalcResult {
+        let w = width_arg(&width, "signed")?;
+        let i = match &self {
+            Value::Int(i) => low_bits(i, w),
+            _ => return Err(CalcError::OnlyInt("signed".to_string())),
+        };
+        if (&i >> (w - 1)).is_zero() {
+            return Ok(Value::Int(i));
+        }
+        Ok(Value::Int(i - (BigInt::one() << w)))
+    }
+
+    /// The lowest `width` bits of the integer as an unsigned number:
+    /// `unsigned(-1; 8)` = `255`, `unsigned(0x1FF; 8)` = `255`
+    pub fn unsigned(self, width: Value) -> CalcResult {
+        let w = width_arg(&width, "unsigned")?;
+        match &self {
+            Value::Int(i) => Ok(Value::Int(low_bits(i, w))),
+            _ => Err(CalcError::OnlyInt("unsigned".to_string())),
+        }
+    }
+
+    /// Extracts the bit field from bit `hi` down to bit `lo`, both
+    /// inclusive: `bits(0xABCD; 11; 4)` = `0xBC`. Negative integers are
+    /// in two's-complement form
+    pub fn bits(self, hi: Value, lo: Value) -> CalcResult {
+        let (hi, lo) = bit_range_args(&hi, &lo, "bits")?;
+        match &self {
+            Value::Int(i) => Ok(Value::Int(low_bits(&(i >> lo), hi - lo + 1))),
+            _ => Err(CalcError::OnlyInt("bits".to_string())),
+        }
+    }
+
+    /// Replaces the bit field from bit `hi` down to bit `lo` with `v`:
+    /// `setbits(0xFF00; 7; 4; 0xA)` = `0xFFA0`. The value must fit the field
+    pub fn setbits(self, hi: Value, lo: Value, v: Value) -> CalcResult {
+        let (hi, lo) = bit_range_args(&hi, &lo, "setbits")?;
+        let (i, v) = match (&self, &v) {
+            (Value::Int(i), Value::Int(v)) => (i, v),
+            _ => return Err(CalcError::OnlyInt("setbits".to_string())),
+        };
+        let width = hi - lo + 1;
+        let max = (BigInt::one() << width) - 1u32;
+        if v.is_negative() || *v > max {
+            return Err(CalcError::ArgumentOutOfRange("setbits".to_string(), v.to_string(), format!("[0..{}]", max)));
+        }
+        let mask = max << lo;
+        Ok(Value::Int((i & !mask) | (v << lo)))
+    }
+
+    // reverses the order of bytes in the lowest `width` bits
+    fn byte_swap(&self, width: usize, name: &str) -> CalcResult {
+        let i = match self {
+            Value::Int(i) => low_bits(i, width),
+            _ => return Err(CalcError::OnlyInt(name.to_string())),
+        };
+        let mut bytes = i.to_bytes_le().1;
+        bytes.resize(width / 8, 0);
+        Ok(Value::Int(BigInt::from_bytes_be(Sign::Plus, &bytes)))
+    }
+
+    /// Reverses the order of bytes in the lowest `width` bits of the
+    /// integer: `bswap(0x123456; 24)` = `0x563412`. The width must be
+    /// a multiple of 8
+    pub fn bswap(self, width: Value) -> CalcResult {
+        let w = width_arg(&width, "bswap")?;
+        if w % 8 != 0 {
+            return Err(CalcError::InvalidAgrument("bswap".to_string(), format!("width {} is not a multiple of 8", w)));
+        }
+        self.byte_swap(w, "bswap")
+    }
+
+    /// Swaps the bytes of a 16-bit integer: `bswap16(0x1234)` = `0x3412`
+    pub fn bswap16(self) -> CalcResult {
+        self.byte_swap(16, "bswap16")
+    }
+
+    /// Reverses the byte order of a 32-bit integer
+    pub fn bswap32(self) -> CalcResult {
+        self.byte_swap(32, "bswap32")
+    }
+
+    /// Reverses the byte order of a 64-bit integer
+    pub fn bswap64(self) -> CalcResult {
+        self.byte_swap(64, "bswap64")
+    }
+
+    /// CRC-32 checksum. For a string, the checksum of its UTF-8 bytes is
+    /// calculated. For a non-negative integer, the checksum of its bytes in
+    /// big-endian order: `crc32(0x1234)` is the checksum of two bytes `0x12`
+    /// and `0x34`
+    pub fn crc32(self) -> CalcResult {
+        let bytes = hash_bytes(&self, "crc32")?;
+        Ok(Value::Int(BigInt::from(crc32_bytes(&bytes))))
+    }
+
+    /// Hexadecimal form of the UTF-8 bytes of a string: `hex("Hi")` =
+    /// `"4869"`. Integers are shown in hexadecimal form by function `hex`
+    /// without converting them to strings
+    pub fn hexstr(self) -> CalcResult {
+        match &self {
+            Value::Str(s) => Ok(Value::Str(encoding::hex_encode(s.as_bytes()))),
+            _ => Err(CalcError::InvalidAgrument("hex".to_string(), self.to_string())),
+        }
+    }
+
+    /// Converts a hexadecimal string to an integer: `unhex("ff")` = `255`.
+    /// The string can start with `0x`, and spaces and underscores between
+    /// digits are ignored: `unhex("de ad be ef")`
+    pub fn unhex(self) -> CalcResult {
+        let s = match &self {
+            Value::Str(s) => s,
+            _ => return Err(CalcError::InvalidAgrument("unhex".to_string(), self.to_string())),
+        };
+        let t = s.trim();
+        let t = t.strip_prefix("0x").or_else(|| t.strip_prefix("0X")).unwrap_or(t);
+        let digits: String = t.chars().filter(|c| *c != ' ' && *c != '_').collect();
+        if digits.is_empty() || !digits.chars().all(|c| c.is_ascii_hexdigit()) {
+            return Err(CalcError::InvalidAgrument("unhex".to_string(), s.to_string()));
+        }
+        // all characters are hexadecimal digits, so parsing never fails
+        Ok(Value::Int(BigInt::parse_bytes(digits.as_bytes(), 16).unwrap()))
+    }
+
+    /// Base64 form of a string or an integer. The bytes are the same as
+    /// for `crc32`: `b64("foo")` = `"Zm9v"`, `b64(0x666f6f)` = `"Zm9v"`
+    pub fn b64(self) -> CalcResult {
+        let bytes = hash_bytes(&self, "b64")?;
+        Ok(Value::Str(encoding::b64_encode(&bytes)))
+    }
+
+    /// Decodes a Base64 string. The result is a string if the decoded
+    /// bytes are a valid UTF-8 text without control characters(except
+    /// whitespace), and an integer made of bytes in big-endian order
+    /// otherwise: `unb64("Zm9v")` = `"foo"`,
+    /// `unb64("/w==")` = `255`
+    pub fn unb64(self) -> CalcResult {
+        let s = match &self {
+            Value::Str(s) => s,
+            _ => return Err(CalcError::InvalidAgrument("unb64".to_string(), self.to_string())),
+        };
+        let bytes = match encoding::b64_decode(s) {
+            Some(b) => b,
+            None => return Err(CalcError::InvalidAgrument("unb64".to_string(), s.to_string())),
+        };
+        match String::from_utf8(bytes) {
+            Ok(text) if !text.chars().any(|c| c.is_control() && !c.is_whitespace()) => Ok(Value::Str(text)),
+            Ok(text) => Ok(Value::Int(BigInt::from_bytes_be(Sign::Plus, text.as_bytes()))),
+            Err(e) => Ok(Value::Int(BigInt::from_bytes_be(Sign::Plus, e.as_bytes()))),
+        }
+    }
+
+    /// 64-bit FNV-1a hash of a string or an integer. The bytes are the same
+    /// as for `crc32`
+    pub fn fnv1a(self) -> CalcResult {
+        const OFFSET: u64 = 0xCBF2_9CE4_8422_2325;
+        const PRIME: u64 = 0x0100_0000_01B3;
+        let bytes = hash_bytes(&self, "fnv1a")?;
+        let h = bytes.iter().fold(OFFSET, |h, b| (h ^ u64::from(*b)).wrapping_mul(PRIME));
+        Ok(Value::Int(BigInt::from(h)))
+    }
+
+    /// 32-bit MurmurHash3(x86 variant, seed `0`) of a string or an integer.
+    /// The bytes are the same as for `crc32`
+    pub fn murmur3(self) -> CalcResult {
+        let bytes = hash_bytes(&self, "murmur3")?;
+        Ok(Value::Int(BigInt::from(murmur3_bytes(&bytes))))
+    }
+
+    /// SHA-256 digest of a string or an integer as a 256-bit integer. The
+    /// bytes are the same as for `crc32`
+    #[cfg(feature = "sha256")]
+    pub fn sha256(self) -> CalcResult {
+        use sha2::{Digest, Sha256};
+        let bytes = hash_bytes(&self, "sha256")?;
+        Ok(Value::Int(BigInt::from_bytes_be(Sign::Plus, &Sha256::digest(&bytes))))
+    }
+
+    /// Luhn checksum validation(credit card numbers, IMEI): returns `1` if
+    /// the last digit of the number is the correct check digit, and `0`
+    /// otherwise. A number can be a non-negative integer or a string of
+    /// digits. Spaces and dashes in a string are ignored
+    pub fn luhn(self) -> CalcResult {
+        let digits: Vec<u32> = match &self {
+            Value::Int(i) if i.is_negative() => return Err(CalcError::NotForNegativeInt("luhn".to_string())),
+            Value::Int(i) => i.to_str_radix(10).chars().filter_map(|c| c.to_digit(10)).collect(),
+            Value::Str(s) => {
+                let s: String = s.chars().filter(|c| *c != ' ' && *c != '-').collect();
+                match s.chars().map(|c| c.to_digit(10)).collect::<Option<Vec<u32>>>() {
+                    Some(d) if !d.is_empty() => d,
+                    _ => return Err(CalcError::InvalidAgrument("luhn".to_string(), self.to_string())),
+                }
+            }
+            _ => return Err(CalcError::OnlyInt("luhn".to_string())),
+        };
+        let sum: u32 = digits
+            .iter()
+            .rev()
+            .enumerate()
+            .map(|(idx, &d)| {
+                if idx % 2 == 0 {
+                    d
+                } else if d * 2 > 9 {
+                    d * 2 - 9
+                } else {
+                    d * 2
+                }
+            })
+            .sum();
+        Ok(Value::Int(BigInt::from(u8::from(sum.is_multiple_of(10)))))
+    }
+
+    #[cfg(feature = "units")]
+    /// Formats a number of seconds as a duration string with hours,
+    /// minutes, and seconds: `duration(5430)` = `1h30m30s`. Fractional
+    /// seconds are rounded to milliseconds. The result can be used as a
+    /// duration literal if it has no fractional seconds
+    pub fn duration(self) -> CalcResult {
+        let ms = match &self {
+            Value::Int(i) => i * 1000,
+            Value::Str(..) => return Err(CalcError::NotForString("duration".to_string())),
+            Value::Poly(..) => return Err(CalcError::NotForPoly("duration".to_string())),
+            Value::List(..) => return Err(CalcError::NotForList("duration".to_string())),
+            _ => {
+                let f = self.as_f64()?;
+                f64_to_int((f * 1000.0).round())?
+            }
+        };
+        if ms.is_zero() {
+            return Ok(Value::Str("0s".to_string()));
+        }
+        let mut s = if ms.is_negative() { "-".to_string() } else { String::new() };
+        let ms = ms.abs();
+        let hours: BigInt = &ms / 3_600_000;
+        let mins: BigInt = &ms % 3_600_000 / 60_000;
+        let secs: BigInt = &ms % 60_000 / 1000;
+        let rest: BigInt = &ms % 1000;
+        if !hours.is_zero() {
+            s += &format!("{}h", hours);
+        }
+        if !mins.is_zero() {
+            s += &format!("{}m", mins);
+        }
+        if !rest.is_zero() {
+            let frac = format!("{:03}", rest);
+            s += &format!("{}.{}s", secs, frac.trim_end_matches('0'));
+        } else if !secs.is_zero() {
+            s += &format!("{}s", secs);
+        }
+        Ok(Value::Str(s))
+    }
+
+    #[cfg(feature = "datetime")]
+    /// Converts a date string to Unix timestamp: `unix("1970-01-02")` =
+    /// `86400`. The result is a float number if the date has fractions of
+    /// a second. See module `datetime` for supported date formats
+    pub fn unix(self) -> CalcResult {
+        let dt = match &self {
+            Value::Str(s) => match datetime::parse(s) {
+                Some(dt) => dt,
+                None => return Err(CalcError::InvalidAgrument("unix".to_string(), s.to_string())),
+            },
+            Value::List(..) => return Err(CalcError::NotForList("unix".to_string())),
+            _ => return Err(CalcError::InvalidAgrument("unix".to_string(), self.to_string())),
+        };
+        let nanos = dt.timestamp_subsec_nanos();
+        if nanos == 0 {
+            Ok(Value::Int(BigInt::from(dt.timestamp())))
+        } else {
+            Ok(Value::Float(dt.timestamp() as f64 + f64::from(nanos) / 1e9))
+        }
+    }
+
+    #[cfg(feature = "datetime")]
+    /// Converts Unix timestamp to UTC date string:
+    /// `fromunix(86400)` = `1970-01-02T00:00:00Z`
+    pub fn fromunix(self) -> CalcResult {
+        if let Value::Str(..) = &self {
+            return Err(CalcError::NotForString("fromunix".to_string()));
+        }
+        let (secs, nanos) = timestamp_arg(&self, "fromunix")?;
+        match datetime::format_utc(secs, nanos) {
+            Some(s) => Ok(Value::Str(s)),
+            None => Err(CalcError::InvalidAgrument("fromunix".to_string(), self.to_string())),
+        }
+    }
+
+    #[cfg(feature = "datetime")]
+    /// Converts a date to the local time of the time zone:
+    /// `tz("2024-03-01 12:00"; "Europe/Stockholm")` =
+    /// `2024-03-01T13:00:00+01:00`. A date can be a string or Unix
+    /// timestamp, a time zone is a name from IANA time zone database
+    pub fn tz(self, zone: Value) -> CalcResult {
+        let dt = date_arg(&self, "tz")?;
+        let zone = match &zone {
+            Value::Str(s) => s,
+            _ => return Err(CalcError::InvalidAgrument("tz".to_string(), zone.to_string())),
+        };
+        match datetime::in_timezone(&dt, zone) {
+            Some(s) => Ok(Value::Str(s)),
+            None => Err(CalcError::InvalidAgrument("tz".to_string(), zone.to_string())),
+        }
+    }
+
+    #[cfg(feature = "datetime")]
+    /// ISO day of the week of a date: from `1`(Monday) to `7`(Sunday).
+    /// A date can be a string or Unix timestamp
+    pub fn weekday(self) -> CalcResult {
+        let dt = date_arg(&self, "weekday")?;
+        Ok(Value::Int(BigInt::from(dt.weekday().number_from_monday())))
+    }
+
+    #[cfg(feature = "datetime")]
+    /// ISO week number of a date: from `1` to `53`. The first days of
+    /// January can belong to the last week of the previous year, see
+    /// `isoyear`
+    pub fn isoweek(self) -> CalcResult {
+        let dt = date_arg(&self, "isoweek")?;
+        Ok(Value::Int(BigInt::from(dt.iso_week().week())))
+    }
+
+    #[cfg(feature = "datetime")]
+    /// The year the ISO week of a date belongs to: `isoyear("2021-01-01")`
+    /// = `2020`
+    pub fn isoyear(self) -> CalcResult {
+        let dt = date_arg(&self, "isoyear")?;
+        Ok(Value::Int(BigInt::from(dt.iso_week().year())))
+    }
+
+    #[cfg(feature = "datetime")]
+    /// The number of days in the month of the year: `daysin(2; 2024)` = `29`
+    pub fn daysin(self, year: Value) -> CalcResult {
+        let month = match &self {
+            Value::Int(i) => match i.to_u32() {
+                Some(m) if (1..=12).contains(&m) => m,
+                _ => {
+                    return Err(CalcError::ArgumentOutOfRange(
+                        "daysin".to_string(),
+                        i.to_string(),
+                        "[1..12]".to_string(),
+                    ))
+                }
+            },
+            _ => return Err(CalcError::OnlyInt("daysin".to_string())),
+        };
+        let days = match &year {
+            Value::Int(i) => i.to_i32().and_then(|y| datetime::days_in_month(month, y)),
+            _ => return Err(CalcError::OnlyInt("daysin".to_string())),
+        };
+        match days {
+            Some(days) => Ok(Value::Int(BigInt::from(days))),
+            None => Err(CalcError::InvalidAgrument("daysin".to_string(), format!("year {}", year))),
+        }
+    }
+
+    #[cfg(feature = "units")]
+    data_unit!(kib, 1024, 1, "Converts kibibytes to bytes: `kib(4)` = `4096`");
+    #[cfg(feature = "units")]
+    data_unit!(mib, 1024, 2, "Converts mebibytes to bytes");
+    #[cfg(feature = "units")]
+    data_unit!(gib, 1024, 3, "Converts gibibytes to bytes");
+    #[cfg(feature = "units")]
+    data_unit!(tib, 1024, 4, "Converts tebibytes to bytes");
+    #[cfg(feature = "units")]
+    data_unit!(kb, 1000, 1, "Converts kilobytes to bytes: `kb(4)` = `4000`");
+    #[cfg(feature = "units")]
+    data_unit!(mb, 1000, 2, "Converts megabytes to bytes");
+    #[cfg(feature = "units")]
+    data_unit!(gb, 1000, 3, "Converts gigabytes to bytes");
+    #[cfg(feature = "units")]
+    data_unit!(tb, 1000, 4, "Converts terabytes to bytes");
+
+    #[cfg(feature = "units")]
+    /// Formats a number of bytes as a string with the biggest unit that
+    /// keeps the number not less than one: `hsize(1536)` = `1.5 KiB`. The
+    /// unit base is `1024`(binary units, default) or `1000`(decimal
+    /// units). The number is rounded to two digits after the point
+    pub fn hsize(self, base: Value) -> CalcResult {
+        let (unit, names) = match &base {
+            Value::Int(i) if *i == BigInt::from(1024) => (1024, BINARY_SIZE_UNITS),
+            Value::Int(i) if *i == BigInt::from(1000) => (1000, DECIMAL_SIZE_UNITS),
+            _ => return Err(CalcError::InvalidAgrument("hsize".to_string(), format!("base {}", base))),
+        };
+        match &self {
+            Value::Str(..) => return Err(CalcError::NotForString("hsize".to_string())),
+            Value::Poly(..) => return Err(CalcError::NotForPoly("hsize".to_string())),
+            Value::List(..) => return Err(CalcError::NotForList("hsize".to_string())),
+            _ => {}
+        }
+        if let Value::Int(i) = &self {
+            if i.abs() < BigInt::from(unit) {
+                return Ok(Value::Str(format!("{} {}", i, names[0])));
+            }
+        }
+        let mut size = self.as_f64()?;
+        let mut idx = 0;
+        while size.abs() >= f64::from(unit) && idx < names.len() - 1 {
+            size /= f64::from(unit);
+            idx += 1;
+        }
+        let mut s = format!("{:.2}", size);
+        if s.contains('.') {
+            s = s.trim_end_matches('0').trim_end_matches('.').to_string();
+        }
+        Ok(Value::Str(format!("{} {}", s, names[idx])))
+    }
+
+    #[cfg(feature = "units")]
+    /// Converts an IPv4 address in dotted-quad form to an integer:
+    /// `ip("192.168.1.10")` = `3232235786`. Integers are checked and
+    /// returned as is
+    pub fn ip(self) -> CalcResult {
+        let a = ipv4_arg(&self, "ip")?;
+        Ok(Value::Int(BigInt::from(a)))
+    }
+
+    #[cfg(feature = "units")]
+    /// Converts an integer to an IPv4 address string in dotted-quad form
+    pub fn ipstr(self) -> CalcResult {
+        let a = ipv4_arg(&self, "ipstr")?;
+        Ok(Value::Str(Ipv4Addr::from(a).to_string()))
+    }
+
+    #[cfg(feature = "units")]
+    /// IPv4 network mask for the prefix length: `netmask(24)` is
+    /// `255.255.255.0`
+    pub fn netmask(self) -> CalcResult {
+        let prefix = ipv4_prefix(&self, "netmask")?;
+        Ok(Value::Int(BigInt::from(ipv4_mask(prefix))))
+    }
+
+    #[cfg(feature = "units")]
+    /// The first address of the IPv4 network: `network(ip; prefix)`. The
+    /// address can be an integer or a string
+    pub fn network(self, prefix: Value) -> CalcResult {
+        let a = ipv4_arg(&self, "network")?;
+        let prefix = ipv4_prefix(&prefix, "network")?;
+        Ok(Value::Int(BigInt::from(a & ipv4_mask(prefix))))
+    }
+
+    #[cfg(feature = "units")]
+    /// The last(broadcast) address of the IPv4 network:
+    /// `broadcast(ip; prefix)`. The address can be an integer or a string
+    pub fn broadcast(self, prefix: Value) -> CalcResult {
+        let a = ipv4_arg(&self, "broadcast")?;
+        let prefix = ipv4_prefix(&prefix, "broadcast")?;
+        Ok(Value::Int(BigInt::from(a | !ipv4_mask(prefix))))
+    }
+
+    /// Euclidean remainder: unlike `%`, the result is never negative,
+    /// `0 <= r < |rhs|`, e.g. `mod(-7; 3)` = `2` while `-7 % 3` = `-1`.
+    /// Complex numbers generate an error
+    pub fn mod_euclid(self, rhs: Value) -> CalcResult {
+        if let (Value::Complex(..), ..) | (.., Value::Complex(..)) = (&self, &rhs) {
+            return Err(CalcError::NotForComplex("mod".to_string()));
+        }
+        let r = self.remainder(rhs.clone())?;
+        if r.is_positive() {
+            return Ok(r);
+        }
+        r.addition(rhs.abs()?)
+    }
+
+    /// Euclidean division: returns a list `[q; r]` where `r` is the result
+    /// of `mod_euclid` and `self = q * rhs + r`. `q` is always an integer
+    pub fn divmod(self, rhs: Value) -> CalcResult {
+        if let (Value::Complex(..), ..) | (.., Value::Complex(..)) = (&self, &rhs) {
+            return Err(CalcError::NotForComplex("divmod".to_string()));
+        }
+        let r = self.clone().mod_euclid(rhs.clone())?;
+        // the division is exact for integers and fractions, float numbers
+        // may be a bit off, so the quotient is rounded
+        let q = self.subtract(r.clone())?.divide(rhs)?.round()?.into_int()?;
+        Ok(Value::List(vec![q, r]))
+    }
+
     /// Is Value a prime number. Returns error if a value is not integer
     /// Naive and slow algorithm for large integers
     pub fn prime(self) -> CalcResult {
@@ -1472,6 +4586,21 @@ mod tests {
         assert_eq!(v, Ok(Value::Float(10.75f64 * consts::PI / 180.0f64)));
     }
     #[test]
+    fn test_duration_str() {
+        let secs = |i: i64| Ok(Value::Int(BigInt::from(i)));
+        assert_eq!(Value::from_str_duration("90s"), secs(90));
+        assert_eq!(Value::from_str_duration("1h30m"), secs(5_400));
+        assert_eq!(Value::from_str_duration("2D4H"), secs(187_200));
+        assert_eq!(Value::from_str_duration("1d0h1m1s"), secs(86_461));
+        assert_eq!(Value::from_str_duration("1_000s"), secs(1_000));
+        assert_eq!(Value::from_str_duration("1h30"), Err(CalcError::StrToInt("1h30".to_string())));
+        assert_eq!(Value::from_str_duration("1.5h"), secs(5_400));
+        assert_eq!(Value::from_str_duration("2,5m"), secs(150));
+        let quarter = BigRational::new(BigInt::from(1), BigInt::from(4));
+        assert_eq!(Value::from_str_duration("0.25s"), Ok(Value::Ratio(quarter)));
+        assert_eq!(Value::from_str_duration("1.2.3s"), Err(CalcError::StrToInt("1.2.3s".to_string())));
+    }
+    #[test]
     fn test_complex_str() {
         let v = Value::from_str_complex("10.0e-1+50e-3i");
         assert_eq!(v, Ok(Value::Complex(Complex::new(1.0f64, 0.05f64))));
@@ -1530,6 +4659,55 @@ mod tests {
         assert_eq!(v.to_string(), "4.5-3.25i");
     }
     #[test]
+    fn test_native_conversion() {
+        assert_eq!(Value::from(-5i64), Value::Int(BigInt::from(-5)));
+        assert_eq!(Value::from(5u64), Value::Int(BigInt::from(5)));
+        assert_eq!(Value::from(2.5f64), Value::Float(2.5));
+        assert_eq!(Value::from(BigInt::from(7)), Value::Int(BigInt::from(7)));
+
+        assert_eq!(Value::Float(3.0).as_i64(), Ok(3));
+        assert_eq!(Value::Ratio(BigRational::new(BigInt::from(6), BigInt::from(3))).as_i64(), Ok(2));
+        assert_eq!(Value::Float(3.5).as_i64(), Err(CalcError::OnlyInt("as_bigint".to_string())));
+        assert!(Value::Int(BigInt::from(-1)).as_u64().is_err());
+        assert!(Value::Int(BigInt::from(u64::MAX) + BigInt::one()).as_i64().is_err());
+        assert_eq!(Value::Ratio(BigRational::new(BigInt::from(1), BigInt::from(4))).as_f64(), Ok(0.25));
+        assert_eq!(Value::Complex(Complex::new(1.5, 0.0)).as_f64(), Ok(1.5));
+        assert!(Value::Complex(Complex::new(1.5, 1.0)).as_f64().is_err());
+
+        assert_eq!(i64::try_from(Value::Int(BigInt::from(42))), Ok(42));
+        assert_eq!(f64::try_from(Value::Int(BigInt::from(42))), Ok(42.0));
+        assert_eq!(BigInt::try_from(Value::Float(1e20)), Ok(BigInt::from(100_000_000_000_000_000_000u128)));
+    }
+    #[test]
+    fn test_std_ops() {
+        let v = Value::from(3) + Value::from(0.5);
+        assert_eq!(v, Value::Float(3.5));
+        let v = Value::from(3) - Value::from(5);
+        assert_eq!(v, Value::Int(BigInt::from(-2)));
+        let v = Value::from(BigRational::new(BigInt::from(2), BigInt::from(3))) * Value::from(3);
+        assert_eq!(v, Value::Int(BigInt::from(2)));
+        let v = Value::from(6) / Value::from(4);
+        assert_eq!(v, Value::Float(1.5));
+        assert_eq!(-Value::from(2), Value::Int(BigInt::from(-2)));
+
+        assert!(Value::from(3) < Value::from(3.5));
+        assert!(Value::from(4) > Value::from(BigRational::new(BigInt::from(7), BigInt::from(2))));
+        assert_eq!(Value::from(3).partial_cmp(&Value::from(3)), Some(Ordering::Equal));
+        assert_eq!(Value::from(3).partial_cmp(&Value::from(3.0)), None);
+        assert_eq!(Value::from(f64::NAN).partial_cmp(&Value::from(1)), None);
+        assert_eq!(Value::from(Complex::new(1.0, 3.0)).partial_cmp(&Value::from(Complex::new(2.0, 2.0))), None);
+    }
+    #[test]
+    #[should_panic]
+    fn test_std_ops_panic() {
+        let _ = Value::from(1) / Value::from(0);
+    }
+    #[test]
+    #[should_panic]
+    fn test_neg_str_panic() {
+        let _ = -Value::Str("abc".to_string());
+    }
+    #[test]
     fn test_convert_int() {
         let v = Value::Int(BigInt::from(123));
         let f = Value::into_float(v.clone());
@@ -1650,6 +4828,143 @@ mod tests {
         assert_eq!(r, Ok(Value::Complex(Complex::new(0.5, -0.5))));
     }
     #[test]
+    fn test_clamp_lerp_wrap() {
+        let i = |n: i64| Value::Int(BigInt::from(n));
+        assert_eq!(i(5).clamp(i(0), i(3)), Ok(i(3)));
+        assert_eq!(i(-5).clamp(i(0), Value::Float(3.5)), Ok(i(0)));
+        assert_eq!(Value::Float(1.5).clamp(i(0), i(3)), Ok(Value::Float(1.5)));
+        assert_eq!(i(1).clamp(i(3), i(0)), Err(CalcError::InvalidAgrument("clamp".to_string(), "3 > 0".to_string())));
+        assert_eq!(
+            Value::Complex(Complex::new(1.0, 1.0)).clamp(i(0), i(3)),
+            Err(CalcError::NotForComplex("clamp".to_string()))
+        );
+
+        assert_eq!(i(10).lerp(i(20), Value::Float(0.25)), Ok(Value::Float(12.5)));
+        assert_eq!(
+            i(0).lerp(i(1), Value::Ratio(BigRational::new(BigInt::from(1), BigInt::from(3)))),
+            Ok(Value::Ratio(BigRational::new(BigInt::from(1), BigInt::from(3))))
+        );
+        assert_eq!(i(1).lerp(i(3), i(2)), Ok(i(5)));
+
+        assert_eq!(i(370).wrap(i(0), i(360)), Ok(i(10)));
+        assert_eq!(i(-30).wrap(i(0), i(360)), Ok(i(330)));
+        assert_eq!(i(360).wrap(i(0), i(360)), Ok(i(0)));
+        assert_eq!(i(270).wrap(i(-180), i(180)), Ok(i(-90)));
+        assert_eq!(Value::Float(-0.5).wrap(i(0), i(1)), Ok(Value::Float(0.5)));
+        assert_eq!(i(1).wrap(i(2), i(2)), Err(CalcError::InvalidAgrument("wrap".to_string(), "2 >= 2".to_string())));
+    }
+    #[test]
+    fn test_float_utils() {
+        let i = |n: i64| Value::Int(BigInt::from(n));
+        assert_eq!(i(5).copysign(i(-1)), Ok(i(-5)));
+        assert_eq!(i(-5).copysign(i(0)), Ok(i(5)));
+        assert_eq!(Value::Float(2.5).copysign(Value::Float(-0.0)), Ok(Value::Float(-2.5)));
+        assert_eq!(
+            Value::Ratio(BigRational::new(BigInt::from(-1), BigInt::from(2))).copysign(i(3)),
+            Ok(Value::Ratio(BigRational::new(BigInt::from(1), BigInt::from(2))))
+        );
+
+        assert_eq!(i(2).fma(i(3), i(4)), Ok(i(10)));
+        assert_eq!(Value::Float(0.5).fma(i(3), i(1)), Ok(Value::Float(2.5)));
+
+        assert_eq!(i(3).ldexp(i(4)), Ok(i(48)));
+        assert_eq!(i(3).ldexp(i(-1)), Ok(Value::Ratio(BigRational::new(BigInt::from(3), BigInt::from(2)))));
+        assert_eq!(Value::Float(0.75).ldexp(i(2)), Ok(i(3)));
+        assert_eq!(i(3).ldexp(Value::Float(0.5)), Err(CalcError::OnlyInt("ldexp".to_string())));
+
+        assert_eq!(i(8).frexp(), Ok(Value::List(vec![Value::Float(0.5), i(4)])));
+        assert_eq!(Value::Float(-3.0).frexp(), Ok(Value::List(vec![Value::Float(-0.75), i(2)])));
+        assert_eq!(Value::Float(0.1).frexp(), Ok(Value::List(vec![Value::Float(0.8), i(-3)])));
+        assert_eq!(i(0).frexp(), Ok(Value::List(vec![Value::Float(0.0), i(0)])));
+        assert_eq!(Value::Complex(Complex::new(1.0, 1.0)).frexp(), Err(CalcError::NotForComplex("frexp".to_string())));
+    }
+    #[test]
+    fn test_signal_functions() {
+        let i = |n: i64| Value::Int(BigInt::from(n));
+        let eq = |r: CalcResult, f: f64| assert!((r.unwrap().as_f64().unwrap() - f).abs() < 1e-12);
+        assert_eq!(i(0).sinc(), Ok(i(1)));
+        eq(i(2).sinc(), 2.0f64.sin() / 2.0);
+        assert_eq!(i(3).sincn(), Ok(i(0)));
+        assert_eq!(i(0).sincn(), Ok(i(1)));
+        eq(Value::Float(0.5).sincn(), 2.0 / consts::PI);
+        assert_eq!(i(0).sigmoid(), Ok(Value::Float(0.5)));
+        eq(i(2).sigmoid(), 1.0 / (1.0 + (-2.0f64).exp()));
+
+        assert_eq!(i(0).smoothstep(i(10), i(5)), Ok(Value::Float(0.5)));
+        assert_eq!(i(0).smoothstep(i(10), i(-5)), Ok(i(0)));
+        assert_eq!(i(0).smoothstep(i(10), i(15)), Ok(i(1)));
+        assert_eq!(i(0).smoothstep(i(4), i(1)), Ok(Value::Float(0.15625)));
+        assert_eq!(
+            i(1).smoothstep(i(1), i(1)),
+            Err(CalcError::InvalidAgrument("smoothstep".to_string(), "1 == 1".to_string()))
+        );
+    }
+    #[test]
+    fn test_dms() {
+        let i = |n: i64| Value::Int(BigInt::from(n));
+        let l = |v: Vec<Value>| Ok(Value::List(v));
+        assert_eq!(Value::Float(12.5125).dms(), l(vec![i(12), i(30), i(45)]));
+        assert_eq!(Value::Float(-0.5).dms(), l(vec![i(0), i(-30), i(0)]));
+        assert_eq!(Value::Float(-12.25).dms(), l(vec![i(-12), i(15), i(0)]));
+        assert_eq!(Value::Float(1.0 / 3.0).dms(), l(vec![i(0), i(20), i(0)]));
+        assert_eq!(Value::Float(0.0001).dms(), l(vec![i(0), i(0), Value::Float(0.36)]));
+        assert_eq!(i(90).dms(), l(vec![i(90), i(0), i(0)]));
+
+        assert_eq!(i(12).fromdms(i(30), i(45)), Ok(Value::Float(12.5125)));
+        assert_eq!(i(-12).fromdms(i(15), i(0)), Ok(Value::Float(-12.25)));
+        assert_eq!(i(0).fromdms(i(-30), i(0)), Ok(Value::Float(-0.5)));
+        assert_eq!(i(10).fromdms(i(0), i(0)), Ok(i(10)));
+    }
+    #[test]
+    fn test_polar() {
+        let i = |n: i64| Value::Int(BigInt::from(n));
+        let v = i(3).topolar(i(4)).unwrap();
+        assert_eq!(v, Value::List(vec![i(5), Value::Float(4.0f64.atan2(3.0))]));
+        let v = i(-1).topolar(i(0)).unwrap();
+        assert_eq!(v, Value::List(vec![i(1), Value::Float(consts::PI)]));
+        let v = i(2).tocart(Value::Float(consts::FRAC_PI_2)).unwrap();
+        if let Value::List(l) = v {
+            assert!(l[0].as_f64().unwrap().abs() < 1e-12);
+            assert_eq!(l[1], i(2));
+        } else {
+            panic!("not a list");
+        }
+        let v = i(3).topolar(i(4)).unwrap();
+        if let Value::List(l) = v {
+            let v = l[0].clone().tocart(l[1].clone()).unwrap();
+            if let Value::List(l) = v {
+                assert!((l[0].as_f64().unwrap() - 3.0).abs() < 1e-12);
+                assert!((l[1].as_f64().unwrap() - 4.0).abs() < 1e-12);
+            }
+        }
+        assert_eq!(
+            Value::Complex(Complex::new(1.0, 1.0)).topolar(i(1)),
+            Err(CalcError::NotForComplex("topolar".to_string()))
+        );
+    }
+    #[test]
+    fn test_mod_euclid() {
+        let i = |n: i64| Value::Int(BigInt::from(n));
+        assert_eq!(i(-7).mod_euclid(i(3)), Ok(i(2)));
+        assert_eq!(i(7).mod_euclid(i(-3)), Ok(i(1)));
+        assert_eq!(i(-7).mod_euclid(i(-3)), Ok(i(2)));
+        assert_eq!(i(6).mod_euclid(i(3)), Ok(i(0)));
+        assert_eq!(Value::Float(-7.5).mod_euclid(i(2)), Ok(Value::Float(0.5)));
+        assert_eq!(
+            Value::Ratio(BigRational::new(BigInt::from(-1), BigInt::from(2))).mod_euclid(i(1)),
+            Ok(Value::Ratio(BigRational::new(BigInt::from(1), BigInt::from(2))))
+        );
+        assert!(i(1).mod_euclid(i(0)).is_err());
+
+        assert_eq!(i(-7).divmod(i(3)), Ok(Value::List(vec![i(-3), i(2)])));
+        assert_eq!(i(7).divmod(i(-3)), Ok(Value::List(vec![i(-2), i(1)])));
+        assert_eq!(Value::Float(-7.5).divmod(i(2)), Ok(Value::List(vec![i(-4), Value::Float(0.5)])));
+        assert_eq!(
+            Value::Complex(Complex::new(1.0, 1.0)).divmod(i(3)),
+            Err(CalcError::NotForComplex("divmod".to_string()))
+        );
+    }
+    #[test]
     fn test_div_int() {
         let v1 = Value::Int(BigInt::from(12));
         let v2 = Value::Int(BigInt::from(4));
@@ -1677,6 +4992,91 @@ mod tests {
         assert_eq!(r, Ok(Value::Int(BigInt::from(10))));
     }
     #[test]
+    fn test_div_round() {
+        let i = |n: i64| Value::Int(BigInt::from(n));
+        assert_eq!(i(-7).div_round(i(2), Rounding::Trunc), Ok(i(-3)));
+        assert_eq!(i(-7).div_round(i(2), Rounding::Floor), Ok(i(-4)));
+        assert_eq!(i(7).div_round(i(2), Rounding::Ceil), Ok(i(4)));
+        assert_eq!(i(5).div_round(i(2), Rounding::HalfEven), Ok(i(2)));
+        assert_eq!(i(7).div_round(i(2), Rounding::HalfEven), Ok(i(4)));
+        assert_eq!(i(-5).div_round(i(2), Rounding::HalfEven), Ok(i(-2)));
+        assert_eq!(i(8).div_round(i(3), Rounding::HalfEven), Ok(i(3)));
+        assert_eq!(Value::Float(-7.5).div_round(i(3), Rounding::Trunc), Ok(i(-2)));
+        assert_eq!(Value::Float(-7.5).div_round(i(3), Rounding::Floor), Ok(i(-3)));
+        assert_eq!(Value::Float(7.5).div_round(i(3), Rounding::HalfEven), Ok(i(2)));
+        assert!(i(1).div_round(i(0), Rounding::Floor).is_err());
+    }
+    #[test]
+    fn test_rounding_modes() {
+        let f = Value::Float;
+        let i = |n: i64| Value::Int(BigInt::from(n));
+        assert_eq!(f(2.5).round_with(Rounding::HalfEven), Ok(i(2)));
+        assert_eq!(f(3.5).round_with(Rounding::HalfEven), Ok(i(4)));
+        assert_eq!(f(-2.5).round_with(Rounding::HalfUp), Ok(i(-2)));
+        assert_eq!(f(-2.5).round_with(Rounding::HalfAwayFromZero), Ok(i(-3)));
+        assert_eq!(f(-2.7).round_with(Rounding::Trunc), Ok(i(-2)));
+        let half = Value::Ratio(BigRational::new(BigInt::from(-5), BigInt::from(2)));
+        assert_eq!(half.clone().round_with(Rounding::HalfEven), Ok(i(-2)));
+        assert_eq!(half.clone().round_with(Rounding::HalfUp), Ok(i(-2)));
+        assert_eq!(half.round(), Ok(i(-3)));
+
+        assert_eq!(f(2.675).to_decimal(2, Rounding::HalfEven), Ok("2.68".to_string()));
+        assert_eq!(f(2.665).to_decimal(2, Rounding::HalfEven), Ok("2.66".to_string()));
+        assert_eq!(f(2.665).to_decimal(2, Rounding::HalfAwayFromZero), Ok("2.67".to_string()));
+        assert_eq!(f(-0.125).to_decimal(2, Rounding::HalfUp), Ok("-0.12".to_string()));
+        assert_eq!(f(-0.001).to_decimal(2, Rounding::HalfEven), Ok("0.00".to_string()));
+        assert_eq!(f(1e-7).to_decimal(8, Rounding::Trunc), Ok("0.00000010".to_string()));
+        assert_eq!(i(-12).to_decimal(1, Rounding::Trunc), Ok("-12.0".to_string()));
+        assert_eq!(i(7).to_decimal(0, Rounding::Trunc), Ok("7".to_string()));
+        let third = Value::Ratio(BigRational::new(BigInt::from(1), BigInt::from(3)));
+        assert_eq!(third.to_decimal(3, Rounding::Ceil), Ok("0.334".to_string()));
+        let c = Value::Complex(Complex::new(1.005, -2.5));
+        assert_eq!(c.to_decimal(2, Rounding::HalfEven), Ok("1.00-2.50i".to_string()));
+    }
+    #[test]
+    fn test_exact() {
+        let i = |n: i64| Value::Int(BigInt::from(n));
+        let r = |n: i64, d: i64| Value::Ratio(BigRational::new(BigInt::from(n), BigInt::from(d)));
+        assert_eq!(i(1).divide_exact(i(3)), Ok(r(1, 3)));
+        assert_eq!(i(6).divide_exact(i(3)), Ok(i(2)));
+        assert_eq!(r(1, 2).divide_exact(r(1, 4)), Ok(i(2)));
+        assert_eq!(i(1).divide_exact(Value::Float(4.0)), Ok(Value::Float(0.25)));
+        assert!(i(1).divide_exact(i(0)).is_err());
+        assert_eq!(i(2).power_exact(i(-2)), Ok(r(1, 4)));
+        assert_eq!(r(2, 3).power_exact(i(-1)), Ok(r(3, 2)));
+        assert_eq!(i(2).power_exact(i(3)), Ok(i(8)));
+
+        assert_eq!(Value::from_str_decimal("0.1"), Ok(r(1, 10)));
+        assert_eq!(Value::from_str_decimal("1_250,5"), Ok(r(2501, 2)));
+        assert_eq!(Value::from_str_decimal("2.50e1"), Ok(i(25)));
+        assert_eq!(Value::from_str_decimal("15e-1"), Ok(r(3, 2)));
+
+        assert_eq!(r(1, 4).approx(), Ok(Value::Float(0.25)));
+        assert_eq!(
+            Value::List(vec![i(1), r(1, 2)]).approx(),
+            Ok(Value::List(vec![Value::Float(1.0), Value::Float(0.5)]))
+        );
+    }
+    #[test]
+    fn test_as_fraction() {
+        let i = |n: i64| Value::Int(BigInt::from(n));
+        let r = |n: i64, d: i64| Value::Ratio(BigRational::new(BigInt::from(n), BigInt::from(d)));
+        assert_eq!(Value::Float(0.333333).as_fraction(i(100)), Ok(r(1, 3)));
+        assert_eq!(Value::Float(consts::PI).as_fraction(i(10)), Ok(r(22, 7)));
+        assert_eq!(Value::Float(consts::PI).as_fraction(i(1000)), Ok(r(355, 113)));
+        assert_eq!(Value::Float(-0.75).as_fraction(i(100)), Ok(r(-3, 4)));
+        assert_eq!(Value::Float(2.99).as_fraction(i(5)), Ok(i(3)));
+        assert_eq!(r(1, 3).as_fraction(i(2)), Ok(r(1, 2)));
+        assert_eq!(i(5).as_fraction(i(1)), Ok(i(5)));
+        assert!(Value::Float(0.5).as_fraction(i(0)).is_err());
+
+        assert_eq!(r(3, 2).to_mixed(100), Ok("1 1/2".to_string()));
+        assert_eq!(r(-7, 2).to_mixed(100), Ok("-3 1/2".to_string()));
+        assert_eq!(r(-1, 3).to_mixed(100), Ok("-1/3".to_string()));
+        assert_eq!(Value::Float(2.125).to_mixed(100), Ok("2 1/8".to_string()));
+        assert_eq!(Value::List(vec![i(2), r(5, 4)]).to_mixed(100), Ok("[2; 1 1/4]".to_string()));
+    }
+    #[test]
     fn test_neg() {
         let v = Value::Int(BigInt::from(12)).negate();
         assert_eq!(v, Ok(Value::Int(BigInt::from(-12))));
@@ -1863,6 +5263,22 @@ mod tests {
         let v = Value::Float(0.5);
         let r = v.clone().exp().unwrap().ln();
         assert_eq!(Ok(v), r);
+        assert_eq!(Value::Int(BigInt::from(1000)).log10(), Ok(Value::Float(3.0)));
+        assert!(matches!(Value::Float(-10.0).log10(), Ok(Value::Complex(..))));
+    }
+    #[test]
+    fn test_exp_near_zero() {
+        let i = |n: i64| Value::Int(BigInt::from(n));
+        assert_eq!(i(10).exp2(), Ok(i(1024)));
+        assert_eq!(i(-2).exp2(), Ok(Value::Float(0.25)));
+        assert_eq!(Value::Float(0.5).exp2(), Ok(Value::Float(2.0f64.sqrt())));
+        assert_eq!(Value::Float(1e-20).expm1(), Ok(Value::Float(1e-20)));
+        assert_eq!(Value::Float(1e-20).ln1p(), Ok(Value::Float(1e-20)));
+        assert_eq!(i(-1).ln1p(), Err(CalcError::InvalidAgrument("ln1p".to_string(), "-1".to_string())));
+        assert!(matches!(i(-3).ln1p(), Ok(Value::Complex(..))));
+        let c = Value::Complex(Complex::new(0.0, consts::PI));
+        let r = c.expm1().unwrap();
+        assert!((r.re().unwrap().as_f64().unwrap() + 2.0).abs() < 1e-12);
     }
     #[test]
     fn test_trigonometry() {
@@ -1873,6 +5289,784 @@ mod tests {
         assert_eq!(Ok(v), r);
     }
     #[test]
+    fn test_reciprocal_trigonometry() {
+        let v = Value::Float(0.5);
+        let eq = |r: CalcResult, f: f64| assert!((r.unwrap().as_f64().unwrap() - f).abs() < 1e-12);
+        eq(v.clone().cot(), 1.0 / 0.5f64.tan());
+        eq(v.clone().sec(), 1.0 / 0.5f64.cos());
+        eq(v.clone().csc(), 1.0 / 0.5f64.sin());
+        eq(v.clone().cot().unwrap().acot(), 0.5);
+        eq(Value::Float(2.0).asec().unwrap().sec(), 2.0);
+        eq(Value::Float(2.0).acsc().unwrap().csc(), 2.0);
+        eq(v.clone().coth().unwrap().acoth(), 0.5);
+        eq(v.clone().sech().unwrap().asech(), 0.5);
+        eq(v.clone().csch().unwrap().acsch(), 0.5);
+        eq(Value::Float(consts::FRAC_PI_2).cot(), 0.0);
+        eq(Value::Int(BigInt::zero()).acot(), consts::FRAC_PI_2);
+
+        let err = |f: &str, v: &str| Err(CalcError::InvalidAgrument(f.to_string(), v.to_string()));
+        assert_eq!(Value::Int(BigInt::zero()).cot(), err("cot", "0"));
+        assert_eq!(Value::Float(consts::PI).csc(), err("csc", "3.141592653589793"));
+        assert_eq!(Value::Float(consts::FRAC_PI_2).sec(), err("sec", "1.5707963267948966"));
+        assert_eq!(Value::Int(BigInt::zero()).coth(), err("coth", "0"));
+        assert_eq!(Value::Float(0.5).acoth(), err("acoth", "0.5"));
+        assert_eq!(Value::Int(BigInt::zero()).asec(), err("asec", "0"));
+        assert!(matches!(Value::Float(0.5).asec(), Ok(Value::Complex(..))));
+        assert!(matches!(Value::Float(2.0).asech(), Ok(Value::Complex(..))));
+
+        let c = Value::Complex(Complex::new(1.0, 1.0));
+        let r = c.clone().cot().unwrap().multiply(c.tan().unwrap()).unwrap();
+        eq(r.re(), 1.0);
+    }
+    #[test]
+    fn test_arg() {
+        let r = Value::Complex(Complex::new(0.0, 2.0)).arg();
+        assert_eq!(r, Ok(Value::Float(consts::FRAC_PI_2)));
+        let r = Value::Complex(Complex::new(-1.0, -1.0)).arg();
+        assert_eq!(r, Ok(Value::Float(-3.0 * consts::FRAC_PI_4)));
+        assert_eq!(Value::Int(BigInt::from(-2)).arg(), Ok(Value::Float(consts::PI)));
+        assert_eq!(Value::Float(2.5).arg(), Ok(Value::Float(0.0)));
+
+        let c = Value::Complex(Complex::new(1.0, -2.0));
+        assert_eq!(c.clone().proj(), Ok(c));
+        let r = Value::Complex(Complex::new(1.0, f64::NEG_INFINITY)).proj();
+        assert_eq!(r, Ok(Value::Complex(Complex::new(f64::INFINITY, -0.0))));
+        assert_eq!(Value::Int(BigInt::from(3)).proj(), Ok(Value::Int(BigInt::from(3))));
+    }
+    #[test]
+    fn test_stirling_bell() {
+        let int = |i: i64| Value::Int(BigInt::from(i));
+        let s1 = [[1, 0, 0, 0], [0, 1, 0, 0], [0, -1, 1, 0], [0, 2, -3, 1]];
+        let s2 = [[1, 0, 0, 0], [0, 1, 0, 0], [0, 1, 1, 0], [0, 1, 3, 1]];
+        for n in 0..4 {
+            for k in 0..4 {
+                assert_eq!(int(n).stirling1(int(k)), Ok(int(s1[n as usize][k as usize])), "s({}, {})", n, k);
+                assert_eq!(int(n).stirling2(int(k)), Ok(int(s2[n as usize][k as usize])), "S({}, {})", n, k);
+            }
+        }
+        assert_eq!(int(10).stirling1(int(3)), Ok(int(-1_172_700)));
+        assert_eq!(int(10).stirling2(int(3)), Ok(int(9_330)));
+        let bells = [1, 1, 2, 5, 15, 52, 203, 877, 4140];
+        for (n, b) in bells.iter().enumerate() {
+            assert_eq!(int(n as i64).bell(), Ok(int(*b)));
+        }
+        let r = int(25).bell().unwrap();
+        assert_eq!(r.to_string(), "4638590332229999353");
+        assert_eq!(int(3).stirling2(int(-1)), Err(CalcError::NotForNegativeInt("stirling2".to_string())));
+        assert_eq!(Value::Float(2.0).bell(), Err(CalcError::OnlyInt("bell".to_string())));
+        let r = int(1001).bell();
+        assert_eq!(
+            r,
+            Err(CalcError::ArgumentOutOfRange("bell".to_string(), "1001".to_string(), "[0..1000]".to_string()))
+        );
+    }
+    #[test]
+    fn test_primorial_multifact() {
+        let int = |i: i64| Value::Int(BigInt::from(i));
+        let primorials = [1, 1, 2, 6, 6, 30, 30, 210, 210, 210, 210, 2310];
+        for (n, p) in primorials.iter().enumerate() {
+            assert_eq!(int(n as i64).primorial(), Ok(int(*p)));
+        }
+        assert_eq!(int(30).primorial(), Ok(int(6_469_693_230)));
+        assert_eq!(int(-3).primorial(), Err(CalcError::NotForNegativeInt("primorial".to_string())));
+
+        assert_eq!(int(9).multifact(int(2)), Ok(int(945)));
+        assert_eq!(int(10).multifact(int(2)), Ok(int(3840)));
+        assert_eq!(int(10).multifact(int(3)), Ok(int(280)));
+        assert_eq!(int(10).multifact(int(1)), int(10).fact());
+        assert_eq!(int(5).multifact(int(100)), Ok(int(5)));
+        assert_eq!(int(0).multifact(int(2)), Ok(int(1)));
+        assert_eq!(
+            int(5).multifact(int(0)),
+            Err(CalcError::InvalidAgrument("multifact".to_string(), "step 0".to_string()))
+        );
+        assert_eq!(Value::Float(5.5).multifact(int(2)), Err(CalcError::OnlyInt("multifact".to_string())));
+        let r = int(100_001).multifact(int(2));
+        let range = "[0..100000]".to_string();
+        assert_eq!(r, Err(CalcError::ArgumentOutOfRange("multifact".to_string(), "100001".to_string(), range)));
+    }
+    #[test]
+    fn test_egcd() {
+        let int = |i: i64| Value::Int(BigInt::from(i));
+        for &(a, b) in &[(240, 46), (-240, 46), (46, -240), (17, 5), (0, 7), (-7, 0), (12, 12)] {
+            let r = int(a).egcd(int(b)).unwrap();
+            let (g, x, y) = match r {
+                Value::List(ref l) => (l[0].as_i64().unwrap(), l[1].as_i64().unwrap(), l[2].as_i64().unwrap()),
+                _ => panic!("egcd({}; {}) = {}", a, b, r),
+            };
+            assert_eq!(int(g), int(a).gcd(int(b)).unwrap());
+            assert_eq!(a * x + b * y, g, "egcd({}; {}) = {}", a, b, r);
+        }
+        assert_eq!(int(240).egcd(int(46)).unwrap().to_string(), "[2; -9; 47]");
+        assert_eq!(int(0).egcd(int(0)).unwrap().to_string(), "[0; 1; 0]");
+        let r = Value::Complex(Complex::new(1.0, 1.0)).egcd(int(2));
+        assert_eq!(r, Err(CalcError::OnlyInt("egcd".to_string())));
+    }
+    #[test]
+    fn test_collatz() {
+        let int = |i: i64| Value::Int(BigInt::from(i));
+        assert_eq!(int(1).collatz(), Ok(int(0)));
+        assert_eq!(int(6).collatz(), Ok(int(8)));
+        assert_eq!(int(27).collatz(), Ok(int(111)));
+        assert_eq!(int(1024).collatz(), Ok(int(10)));
+        assert_eq!(int(837_799).collatz(), Ok(int(524)));
+        let r = Value::Int(BigInt::from(2).pow(200u32) - 1).collatz();
+        assert_eq!(r, Ok(int(2_731)));
+        assert_eq!(int(0).collatz(), Err(CalcError::InvalidAgrument("collatz".to_string(), "0".to_string())));
+        assert_eq!(int(-5).collatz(), Err(CalcError::NotForNegativeInt("collatz".to_string())));
+        assert_eq!(Value::Float(6.5).collatz(), Err(CalcError::OnlyInt("collatz".to_string())));
+    }
+    #[test]
+    fn test_digits() {
+        let int = |i: i64| Value::Int(BigInt::from(i));
+        assert_eq!(int(12345).digitsum(int(10)), Ok(int(15)));
+        assert_eq!(int(-12345).digitsum(int(10)), Ok(int(15)));
+        assert_eq!(int(255).digitsum(int(2)), Ok(int(8)));
+        assert_eq!(int(255).digitsum(int(16)), Ok(int(30)));
+        assert_eq!(int(1000).digitsum(int(1000)), Ok(int(1)));
+        assert_eq!(int(0).numdigits(int(10)), Ok(int(1)));
+        assert_eq!(int(-99_999).numdigits(int(10)), Ok(int(5)));
+        assert_eq!(int(256).numdigits(int(2)), Ok(int(9)));
+        let big = Value::Int(BigInt::from(10).pow(100u32));
+        assert_eq!(big.clone().numdigits(int(10)), Ok(int(101)));
+        assert_eq!(big.revdigits(int(10)), Ok(int(1)));
+        assert_eq!(int(-1230).revdigits(int(10)), Ok(int(-321)));
+        assert_eq!(int(0b1101).revdigits(int(2)), Ok(int(0b1011)));
+        assert_eq!(int(7).revdigits(int(10)), Ok(int(7)));
+        let r = int(10).digitsum(int(1));
+        assert_eq!(r, Err(CalcError::InvalidAgrument("digitsum".to_string(), "base 1".to_string())));
+        let r = Value::Float(1.5).numdigits(int(10));
+        assert_eq!(r, Err(CalcError::OnlyInt("numdigits".to_string())));
+    }
+    #[test]
+    fn test_roman() {
+        let int = |i: i64| Value::Int(BigInt::from(i));
+        let text = |s: &str| Value::Str(s.to_string());
+        let cases = [
+            (1, "I"),
+            (4, "IV"),
+            (9, "IX"),
+            (14, "XIV"),
+            (40, "XL"),
+            (1990, "MCMXC"),
+            (2024, "MMXXIV"),
+            (3999, "MMMCMXCIX"),
+        ];
+        for (n, r) in cases {
+            assert_eq!(int(n).roman(), Ok(text(r)));
+            assert_eq!(text(r).unroman(), Ok(int(n)));
+        }
+        for n in 1..=3999 {
+            assert_eq!(int(n).roman().unwrap().unroman(), Ok(int(n)));
+        }
+        assert_eq!(text(" mcmxc ").unroman(), Ok(int(1990)));
+        for r in ["", "IIII", "VX", "IM", "MMMM", "XIVX", "ABC"] {
+            assert_eq!(text(r).unroman(), Err(CalcError::InvalidAgrument("unroman".to_string(), r.to_string())));
+        }
+        let range = "[1..3999]".to_string();
+        assert_eq!(int(0).roman(), Err(CalcError::ArgumentOutOfRange("roman".to_string(), "0".to_string(), range)));
+        assert_eq!(Value::Float(1.5).roman(), Err(CalcError::OnlyInt("roman".to_string())));
+        assert_eq!(int(12).unroman(), Err(CalcError::InvalidAgrument("unroman".to_string(), "12".to_string())));
+    }
+    #[test]
+    fn test_spell() {
+        let int = |i: i64| Value::Int(BigInt::from(i));
+        let text = |s: &str| Value::Str(s.to_string());
+        let cases = [
+            (0, "zero"),
+            (7, "seven"),
+            (13, "thirteen"),
+            (40, "forty"),
+            (99, "ninety-nine"),
+            (100, "one hundred"),
+            (105, "one hundred five"),
+            (1234, "one thousand two hundred thirty-four"),
+            (-20_015, "minus twenty thousand fifteen"),
+            (1_000_000, "one million"),
+            (7_000_300_012, "seven billion three hundred thousand twelve"),
+        ];
+        for (n, s) in cases {
+            assert_eq!(int(n).spell(text("en")), Ok(text(s)));
+        }
+        let gb = [
+            (105, "one hundred and five"),
+            (1005, "one thousand and five"),
+            (2310, "two thousand three hundred and ten"),
+            (1_000_100, "one million one hundred"),
+        ];
+        for (n, s) in gb {
+            assert_eq!(int(n).spell(text("en-GB")), Ok(text(s)));
+        }
+        let big = Value::Int(BigInt::from(10).pow(36u32) - 1);
+        let r = big.clone().spell(text("en")).unwrap().to_string();
+        assert!(r.starts_with("nine hundred ninety-nine decillion nine hundred"));
+        assert!(big.addition(int(1)).unwrap().spell(text("en")).is_err());
+        let r = int(1).spell(text("fr"));
+        assert_eq!(r, Err(CalcError::InvalidAgrument("spell".to_string(), "locale fr".to_string())));
+        assert_eq!(Value::Float(1.5).spell(text("en")), Err(CalcError::OnlyInt("spell".to_string())));
+    }
+    #[test]
+    fn test_ilog() {
+        let int = |i: i64| Value::Int(BigInt::from(i));
+        assert_eq!(int(1).ilog2(), Ok(int(0)));
+        assert_eq!(int(1023).ilog2(), Ok(int(9)));
+        assert_eq!(int(1024).ilog2(), Ok(int(10)));
+        assert_eq!(int(999).ilog10(), Ok(int(2)));
+        assert_eq!(int(1000).ilog10(), Ok(int(3)));
+        assert_eq!(int(80).ilog(int(3)), Ok(int(3)));
+        assert_eq!(int(81).ilog(int(3)), Ok(int(4)));
+        assert_eq!(int(255).ilog(int(256)), Ok(int(0)));
+        for k in [1u32, 50, 333, 1000] {
+            let p = BigInt::from(10).pow(k);
+            assert_eq!(Value::Int(p.clone()).ilog10(), Ok(int(k as i64)));
+            assert_eq!(Value::Int(p - 1).ilog10(), Ok(int(k as i64 - 1)));
+            let p = BigInt::from(7).pow(k);
+            assert_eq!(Value::Int(p.clone()).ilog(int(7)), Ok(int(k as i64)));
+            assert_eq!(Value::Int(p - 1).ilog(int(7)), Ok(int(k as i64 - 1)));
+        }
+        assert_eq!(int(0).ilog2(), Err(CalcError::InvalidAgrument("ilog2".to_string(), "0".to_string())));
+        assert_eq!(int(10).ilog(int(1)), Err(CalcError::InvalidAgrument("ilog".to_string(), "base 1".to_string())));
+        assert_eq!(Value::Float(10.0).ilog10(), Err(CalcError::OnlyInt("ilog10".to_string())));
+
+        assert_eq!(int(0).nextpow2(), Ok(int(1)));
+        assert_eq!(int(1).nextpow2(), Ok(int(1)));
+        assert_eq!(int(3).nextpow2(), Ok(int(4)));
+        assert_eq!(int(64).nextpow2(), Ok(int(64)));
+        assert_eq!(int(65).nextpow2(), Ok(int(128)));
+        assert_eq!(int(-4).nextpow2(), Err(CalcError::NotForNegativeInt("nextpow2".to_string())));
+    }
+    #[test]
+    fn test_bits() {
+        let int = |i: i64| Value::Int(BigInt::from(i));
+        assert_eq!(int(0xFF).signed(int(8)), Ok(int(-1)));
+        assert_eq!(int(0x7F).signed(int(8)), Ok(int(127)));
+        assert_eq!(int(0x180).signed(int(8)), Ok(int(-128)));
+        assert_eq!(int(-1).signed(int(16)), Ok(int(-1)));
+        assert_eq!(int(1).signed(int(1)), Ok(int(-1)));
+        assert_eq!(int(-1).unsigned(int(8)), Ok(int(255)));
+        assert_eq!(int(0x1FF).unsigned(int(8)), Ok(int(255)));
+        assert_eq!(int(-2).unsigned(int(64)), Ok(Value::Int(BigInt::from(u64::MAX - 1))));
+        let r = int(1).signed(int(0));
+        assert_eq!(
+            r,
+            Err(CalcError::ArgumentOutOfRange("signed".to_string(), "0".to_string(), "[1..65536]".to_string()))
+        );
+        assert_eq!(Value::Float(1.5).unsigned(int(8)), Err(CalcError::OnlyInt("unsigned".to_string())));
+
+        assert_eq!(int(0xABCD).bits(int(11), int(4)), Ok(int(0xBC)));
+        assert_eq!(int(0xABCD).bits(int(0), int(0)), Ok(int(1)));
+        assert_eq!(int(-1).bits(int(99), int(96)), Ok(int(0xF)));
+        assert_eq!(int(0xFF00).setbits(int(7), int(4), int(0xA)), Ok(int(0xFFA0)));
+        assert_eq!(int(0xFF00).setbits(int(11), int(8), int(0)), Ok(int(0xF000)));
+        assert_eq!(int(-1).setbits(int(3), int(0), int(0)), Ok(int(-16)));
+        let r = int(0).bits(int(3), int(4));
+        assert_eq!(r, Err(CalcError::InvalidAgrument("bits".to_string(), "bit 3 is lower than bit 4".to_string())));
+        let r = int(0).setbits(int(7), int(4), int(16));
+        assert_eq!(
+            r,
+            Err(CalcError::ArgumentOutOfRange("setbits".to_string(), "16".to_string(), "[0..15]".to_string()))
+        );
+
+        assert_eq!(int(0x1234).bswap16(), Ok(int(0x3412)));
+        assert_eq!(int(0x12).bswap16(), Ok(int(0x1200)));
+        assert_eq!(int(0x1234_5678).bswap32(), Ok(int(0x7856_3412)));
+        assert_eq!(int(0x0102_0304_0506_0708).bswap64(), Ok(int(0x0807_0605_0403_0201)));
+        assert_eq!(int(-2).bswap16(), Ok(int(0xFEFF)));
+        assert_eq!(int(0x123456).bswap(int(24)), Ok(int(0x563412)));
+        let r = int(1).bswap(int(12));
+        assert_eq!(
+            r,
+            Err(CalcError::InvalidAgrument("bswap".to_string(), "width 12 is not a multiple of 8".to_string()))
+        );
+        assert_eq!(Value::Float(1.0).bswap32(), Err(CalcError::OnlyInt("bswap32".to_string())));
+    }
+    #[test]
+    fn test_checksums() {
+        let int = |i: i64| Value::Int(BigInt::from(i));
+        let text = |s: &str| Value::Str(s.to_string());
+        assert_eq!(text("123456789").crc32(), Ok(int(0xCBF4_3926)));
+        assert_eq!(text("").crc32(), Ok(int(0)));
+        assert_eq!(text("The quick brown fox jumps over the lazy dog").crc32(), Ok(int(0x414F_A339)));
+        assert_eq!(int(0x31_32_33).crc32(), text("123").crc32());
+        assert_eq!(int(-1).crc32(), Err(CalcError::NotForNegativeInt("crc32".to_string())));
+
+        assert_eq!(text("a").addition(Value::Float(1.5)), Ok(text("a1.5")));
+        assert_eq!(int(1).addition(text("b")), Ok(text("1b")));
+        assert_eq!(text("héllo").len(), Ok(int(5)));
+        assert_eq!(Value::List(vec![int(1)]).len(), Ok(int(1)));
+        assert_eq!(int(12).len(), Err(CalcError::InvalidAgrument("len".to_string(), "12".to_string())));
+        assert_eq!(text("Hi").hexstr(), Ok(text("4869")));
+        assert_eq!(text("").hexstr(), Ok(text("")));
+        assert_eq!(text("ff").unhex(), Ok(int(255)));
+        assert_eq!(text("0xDE AD_BE EF").unhex(), Ok(int(0xDEAD_BEEF)));
+        assert_eq!(text("0x").unhex(), Err(CalcError::InvalidAgrument("unhex".to_string(), "0x".to_string())));
+        assert_eq!(text("fg").unhex(), Err(CalcError::InvalidAgrument("unhex".to_string(), "fg".to_string())));
+        assert_eq!(text("foo").b64(), Ok(text("Zm9v")));
+        assert_eq!(int(0x66_6F_6F).b64(), Ok(text("Zm9v")));
+        assert_eq!(text("Zm9v").unb64(), Ok(text("foo")));
+        assert_eq!(text("/w==").unb64(), Ok(int(255)));
+        assert_eq!(text("AQA=").unb64(), Ok(int(256)));
+        assert_eq!(text("Zm9v!").unb64(), Err(CalcError::InvalidAgrument("unb64".to_string(), "Zm9v!".to_string())));
+        assert_eq!(int(-1).b64(), Err(CalcError::NotForNegativeInt("b64".to_string())));
+        let big = |s: &str| Value::Int(BigInt::parse_bytes(s.as_bytes(), 16).unwrap());
+        assert_eq!(text("").fnv1a(), Ok(big("cbf29ce484222325")));
+        assert_eq!(text("a").fnv1a(), Ok(big("af63dc4c8601ec8c")));
+        assert_eq!(text("foobar").fnv1a(), Ok(big("85944171f73967e8")));
+        assert_eq!(int(0x61).fnv1a(), text("a").fnv1a());
+        assert_eq!(text("").murmur3(), Ok(int(0)));
+        assert_eq!(text("hello").murmur3(), Ok(int(0x248B_FA47)));
+        assert_eq!(text("The quick brown fox jumps over the lazy dog").murmur3(), Ok(int(0x2E4F_F723)));
+        assert_eq!(Value::Float(1.5).murmur3(), Err(CalcError::OnlyInt("murmur3".to_string())));
+        #[cfg(feature = "sha256")]
+        {
+            let r = text("abc").sha256();
+            assert_eq!(r, Ok(big("ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad")));
+            let r = text("").sha256();
+            assert_eq!(r, Ok(big("e3b0c44298fc1c149afbf4c8996fb92427ae41e4649b934ca495991b7852b855")));
+        }
+
+        assert_eq!(int(79_927_398_713).luhn(), Ok(int(1)));
+        assert_eq!(int(79_927_398_710).luhn(), Ok(int(0)));
+        assert_eq!(text("4539 1488 0343 6467").luhn(), Ok(int(1)));
+        assert_eq!(text("4539-1488-0343-6468").luhn(), Ok(int(0)));
+        assert_eq!(int(0).luhn(), Ok(int(1)));
+        let r = text("12a4").luhn();
+        assert_eq!(r, Err(CalcError::InvalidAgrument("luhn".to_string(), "12a4".to_string())));
+        assert_eq!(Value::Float(1.5).luhn(), Err(CalcError::OnlyInt("luhn".to_string())));
+    }
+    #[test]
+    #[cfg(feature = "units")]
+    fn test_duration() {
+        let text = |s: &str| Ok(Value::Str(s.to_string()));
+        assert_eq!(Value::Int(BigInt::from(5430)).duration(), text("1h30m30s"));
+        assert_eq!(Value::Int(BigInt::from(-7200)).duration(), text("-2h"));
+        assert_eq!(Value::Int(BigInt::from(0)).duration(), text("0s"));
+        assert_eq!(Value::Float(61.25).duration(), text("1m1.25s"));
+        assert_eq!(Value::Float(0.0004).duration(), text("0s"));
+        let r = Value::Str("1h".to_string()).duration();
+        assert_eq!(r, Err(CalcError::NotForString("duration".to_string())));
+    }
+
+    #[test]
+    #[cfg(feature = "datetime")]
+    fn test_unix_time() {
+        let int = |i: i64| Value::Int(BigInt::from(i));
+        let text = |s: &str| Value::Str(s.to_string());
+        assert_eq!(text("1970-01-02").unix(), Ok(int(86_400)));
+        assert_eq!(text("2024-03-01T12:30:00+02:00").unix(), Ok(int(1_709_289_000)));
+        assert_eq!(text("1970-01-01 00:00:01.5").unix(), Ok(Value::Float(1.5)));
+        assert_eq!(int(1_709_289_000).fromunix(), Ok(text("2024-03-01T10:30:00Z")));
+        assert_eq!(Value::Float(-0.25).fromunix(), Ok(text("1969-12-31T23:59:59.750Z")));
+        let half = Value::Ratio(BigRational::new(BigInt::from(1), BigInt::from(2)));
+        assert_eq!(half.fromunix(), Ok(text("1970-01-01T00:00:00.500Z")));
+
+        let r = text("2024-13-01").unix();
+        assert_eq!(r, Err(CalcError::InvalidAgrument("unix".to_string(), "2024-13-01".to_string())));
+        assert_eq!(int(5).unix(), Err(CalcError::InvalidAgrument("unix".to_string(), "5".to_string())));
+        let r = int(i64::MAX).fromunix();
+        assert_eq!(r, Err(CalcError::InvalidAgrument("fromunix".to_string(), i64::MAX.to_string())));
+        assert_eq!(text("0").fromunix(), Err(CalcError::NotForString("fromunix".to_string())));
+    }
+
+    #[test]
+    #[cfg(feature = "special")]
+    fn test_discrete_distributions() {
+        let int = |i: i64| Value::Int(BigInt::from(i));
+        let close = |r: CalcResult, v: f64| (r.unwrap().as_f64().unwrap() - v).abs() <= 1e-12 * v;
+        // the error of logarithms of big factorials is about `1e-16 * n * ln(n)`
+        let near = |r: CalcResult, v: f64| (r.unwrap().as_f64().unwrap() - v).abs() <= 1e-8 * v;
+        assert!(close(int(3).binompmf(int(10), Value::Float(0.5)), 120.0 / 1024.0));
+        assert!(close(int(0).binompmf(int(5), Value::Float(0.0)), 1.0));
+        assert!(close(int(5).binompmf(int(5), Value::Float(1.0)), 1.0));
+        assert_eq!(int(6).binompmf(int(5), Value::Float(0.3)), Ok(Value::Float(0.0)));
+        assert!(close(int(3).binomcdf(int(10), Value::Float(0.5)), 176.0 / 1024.0));
+        assert!(close(int(10).binomcdf(int(10), Value::Float(0.5)), 1.0));
+        assert_eq!(int(-1).binomcdf(int(10), Value::Float(0.5)), Ok(Value::Float(0.0)));
+        // big numbers of trials do not overflow
+        assert!(near(int(500_000).binompmf(int(1_000_000), Value::Float(0.5)), 0.0007978843613317501));
+        assert!(near(int(500_000).binomcdf(int(1_000_000), Value::Float(0.5)), 0.5003989421806659));
+
+        assert!(close(int(2).poispmf(int(3)), 4.5 * (-3.0f64).exp()));
+        assert!(close(int(0).poispmf(int(0)), 1.0));
+        assert!(close(int(2).poiscdf(int(3)), 8.5 * (-3.0f64).exp()));
+        assert!(near(int(1000).poispmf(int(1000)), 0.0126146113487215));
+        assert!(near(int(1000).poiscdf(int(1000)), 0.508409367168506));
+
+        let r = int(1).binompmf(int(2), Value::Float(1.5));
+        assert_eq!(
+            r,
+            Err(CalcError::ArgumentOutOfRange("binompmf".to_string(), "1.5".to_string(), "[0..1]".to_string()))
+        );
+        let r = int(1).binomcdf(int(-2), Value::Float(0.5));
+        assert_eq!(r, Err(CalcError::NotForNegativeInt("binomcdf".to_string())));
+        let r = int(1).poiscdf(int(-2));
+        assert_eq!(r, Err(CalcError::InvalidAgrument("poiscdf".to_string(), "lambda -2".to_string())));
+        assert_eq!(Value::Float(1.5).poispmf(int(1)), Err(CalcError::OnlyInt("poispmf".to_string())));
+    }
+
+    #[test]
+    #[cfg(feature = "special")]
+    fn test_continuous_distributions() {
+        let int = |i: i64| Value::Int(BigInt::from(i));
+        let close = |r: CalcResult, v: f64| (r.unwrap().as_f64().unwrap() - v).abs() <= 1e-12 * v.abs();
+        assert!(close(Value::Float(2.0).tcdf(int(10)), 0.9633059826146298));
+        assert!(close(Value::Float(0.975).tinv(int(10)), 2.2281388519862742));
+        assert!(close(int(3).chi2cdf(int(5)), 0.3000141641213725));
+        assert!(close(Value::Float(0.95).chi2inv(Value::Float(5.0)), 11.070497693516352));
+
+        let r = int(1).tinv(int(10));
+        assert_eq!(r, Err(CalcError::ArgumentOutOfRange("tinv".to_string(), "1".to_string(), "(0..1)".to_string())));
+        let r = Value::Float(0.5).chi2inv(int(0));
+        assert_eq!(r, Err(CalcError::InvalidAgrument("chi2inv".to_string(), "df 0".to_string())));
+        let r = Value::Float(1.5).chi2inv(int(2));
+        assert_eq!(
+            r,
+            Err(CalcError::ArgumentOutOfRange("chi2inv".to_string(), "1.5".to_string(), "[0..1]".to_string()))
+        );
+    }
+
+    #[test]
+    fn test_matrix() {
+        let int = |i: i64| Value::Int(BigInt::from(i));
+        let ratio = |n: i64, d: i64| Value::Ratio(BigRational::new(BigInt::from(n), BigInt::from(d)));
+        let row = |v: &[i64]| Value::List(v.iter().map(|i| int(*i)).collect());
+        let mat = |rows: &[&[i64]]| Value::List(rows.iter().map(|r| row(r)).collect());
+
+        assert_eq!(mat(&[&[1, 2], &[3, 4]]).det(), Ok(int(-2)));
+        assert_eq!(mat(&[&[2, 0, 1], &[1, 3, 2], &[1, 1, 2]]).det(), Ok(int(6)));
+        assert_eq!(mat(&[&[1, 2], &[2, 4]]).det(), Ok(int(0)));
+        assert_eq!(mat(&[&[0, 1], &[1, 0]]).det(), Ok(int(-1)));
+        let m = Value::List(vec![Value::List(vec![Value::Float(0.5), Value::Float(1.5)]), row(&[2, 4])]);
+        assert_eq!(m.det(), Ok(int(-1)));
+
+        let inv = Value::List(vec![Value::List(vec![int(-2), int(1)]), Value::List(vec![ratio(3, 2), ratio(-1, 2)])]);
+        assert_eq!(mat(&[&[1, 2], &[3, 4]]).inv(), Ok(inv));
+        assert_eq!(mat(&[&[1, 2], &[2, 4]]).inv(), Err(CalcError::SingularMatrix("inv".to_string())));
+
+        assert_eq!(mat(&[&[1, 2, 3], &[2, 4, 6], &[1, 0, 1]]).rank(), Ok(int(2)));
+        assert_eq!(mat(&[&[1, 2, 3]]).rank(), Ok(int(1)));
+        assert_eq!(mat(&[&[0, 0], &[0, 0]]).rank(), Ok(int(0)));
+
+        // 2x + y = 1, 4x + 3y = 5
+        assert_eq!(mat(&[&[2, 1], &[4, 3]]).linsolve(row(&[1, 5])), Ok(row(&[-1, 3])));
+        let r = mat(&[&[2, 1], &[4, 3]]).linsolve(mat(&[&[1, 0], &[5, 1]]));
+        assert_eq!(r, Ok(Value::List(vec![Value::List(vec![int(-1), ratio(-1, 2)]), row(&[3, 1])])));
+        let r = mat(&[&[1, 2], &[2, 4]]).linsolve(row(&[1, 2]));
+        assert_eq!(r, Err(CalcError::SingularMatrix("solve".to_string())));
+
+        let r = mat(&[&[1, 2, 3], &[4, 5, 6]]).det();
+        let msg = "matrix 2x3 is not square".to_string();
+        assert_eq!(r, Err(CalcError::DimensionMismatch("det".to_string(), msg)));
+        let r = mat(&[&[1, 0], &[0, 1]]).linsolve(row(&[1, 2, 3]));
+        let msg = "2 equations and 3 right sides".to_string();
+        assert_eq!(r, Err(CalcError::DimensionMismatch("solve".to_string(), msg)));
+        let r = row(&[1, 2]).det();
+        assert_eq!(r, Err(CalcError::DimensionMismatch("det".to_string(), "[1; 2] is not a matrix".to_string())));
+    }
+
+    #[test]
+    fn test_vectors() {
+        let int = |i: i64| Value::Int(BigInt::from(i));
+        let vec = |v: &[i64]| Value::List(v.iter().map(|i| int(*i)).collect());
+
+        assert_eq!(vec(&[1, 2, 3]).dot(vec(&[4, -5, 6])), Ok(int(12)));
+        let half = Value::Ratio(BigRational::new(BigInt::from(1), BigInt::from(2)));
+        assert_eq!(
+            Value::List(vec![half.clone(), int(1)]).dot(vec(&[1, 1])),
+            Ok(Value::Ratio(BigRational::new(BigInt::from(3), BigInt::from(2))))
+        );
+        assert_eq!(vec(&[1, 0, 0]).cross(vec(&[0, 1, 0])), Ok(vec(&[0, 0, 1])));
+        assert_eq!(vec(&[1, 2, 3]).cross(vec(&[4, 5, 6])), Ok(vec(&[-3, 6, -3])));
+        assert_eq!(vec(&[3, 4]).vnorm(), Ok(int(5)));
+        assert_eq!(vec(&[1, 1]).vnorm(), Ok(Value::Float(2f64.sqrt())));
+        let a = vec(&[1, 0]).angle(vec(&[0, 2])).unwrap().as_f64().unwrap();
+        assert!((a - std::f64::consts::FRAC_PI_2).abs() < 1e-15);
+        let a = vec(&[1, 1, 1]).angle(vec(&[-2, -2, -2])).unwrap().as_f64().unwrap();
+        assert!((a - std::f64::consts::PI).abs() < 1e-15);
+        assert_eq!(vec(&[3, 3]).angle(vec(&[1, 1])), Ok(Value::Float(0.0)));
+
+        let r = vec(&[1, 2]).cross(vec(&[3, 4]));
+        let msg = "vectors must have 3 elements, not 2".to_string();
+        assert_eq!(r, Err(CalcError::DimensionMismatch("cross".to_string(), msg)));
+        let r = vec(&[1, 2]).dot(vec(&[3, 4, 5]));
+        let msg = "vectors of different lengths 2 and 3".to_string();
+        assert_eq!(r, Err(CalcError::DimensionMismatch("dot".to_string(), msg)));
+        let r = vec(&[0, 0]).angle(vec(&[1, 1]));
+        assert_eq!(r, Err(CalcError::InvalidAgrument("angle".to_string(), "zero vector".to_string())));
+        let r = int(3).vnorm();
+        assert_eq!(r, Err(CalcError::DimensionMismatch("vnorm".to_string(), "3 is not a vector".to_string())));
+    }
+
+    #[test]
+    fn test_poly() {
+        let int = |i: i64| Value::Int(BigInt::from(i));
+        let ratio = |n: i64, d: i64| Value::Ratio(BigRational::new(BigInt::from(n), BigInt::from(d)));
+        let p = |c: &[i64]| Value::poly(c.iter().map(|i| int(*i)).collect()).unwrap();
+        let ints = |v: &[i64]| Ok(Value::List(v.iter().map(|i| int(*i)).collect()));
+
+        assert_eq!(p(&[0, 0, 1, -3, 2]), Value::Poly(vec![int(1), int(-3), int(2)]));
+        assert_eq!(p(&[1, -3, 2]).to_string(), "x**2-3*x+2");
+        assert_eq!(p(&[0, 0]).to_string(), "0");
+        assert_eq!(p(&[1, 1]).addition(p(&[1, -1, 0])), Ok(p(&[1, 0, 1])));
+        assert_eq!(p(&[1, 1]).subtract(p(&[1, 1])), Ok(p(&[0])));
+        assert_eq!(p(&[1, 1]).multiply(p(&[1, -1])), Ok(p(&[1, 0, -1])));
+        assert_eq!(p(&[1, 1]).multiply(ratio(1, 2)), Ok(Value::Poly(vec![ratio(1, 2), ratio(1, 2)])));
+        assert_eq!(int(3).subtract(p(&[1, 1])), Ok(p(&[-1, 2])));
+        assert_eq!(p(&[1, -3]).negate(), Ok(p(&[-1, 3])));
+        assert_eq!(p(&[1, -3, 2]).polyval(int(5)), Ok(int(12)));
+        assert_eq!(p(&[2, 0, 1]).polyval(ratio(1, 2)), Ok(ratio(3, 2)));
+        assert_eq!(p(&[1, 0, 1]).polyval(Value::Complex(Complex::new(0.0, 1.0))), Ok(int(0)));
+        assert_eq!(Value::List(vec![int(1), int(2)]).polyval(int(3)), Ok(int(5)));
+        assert_eq!(p(&[1, 0]).polyval(p(&[1, 1])), Ok(p(&[1, 1])));
+
+        assert_eq!(p(&[2, -3]).roots(), Ok(Value::List(vec![ratio(3, 2)])));
+        assert_eq!(p(&[1, -3, 2]).roots(), ints(&[1, 2]));
+        assert_eq!(p(&[4, 0, -1]).roots(), Ok(Value::List(vec![ratio(-1, 2), ratio(1, 2)])));
+        assert_eq!(p(&[1, 0, 1]).roots().unwrap().to_string(), "[0.0-1.0i; 0.0+1.0i]");
+        assert_eq!(
+            p(&[1, 0, -2]).roots(),
+            Ok(Value::List(vec![Value::Float(-2f64.sqrt()), Value::Float(2f64.sqrt())]))
+        );
+        assert_eq!(p(&[1, -6, 11, -6]).roots(), ints(&[1, 2, 3]));
+        assert_eq!(p(&[1, -3, 3, -1]).roots(), ints(&[1, 1, 1]));
+        assert_eq!(p(&[1, -10, 35, -50, 24]).roots(), ints(&[1, 2, 3, 4]));
+        assert_eq!(p(&[1, 0, -5, 0, 4, 0]).roots(), ints(&[-2, -1, 0, 1, 2]));
+        assert_eq!(p(&[6, -5, 1]).roots(), Ok(Value::List(vec![ratio(1, 3), ratio(1, 2)])));
+        let r = p(&[1, 0, 0, 0, 1]).roots().unwrap();
+        if let Value::List(r) = r {
+            assert_eq!(r.len(), 4);
+            for z in r {
+                let z = z.into_raw_complex().unwrap();
+                assert!((z.powf(4.0) + 1.0).norm() < 1e-12);
+            }
+        }
+        let r =
+            Value::poly(vec![Value::Float(1.0), Value::Float(0.0), Value::Float(-2.0), Value::Float(-5.0)]).unwrap();
+        if let Ok(Value::List(r)) = r.roots() {
+            assert!((r[0].as_f64().unwrap() - 2.094_551_481_542_327).abs() < 1e-14);
+            assert_eq!(r.len(), 3);
+        }
+
+        assert_eq!(p(&[3]).roots(), Err(CalcError::NoRoots));
+        assert_eq!(
+            p(&[0]).roots(),
+            Err(CalcError::InvalidAgrument("roots".to_string(), "zero polynomial".to_string()))
+        );
+        let msg = "degree 5 is greater than 4".to_string();
+        assert_eq!(p(&[1, 0, 0, 0, 0, 1]).roots(), Err(CalcError::InvalidAgrument("roots".to_string(), msg)));
+        assert_eq!(Value::poly(vec![Value::Str("x".to_string())]), Err(CalcError::NotForString("poly".to_string())));
+        assert_eq!(p(&[2, 1]).divide(int(2)), Ok(Value::Poly(vec![int(1), ratio(1, 2)])));
+        assert_eq!(p(&[1, 1]).divide(p(&[1, 0])), Err(CalcError::PolyToNumber("x".to_string())));
+        assert_eq!(p(&[1, 1]).remainder(int(2)), Err(CalcError::NotForPoly("%".to_string())));
+        assert_eq!(p(&[1, 1]).power(int(2)), Ok(p(&[1, 2, 1])));
+        assert_eq!(p(&[1, 1]).power(int(0)), Ok(int(1)));
+        assert_eq!(int(2).power(p(&[1, 0])), Err(CalcError::PolyToNumber("x".to_string())));
+        assert_eq!(p(&[1, 1]).sqrt(), Err(CalcError::NotForPoly("sqrt".to_string())));
+    }
+
+    #[test]
+    fn test_regression() {
+        let list = |v: &[i64]| Value::List(v.iter().map(|i| Value::Int(BigInt::from(*i))).collect());
+        let floats = |v: &[f64]| Value::List(v.iter().map(|f| Value::Float(*f)).collect());
+        let r = list(&[1, 2, 3, 4]).linreg(list(&[3, 5, 7, 9]));
+        assert_eq!(r, Ok(floats(&[2.0, 1.0, 1.0])));
+        let r = list(&[1, 2, 3]).linreg(list(&[4, 4, 4]));
+        assert_eq!(r, Ok(floats(&[0.0, 4.0, 1.0])));
+        if let Ok(Value::List(v)) = list(&[1, 2, 3, 4, 5]).linreg(list(&[2, 4, 5, 4, 5])) {
+            assert_eq!(v[0], Value::Float(0.6));
+            assert_eq!(v[1], Value::Float(2.2));
+            assert!((v[2].as_f64().unwrap() - 0.6).abs() < 1e-12);
+        } else {
+            panic!("linreg failed");
+        }
+        let r = list(&[2, 2]).linreg(list(&[1, 3]));
+        assert_eq!(r, Err(CalcError::InvalidAgrument("linreg".to_string(), "all x values are equal".to_string())));
+
+        assert_eq!(list(&[1, 2, 3]).corr(list(&[6, 4, 2])), Ok(Value::Float(-1.0)));
+        let r = list(&[1, 2, 3, 4, 5]).corr(list(&[2, 4, 5, 4, 5])).unwrap().as_f64().unwrap();
+        assert!((r - 0.6f64.sqrt()).abs() < 1e-12);
+        assert_eq!(list(&[1, 2, 3, 4]).cov(list(&[2, 4, 6, 10])), Ok(Value::Float(13.0 / 3.0)));
+        assert_eq!(list(&[1, 2, 3]).cov(list(&[1, 2, 3])), Ok(Value::Float(1.0)));
+
+        let r = list(&[1, 2, 3]).cov(list(&[1, 2]));
+        let msg = "lists of different lengths 3 and 2".to_string();
+        assert_eq!(r, Err(CalcError::InvalidAgrument("cov".to_string(), msg)));
+        let r = list(&[1]).corr(list(&[1]));
+        assert_eq!(r, Err(CalcError::InvalidAgrument("corr".to_string(), "less than two points".to_string())));
+        let r = Value::Int(BigInt::from(1)).cov(list(&[1, 2]));
+        assert_eq!(r, Err(CalcError::InvalidAgrument("cov".to_string(), "1".to_string())));
+    }
+
+    #[test]
+    #[cfg(feature = "datetime")]
+    fn test_calendar() {
+        let int = |i: i64| Value::Int(BigInt::from(i));
+        let text = |s: &str| Value::Str(s.to_string());
+        let r = text("2024-03-01 12:00").tz(text("Europe/Stockholm"));
+        assert_eq!(r, Ok(text("2024-03-01T13:00:00+01:00")));
+        let r = text("2024-07-01T12:00:00+02:00").tz(text("America/New_York"));
+        assert_eq!(r, Ok(text("2024-07-01T06:00:00-04:00")));
+        assert_eq!(int(0).tz(text("Asia/Tokyo")), Ok(text("1970-01-01T09:00:00+09:00")));
+        let r = int(0).tz(text("Mars/Olympus"));
+        assert_eq!(r, Err(CalcError::InvalidAgrument("tz".to_string(), "Mars/Olympus".to_string())));
+
+        assert_eq!(text("2024-03-01").weekday(), Ok(int(5)));
+        assert_eq!(text("2024-03-03T23:30:00-05:00").weekday(), Ok(int(7)));
+        assert_eq!(int(0).weekday(), Ok(int(4)));
+        assert_eq!(text("2021-01-01").isoweek(), Ok(int(53)));
+        assert_eq!(text("2021-01-01").isoyear(), Ok(int(2020)));
+        assert_eq!(text("2024-12-30").isoweek(), Ok(int(1)));
+        assert_eq!(text("2024-12-30").isoyear(), Ok(int(2025)));
+        let r = text("2024-02-30").weekday();
+        assert_eq!(r, Err(CalcError::InvalidAgrument("weekday".to_string(), "2024-02-30".to_string())));
+
+        assert_eq!(int(2).daysin(int(2024)), Ok(int(29)));
+        assert_eq!(int(2).daysin(int(1900)), Ok(int(28)));
+        assert_eq!(int(12).daysin(int(2023)), Ok(int(31)));
+        assert_eq!(int(4).daysin(int(2023)), Ok(int(30)));
+        let r = int(13).daysin(int(2023));
+        assert_eq!(
+            r,
+            Err(CalcError::ArgumentOutOfRange("daysin".to_string(), "13".to_string(), "[1..12]".to_string()))
+        );
+    }
+
+    #[test]
+    #[cfg(feature = "units")]
+    fn test_data_size() {
+        let int = |i: i64| Value::Int(BigInt::from(i));
+        let text = |s: &str| Value::Str(s.to_string());
+        assert_eq!(int(4).kib(), Ok(int(4096)));
+        assert_eq!(int(2).gib(), Ok(int(2 << 30)));
+        assert_eq!(int(3).tb(), Ok(int(3_000_000_000_000)));
+        assert_eq!(Value::Float(1.5).mb(), Ok(int(1_500_000)));
+        assert_eq!(text("1").kb(), Err(CalcError::NotForString("kb".to_string())));
+
+        let bin = || int(1024);
+        assert_eq!(int(0).hsize(bin()), Ok(text("0 B")));
+        assert_eq!(int(1000).hsize(bin()), Ok(text("1000 B")));
+        assert_eq!(int(1024).hsize(bin()), Ok(text("1 KiB")));
+        assert_eq!(int(1536).hsize(bin()), Ok(text("1.5 KiB")));
+        assert_eq!(int(-1536).hsize(bin()), Ok(text("-1.5 KiB")));
+        assert_eq!(int(5 << 40).hsize(bin()), Ok(text("5 TiB")));
+        assert_eq!(Value::Float(0.5).hsize(bin()), Ok(text("0.5 B")));
+        assert_eq!(int(1234567).hsize(int(1000)), Ok(text("1.23 MB")));
+        assert_eq!(int(1000).hsize(int(1000)), Ok(text("1 kB")));
+        let r = int(1).hsize(int(10));
+        assert_eq!(r, Err(CalcError::InvalidAgrument("hsize".to_string(), "base 10".to_string())));
+    }
+
+    #[test]
+    #[cfg(feature = "units")]
+    fn test_ipv4() {
+        let int = |i: i64| Value::Int(BigInt::from(i));
+        let text = |s: &str| Value::Str(s.to_string());
+        assert_eq!(text("192.168.1.10").ip(), Ok(int(3_232_235_786)));
+        assert_eq!(int(3_232_235_786).ipstr(), Ok(text("192.168.1.10")));
+        assert_eq!(int(0).ipstr(), Ok(text("0.0.0.0")));
+        assert_eq!(int(24).netmask().unwrap().ipstr(), Ok(text("255.255.255.0")));
+        assert_eq!(int(0).netmask(), Ok(int(0)));
+        assert_eq!(int(32).netmask(), Ok(int(0xFFFF_FFFF)));
+        let r = text("10.1.2.3").network(int(20)).unwrap().ipstr();
+        assert_eq!(r, Ok(text("10.1.0.0")));
+        let r = text("10.1.2.3").broadcast(int(20)).unwrap().ipstr();
+        assert_eq!(r, Ok(text("10.1.15.255")));
+        assert_eq!(text("10.1.2.3").broadcast(int(32)), text("10.1.2.3").ip());
+        assert_eq!(text("10.1.2.3").broadcast(int(0)), Ok(int(0xFFFF_FFFF)));
+
+        assert_eq!(text("1.2.3").ip(), Err(CalcError::InvalidAgrument("ip".to_string(), "1.2.3".to_string())));
+        let r = int(1 << 32).ipstr();
+        let range = "[0..4294967295]".to_string();
+        assert_eq!(r, Err(CalcError::ArgumentOutOfRange("ipstr".to_string(), "4294967296".to_string(), range)));
+        let r = int(33).netmask();
+        assert_eq!(
+            r,
+            Err(CalcError::ArgumentOutOfRange("netmask".to_string(), "33".to_string(), "[0..32]".to_string()))
+        );
+    }
+    #[test]
+    fn test_from_str() {
+        let int = |i: i64| Value::Int(BigInt::from(i));
+        let ratio = |n: i64, d: i64| Value::Ratio(BigRational::new(BigInt::from(n), BigInt::from(d)));
+        let values = vec![
+            int(0),
+            int(-12),
+            Value::Int(BigInt::from(10).pow(40u32)),
+            Value::Float(1.5),
+            Value::Float(-2.0),
+            Value::Float(1e300),
+            Value::Float(-1.25e-7),
+            Value::Float(f64::INFINITY),
+            Value::Float(f64::NEG_INFINITY),
+            ratio(1, 3),
+            ratio(-7, 2),
+            Value::Complex(Complex::new(1.5, -2.0)),
+            Value::Complex(Complex::new(-1e-9, 3.25)),
+            Value::List(vec![]),
+            Value::List(vec![int(1), Value::List(vec![ratio(1, 2), Value::Str("a b".to_string())]), Value::Float(0.5)]),
+            Value::Poly(vec![int(1), int(-3), int(2)]),
+            Value::Poly(vec![ratio(-1, 64), int(0), Value::Float(1.5), ratio(3, 4)]),
+            Value::Str("MMXXIV".to_string()),
+            Value::Str("1+2".to_string()),
+            Value::Str(" padded ".to_string()),
+            Value::Str(String::new()),
+            Value::Bool(true),
+            Value::Bool(false),
+            Value::List(vec![Value::Bool(true), int(2)]),
+        ];
+        for v in values {
+            assert_eq!(v.to_string().parse::<Value>(), Ok(v.clone()), "{}", v);
+        }
+        assert!(matches!("NaN".parse::<Value>(), Ok(Value::Float(f)) if f.is_nan()));
+        assert_eq!("2.0".parse::<Value>(), Ok(Value::Float(2.0)));
+        assert_eq!(" 42 ".parse::<Value>(), Ok(int(42)));
+        assert_eq!("[1;2]".parse::<Value>(), Ok(Value::List(vec![int(1), int(2)])));
+        assert_eq!("x".parse::<Value>(), Ok(Value::Poly(vec![int(1), int(0)])));
+        assert_eq!("x**2*3".parse::<Value>(), Ok(Value::Str("x**2*3".to_string())));
+        assert_eq!("12".parse::<Value>(), Ok(int(12)));
+        assert_eq!("True".parse::<Value>(), Ok(Value::Str("True".to_string())));
+    }
+    #[test]
+    fn test_to_string_with() {
+        let int = |i: i64| Value::Int(BigInt::from(i));
+        let def = FormatOptions::default();
+        let values =
+            [int(-1234), Value::Float(2.5), Value::Complex(Complex::new(1.5, -2.0)), Value::Str("a".to_string())];
+        for v in values.iter() {
+            assert_eq!(v.to_string_with(&def), v.to_string());
+        }
+
+        let opts = FormatOptions { precision: Some(3), ..Default::default() };
+        assert_eq!(Value::Float(2.5).to_string_with(&opts), "2.500");
+        assert_eq!(Value::Float(-0.0004).to_string_with(&opts), "0.000");
+        assert_eq!(Value::Float(0.0625).to_string_with(&opts), "0.062");
+        let opts = FormatOptions { precision: Some(2), rounding: Rounding::HalfAwayFromZero, ..Default::default() };
+        assert_eq!(Value::Float(2.675).to_string_with(&opts), "2.68");
+        let opts = FormatOptions { precision: Some(4), trim_zeros: true, ..Default::default() };
+        assert_eq!(Value::Float(2.5).to_string_with(&opts), "2.5");
+        assert_eq!(Value::Float(3.0).to_string_with(&opts), "3");
+        assert_eq!(Value::Float(f64::INFINITY).to_string_with(&opts), "inf");
+        assert_eq!(Value::Float(1e300).to_string_with(&FormatOptions { trim_zeros: true, ..def }), "1e300");
+
+        let c = Value::Complex(Complex::new(1.0, -2.5));
+        let opts = FormatOptions { trim_zeros: true, complex: ComplexStyle::Pair, ..Default::default() };
+        assert_eq!(c.to_string_with(&opts), "(1, -2.5)");
+        assert_eq!(c.to_string_with(&FormatOptions { trim_zeros: true, ..def }), "1-2.5i");
+
+        let opts = FormatOptions { group_sep: Some(','), precision: Some(1), ..Default::default() };
+        assert_eq!(int(-1234567).to_string_with(&opts), "-1,234,567");
+        assert_eq!(int(123).to_string_with(&opts), "123");
+        assert_eq!(Value::Float(12345.25).to_string_with(&opts), "12,345.2");
+        let l = Value::List(vec![int(1000), Value::Float(0.5)]);
+        assert_eq!(l.to_string_with(&opts), "[1,000; 0.5]");
+
+        let opts = FormatOptions { radix: 16, ..Default::default() };
+        assert_eq!(int(-255).to_string_with(&opts), "-0xff");
+        assert_eq!(Value::Float(2.5).to_string_with(&opts), "2.5");
+        let opts = FormatOptions { radix: 2, group_sep: Some('_'), ..Default::default() };
+        assert_eq!(int(10).to_string_with(&opts), "0b1_010");
+    }
+    #[test]
     fn test_img() {
         let v = Value::Complex(Complex::new(3.0, -4.0));
         let r = v.clone().im();